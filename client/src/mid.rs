@@ -3,10 +3,21 @@
 use color_eyre::eyre::{Context, ContextCompat};
 use common::{
     backend::{
-        CreateTaskRequest, CreateTaskResponse, DeleteTaskRequest, DeleteTaskResponse,
-        FilterRequest, FilterResponse, ReadTaskShortRequest, ReadTaskShortResponse,
-        ReadTasksShortRequest, ReadTasksShortResponse, UpdateTaskRequest, UpdateTaskResponse,
+        AggregateRequest, AggregateResponse, Attachment, AttachmentID, Comment, CommentID,
+        CreateCommentRequest, CreateCommentResponse, CreateTagRequest, CreateTagResponse,
+        CreateTaskRequest, CreateTaskResponse, DeleteCommentRequest, DeleteCommentResponse,
+        DeleteTagRequest, DeleteTagResponse, DeleteTaskRequest, DeleteTaskResponse,
+        DownloadAttachmentQuery, FilterRequest, FilterResponse, GetAttachmentsRequest,
+        GetAttachmentsResponse, GetCommentsRequest, GetCommentsResponse, GetGlobalsRequest,
+        GetGlobalsResponse, GetNotificationsRequest, GetNotificationsResponse, GetSchemaRequest,
+        GetSchemaResponse, GetTagsRequest, GetTagsResponse, GetTaskTagsRequest,
+        GetTaskTagsResponse, MoveTaskRequest, MoveTaskResponse, Notification, NotificationID,
+        NotificationKind, ParentChange, PropertiesRequest, PropertiesResponse,
+        ReadTaskShortRequest, ReadTaskShortResponse, ReadTasksShortRequest, ReadTasksShortResponse,
+        SearchRequest, SearchResponse, SearchResult, SetTaskTagsRequest, SetTaskTagsResponse, Tag,
+        TaskGroup, TaskTags, UpdateTaskRequest, UpdateTaskResponse,
     },
+    filter::FilterTarget,
     *,
 };
 use futures::{
@@ -21,6 +32,8 @@ use slotmap::{new_key_type, KeyData, SlotMap};
 use std::{
     collections::{HashMap, HashSet},
     fmt,
+    path::{Path, PathBuf},
+    process::Stdio,
 };
 use thiserror::Error;
 use tokio::task::JoinHandle;
@@ -39,18 +52,26 @@ pub struct Task {
     pub dependencies: Vec<TaskKey>,
     /// Associated scripts
     pub scripts: Vec<ScriptID>,
+    /// task this is a subtask of
+    pub parent: Option<TaskKey>,
+    /// subtasks of this task, in order
+    pub subtasks: Vec<TaskKey>,
     /// if it is stored in the database, it will have a unique task_id.
     pub db_id: Option<TaskID>,
     /// latest should be set to true if this value matches server (if false and needed, it should be fetched and updated as soon as possible)
     pub current_rollback: Option<Box<Task>>,
     /// if task is pending deletion request
     pub pending_deletion: bool,
+    /// last edited timestamp, as last reported by the server or stamped by a local edit
+    pub last_edited: chrono::NaiveDateTime,
 }
 impl Task {
     pub fn new(name: String, completed: bool) -> Task {
         Task {
             name,
             completed,
+            // created now as far as local filters can tell, until the server reports its own stamp
+            last_edited: chrono::Utc::now().naive_utc(),
             ..Default::default()
         }
     }
@@ -70,8 +91,32 @@ pub struct View {
     pub filter: Filter,
     /// Properties shown in view
     pub props: Vec<PropNameKey>,
+    /// Sort order of the view's tasks, applied on the backend
+    pub sort: Vec<SortKey>,
+    /// Maximum number of tasks shown in the view
+    pub max_tasks: Option<u64>,
     /// Tasks that are apart of the view, calculated on the backend via calls to /filterids
     pub tasks: Option<Vec<TaskKey>>,
+    /// Whether the backend has more tasks matching the view than have been paged into `tasks`
+    pub more_tasks: bool,
+    /// Number of the backend's matching tasks paged in so far, where the next page starts.
+    /// Tasks added to or removed from `tasks` by local edits don't move it
+    pub server_offset: u64,
+    /// Whether local edits changed which tasks match the view while it's sorted or paged, so its pages
+    /// are fetched again once the server has the edits
+    pub stale: bool,
+    /// Whether a page of tasks is currently being requested for the view
+    pub pending_page: bool,
+    /// Date property the view's tasks are plotted on in the calendar
+    pub date_prop: Option<PropNameKey>,
+    /// How the view's tasks are grouped into the columns of the board and the sections of the task list
+    pub group_by: GroupBy,
+    /// Values summarizing each group of the view's tasks
+    pub summaries: Vec<Summary>,
+    /// Groups of all tasks matching the view, calculated on the backend via calls to /aggregate
+    pub groups: Vec<TaskGroup>,
+    /// Index in `groups` of each loaded task of the view
+    pub task_groups: HashMap<TaskKey, usize>,
     /// Computed task list for view
     pub db_id: Option<ViewID>,
 }
@@ -88,6 +133,15 @@ impl View {
 new_key_type! { pub struct PropNameKey; }
 new_key_type! { pub struct ViewKey; }
 
+/// number of task ids requested per page when filling a view
+pub const VIEW_PAGE_SIZE: u64 = 50;
+/// maximum number of search results requested
+pub const SEARCH_LIMIT: u64 = 20;
+/// maximum number of notifications requested per poll, and kept in the tray
+pub const NOTIFICATION_LIMIT: u64 = 50;
+/// how often the server is polled for notifications
+pub const NOTIFICATION_POLL_PERIOD: std::time::Duration = std::time::Duration::from_secs(10);
+
 /// Middleware State structure.
 #[derive(Debug)]
 pub struct State {
@@ -102,16 +156,49 @@ pub struct State {
     prop_names: SlotMap<PropNameKey, String>,
     /// lookup prop name key with string
     prop_name_map: HashMap<PropName, PropNameKey>,
+    /// workspace definitions of properties, as received from the server
+    prop_schema: HashMap<PropNameKey, PropertyDefinition>,
     /// properties stored in the middleware can be uniquely identified by the task they are stored upon and the string of the property
     prop_map: HashMap<(TaskKey, PropNameKey), PropKey>,
     /// efficient, dense storage of all locally-stored task properties
     props: SlotMap<PropKey, TaskPropVariant>,
+    /// workspace-wide properties, as received from the server
+    globals: HashMap<PropName, TaskPropVariant>,
 
     /// scripts are identified by database's ScriptID
     scripts: HashMap<ScriptID, Script>,
     /// views are identified by database's ViewID
     views_map: HashMap<ViewID, ViewKey>,
     views: SlotMap<ViewKey, View>,
+    /// results of the latest search, most relevant first
+    search_results: Vec<(TaskKey, SearchResult)>,
+    /// id of the latest search request, responses to older searches are ignored
+    search_req_id: u64,
+    /// notifications fired by the server's scheduler and the tasks they are about, oldest first
+    notifications: Vec<(Option<TaskKey>, Notification)>,
+    /// latest notification received, the next poll asks for the ones after it
+    notification_cursor: Option<NotificationID>,
+    /// whether a poll for notifications is waiting for its response
+    notification_poll_pending: bool,
+    /// threads of the tasks whose comments were requested, oldest comment first
+    comments: HashMap<TaskKey, Vec<Comment>>,
+    /// user name comments are posted under, the login name by default.
+    /// The server takes it as given, so it only says who wrote a comment among people who trust each other
+    user: String,
+    /// files attached to the tasks whose attachments were requested, oldest first
+    attachments: HashMap<TaskKey, Vec<Attachment>>,
+    /// every tag of the workspace
+    tags: HashMap<TagID, Tag>,
+    /// tags of the tasks whose tags were requested, in id order
+    task_tags: HashMap<TaskKey, Vec<TagID>>,
+    /// tasks whose properties were requested, by request id, in the order the values are returned
+    prop_requests: HashMap<u64, Vec<TaskKey>>,
+    /// id of the latest property request
+    prop_req_id: u64,
+    /// view and tasks whose groups were requested, by request id, in the order the groups are returned
+    aggregate_requests: HashMap<u64, (ViewKey, Vec<TaskKey>)>,
+    /// id of the latest aggregate request
+    aggregate_req_id: u64,
     /// connected url
     url: String,
     client: ClientWithMiddleware,
@@ -126,6 +213,18 @@ enum RevertError {
     Task(TaskKey),
     Tasks(Vec<TaskKey>),
     Filter(ViewKey),
+    Search,
+    Schema,
+    Globals,
+    Notifications,
+    Props(u64),
+    Aggregate(u64),
+    Rank(ViewKey),
+    Comments,
+    Attachments,
+    Tags,
+    /// tags of the tasks couldn't be fetched or set
+    TaskTags(Vec<TaskKey>),
 }
 
 #[derive(Debug)]
@@ -150,8 +249,55 @@ impl State {
                 RevertError::Tasks(tasks) => {
                     tasks.iter().for_each(|key| self.revert_task(*key));
                 }
-                RevertError::Filter(_) => {
-                    tracing::debug!("cannot revert filter request")
+                RevertError::Filter(view_key) => {
+                    tracing::debug!("cannot revert filter request");
+                    if let Some(view) = self.views.get_mut(view_key) {
+                        view.pending_page = false; // allow retrying the page
+                    }
+                }
+                RevertError::Search => {
+                    tracing::debug!("search request failed")
+                }
+                RevertError::Schema => {
+                    tracing::debug!("schema request failed")
+                }
+                RevertError::Globals => {
+                    tracing::debug!("globals request failed")
+                }
+                RevertError::Notifications => {
+                    tracing::debug!("notifications request failed");
+                    self.notification_poll_pending = false; // poll again next time
+                }
+                RevertError::Props(req_id) => {
+                    tracing::debug!("properties request failed");
+                    self.prop_requests.remove(&req_id);
+                }
+                RevertError::Aggregate(req_id) => {
+                    tracing::debug!("aggregate request failed");
+                    self.aggregate_requests.remove(&req_id);
+                }
+                RevertError::Rank(view_key) => {
+                    // the server's order may have changed under us, so take it from the server again
+                    tracing::debug!("move rejected, fetching the order of the view again");
+                    self.view_fetch_page(view_key, 0);
+                }
+                RevertError::Comments => {
+                    tracing::debug!("comments request failed")
+                }
+                RevertError::Attachments => {
+                    tracing::debug!("attachments request failed")
+                }
+                RevertError::Tags => {
+                    tracing::debug!("tags request failed")
+                }
+                RevertError::TaskTags(keys) => {
+                    // forget what we thought the tags were, so they're fetched again with the view
+                    tracing::debug!("task tags request failed");
+                    for key in keys {
+                        self.task_tags.remove(&key);
+                    }
+                    self.mid_event_sender
+                        .try_send(MidEvent::StateEvent(StateEvent::TagsUpdate))?;
                 }
             },
             MidEvent::StateEvent(_) => panic!("middleware does not handle state events"),
@@ -172,6 +318,9 @@ pub enum PropDataError {
     /// Property does not exist
     #[error("property associated with task {0:?} and prop name: {0:?} does not exist")]
     Prop(TaskKey, PropNameKey),
+    /// Value doesn't fit the property's definition in the schema
+    #[error("{0}")]
+    Schema(String),
 }
 
 /// Anything in this enum is sent to the middleware script executor when a UI event is triggered.
@@ -189,6 +338,16 @@ pub enum StateEvent {
     PropsUpdate,
     /// One or more views updated
     ViewsUpdate,
+    /// Search results were received
+    SearchUpdate,
+    /// New notifications were received
+    NotificationsUpdate,
+    /// The comments on a task were received or changed
+    CommentsUpdate,
+    /// The files attached to a task were received
+    AttachmentsUpdate,
+    /// Tags or the tags of tasks were received or changed
+    TagsUpdate,
     /// A script was updated
     ScriptUpdate(ScriptID),
     /// The connection has either connected or disconnected.
@@ -212,9 +371,16 @@ impl ServerResponse for ReadTaskShortResponse {
                 .map(|tid| state.new_server_task(*tid).0)
                 .collect::<Vec<TaskKey>>(),
             scripts: self.scripts,
+            parent: self.parent.map(|tid| state.new_server_task(tid).0),
+            subtasks: self
+                .subtasks
+                .iter()
+                .map(|tid| state.new_server_task(*tid).0)
+                .collect::<Vec<TaskKey>>(),
             db_id: Some(self.task_id),
             current_rollback: None,
             pending_deletion: false,
+            last_edited: self.last_edited,
         };
         // create/update existing task with read task
         *state.new_server_task(self.task_id).1 = task;
@@ -230,15 +396,61 @@ impl ServerResponse for UpdateTaskResponse {
                 self.task_id
             )
         })?;
-        if let Some(task) = state.tasks.get_mut(*task_key) {
-            task.db_id = Some(self.task_id);
-            task.current_rollback = None;
-        } else {
+        let task_key = *task_key;
+        let Some(task) = state.tasks.get_mut(task_key) else {
             panic!(
                 "fatal: DB id {:?} was associated with task key {:?} but task didn't exist",
                 self.task_id, task_key
             );
+        };
+        task.db_id = Some(self.task_id);
+        let old = task.current_rollback.take();
+        let (completed, parent) = (task.completed, task.parent);
+        // the server cascades completion up the hierarchy and renumbers subtasks,
+        // so the parents the task had and has are fetched again when they may have changed
+        let mut related = vec![];
+        if let Some(old) = old {
+            let reordered = parent
+                .and_then(|key| state.tasks.get(key))
+                .is_some_and(|parent| parent.current_rollback.is_some());
+            if old.completed != completed || old.parent != parent || reordered {
+                let mut ancestor = parent;
+                while let Some(key) = ancestor.filter(|key| !related.contains(key)) {
+                    related.push(key);
+                    ancestor = state.tasks.get(key).and_then(|task| task.parent);
+                }
+                related.extend(old.parent.filter(|key| !related.contains(key)));
+            }
+        }
+        let tasks_to_fetch = related
+            .iter()
+            .filter_map(|key| state.tasks.get(*key)?.db_id)
+            .map(|task_id| ReadTaskShortRequest { task_id, req_id: 0 })
+            .collect::<Vec<ReadTaskShortRequest>>();
+        if !tasks_to_fetch.is_empty() {
+            state.spawn_request::<ReadTasksShortRequest, ReadTasksShortResponse>(
+                state.client.get(format!("{}/tasks", state.url)),
+                tasks_to_fetch,
+                RevertError::Tasks(related),
+            );
+        }
+        // the edit may have moved the task to another group
+        let grouped_views = state
+            .views
+            .iter()
+            .filter(|(_, view)| {
+                view.group_by != GroupBy::None
+                    && view
+                        .tasks
+                        .as_ref()
+                        .is_some_and(|tasks| tasks.contains(&task_key))
+            })
+            .map(|(view_key, _)| view_key)
+            .collect::<Vec<ViewKey>>();
+        for view_key in grouped_views {
+            state.view_aggregate(view_key);
         }
+        state.view_refetch_stale();
         Ok(Some(StateEvent::TasksUpdate))
     }
 }
@@ -249,6 +461,7 @@ impl ServerResponse for CreateTaskResponse {
         task.db_id = Some(self.task_id); // record db ID
         state.task_map.insert(self.task_id, task_key); // record in db map
         task.current_rollback = None; // flag syncronized
+        state.view_refetch_stale();
         Ok(Some(StateEvent::TasksUpdate))
     }
 }
@@ -261,7 +474,9 @@ impl ServerResponse for DeleteTaskResponse {
         if let Some(db_id) = task.db_id {
             // if we have a local db_id, remove it from the map
             state.task_map.remove(&db_id);
+            state.task_refs_drop(db_id);
         }
+        state.view_refetch_stale();
         Ok(Some(StateEvent::TasksUpdate))
     }
 }
@@ -278,7 +493,7 @@ impl ServerResponse for ReadTasksShortResponse {
 impl ServerResponse for FilterResponse {
     fn update_state(self: Box<Self>, state: &mut State) -> color_eyre::Result<Option<StateEvent>> {
         // allocate server tasks
-        let tasks: Vec<TaskKey> = self
+        let page: Vec<TaskKey> = self
             .tasks
             .into_iter()
             .map(|tid| state.new_server_task(tid).0)
@@ -291,15 +506,30 @@ impl ServerResponse for FilterResponse {
                 view_key
             )
         })?; // TODO add context
-        view.tasks = Some(tasks);
+             // first page replaces the view's tasks, later pages are appended in order
+        let tasks = view.tasks.get_or_insert_with(Vec::new);
+        if self.offset == 0 {
+            tasks.clear();
+        }
+        tasks.truncate(self.offset as usize);
+        view.server_offset = self.offset + page.len() as u64;
+        let new_tasks = page
+            .into_iter()
+            .filter(|key| !tasks.contains(key))
+            .collect::<Vec<TaskKey>>();
+        tasks.extend(new_tasks.iter().cloned());
+        view.more_tasks = self.more;
+        if let Some(max_tasks) = view.max_tasks {
+            tasks.truncate(max_tasks as usize);
+            view.more_tasks &= (tasks.len() as u64) < max_tasks;
+        }
+        view.pending_page = false;
 
-        // get tasks
-        let view_tasks = state.view_task_keys(view_key).unwrap();
-        let tasks_to_revert = view_tasks.clone().collect::<Vec<TaskKey>>();
-        // calculate which tasks we have and which need fetching using is_syncronized
-        let tasks_to_fetch = view_tasks
+        // calculate which of the received tasks need fetching using is_syncronized
+        let tasks_to_fetch = new_tasks
+            .iter()
             .filter_map(|tkey| {
-                state.tasks.get(tkey).and_then(|t| {
+                state.tasks.get(*tkey).and_then(|t| {
                     if t.current_rollback.is_none() {
                         t.db_id
                     } else {
@@ -316,13 +546,278 @@ impl ServerResponse for FilterResponse {
             state.spawn_request::<ReadTasksShortRequest, ReadTasksShortResponse>(
                 state.client.get(format!("{}/tasks", state.url)),
                 tasks_to_fetch,
-                RevertError::Tasks(tasks_to_revert),
+                RevertError::Tasks(new_tasks),
             );
         }
+        if state.views[view_key].group_by != GroupBy::None {
+            state.view_aggregate(view_key);
+        }
+        Ok(Some(StateEvent::ViewsUpdate))
+    }
+}
+
+impl ServerResponse for AggregateResponse {
+    fn update_state(self: Box<Self>, state: &mut State) -> color_eyre::Result<Option<StateEvent>> {
+        let (view_key, task_keys) = state
+            .aggregate_requests
+            .remove(&self.req_id)
+            .context("received groups that were not requested")?;
+        let Some(view) = state.views.get_mut(view_key) else {
+            return Ok(None); // deleted while the request was pending
+        };
+        view.groups = self.groups;
+        view.task_groups = task_keys
+            .into_iter()
+            .zip(self.task_groups)
+            .filter_map(|(key, group)| Some((key, group?)))
+            .collect();
+        Ok(Some(StateEvent::ViewsUpdate))
+    }
+}
+
+impl ServerResponse for MoveTaskResponse {
+    fn update_state(self: Box<Self>, state: &mut State) -> color_eyre::Result<Option<StateEvent>> {
+        let view_key = ViewKey(KeyData::from_ffi(self.req_id));
+        let Some(view) = state.views.get_mut(view_key) else {
+            return Ok(None); // deleted while the request was pending
+        };
+        view.sort = self.sort;
         Ok(Some(StateEvent::ViewsUpdate))
     }
 }
 
+impl ServerResponse for SearchResponse {
+    fn update_state(self: Box<Self>, state: &mut State) -> color_eyre::Result<Option<StateEvent>> {
+        if self.req_id != state.search_req_id {
+            return Ok(None); // superseded by a newer search
+        }
+        let mut tasks_to_fetch = vec![];
+        state.search_results = self
+            .results
+            .into_iter()
+            .map(|result| {
+                if !state.task_map.contains_key(&result.task_id) {
+                    tasks_to_fetch.push(ReadTaskShortRequest {
+                        task_id: result.task_id,
+                        req_id: 0,
+                    });
+                }
+                (state.new_server_task(result.task_id).0, result)
+            })
+            .collect();
+        // fetch tasks we haven't seen yet so results can be shown by name
+        if !tasks_to_fetch.is_empty() {
+            let tasks_to_revert = tasks_to_fetch
+                .iter()
+                .map(|req| state.task_map[&req.task_id])
+                .collect();
+            state.spawn_request::<ReadTasksShortRequest, ReadTasksShortResponse>(
+                state.client.get(format!("{}/tasks", state.url)),
+                tasks_to_fetch,
+                RevertError::Tasks(tasks_to_revert),
+            );
+        }
+        Ok(Some(StateEvent::SearchUpdate))
+    }
+}
+
+impl ServerResponse for GetSchemaResponse {
+    fn update_state(self: Box<Self>, state: &mut State) -> color_eyre::Result<Option<StateEvent>> {
+        state.prop_schema.clear();
+        for definition in self.definitions {
+            let name_key = state.prop_def_name(definition.name.clone());
+            state.prop_schema.insert(name_key, definition);
+        }
+        Ok(Some(StateEvent::PropsUpdate))
+    }
+}
+
+impl ServerResponse for GetGlobalsResponse {
+    fn update_state(self: Box<Self>, state: &mut State) -> color_eyre::Result<Option<StateEvent>> {
+        state.globals = self
+            .globals
+            .into_iter()
+            .map(|global| (global.name, global.value))
+            .collect();
+        Ok(Some(StateEvent::PropsUpdate))
+    }
+}
+
+impl ServerResponse for GetNotificationsResponse {
+    fn update_state(self: Box<Self>, state: &mut State) -> color_eyre::Result<Option<StateEvent>> {
+        state.notification_poll_pending = false;
+        let Some(latest) = self.notifications.last() else {
+            return Ok(None);
+        };
+        state.notification_cursor = Some(latest.id);
+        let mut tasks_to_fetch = vec![];
+        let mut threads_to_fetch = HashSet::new();
+        for notification in self.notifications {
+            let key = notification.task_id.map(|task_id| {
+                if !state.task_map.contains_key(&task_id) {
+                    tasks_to_fetch.push(ReadTaskShortRequest { task_id, req_id: 0 });
+                }
+                state.new_server_task(task_id).0
+            });
+            // keep threads that are being read up to date
+            if let (Some(key), NotificationKind::Comment { .. }) = (key, &notification.kind) {
+                if state.comments.contains_key(&key) {
+                    threads_to_fetch.insert(key);
+                }
+            }
+            state.notifications.push((key, notification));
+        }
+        for key in threads_to_fetch {
+            state.comments_fetch(key);
+        }
+        let overflow = state
+            .notifications
+            .len()
+            .saturating_sub(NOTIFICATION_LIMIT as usize);
+        state.notifications.drain(..overflow);
+        // fetch tasks we haven't seen yet so notifications can be shown by name
+        if !tasks_to_fetch.is_empty() {
+            let tasks_to_revert = tasks_to_fetch
+                .iter()
+                .map(|req| state.task_map[&req.task_id])
+                .collect();
+            state.spawn_request::<ReadTasksShortRequest, ReadTasksShortResponse>(
+                state.client.get(format!("{}/tasks", state.url)),
+                tasks_to_fetch,
+                RevertError::Tasks(tasks_to_revert),
+            );
+        }
+        Ok(Some(StateEvent::NotificationsUpdate))
+    }
+}
+
+impl ServerResponse for GetCommentsResponse {
+    fn update_state(self: Box<Self>, state: &mut State) -> color_eyre::Result<Option<StateEvent>> {
+        let Some(key) = state.task_map.get(&self.task_id).copied() else {
+            return Ok(None);
+        };
+        state.comments.insert(key, self.comments);
+        Ok(Some(StateEvent::CommentsUpdate))
+    }
+}
+
+impl ServerResponse for CreateCommentResponse {
+    fn update_state(self: Box<Self>, state: &mut State) -> color_eyre::Result<Option<StateEvent>> {
+        let Some(key) = state.task_map.get(&self.comment.task_id).copied() else {
+            return Ok(None);
+        };
+        // a poll may have fetched the thread with the comment in it already
+        let thread = state.comments.entry(key).or_default();
+        match thread.binary_search_by_key(&self.comment.id, |comment| comment.id) {
+            Ok(index) => thread[index] = self.comment,
+            Err(index) => thread.insert(index, self.comment),
+        }
+        Ok(Some(StateEvent::CommentsUpdate))
+    }
+}
+
+impl ServerResponse for DeleteCommentResponse {
+    fn update_state(self: Box<Self>, state: &mut State) -> color_eyre::Result<Option<StateEvent>> {
+        let Some(thread) = state
+            .task_map
+            .get(&self.task_id)
+            .and_then(|key| state.comments.get_mut(key))
+        else {
+            return Ok(None);
+        };
+        thread.retain(|comment| comment.id != self.comment_id);
+        Ok(Some(StateEvent::CommentsUpdate))
+    }
+}
+
+impl ServerResponse for GetAttachmentsResponse {
+    fn update_state(self: Box<Self>, state: &mut State) -> color_eyre::Result<Option<StateEvent>> {
+        let Some(key) = state.task_map.get(&self.task_id).copied() else {
+            return Ok(None);
+        };
+        state.attachments.insert(key, self.attachments);
+        Ok(Some(StateEvent::AttachmentsUpdate))
+    }
+}
+
+impl ServerResponse for GetTagsResponse {
+    fn update_state(self: Box<Self>, state: &mut State) -> color_eyre::Result<Option<StateEvent>> {
+        state.tags = self.tags.into_iter().map(|tag| (tag.id, tag)).collect();
+        Ok(Some(StateEvent::TagsUpdate))
+    }
+}
+
+impl ServerResponse for CreateTagResponse {
+    fn update_state(self: Box<Self>, state: &mut State) -> color_eyre::Result<Option<StateEvent>> {
+        state.tags.insert(self.tag.id, self.tag);
+        Ok(Some(StateEvent::TagsUpdate))
+    }
+}
+
+impl ServerResponse for DeleteTagResponse {
+    fn update_state(self: Box<Self>, state: &mut State) -> color_eyre::Result<Option<StateEvent>> {
+        // the server deletes the tags nested under the tag with it
+        let deleted = state.tag_descendants(self.tag_id);
+        for tag_id in &deleted {
+            state.tags.remove(tag_id);
+        }
+        for tags in state.task_tags.values_mut() {
+            tags.retain(|tag_id| !deleted.contains(tag_id));
+        }
+        Ok(Some(StateEvent::TagsUpdate))
+    }
+}
+
+impl ServerResponse for GetTaskTagsResponse {
+    fn update_state(self: Box<Self>, state: &mut State) -> color_eyre::Result<Option<StateEvent>> {
+        for TaskTags { task_id, tags } in self.tasks {
+            if let Some(key) = state.task_map.get(&task_id) {
+                state.task_tags.insert(*key, tags);
+            }
+        }
+        Ok(Some(StateEvent::TagsUpdate))
+    }
+}
+
+impl ServerResponse for SetTaskTagsResponse {
+    fn update_state(self: Box<Self>, state: &mut State) -> color_eyre::Result<Option<StateEvent>> {
+        let Some(key) = state.task_map.get(&self.task.task_id).copied() else {
+            return Ok(None);
+        };
+        state.task_tags.insert(key, self.task.tags);
+        Ok(Some(StateEvent::TagsUpdate))
+    }
+}
+
+impl ServerResponse for PropertiesResponse {
+    fn update_state(self: Box<Self>, state: &mut State) -> color_eyre::Result<Option<StateEvent>> {
+        let task_keys = state
+            .prop_requests
+            .remove(&self.req_id)
+            .context("received properties that were not requested")?;
+        for column in self.res {
+            let name_key = state.prop_def_name(column.name);
+            for (task_key, value) in task_keys.iter().zip(column.values) {
+                if state.tasks.get(*task_key).is_none() {
+                    continue; // deleted while the request was pending
+                }
+                match value {
+                    Some(value) => {
+                        state.prop_store(*task_key, name_key, value)?;
+                    }
+                    None => {
+                        if let Some(prop_key) = state.prop_map.remove(&(*task_key, name_key)) {
+                            state.props.remove(prop_key);
+                            state.view_refilter_task(*task_key);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(Some(StateEvent::PropsUpdate))
+    }
+}
+
 impl State {
     /// Create a new state. This should be (mostly) used internally, use init_test() or init() for regular applications.
     pub fn new() -> (State, Receiver<MidEvent>) {
@@ -333,11 +828,27 @@ impl State {
                 tasks: Default::default(),
                 prop_names: Default::default(),
                 prop_name_map: Default::default(),
+                prop_schema: Default::default(),
                 prop_map: Default::default(),
                 props: Default::default(),
+                globals: Default::default(),
                 scripts: Default::default(),
                 views_map: Default::default(),
                 views: Default::default(),
+                search_results: Default::default(),
+                search_req_id: Default::default(),
+                notifications: Default::default(),
+                notification_cursor: Default::default(),
+                notification_poll_pending: Default::default(),
+                comments: Default::default(),
+                user: Default::default(),
+                attachments: Default::default(),
+                tags: Default::default(),
+                task_tags: Default::default(),
+                prop_requests: Default::default(),
+                prop_req_id: Default::default(),
+                aggregate_requests: Default::default(),
+                aggregate_req_id: Default::default(),
                 url: Default::default(),
                 status: Default::default(),
                 mid_event_sender,
@@ -411,6 +922,107 @@ impl State {
     }
 }
 
+/// a task and its locally stored properties, so view filters can be evaluated in the middleware
+struct TaskFilterView<'a> {
+    state: &'a State,
+    key: TaskKey,
+    task: &'a Task,
+}
+impl FilterTarget for TaskFilterView<'_> {
+    fn title(&self) -> &str {
+        &self.task.name
+    }
+    fn completed(&self) -> bool {
+        self.task.completed
+    }
+    fn last_edited(&self) -> chrono::NaiveDateTime {
+        self.task.last_edited
+    }
+    fn prop(&self, name: &str) -> Option<&TaskPropVariant> {
+        let name_key = self.state.prop_name_map.get(name)?;
+        let prop_key = self.state.prop_map.get(&(self.key, *name_key))?;
+        self.state.props.get(*prop_key)
+    }
+    fn global(&self, name: &str) -> Option<&TaskPropVariant> {
+        self.state.global_get(name)
+    }
+}
+
+impl State {
+    /// drop references to a task deleted from the database, the server drops them too
+    fn task_refs_drop(&mut self, db_id: TaskID) {
+        let mut dangling = vec![];
+        let mut changed = vec![];
+        for (&(task_key, name_key), &prop_key) in self.prop_map.iter() {
+            match self.props.get_mut(prop_key) {
+                Some(TaskPropVariant::TaskRef(id)) if *id == db_id => {
+                    dangling.push((task_key, name_key))
+                }
+                Some(TaskPropVariant::TaskRefs(ids)) if ids.contains(&db_id) => {
+                    ids.retain(|id| *id != db_id);
+                    changed.push(task_key);
+                }
+                _ => {}
+            }
+        }
+        for key in dangling {
+            if let Some(prop_key) = self.prop_map.remove(&key) {
+                self.props.remove(prop_key);
+            }
+            changed.push(key.0);
+        }
+        for task_key in changed {
+            self.view_refilter_task(task_key);
+        }
+    }
+    /// re-evaluate view filters against a locally modified task so views reflect the edit without a round-trip.
+    /// Views that haven't been fetched yet are skipped, notifies the UI if any view gained or lost the task.
+    fn view_refilter_task(&mut self, task_key: TaskKey) {
+        let Some(task) = self.tasks.get(task_key) else {
+            return;
+        };
+        let target = TaskFilterView {
+            state: self,
+            key: task_key,
+            task,
+        };
+        let changes = self
+            .views
+            .iter()
+            .filter_map(|(view_key, view)| {
+                let tasks = view.tasks.as_ref()?;
+                match view.filter.matches(&target) {
+                    Ok(matches) => {
+                        (matches != tasks.contains(&task_key)).then_some((view_key, matches))
+                    }
+                    Err(err) => {
+                        tracing::debug!("can't evaluate filter of view {view_key:?}: {err}");
+                        None
+                    }
+                }
+            })
+            .collect::<Vec<(ViewKey, bool)>>();
+        for (view_key, matches) in changes.iter() {
+            let view = &mut self.views[*view_key];
+            // the task is shown right away, but only the server knows where it goes among sorted tasks
+            // and how the pages after it shift
+            view.stale |= !view.sort.is_empty() || view.more_tasks || view.max_tasks.is_some();
+            if let Some(tasks) = view.tasks.as_mut() {
+                if *matches {
+                    tasks.push(task_key);
+                } else {
+                    tasks.retain(|k| *k != task_key);
+                }
+            }
+        }
+        if !changes.is_empty() {
+            self.mid_event_sender
+                .try_send(MidEvent::StateEvent(StateEvent::ViewsUpdate))
+                .expect("failed to send client event");
+        }
+    }
+}
+
 #[derive(Debug, Error, Clone)]
 #[error("task: task associated with key {0:?} does not exist")]
 pub struct NoTaskError(TaskKey);
@@ -425,6 +1037,9 @@ pub enum ModifyTaskError {
     NoTask(#[from] NoTaskError),
     #[error(transparent)]
     UnsyncronizedTask(#[from] UnsyncronizedTaskError),
+    /// The task would become a subtask of itself
+    #[error("task: task associated with key {0:?} can't be a subtask of itself")]
+    Hierarchy(TaskKey),
 }
 
 #[derive(Debug, Error, Clone)]
@@ -453,6 +1068,10 @@ impl State {
     pub fn task_get(&self, key: TaskKey) -> Result<&Task, NoTaskError> {
         self.tasks.get(key).ok_or(NoTaskError(key))
     }
+    /// get the key of a task stored in the database, if it has been loaded
+    pub fn task_key(&self, task_id: TaskID) -> Option<TaskKey> {
+        self.task_map.get(&task_id).copied()
+    }
     /// modify a task
     pub fn task_mod(
         &mut self,
@@ -470,6 +1089,7 @@ impl State {
                 // if changed, store old task version
                 if (name.is_some() || completed.is_some()) {
                     task.current_rollback = Some(Box::new(bef));
+                    task.last_edited = chrono::Utc::now().naive_utc();
                 }
                 if let Some(db_id) = task.db_id {
                     self.spawn_request::<UpdateTaskRequest, UpdateTaskResponse>(
@@ -484,11 +1104,13 @@ impl State {
                             deps_to_remove: vec![],
                             scripts_to_add: vec![],
                             scripts_to_remove: vec![],
+                            parent: None,
                             req_id: key.0.as_ffi(),
                         },
                         RevertError::Task(key),
                     );
                 }
+                self.view_refilter_task(key);
                 Ok(())
             } else {
                 Err(UnsyncronizedTaskError(key).into())
@@ -497,6 +1119,80 @@ impl State {
             Err(NoTaskError(key).into())
         }
     }
+    /// make a task a subtask of `parent` at `position` among its subtasks (last if past the end), or top-level if None.
+    /// the hierarchy is updated locally and sent to the server, which cascades completion to the new parent
+    pub fn task_set_parent(
+        &mut self,
+        key: TaskKey,
+        parent: Option<TaskKey>,
+        position: usize,
+    ) -> Result<(), ModifyTaskError> {
+        let task = self.tasks.get(key).ok_or(NoTaskError(key))?;
+        let Some(db_id) = task.db_id.filter(|_| task.current_rollback.is_none()) else {
+            return Err(UnsyncronizedTaskError(key).into());
+        };
+        let old_parent = task.parent;
+        let change = match parent {
+            Some(parent_key) => {
+                // walk up from the new parent to make sure the task isn't one of its ancestors
+                let mut ancestor = Some(parent_key);
+                while let Some(ancestor_key) = ancestor {
+                    if ancestor_key == key {
+                        return Err(ModifyTaskError::Hierarchy(key));
+                    }
+                    ancestor = self.tasks.get(ancestor_key).and_then(|t| t.parent);
+                }
+                let parent_task = self.tasks.get(parent_key).ok_or(NoTaskError(parent_key))?;
+                let parent_id = parent_task
+                    .db_id
+                    .ok_or(UnsyncronizedTaskError(parent_key))?;
+                ParentChange::Set {
+                    parent: parent_id,
+                    position: u32::try_from(position).unwrap_or(u32::MAX),
+                }
+            }
+            None => ParentChange::Remove,
+        };
+        // the task and both parents are pending until the server confirms the move
+        let mut affected = vec![key];
+        affected.extend(old_parent);
+        affected.extend(parent.filter(|p| Some(*p) != old_parent));
+        for affected_key in affected.iter() {
+            if let Some(task) = self.tasks.get_mut(*affected_key) {
+                if task.current_rollback.is_none() {
+                    task.current_rollback = Some(Box::new(task.clone()));
+                }
+            }
+        }
+        if let Some(old_parent) = old_parent.and_then(|k| self.tasks.get_mut(k)) {
+            old_parent.subtasks.retain(|k| *k != key);
+        }
+        if let Some(new_parent) = parent.and_then(|k| self.tasks.get_mut(k)) {
+            let position = position.min(new_parent.subtasks.len());
+            new_parent.subtasks.insert(position, key);
+        }
+        if let Some(task) = self.tasks.get_mut(key) {
+            task.parent = parent;
+        }
+        self.spawn_request::<UpdateTaskRequest, UpdateTaskResponse>(
+            self.client.put(format!("{}/task", self.url)),
+            UpdateTaskRequest {
+                task_id: db_id,
+                name: None,
+                checked: None,
+                props_to_add: vec![],
+                props_to_remove: vec![],
+                deps_to_add: vec![],
+                deps_to_remove: vec![],
+                scripts_to_add: vec![],
+                scripts_to_remove: vec![],
+                parent: Some(change),
+                req_id: key.0.as_ffi(),
+            },
+            RevertError::Tasks(affected),
+        );
+        Ok(())
+    }
     /// delete a task
     pub fn task_rm(&mut self, key: TaskKey) -> Result<(), NoTaskError> {
         if let Some(task) = self.tasks.get_mut(key) {
@@ -536,15 +1232,23 @@ impl State {
 
         let prop_key = self.props.insert(prop);
         self.prop_map.insert((task_key, name_key), prop_key);
+        self.view_refilter_task(task_key);
         Ok(prop_key)
     }
-    /// define a property name
+    /// define a property name, returns the existing key if the name is already defined
     pub fn prop_def_name(&mut self, name: impl Into<String>) -> PropNameKey {
         let name: String = name.into();
+        if let Some(key) = self.prop_name_map.get(&name) {
+            return *key;
+        }
         let key = self.prop_names.insert(name.clone());
         self.prop_name_map.insert(name, key);
         key
     }
+    /// look up the key of a defined property name
+    pub fn prop_name_key(&self, name: &str) -> Option<PropNameKey> {
+        self.prop_name_map.get(name).copied()
+    }
     /// delete a property name
     pub fn prop_rm_name(&mut self, name_key: PropNameKey) -> Result<String, PropDataError> {
         let name = self
@@ -552,8 +1256,90 @@ impl State {
             .remove(name_key)
             .ok_or(PropDataError::PropertyName(name_key))?;
         self.prop_name_map.remove(&name);
+        self.prop_schema.remove(&name_key);
         Ok(name)
     }
+    /// get the workspace definition of a property name, if it has one
+    pub fn prop_definition(&self, name_key: PropNameKey) -> Option<&PropertyDefinition> {
+        self.prop_schema.get(&name_key)
+    }
+    /// get every property definition of the workspace, ordered by name
+    pub fn prop_definitions(&self) -> Vec<(PropNameKey, &PropertyDefinition)> {
+        let mut definitions = self
+            .prop_schema
+            .iter()
+            .map(|(key, definition)| (*key, definition))
+            .collect::<Vec<_>>();
+        definitions.sort_by(|(_, a), (_, b)| a.name.cmp(&b.name));
+        definitions
+    }
+    /// get the value of a global (workspace-wide) property, this is how scripts read them
+    pub fn global_get(&self, name: &str) -> Option<&TaskPropVariant> {
+        self.globals.get(name)
+    }
+    /// set the value of a property on a task, defining it if the task doesn't have it, and send it to the server.
+    /// the value must fit the property's definition, if it has one.
+    pub fn prop_set(
+        &mut self,
+        task_key: TaskKey,
+        name_key: PropNameKey,
+        value: TaskPropVariant,
+    ) -> Result<(), PropDataError> {
+        let Some(task) = self.tasks.get(task_key) else {
+            return Err(PropDataError::Task(task_key));
+        };
+        let Some(name) = self.prop_names.get(name_key) else {
+            return Err(PropDataError::PropertyName(name_key));
+        };
+        if let Some(definition) = self.prop_schema.get(&name_key) {
+            definition
+                .check_value(&value)
+                .map_err(PropDataError::Schema)?;
+        }
+        if let Some(db_id) = task.db_id {
+            self.spawn_request::<UpdateTaskRequest, UpdateTaskResponse>(
+                self.client.put(format!("{}/task", self.url)),
+                UpdateTaskRequest {
+                    task_id: db_id,
+                    name: None,
+                    checked: None,
+                    props_to_add: vec![TaskProp {
+                        name: name.clone(),
+                        value: value.clone(),
+                    }],
+                    props_to_remove: vec![],
+                    deps_to_add: vec![],
+                    deps_to_remove: vec![],
+                    scripts_to_add: vec![],
+                    scripts_to_remove: vec![],
+                    parent: None,
+                    req_id: task_key.0.as_ffi(),
+                },
+                RevertError::Task(task_key),
+            );
+        }
+        // the server stamps the edit too, stamp it here so local filters see it right away
+        self.tasks[task_key].last_edited = chrono::Utc::now().naive_utc();
+        self.prop_store(task_key, name_key, value)
+    }
+    // set the value of a property locally, defining it if the task doesn't have it
+    fn prop_store(
+        &mut self,
+        task_key: TaskKey,
+        name_key: PropNameKey,
+        value: TaskPropVariant,
+    ) -> Result<(), PropDataError> {
+        match self.prop_map.get(&(task_key, name_key)) {
+            Some(prop_key) => {
+                self.props[*prop_key] = value;
+                self.view_refilter_task(task_key);
+            }
+            None => {
+                self.prop_def(task_key, name_key, value)?;
+            }
+        }
+        Ok(())
+    }
     /// get a property
     pub fn prop_get(
         &self,
@@ -573,7 +1359,25 @@ impl State {
             .ok_or(PropDataError::Prop(task_key, name_key))?;
         Ok(&self.props[*key])
     }
-    /// modify a property
+    /// resolve the tasks a property references to their keys, referenced tasks that aren't loaded are skipped.
+    /// properties that aren't task references reference nothing.
+    pub fn prop_task_refs(
+        &self,
+        task_key: TaskKey,
+        name_key: PropNameKey,
+    ) -> Result<Vec<TaskKey>, PropDataError> {
+        let ids = match self.prop_get(task_key, name_key)? {
+            TaskPropVariant::TaskRef(id) => std::slice::from_ref(id),
+            TaskPropVariant::TaskRefs(ids) => ids.as_slice(),
+            _ => &[],
+        };
+        Ok(ids
+            .iter()
+            .filter_map(|id| self.task_map.get(id).copied())
+            .collect())
+    }
+    /// modify a property and send the new value to the server.
+    /// the edit is undone if the new value doesn't fit the property's definition.
     pub fn prop_mod(
         &mut self,
         task_key: TaskKey,
@@ -591,12 +1395,13 @@ impl State {
             .prop_map
             .get(&(task_key, name_key))
             .ok_or(PropDataError::Prop(task_key, name_key))?;
-        edit_fn(
-            self.props
-                .get_mut(*key)
-                .ok_or(PropDataError::Prop(task_key, name_key))?,
-        );
-        Ok(())
+        let mut value = self
+            .props
+            .get(*key)
+            .ok_or(PropDataError::Prop(task_key, name_key))?
+            .clone();
+        edit_fn(&mut value);
+        self.prop_set(task_key, name_key, value)
     }
     /// delete a property
     pub fn prop_rm(
@@ -615,9 +1420,12 @@ impl State {
             .prop_map
             .remove(&(task_key, name_key))
             .ok_or(PropDataError::Prop(task_key, name_key))?;
-        self.props
+        let prop = self
+            .props
             .remove(key)
-            .ok_or(PropDataError::Prop(task_key, name_key))
+            .ok_or(PropDataError::Prop(task_key, name_key))?;
+        self.view_refilter_task(task_key);
+        Ok(prop)
     }
     /// define a view
     pub fn view_def(&mut self, view: View) -> ViewKey {
@@ -650,16 +1458,471 @@ impl State {
         self.view_task_keys(view_key)
             .map(|tks| tks.flat_map(|key| self.task_get(key).ok().map(|t| (key, t))))
     }
-    /// modify a view
-    pub fn view_mod(&mut self, view_key: ViewKey, edit_fn: impl FnOnce(&mut View)) -> Option<()> {
-        edit_fn(self.views.get_mut(view_key)?);
-        None
-    }
-    /// delete a view
-    pub fn view_rm(&mut self, view_key: ViewKey) {
-        self.views.remove(view_key);
+    /// request the page of tasks starting at `offset` for a view from the server
+    fn view_fetch_page(&mut self, view_key: ViewKey, offset: u64) -> Option<()> {
+        self.view_fetch_tasks(view_key, offset, VIEW_PAGE_SIZE)
     }
-    /// create a script
+    /// request up to `count` tasks starting at `offset` for a view from the server
+    fn view_fetch_tasks(&mut self, view_key: ViewKey, offset: u64, count: u64) -> Option<()> {
+        let view = self.views.get_mut(view_key)?;
+        let limit = match view.max_tasks {
+            Some(max_tasks) => count.min(max_tasks.saturating_sub(offset)),
+            None => count,
+        };
+        view.pending_page = true;
+        let req = FilterRequest {
+            filter: view.filter.clone(),
+            sort: view.sort.clone(),
+            limit: Some(limit),
+            offset,
+            req_id: view_key.0.as_ffi(),
+        };
+        self.spawn_request::<FilterRequest, FilterResponse>(
+            self.client.get(format!("{}/filter", self.url)),
+            req,
+            RevertError::Filter(view_key),
+        );
+        Some(())
+    }
+    /// request the next page of tasks for a view if the server has more and none is being requested already.
+    /// returns whether a request was sent
+    pub fn view_load_more(&mut self, view_key: ViewKey) -> bool {
+        let Some(view) = self.views.get(view_key) else {
+            return false;
+        };
+        if !view.more_tasks || view.pending_page {
+            return false;
+        }
+        let offset = view.server_offset;
+        self.view_fetch_page(view_key, offset).is_some()
+    }
+    /// fetch every page loaded so far again for the views that local edits made stale.
+    /// called once the server has answered an edit, so the pages reflect it
+    fn view_refetch_stale(&mut self) {
+        let stale = self
+            .views
+            .iter_mut()
+            .filter(|(_, view)| view.stale)
+            .map(|(view_key, view)| {
+                view.stale = false;
+                (view_key, view.server_offset.max(VIEW_PAGE_SIZE))
+            })
+            .collect::<Vec<(ViewKey, u64)>>();
+        for (view_key, count) in stale {
+            self.view_fetch_tasks(view_key, 0, count);
+        }
+    }
+    /// request the values of a property for the loaded tasks of a view from the server,
+    /// they are received asynchronously. returns whether a request was sent
+    pub fn view_fetch_prop(&mut self, view_key: ViewKey, name_key: PropNameKey) -> bool {
+        let Some(name) = self.prop_names.get(name_key).cloned() else {
+            return false;
+        };
+        let Some(task_keys) = self.view_task_keys(view_key) else {
+            return false;
+        };
+        let (task_keys, task_ids): (Vec<TaskKey>, Vec<TaskID>) = task_keys
+            .filter_map(|key| Some((key, self.tasks.get(key)?.db_id?)))
+            .unzip();
+        if task_ids.is_empty() {
+            return false;
+        }
+        self.prop_req_id += 1;
+        let req = PropertiesRequest {
+            task_ids,
+            properties: vec![name],
+            req_id: self.prop_req_id,
+        };
+        self.prop_requests.insert(self.prop_req_id, task_keys);
+        self.spawn_request::<PropertiesRequest, PropertiesResponse>(
+            self.client.get(format!("{}/props", self.url)),
+            req,
+            RevertError::Props(self.prop_req_id),
+        );
+        true
+    }
+    /// request the groups of all tasks matching a view and their summaries from the server,
+    /// they are received asynchronously. returns whether a request was sent
+    pub fn view_aggregate(&mut self, view_key: ViewKey) -> bool {
+        let Some(view) = self.views.get(view_key) else {
+            return false;
+        };
+        let (task_keys, task_ids): (Vec<TaskKey>, Vec<TaskID>) = view
+            .tasks
+            .iter()
+            .flatten()
+            .filter_map(|key| Some((*key, self.tasks.get(*key)?.db_id?)))
+            .unzip();
+        self.aggregate_req_id += 1;
+        let req = AggregateRequest {
+            filter: view.filter.clone(),
+            group_by: view.group_by.clone(),
+            summaries: view.summaries.clone(),
+            task_ids,
+            req_id: self.aggregate_req_id,
+        };
+        self.aggregate_requests
+            .insert(self.aggregate_req_id, (view_key, task_keys));
+        self.spawn_request::<AggregateRequest, AggregateResponse>(
+            self.client.get(format!("{}/aggregate", self.url)),
+            req,
+            RevertError::Aggregate(self.aggregate_req_id),
+        );
+        true
+    }
+    /// modify a view
+    pub fn view_mod(&mut self, view_key: ViewKey, edit_fn: impl FnOnce(&mut View)) -> Option<()> {
+        edit_fn(self.views.get_mut(view_key)?);
+        None
+    }
+    /// move a task of a view right before or after another of its tasks, putting the view in manual order.
+    /// the move is sent to the server, if it is rejected the order of the view is fetched again.
+    /// returns whether the task was moved
+    pub fn view_move_task(
+        &mut self,
+        view_key: ViewKey,
+        key: TaskKey,
+        neighbour: TaskKey,
+        after: bool,
+    ) -> bool {
+        let Some(view) = self.views.get(view_key) else {
+            return false;
+        };
+        let (Some(view_id), Some(tasks)) = (view.db_id, view.tasks.as_ref()) else {
+            return false;
+        };
+        if key == neighbour || !tasks.contains(&key) {
+            return false;
+        }
+        let mut tasks = tasks.clone();
+        tasks.retain(|k| *k != key);
+        let Some(index) = tasks.iter().position(|k| *k == neighbour) else {
+            return false;
+        };
+        let index = index + usize::from(after);
+        tasks.insert(index, key);
+        // the server places the task by its new neighbours, which it has to know about
+        let db_id = |key: Option<TaskKey>| match key {
+            Some(key) => self.tasks.get(key).and_then(|task| task.db_id).map(Some),
+            None => Some(None),
+        };
+        let before = tasks.get(index + 1).copied();
+        let after = index.checked_sub(1).map(|i| tasks[i]);
+        let (Some(Some(task_id)), Some(after), Some(before)) =
+            (db_id(Some(key)), db_id(after), db_id(before))
+        else {
+            return false;
+        };
+        let manual = SortKey {
+            field: SortField::Rank(view_id),
+            descending: false,
+            nulls_first: false,
+        };
+        let view = &mut self.views[view_key];
+        view.tasks = Some(tasks);
+        if view.sort.first() != Some(&manual) {
+            view.sort.retain(|key| key.field != manual.field);
+            view.sort.insert(0, manual);
+        }
+        self.spawn_request::<MoveTaskRequest, MoveTaskResponse>(
+            self.client.put(format!("{}/rank", self.url)),
+            MoveTaskRequest {
+                view_id,
+                task_id,
+                after,
+                before,
+                req_id: view_key.0.as_ffi(),
+            },
+            RevertError::Rank(view_key),
+        );
+        true
+    }
+    /// delete a view
+    pub fn view_rm(&mut self, view_key: ViewKey) {
+        self.views.remove(view_key);
+    }
+    /// full-text search task titles and string properties on the server, results are received asynchronously.
+    /// an empty query clears the results
+    pub fn search(&mut self, query: &str) {
+        self.search_req_id += 1;
+        if query.trim().is_empty() {
+            self.search_results.clear();
+            return;
+        }
+        self.spawn_request::<SearchRequest, SearchResponse>(
+            self.client.get(format!("{}/search", self.url)),
+            SearchRequest {
+                query: query.to_owned(),
+                limit: Some(SEARCH_LIMIT),
+                req_id: self.search_req_id,
+            },
+            RevertError::Search,
+        );
+    }
+    /// get results of the latest search
+    pub fn search_results(&self) -> &[(TaskKey, SearchResult)] {
+        &self.search_results
+    }
+    /// ask the server for notifications fired since the last poll, they are received asynchronously.
+    /// returns whether a request was sent, only one poll is sent at a time
+    pub fn notifications_poll(&mut self) -> bool {
+        if self.notification_poll_pending {
+            return false;
+        }
+        self.notification_poll_pending = true;
+        self.spawn_request::<GetNotificationsRequest, GetNotificationsResponse>(
+            self.client.get(format!("{}/notifications", self.url)),
+            GetNotificationsRequest {
+                after: self.notification_cursor,
+                limit: Some(NOTIFICATION_LIMIT),
+                req_id: 0,
+            },
+            RevertError::Notifications,
+        );
+        true
+    }
+    /// get received notifications and the tasks they are about, oldest first
+    pub fn notifications(&self) -> &[(Option<TaskKey>, Notification)] {
+        &self.notifications
+    }
+    /// dismiss every received notification, later polls only return newer ones
+    pub fn notifications_clear(&mut self) {
+        self.notifications.clear();
+    }
+    /// request the comments on a task from the server, they are received asynchronously.
+    /// returns whether a request was sent, tasks only stored locally have no comments
+    pub fn comments_fetch(&mut self, key: TaskKey) -> bool {
+        let Some(task_id) = self.tasks.get(key).and_then(|task| task.db_id) else {
+            return false;
+        };
+        self.spawn_request::<GetCommentsRequest, GetCommentsResponse>(
+            self.client.get(format!("{}/comments", self.url)),
+            GetCommentsRequest { task_id, req_id: 0 },
+            RevertError::Comments,
+        );
+        true
+    }
+    /// get the comments on a task, oldest first. None if they weren't received yet
+    pub fn comments(&self, key: TaskKey) -> Option<&[Comment]> {
+        self.comments.get(&key).map(Vec::as_slice)
+    }
+    /// post a comment on a task as the current user, it's added to the thread once the server stores it.
+    /// returns whether a request was sent
+    pub fn comment_add(&mut self, key: TaskKey, body: String) -> bool {
+        let Some(task_id) = self.tasks.get(key).and_then(|task| task.db_id) else {
+            return false;
+        };
+        if body.trim().is_empty() {
+            return false;
+        }
+        self.spawn_request::<CreateCommentRequest, CreateCommentResponse>(
+            self.client.post(format!("{}/comment", self.url)),
+            CreateCommentRequest {
+                task_id,
+                author: self.user.clone(),
+                body,
+                req_id: 0,
+            },
+            RevertError::Comments,
+        );
+        true
+    }
+    /// delete a comment, it's removed from the thread once the server confirms
+    pub fn comment_rm(&mut self, comment_id: CommentID) {
+        self.spawn_request::<DeleteCommentRequest, DeleteCommentResponse>(
+            self.client.delete(format!("{}/comment", self.url)),
+            DeleteCommentRequest {
+                comment_id,
+                req_id: 0,
+            },
+            RevertError::Comments,
+        );
+    }
+    /// user name comments are posted under
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+    /// request the files attached to a task from the server, they are received asynchronously.
+    /// returns whether a request was sent, tasks only stored locally have no attachments
+    pub fn attachments_fetch(&mut self, key: TaskKey) -> bool {
+        let Some(task_id) = self.tasks.get(key).and_then(|task| task.db_id) else {
+            return false;
+        };
+        self.spawn_request::<GetAttachmentsRequest, GetAttachmentsResponse>(
+            self.client.get(format!("{}/attachments", self.url)),
+            GetAttachmentsRequest { task_id, req_id: 0 },
+            RevertError::Attachments,
+        );
+        true
+    }
+    /// get the files attached to a task, oldest first. None if they weren't received yet
+    pub fn attachments(&self, key: TaskKey) -> Option<&[Attachment]> {
+        self.attachments.get(&key).map(Vec::as_slice)
+    }
+    /// request every tag of the workspace from the server, they are received asynchronously
+    pub fn tags_fetch(&mut self) {
+        self.spawn_request::<GetTagsRequest, GetTagsResponse>(
+            self.client.get(format!("{}/tags", self.url)),
+            0,
+            RevertError::Tags,
+        );
+    }
+    /// every tag of the workspace, in no particular order
+    pub fn tags(&self) -> impl Iterator<Item = &Tag> {
+        self.tags.values()
+    }
+    /// get a tag
+    pub fn tag(&self, tag_id: TagID) -> Option<&Tag> {
+        self.tags.get(&tag_id)
+    }
+    /// a tag and every tag nested under it, at any depth
+    pub fn tag_descendants(&self, tag_id: TagID) -> Vec<TagID> {
+        let mut found = vec![tag_id];
+        let mut index = 0;
+        while let Some(parent) = found.get(index).copied() {
+            found.extend(
+                self.tags
+                    .values()
+                    .filter(|tag| tag.parent == Some(parent))
+                    .map(|tag| tag.id),
+            );
+            index += 1;
+        }
+        found
+    }
+    /// create a tag nested under `parent`, or a top-level tag. it's added once the server stores it.
+    /// returns whether a request was sent
+    pub fn tag_create(&mut self, name: String, parent: Option<TagID>, color: String) -> bool {
+        if name.trim().is_empty() {
+            return false;
+        }
+        self.spawn_request::<CreateTagRequest, CreateTagResponse>(
+            self.client.post(format!("{}/tag", self.url)),
+            CreateTagRequest {
+                name,
+                parent,
+                color,
+                req_id: 0,
+            },
+            RevertError::Tags,
+        );
+        true
+    }
+    /// delete a tag and the tags nested under it, they're removed once the server confirms
+    pub fn tag_rm(&mut self, tag_id: TagID) {
+        self.spawn_request::<DeleteTagRequest, DeleteTagResponse>(
+            self.client.delete(format!("{}/tag", self.url)),
+            DeleteTagRequest { tag_id, req_id: 0 },
+            RevertError::Tags,
+        );
+    }
+    /// get the tags of a task in id order. None if they weren't received yet
+    pub fn task_tags(&self, key: TaskKey) -> Option<&[TagID]> {
+        self.task_tags.get(&key).map(Vec::as_slice)
+    }
+    /// request the tags of the loaded tasks of a view whose tags weren't received yet,
+    /// they are received asynchronously. returns whether a request was sent
+    pub fn view_fetch_tags(&mut self, view_key: ViewKey) -> bool {
+        let Some(task_keys) = self.view_task_keys(view_key) else {
+            return false;
+        };
+        let (task_keys, task_ids): (Vec<TaskKey>, Vec<TaskID>) = task_keys
+            .filter(|key| !self.task_tags.contains_key(key))
+            .filter_map(|key| Some((key, self.tasks.get(key)?.db_id?)))
+            .unzip();
+        if task_ids.is_empty() {
+            return false;
+        }
+        self.spawn_request::<GetTaskTagsRequest, GetTaskTagsResponse>(
+            self.client.get(format!("{}/task_tags", self.url)),
+            GetTaskTagsRequest {
+                task_ids,
+                req_id: 0,
+            },
+            RevertError::TaskTags(task_keys),
+        );
+        true
+    }
+    /// tag a task with a tag it doesn't have, or untag it, once its tags were received. the change is shown
+    /// right away and sent to the server, if the server rejects it the tags of the task are fetched again.
+    /// returns whether the tags changed
+    pub fn task_tag_toggle(&mut self, key: TaskKey, tag_id: TagID) -> bool {
+        let Some(task_id) = self.tasks.get(key).and_then(|task| task.db_id) else {
+            return false;
+        };
+        if !self.tags.contains_key(&tag_id) {
+            return false;
+        }
+        // toggling before the tags were received would drop the ones we don't know about
+        let Some(tags) = self.task_tags.get_mut(&key) else {
+            return false;
+        };
+        match tags.binary_search(&tag_id) {
+            Ok(index) => {
+                tags.remove(index);
+            }
+            Err(index) => tags.insert(index, tag_id),
+        }
+        let req = SetTaskTagsRequest {
+            task_id,
+            tags: tags.clone(),
+            req_id: 0,
+        };
+        self.spawn_request::<SetTaskTagsRequest, SetTaskTagsResponse>(
+            self.client.put(format!("{}/task_tags", self.url)),
+            req,
+            RevertError::TaskTags(vec![key]),
+        );
+        true
+    }
+    /// download an attachment into a file at path, replacing it. failures are logged
+    pub fn attachment_save(
+        &self,
+        attachment_id: AttachmentID,
+        path: PathBuf,
+    ) -> JoinHandle<color_eyre::Result<()>> {
+        let download = download_attachment(self.client.clone(), self.url.clone(), attachment_id);
+        tokio::spawn(async move {
+            let res = async {
+                let content = download.await?;
+                tokio::fs::write(&path, content)
+                    .await
+                    .wrap_err_with(|| format!("couldn't write {}", path.display()))
+            }
+            .await;
+            if let Err(err) = &res {
+                tracing::error!("couldn't save attachment {attachment_id}: {err:#}");
+            }
+            res
+        })
+    }
+    /// download an attachment into the temporary directory and open it with `$OPENER`,
+    /// or `xdg-open` if it isn't set. failures are logged
+    pub fn attachment_open(&self, attachment: &Attachment) -> JoinHandle<color_eyre::Result<()>> {
+        // the server strips directories from names, but don't trust it to write outside the temporary directory
+        let name = Path::new(&attachment.name)
+            .file_name()
+            .map_or_else(|| "attachment".into(), |name| name.to_string_lossy());
+        let path = std::env::temp_dir().join(format!("abn-{}-{}", attachment.id, name));
+        let save = self.attachment_save(attachment.id, path.clone());
+        tokio::spawn(async move {
+            save.await??;
+            let opener = std::env::var("OPENER").unwrap_or_else(|_| "xdg-open".to_owned());
+            let res = tokio::process::Command::new(&opener)
+                .arg(&path)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .map(drop)
+                .wrap_err_with(|| format!("couldn't run {opener}"));
+            if let Err(err) = &res {
+                tracing::error!("couldn't open {}: {err:#}", path.display());
+            }
+            res
+        })
+    }
+    /// create a script
     pub fn script_create(&mut self) -> ScriptID {
         self.scripts.insert(0, Script::default());
         0
@@ -703,6 +1966,21 @@ where
     Ok(res)
 }
 
+/// content of an attachment, as sent by the server
+async fn download_attachment(
+    client: ClientWithMiddleware,
+    url: String,
+    attachment_id: AttachmentID,
+) -> color_eyre::Result<Vec<u8>> {
+    let res = client
+        .get(format!("{url}/attachment"))
+        .query(&DownloadAttachmentQuery { attachment_id })
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(res.bytes().await?.to_vec())
+}
+
 /// Init middleware state
 /// This function is called by UI to create the Middleware state and establish a connection to the Database.
 /// Important: Make sure `url` does not contain a trailing `/`
@@ -710,6 +1988,7 @@ where
 pub fn init(url: &str) -> color_eyre::Result<(State, Receiver<MidEvent>)> {
     let (mut state, mut receiver) = State::new();
     url.clone_into(&mut state.url);
+    state.user = std::env::var("USER").unwrap_or_else(|_| "anonymous".to_owned());
 
     let view_key = state.view_def(View {
         name: "Main View".to_string(),
@@ -717,15 +1996,22 @@ pub fn init(url: &str) -> color_eyre::Result<(State, Receiver<MidEvent>)> {
         ..View::default()
     });
 
-    // request all tasks using a "None" filter into the default "Main View"
-    state.spawn_request::<FilterRequest, FilterResponse>(
-        state.client.get(format!("{url}/filter")),
-        FilterRequest {
-            filter: Filter::None,
-            req_id: view_key.0.as_ffi(),
-        },
-        RevertError::Filter(view_key),
+    // request the first page of all tasks using a "None" filter into the default "Main View"
+    state.view_fetch_page(view_key, 0);
+    // property names come from the workspace schema
+    state.spawn_request::<GetSchemaRequest, GetSchemaResponse>(
+        state.client.get(format!("{}/schema", state.url)),
+        0,
+        RevertError::Schema,
     );
+    // global properties for filters and scripts
+    state.spawn_request::<GetGlobalsRequest, GetGlobalsResponse>(
+        state.client.get(format!("{}/globals", state.url)),
+        0,
+        RevertError::Globals,
+    );
+    // tags are shown next to tasks and picked from the whole tree
+    state.tags_fetch();
 
     Ok((state, receiver))
 }
@@ -756,7 +2042,8 @@ pub(crate) mod tests {
     pub use super::*;
     use chrono::{NaiveDate, NaiveDateTime};
     use common::backend::{
-        DeleteTasksRequest, DeleteTasksResponse, FilterResponse, ReadTaskShortResponse,
+        DeleteTasksRequest, DeleteTasksResponse, FilterResponse, GroupKey, NotificationKind,
+        ReadTaskShortResponse, TaskPropColumn,
     };
     use mockito::{Matcher, Server, ServerGuard};
     use serde_json::{to_value, to_vec};
@@ -773,6 +2060,8 @@ pub(crate) mod tests {
                     serde_json::from_slice::<FilterRequest>(req.body().unwrap()).unwrap();
                 to_vec(&FilterResponse {
                     tasks: vec![0, 1],
+                    offset: req.offset,
+                    more: false,
                     req_id: req.req_id,
                 })
                 .unwrap()
@@ -805,6 +2094,50 @@ pub(crate) mod tests {
             .create_async()
             .await;
 
+        server
+            .mock("GET", "/schema")
+            .with_body(
+                to_vec(&GetSchemaResponse {
+                    definitions: vec![PropertyDefinition {
+                        name: "status".into(),
+                        typ: TaskPropType::String,
+                        description: "".into(),
+                        default: None,
+                        required: false,
+                        allowed_values: vec![],
+                        options: vec![],
+                        formula: None,
+                    }],
+                    req_id: 0,
+                })
+                .unwrap(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        server
+            .mock("GET", "/globals")
+            .with_body(
+                to_vec(&GetGlobalsResponse {
+                    globals: vec![
+                        TaskProp {
+                            name: "phase".into(),
+                            value: TaskPropVariant::String("todo".into()),
+                        },
+                        TaskProp {
+                            name: "wip limit".into(),
+                            value: TaskPropVariant::Number(2.0),
+                        },
+                    ],
+                    req_id: 0,
+                })
+                .unwrap(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
         server
             .mock("POST", "/task")
             .with_body_from_request(|req| {
@@ -886,6 +2219,9 @@ pub(crate) mod tests {
             client.get("localhost:1234/cantconnect"),
             FilterRequest {
                 filter: Filter::None,
+                sort: vec![],
+                limit: None,
+                offset: 0,
                 req_id: 0,
             },
         )
@@ -896,6 +2232,9 @@ pub(crate) mod tests {
             client.get(format!("{}/shouldincomplete", server.url())),
             FilterRequest {
                 filter: Filter::None,
+                sort: vec![],
+                limit: None,
+                offset: 0,
                 req_id: 0,
             },
         )
@@ -911,13 +2250,21 @@ pub(crate) mod tests {
 
         // init state
         let (mut state, mut receiver) = init(&url).unwrap();
-        // await server response for FilterRequest
-        state.handle_mid_event(receiver.next().await.unwrap());
-        println!("ui event {:?}", receiver.next().await.unwrap()); // drop UI event
-                                                                   // // await server response for ReadTasksShortResponse (request automatically sent when handle_mid_event is called on FilterResponse)
-                                                                   //dbg!(receiver.next().await.unwrap());
-        state.handle_mid_event(receiver.next().await.unwrap());
-        println!("ui event {:?}", receiver.next().await.unwrap()); // drop UI event
+        // await server responses for the FilterRequest, the GetSchemaRequest, the GetGlobalsRequest (in any order)
+        // and the ReadTasksShortRequest sent when the FilterResponse is handled
+        let (mut responses, mut ui_events) = (0, 0);
+        while responses < 4 || ui_events < 4 {
+            match receiver.next().await.unwrap() {
+                MidEvent::StateEvent(event) => {
+                    println!("ui event {:?}", event); // drop UI event
+                    ui_events += 1;
+                }
+                event => {
+                    state.handle_mid_event(event).unwrap();
+                    responses += 1;
+                }
+            }
+        }
 
         // make sure view was created with correct state
         let view_key = state.view_get_default().unwrap();
@@ -1131,6 +2478,149 @@ pub(crate) mod tests {
         assert!(state.prop_get(tasks[0], name_key).is_err()); // should throw err
     }
 
+    #[tokio::test]
+    async fn test_schema() {
+        let (server, mut state, mut receiver, view_key) = test_init().await;
+
+        // names are populated from the schema on init
+        let name_key = state.prop_def_name("status");
+        assert_eq!(state.prop_names.len(), 1);
+        let definition = state.prop_definition(name_key).unwrap();
+        assert_eq!(definition.typ, TaskPropType::String);
+
+        // a new schema replaces the old definitions, but keeps the names
+        state.handle_mid_event(MidEvent::ServerResponse(Ok(Box::new(GetSchemaResponse {
+            definitions: vec![],
+            req_id: 0,
+        }))));
+        assert!(matches!(
+            get_event(&mut receiver).await,
+            MidEvent::StateEvent(StateEvent::PropsUpdate)
+        ));
+        assert!(state.prop_definition(name_key).is_none());
+        assert_eq!(state.prop_def_name("status"), name_key);
+
+        state.prop_rm_name(name_key).unwrap();
+        assert!(state.prop_definition(name_key).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_globals() {
+        let (server, mut state, mut receiver, view_key) = test_init().await;
+        let task_key = state.view_task_keys(view_key).unwrap().next().unwrap();
+
+        // globals are fetched on init
+        assert_eq!(
+            state.global_get("wip limit"),
+            Some(&TaskPropVariant::Number(2.0))
+        );
+        assert!(state.global_get("sprint end").is_none());
+
+        // view filters can compare task properties against globals
+        let name_key = state.prop_def_name("status");
+        state
+            .prop_set(task_key, name_key, TaskPropVariant::String("todo".into()))
+            .unwrap();
+        let filter = Filter::LeafGlobal {
+            field: "status".into(),
+            comparator: Comparator::EQ,
+            global: "phase".into(),
+        };
+        let target = TaskFilterView {
+            state: &state,
+            key: task_key,
+            task: state.task_get(task_key).unwrap(),
+        };
+        assert_eq!(filter.matches(&target), Ok(true));
+
+        // a new response replaces the globals
+        state
+            .handle_mid_event(MidEvent::ServerResponse(Ok(Box::new(GetGlobalsResponse {
+                globals: vec![],
+                req_id: 0,
+            }))))
+            .unwrap();
+        assert!(matches!(
+            get_event(&mut receiver).await,
+            MidEvent::StateEvent(StateEvent::PropsUpdate)
+        ));
+        assert!(state.global_get("wip limit").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_prop_set() {
+        let (server, mut state, mut receiver, view_key) = test_init().await;
+        let task_key = state.view_task_keys(view_key).unwrap().next().unwrap();
+        let name_key = state.prop_def_name("status");
+
+        // values have to fit the definition
+        assert!(matches!(
+            state.prop_set(task_key, name_key, TaskPropVariant::Boolean(true)),
+            Err(PropDataError::Schema(_))
+        ));
+        let before = chrono::Utc::now().naive_utc();
+        state
+            .prop_set(task_key, name_key, TaskPropVariant::String("todo".into()))
+            .unwrap();
+        assert_eq!(
+            state.prop_get(task_key, name_key).unwrap(),
+            &TaskPropVariant::String("todo".into())
+        );
+        // the edit is stamped locally, before the server answers
+        assert!(state.task_get(task_key).unwrap().last_edited >= before);
+        // setting again replaces the value
+        state
+            .prop_set(task_key, name_key, TaskPropVariant::String("done".into()))
+            .unwrap();
+        assert_eq!(
+            state.prop_get(task_key, name_key).unwrap(),
+            &TaskPropVariant::String("done".into())
+        );
+        // both updates are sent to the server
+        let mut updates = 0;
+        for _ in 0..4 {
+            match get_event(&mut receiver).await {
+                MidEvent::StateEvent(StateEvent::TasksUpdate) => updates += 1,
+                event => state.handle_mid_event(event).unwrap(),
+            }
+        }
+        assert_eq!(updates, 2);
+    }
+
+    #[tokio::test]
+    async fn test_task_refs() {
+        let (server, mut state, mut receiver, view_key) = test_init().await;
+        let mut tasks = state.view_task_keys(view_key).unwrap().collect::<Vec<_>>();
+        tasks.sort(); // tasks[1] has db id 1
+
+        let duplicate = state.prop_def_name("duplicate of");
+        let blocked = state.prop_def_name("blocked by");
+        state
+            .prop_def(tasks[0], duplicate, TaskPropVariant::TaskRef(1))
+            .unwrap();
+        state
+            .prop_def(tasks[0], blocked, TaskPropVariant::TaskRefs(vec![1, 0, 7]))
+            .unwrap();
+        assert_eq!(
+            state.prop_task_refs(tasks[0], duplicate).unwrap(),
+            vec![tasks[1]]
+        );
+        // task 7 isn't loaded
+        assert_eq!(
+            state.prop_task_refs(tasks[0], blocked).unwrap(),
+            vec![tasks[1], tasks[0]]
+        );
+
+        // deleting the referenced task drops the references to it
+        state.task_rm(tasks[1]).unwrap();
+        state.handle_mid_event(get_event(&mut receiver).await); // handle server response
+        assert!(state.prop_get(tasks[0], duplicate).is_err());
+        assert_eq!(
+            state.prop_get(tasks[0], blocked).unwrap(),
+            &TaskPropVariant::TaskRefs(vec![0, 7])
+        );
+    }
+
     #[tokio::test]
     async fn test_remove_prop_name_deletes_props_prop_map_and_props() {
         let (server, mut state, mut receiver, view_key) = test_init().await;
@@ -1205,6 +2695,878 @@ pub(crate) mod tests {
         let old_prop_ref = &state.props[old_prop_key];
         let new_prop_ref = &state.props[prop_key];
     }
+    #[tokio::test]
+    async fn test_view_paging() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/filter")
+            .with_body_from_request(|req| {
+                let req: FilterRequest =
+                    serde_json::from_slice::<FilterRequest>(req.body().unwrap()).unwrap();
+                // 3 tasks on server, 2 per page
+                assert_eq!(req.limit, Some(2));
+                let tasks = (0..3)
+                    .skip(req.offset as usize)
+                    .take(2)
+                    .collect::<Vec<TaskID>>();
+                to_vec(&FilterResponse {
+                    more: req.offset + (tasks.len() as u64) < 3,
+                    tasks,
+                    offset: req.offset,
+                    req_id: req.req_id,
+                })
+                .unwrap()
+            })
+            .expect(2)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/tasks")
+            .with_body(to_vec::<ReadTasksShortResponse>(&vec![]).unwrap())
+            .create_async()
+            .await;
+
+        let (mut state, mut receiver) = State::new();
+        state.url = server.url();
+        let view_key = state.view_def(View {
+            max_tasks: Some(2),
+            ..View::new("Paged".to_owned())
+        });
+        // wait for the pending page to be received
+        async fn await_page(state: &mut State, receiver: &mut Receiver<MidEvent>, key: ViewKey) {
+            while state.view_get(key).unwrap().pending_page {
+                match get_event(receiver).await {
+                    MidEvent::StateEvent(_) => {}
+                    event => state.handle_mid_event(event).unwrap(),
+                }
+            }
+        }
+
+        // max_tasks caps the page size, and more tasks can't be loaded past it
+        state.view_fetch_page(view_key, 0);
+        await_page(&mut state, &mut receiver, view_key).await;
+        let view = state.view_get(view_key).unwrap();
+        assert_eq!(view.tasks.as_ref().unwrap().len(), 2);
+        assert!(!view.more_tasks);
+        assert!(!state.view_load_more(view_key));
+
+        // raising max_tasks allows loading the next page, which is appended in order.
+        // it starts after the tasks the server sent, even once a local edit removed one of them
+        state.view_mod(view_key, |v| {
+            v.max_tasks = Some(4);
+            v.more_tasks = true;
+            v.tasks.as_mut().unwrap().pop();
+        });
+        assert!(state.view_load_more(view_key));
+        assert!(!state.view_load_more(view_key)); // already pending
+        await_page(&mut state, &mut receiver, view_key).await;
+        let view = state.view_get(view_key).unwrap();
+        let ids = view
+            .tasks
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|key| state.task_get(*key).unwrap().db_id.unwrap())
+            .collect::<Vec<TaskID>>();
+        assert_eq!(ids, vec![0, 2]);
+        assert_eq!(view.server_offset, 3);
+        assert!(!view.more_tasks);
+        assert!(!state.view_load_more(view_key));
+    }
+
+    #[tokio::test]
+    async fn test_search() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/search")
+            .with_body_from_request(|req| {
+                let req: SearchRequest =
+                    serde_json::from_slice::<SearchRequest>(req.body().unwrap()).unwrap();
+                to_vec(&SearchResponse {
+                    results: vec![SearchResult {
+                        task_id: 7,
+                        field: None,
+                        snippet: format!("a {}", req.query),
+                        highlights: vec![(2, 2 + req.query.len())],
+                        rank: 1.0,
+                    }],
+                    req_id: req.req_id,
+                })
+                .unwrap()
+            })
+            .expect(1)
+            .create_async()
+            .await;
+        // unknown task in results gets fetched
+        server
+            .mock("GET", "/tasks")
+            .with_body(
+                to_vec::<ReadTasksShortResponse>(&vec![Ok(ReadTaskShortResponse {
+                    task_id: 7,
+                    name: "a dog".into(),
+                    ..Default::default()
+                })])
+                .unwrap(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let (mut state, mut receiver) = State::new();
+        state.url = server.url();
+        state.search("dog");
+        state
+            .handle_mid_event(get_event(&mut receiver).await)
+            .unwrap();
+        assert!(matches!(
+            get_event(&mut receiver).await,
+            MidEvent::StateEvent(StateEvent::SearchUpdate)
+        ));
+        let (key, result) = state.search_results()[0].clone();
+        assert_eq!(result.snippet, "a dog");
+        assert_eq!(state.task_get(key).unwrap().db_id, Some(7));
+        state
+            .handle_mid_event(get_event(&mut receiver).await)
+            .unwrap();
+        assert_eq!(state.task_get(key).unwrap().name, "a dog");
+
+        // empty query clears results without a request
+        state.search(" ");
+        assert!(state.search_results().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_notifications() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/notifications")
+            .with_body_from_request(|req| {
+                let req: GetNotificationsRequest =
+                    serde_json::from_slice::<GetNotificationsRequest>(req.body().unwrap()).unwrap();
+                // the first poll gets the latest notifications, the next one those after them
+                let notifications = match req.after {
+                    None => vec![
+                        Notification {
+                            id: 4,
+                            task_id: None,
+                            kind: NotificationKind::ScriptRun(1),
+                            fire_at: NaiveDateTime::default(),
+                        },
+                        Notification {
+                            id: 5,
+                            task_id: Some(7),
+                            kind: NotificationKind::Overdue,
+                            fire_at: NaiveDateTime::default(),
+                        },
+                    ],
+                    Some(after) => {
+                        assert_eq!(after, 5);
+                        vec![]
+                    }
+                };
+                to_vec(&GetNotificationsResponse {
+                    notifications,
+                    req_id: req.req_id,
+                })
+                .unwrap()
+            })
+            .expect(2)
+            .create_async()
+            .await;
+        // the overdue task isn't loaded yet so it gets fetched
+        server
+            .mock("GET", "/tasks")
+            .with_body(
+                to_vec::<ReadTasksShortResponse>(&vec![Ok(ReadTaskShortResponse {
+                    task_id: 7,
+                    name: "pay rent".into(),
+                    ..Default::default()
+                })])
+                .unwrap(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let (mut state, mut receiver) = State::new();
+        state.url = server.url();
+        assert!(state.notifications_poll());
+        assert!(!state.notifications_poll()); // one poll at a time
+        state
+            .handle_mid_event(get_event(&mut receiver).await)
+            .unwrap();
+        assert!(matches!(
+            get_event(&mut receiver).await,
+            MidEvent::StateEvent(StateEvent::NotificationsUpdate)
+        ));
+        assert_eq!(state.notifications().len(), 2);
+        assert_eq!(state.notifications()[0].0, None);
+        let key = state.notifications()[1].0.unwrap();
+        state
+            .handle_mid_event(get_event(&mut receiver).await)
+            .unwrap();
+        assert_eq!(state.task_get(key).unwrap().name, "pay rent");
+        get_event(&mut receiver).await; // drop UI event
+
+        // dismissed notifications aren't received again
+        state.notifications_clear();
+        assert!(state.notifications_poll());
+        state
+            .handle_mid_event(get_event(&mut receiver).await)
+            .unwrap();
+        assert!(state.notifications().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_comments() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+        let comment = |id: CommentID, body: &str| Comment {
+            id,
+            task_id: 7,
+            author: "ana".to_owned(),
+            body: body.to_owned(),
+            created_at: NaiveDateTime::default(),
+            edited_at: None,
+        };
+        let mut server = Server::new_async().await;
+        let fetches = Arc::new(AtomicUsize::new(0));
+        server
+            .mock("GET", "/comments")
+            .with_body_from_request(move |req| {
+                let req =
+                    serde_json::from_slice::<GetCommentsRequest>(req.body().unwrap()).unwrap();
+                assert_eq!(req.task_id, 7);
+                // the second fetch sees the comment posted in between
+                let mut comments = vec![comment(1, "when is it due?")];
+                if fetches.fetch_add(1, Ordering::SeqCst) > 0 {
+                    comments.push(comment(2, "friday"));
+                }
+                to_vec(&GetCommentsResponse {
+                    task_id: 7,
+                    comments,
+                    req_id: req.req_id,
+                })
+                .unwrap()
+            })
+            .expect(2)
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/comment")
+            .with_body_from_request(move |req| {
+                let req =
+                    serde_json::from_slice::<CreateCommentRequest>(req.body().unwrap()).unwrap();
+                assert_eq!((req.task_id, req.author.as_str()), (7, "ana"));
+                to_vec(&CreateCommentResponse {
+                    comment: comment(2, &req.body),
+                    req_id: req.req_id,
+                })
+                .unwrap()
+            })
+            .expect(1)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/notifications")
+            .with_body(
+                to_vec(&GetNotificationsResponse {
+                    notifications: vec![Notification {
+                        id: 3,
+                        task_id: Some(7),
+                        kind: NotificationKind::Comment {
+                            comment_id: 2,
+                            author: "ana".to_owned(),
+                        },
+                        fire_at: NaiveDateTime::default(),
+                    }],
+                    req_id: 0,
+                })
+                .unwrap(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let (mut state, mut receiver) = State::new();
+        state.url = server.url();
+        state.user = "ana".to_owned();
+        let key = state.new_server_task(7).0;
+        let local_key = state.tasks.insert(Task::new("draft".to_owned(), false));
+        assert!(!state.comments_fetch(local_key)); // not on the server yet
+        assert_eq!(state.comments(key), None);
+        assert!(state.comments_fetch(key));
+        state
+            .handle_mid_event(get_event(&mut receiver).await)
+            .unwrap();
+        assert!(matches!(
+            get_event(&mut receiver).await,
+            MidEvent::StateEvent(StateEvent::CommentsUpdate)
+        ));
+        assert_eq!(state.comments(key).unwrap().len(), 1);
+
+        assert!(!state.comment_add(key, " \n".to_owned()));
+        assert!(state.comment_add(key, "friday".to_owned()));
+        state
+            .handle_mid_event(get_event(&mut receiver).await)
+            .unwrap();
+        get_event(&mut receiver).await; // drop UI event
+        let ids = |state: &State| {
+            state
+                .comments(key)
+                .unwrap()
+                .iter()
+                .map(|comment| comment.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&state), vec![1, 2]);
+
+        // the notification of the comment refetches the open thread without duplicating it
+        state.notifications_poll();
+        state
+            .handle_mid_event(get_event(&mut receiver).await)
+            .unwrap();
+        get_event(&mut receiver).await; // drop UI event
+        state
+            .handle_mid_event(get_event(&mut receiver).await)
+            .unwrap();
+        assert_eq!(ids(&state), vec![1, 2]);
+        assert_eq!(state.comments(key).unwrap()[1].body, "friday");
+    }
+
+    #[tokio::test]
+    async fn test_attachments() {
+        let attachment = Attachment {
+            id: 4,
+            task_id: 7,
+            name: "crash.log".to_owned(),
+            mime: "text/plain".to_owned(),
+            size: 11,
+            hash: "ab".repeat(32),
+            uploaded_at: NaiveDateTime::default(),
+        };
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/attachments")
+            .with_body(
+                to_vec(&GetAttachmentsResponse {
+                    task_id: 7,
+                    attachments: vec![attachment.clone()],
+                    req_id: 0,
+                })
+                .unwrap(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/attachment")
+            .match_query(Matcher::UrlEncoded("attachment_id".into(), "4".into()))
+            .with_header("content-type", "text/plain")
+            .with_body("panicked at")
+            .expect(1)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/attachment")
+            .match_query(Matcher::UrlEncoded("attachment_id".into(), "5".into()))
+            .with_status(404)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let (mut state, mut receiver) = State::new();
+        state.url = server.url();
+        let key = state.new_server_task(7).0;
+        assert_eq!(state.attachments(key), None);
+        assert!(state.attachments_fetch(key));
+        state
+            .handle_mid_event(get_event(&mut receiver).await)
+            .unwrap();
+        assert!(matches!(
+            get_event(&mut receiver).await,
+            MidEvent::StateEvent(StateEvent::AttachmentsUpdate)
+        ));
+        assert_eq!(state.attachments(key).unwrap(), [attachment]);
+
+        let path = std::env::temp_dir().join(format!("abn-test-save-{}", std::process::id()));
+        state
+            .attachment_save(4, path.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"panicked at");
+        std::fs::remove_file(&path).unwrap();
+        // errors from the server aren't written as content
+        assert!(state
+            .attachment_save(5, path.clone())
+            .await
+            .unwrap()
+            .is_err());
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_tags() {
+        let tag = |id, name: &str, parent| Tag {
+            id,
+            name: name.to_owned(),
+            parent,
+            color: "#1e90ff".to_owned(),
+        };
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/tags")
+            .with_body(
+                to_vec(&GetTagsResponse {
+                    tags: vec![
+                        tag(1, "work", None),
+                        tag(2, "frontend", Some(1)),
+                        tag(3, "home", None),
+                    ],
+                    req_id: 0,
+                })
+                .unwrap(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        server
+            .mock("PUT", "/task_tags")
+            .with_status(500)
+            .expect(1)
+            .create_async()
+            .await;
+        server
+            .mock("DELETE", "/tag")
+            .with_body(
+                to_vec(&DeleteTagResponse {
+                    tag_id: 1,
+                    req_id: 0,
+                })
+                .unwrap(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let (mut state, mut receiver) = State::new();
+        state.url = server.url();
+        let key = state.new_server_task(7).0;
+        state.tags_fetch();
+        state
+            .handle_mid_event(get_event(&mut receiver).await)
+            .unwrap();
+        assert!(matches!(
+            get_event(&mut receiver).await,
+            MidEvent::StateEvent(StateEvent::TagsUpdate)
+        ));
+        assert_eq!(state.tags().count(), 3);
+        assert_eq!(state.tag_descendants(1), vec![1, 2]);
+
+        // the tag is shown right away and dropped when the server rejects it
+        assert!(!state.task_tag_toggle(key, 2));
+        state.task_tags.insert(key, vec![]);
+        assert!(state.task_tag_toggle(key, 2));
+        assert!(!state.task_tag_toggle(key, 9));
+        assert_eq!(state.task_tags(key), Some(&[2][..]));
+        state
+            .handle_mid_event(get_event(&mut receiver).await)
+            .unwrap();
+        get_event(&mut receiver).await; // drop UI event
+        assert_eq!(state.task_tags(key), None);
+
+        // deleting a tag deletes the tags nested under it
+        state.task_tags.insert(key, vec![2, 3]);
+        state.tag_rm(1);
+        state
+            .handle_mid_event(get_event(&mut receiver).await)
+            .unwrap();
+        assert_eq!(state.tags().map(|tag| tag.id).collect::<Vec<_>>(), vec![3]);
+        assert_eq!(state.task_tags(key), Some(&[3][..]));
+    }
+
+    #[tokio::test]
+    async fn test_view_move_task() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("PUT", "/rank")
+            .with_body_from_request(|req| {
+                let req = serde_json::from_slice::<MoveTaskRequest>(req.body().unwrap()).unwrap();
+                assert_eq!((req.view_id, req.task_id), (2, 3));
+                assert_eq!((req.after, req.before), (Some(1), Some(2)));
+                to_vec(&MoveTaskResponse {
+                    sort: vec![SortKey {
+                        field: SortField::Rank(2),
+                        descending: false,
+                        nulls_first: false,
+                    }],
+                    req_id: req.req_id,
+                })
+                .unwrap()
+            })
+            .expect(1)
+            .create_async()
+            .await;
+
+        let (mut state, mut receiver) = State::new();
+        state.url = server.url();
+        let tasks = (1..=3)
+            .map(|task_id| state.new_server_task(task_id).0)
+            .collect::<Vec<TaskKey>>();
+        let view_key = state.view_def(View {
+            db_id: Some(2),
+            tasks: Some(tasks.clone()),
+            sort: vec![SortKey {
+                field: SortField::Primitive(PrimitiveField::TITLE),
+                descending: false,
+                nulls_first: false,
+            }],
+            ..View::new("today".to_owned())
+        });
+        assert!(!state.view_move_task(view_key, tasks[2], tasks[2], false));
+        assert!(state.view_move_task(view_key, tasks[2], tasks[1], false));
+        let view = state.view_get(view_key).unwrap();
+        assert_eq!(view.tasks, Some(vec![tasks[0], tasks[2], tasks[1]]));
+        // the view is put in manual order, ties still sorted as before
+        assert_eq!(view.sort[0].field, SortField::Rank(2));
+        assert_eq!(
+            view.sort[1].field,
+            SortField::Primitive(PrimitiveField::TITLE)
+        );
+
+        state
+            .handle_mid_event(get_event(&mut receiver).await)
+            .unwrap();
+        assert!(matches!(
+            get_event(&mut receiver).await,
+            MidEvent::StateEvent(StateEvent::ViewsUpdate)
+        ));
+        assert_eq!(state.view_get(view_key).unwrap().sort.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_view_move_task_conflict() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("PUT", "/rank")
+            .with_status(409)
+            .with_body("the order of the view has changed")
+            .expect(1)
+            .create_async()
+            .await;
+        // the order of the view is fetched again
+        server
+            .mock("GET", "/filter")
+            .with_body_from_request(|req| {
+                let req = serde_json::from_slice::<FilterRequest>(req.body().unwrap()).unwrap();
+                assert_eq!(req.sort[0].field, SortField::Rank(2));
+                to_vec(&FilterResponse {
+                    tasks: vec![1, 2],
+                    offset: 0,
+                    more: false,
+                    req_id: req.req_id,
+                })
+                .unwrap()
+            })
+            .expect(1)
+            .create_async()
+            .await;
+
+        let (mut state, mut receiver) = State::new();
+        state.url = server.url();
+        let tasks = (1..=2)
+            .map(|task_id| state.new_server_task(task_id).0)
+            .collect::<Vec<TaskKey>>();
+        let view_key = state.view_def(View {
+            db_id: Some(2),
+            tasks: Some(tasks.clone()),
+            ..View::new("today".to_owned())
+        });
+        assert!(state.view_move_task(view_key, tasks[0], tasks[1], true));
+        assert_eq!(
+            state.view_get(view_key).unwrap().tasks,
+            Some(vec![tasks[1], tasks[0]])
+        );
+        // the move is rejected by the server, so its order is taken again
+        for _ in 0..2 {
+            state
+                .handle_mid_event(get_event(&mut receiver).await)
+                .unwrap();
+        }
+        assert_eq!(state.view_get(view_key).unwrap().tasks, Some(tasks));
+    }
+
+    #[tokio::test]
+    async fn test_task_set_parent() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("PUT", "/task")
+            .with_body_from_request(|req| {
+                let req = serde_json::from_slice::<UpdateTaskRequest>(req.body().unwrap()).unwrap();
+                assert_eq!(
+                    req.parent,
+                    Some(ParentChange::Set {
+                        parent: 1,
+                        position: 3
+                    })
+                );
+                to_vec(&UpdateTaskResponse {
+                    task_id: req.task_id,
+                    req_id: req.req_id,
+                })
+                .unwrap()
+            })
+            .expect(1)
+            .create_async()
+            .await;
+        // the new parent is fetched again once the server confirms the move
+        server
+            .mock("GET", "/tasks")
+            .with_body(
+                to_vec::<ReadTasksShortResponse>(&vec![Ok(ReadTaskShortResponse {
+                    task_id: 1,
+                    name: "plan trip".into(),
+                    completed: true,
+                    subtasks: vec![2],
+                    ..Default::default()
+                })])
+                .unwrap(),
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let (mut state, mut receiver) = State::new();
+        state.url = server.url();
+        for (task_id, name) in [(1, "plan trip"), (2, "book flights")] {
+            Box::new(ReadTaskShortResponse {
+                task_id,
+                name: name.into(),
+                ..Default::default()
+            })
+            .update_state(&mut state)
+            .unwrap();
+        }
+        let parent = state.task_key(1).unwrap();
+        let child = state.task_key(2).unwrap();
+        state.task_set_parent(child, Some(parent), 3).unwrap();
+        assert_eq!(state.task_get(parent).unwrap().subtasks, vec![child]);
+        assert_eq!(state.task_get(child).unwrap().parent, Some(parent));
+        assert!(state.task_get(parent).unwrap().current_rollback.is_some());
+
+        // handle the UpdateTaskResponse and the ReadTasksShortResponse it triggers
+        let mut responses = 0;
+        while responses < 2 {
+            if let MidEvent::ServerResponse(resp) = get_event(&mut receiver).await {
+                state
+                    .handle_mid_event(MidEvent::ServerResponse(resp))
+                    .unwrap();
+                responses += 1;
+            }
+        }
+        let parent_task = state.task_get(parent).unwrap();
+        assert!(parent_task.current_rollback.is_none());
+        assert!(parent_task.completed);
+        assert_eq!(parent_task.subtasks, vec![child]);
+
+        // a task can't become a subtask of its own subtask
+        assert!(matches!(
+            state.task_set_parent(parent, Some(child), 0),
+            Err(ModifyTaskError::Hierarchy(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_view_fetch_prop() {
+        fn due(day: u32) -> TaskPropVariant {
+            TaskPropVariant::Date(
+                NaiveDate::from_ymd_opt(2024, 3, day)
+                    .unwrap()
+                    .and_hms_opt(9, 0, 0)
+                    .unwrap(),
+            )
+        }
+        let (mut server, mut state, mut receiver, view_key) = test_init().await;
+        server
+            .mock("GET", "/props")
+            .with_body_from_request(|req| {
+                let req: PropertiesRequest =
+                    serde_json::from_slice::<PropertiesRequest>(req.body().unwrap()).unwrap();
+                assert_eq!(req.task_ids, vec![0, 1]);
+                to_vec(&PropertiesResponse {
+                    res: vec![TaskPropColumn {
+                        name: req.properties[0].clone(),
+                        values: vec![Some(due(4)), None],
+                    }],
+                    req_id: req.req_id,
+                })
+                .unwrap()
+            })
+            .expect(1)
+            .create_async()
+            .await;
+        let tasks = state.view_get(view_key).unwrap().tasks.clone().unwrap();
+        let name_key = state.prop_def_name(DUE_PROPERTY);
+        assert!(state.view_fetch_prop(view_key, name_key));
+        state
+            .handle_mid_event(get_event(&mut receiver).await)
+            .unwrap();
+        assert!(matches!(
+            get_event(&mut receiver).await,
+            MidEvent::StateEvent(StateEvent::PropsUpdate)
+        ));
+        assert_eq!(state.prop_get(tasks[0], name_key).unwrap(), &due(4));
+        assert!(state.prop_get(tasks[1], name_key).is_err());
+
+        // modifying the fetched value is sent to the server
+        state
+            .prop_mod(tasks[0], name_key, |prop| {
+                if let TaskPropVariant::Date(date) = prop {
+                    *date += chrono::Duration::days(1);
+                }
+            })
+            .unwrap();
+        assert_eq!(state.prop_get(tasks[0], name_key).unwrap(), &due(5));
+        state
+            .handle_mid_event(get_event(&mut receiver).await)
+            .unwrap();
+        assert!(matches!(
+            get_event(&mut receiver).await,
+            MidEvent::StateEvent(StateEvent::TasksUpdate)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_view_aggregate() {
+        let (mut server, mut state, mut receiver, view_key) = test_init().await;
+        server
+            .mock("GET", "/aggregate")
+            .with_body_from_request(|req| {
+                let req: AggregateRequest =
+                    serde_json::from_slice::<AggregateRequest>(req.body().unwrap()).unwrap();
+                assert_eq!(req.group_by, GroupBy::Completed);
+                assert_eq!(req.task_ids, vec![0, 1]);
+                to_vec(&AggregateResponse {
+                    groups: vec![
+                        TaskGroup {
+                            key: GroupKey::Completed(false),
+                            count: 40,
+                            summaries: vec![],
+                        },
+                        TaskGroup {
+                            key: GroupKey::Completed(true),
+                            count: 2,
+                            summaries: vec![],
+                        },
+                    ],
+                    task_groups: vec![Some(1), Some(0)],
+                    req_id: req.req_id,
+                })
+                .unwrap()
+            })
+            .expect(1)
+            .create_async()
+            .await;
+        let tasks = state.view_get(view_key).unwrap().tasks.clone().unwrap();
+        state.view_mod(view_key, |view| view.group_by = GroupBy::Completed);
+        assert!(state.view_aggregate(view_key));
+        state
+            .handle_mid_event(get_event(&mut receiver).await)
+            .unwrap();
+        assert!(matches!(
+            get_event(&mut receiver).await,
+            MidEvent::StateEvent(StateEvent::ViewsUpdate)
+        ));
+        let view = state.view_get(view_key).unwrap();
+        assert_eq!(view.groups[0].count, 40);
+        assert_eq!(view.task_groups[&tasks[0]], 1);
+        assert_eq!(view.task_groups[&tasks[1]], 0);
+    }
+
+    #[tokio::test]
+    async fn test_view_refilter_task() {
+        let (mut state, mut receiver) = State::new();
+        let task = state.task_def(Task::new("Walk Dog".to_owned(), false));
+        let view_key = state.view_def(View {
+            name: "Urgent".to_string(),
+            filter: Filter::Leaf {
+                field: "priority".to_owned(),
+                comparator: Comparator::GEQ,
+                immediate: TaskPropVariant::Number(3.0),
+            },
+            tasks: Some(vec![]),
+            ..View::default()
+        });
+        let name_key = state.prop_def_name("priority");
+
+        // task gains matching property, should be added to view
+        state
+            .prop_def(task, name_key, TaskPropVariant::Number(5.0))
+            .unwrap();
+        assert_eq!(state.view_get(view_key).unwrap().tasks, Some(vec![task]));
+        assert!(matches!(
+            get_event(&mut receiver).await,
+            MidEvent::StateEvent(StateEvent::ViewsUpdate)
+        ));
+
+        // still matches, view unchanged
+        state
+            .prop_mod(task, name_key, |p| *p = TaskPropVariant::Number(4.0))
+            .unwrap();
+        assert_eq!(state.view_get(view_key).unwrap().tasks, Some(vec![task]));
+
+        // no longer matches, should be removed
+        state
+            .prop_mod(task, name_key, |p| *p = TaskPropVariant::Number(1.0))
+            .unwrap();
+        assert_eq!(state.view_get(view_key).unwrap().tasks, Some(vec![]));
+
+        // NOT filter matches once the property is gone
+        state.view_mod(view_key, |v| {
+            v.filter = Filter::Operator {
+                op: Operator::NOT,
+                childs: vec![v.filter.clone()],
+            }
+        });
+        state.prop_rm(task, name_key).unwrap();
+        assert_eq!(state.view_get(view_key).unwrap().tasks, Some(vec![task]));
+
+        // primitive filters are re-evaluated on task_mod
+        state.view_mod(view_key, |v| {
+            v.filter = Filter::LeafPrimitive {
+                field: PrimitiveField::COMPLETED,
+                comparator: Comparator::EQ,
+                immediate: TaskPropVariant::Boolean(false),
+            }
+        });
+        state.task_mod(task, |t| t.completed = true).unwrap();
+        assert_eq!(state.view_get(view_key).unwrap().tasks, Some(vec![]));
+        assert!(!state.view_get(view_key).unwrap().stale);
+
+        // a sorted view shows the task, and takes its place from the server later
+        state.view_mod(view_key, |v| {
+            v.filter = Filter::Leaf {
+                field: "priority".to_owned(),
+                comparator: Comparator::GEQ,
+                immediate: TaskPropVariant::Number(3.0),
+            };
+            v.sort = vec![SortKey {
+                field: SortField::Primitive(PrimitiveField::TITLE),
+                descending: false,
+                nulls_first: false,
+            }]
+        });
+        state
+            .prop_def(task, name_key, TaskPropVariant::Number(5.0))
+            .unwrap();
+        let view = state.view_get(view_key).unwrap();
+        assert_eq!(view.tasks, Some(vec![task]));
+        assert!(view.stale);
+    }
+
     #[tokio::test]
     async fn test_view_task_keys() {
         let (server, mut state, mut receiver, view_key) = test_init().await;
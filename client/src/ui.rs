@@ -1,5 +1,6 @@
 use std::io;
 
+use common::TaskPropVariant;
 use crossterm::event::{Event, KeyCode, KeyEventKind};
 use futures::{channel::mpsc::Receiver, Stream, StreamExt};
 use ratatui::{
@@ -9,10 +10,14 @@ use ratatui::{
 };
 
 use crate::{
-    mid::{MidEvent, State, StateEvent},
+    mid::{MidEvent, State, StateEvent, NOTIFICATION_POLL_PERIOD},
     term,
 };
 
+mod board;
+mod calendar;
+mod markdown;
+mod notification_tray;
 mod task_list;
 
 const BACKGROUND: Color = Color::Reset;
@@ -42,6 +47,12 @@ pub struct App {
     state: State,
     /// task list widget
     task_list: task_list::TaskList,
+    /// notifications received from the server
+    notification_tray: notification_tray::NotificationTray,
+    /// calendar shown in place of the task list, if open
+    calendar: Option<calendar::Calendar>,
+    /// board shown in place of the task list, if open
+    board: Option<board::Board>,
     /// number of frame updates (used for debug purposes)
     updates: usize,
     help_box_shown: bool,
@@ -59,6 +70,9 @@ impl App {
             should_exit: false,
             state,
             task_list: task_list::TaskList::default(),
+            notification_tray: Default::default(),
+            calendar: None,
+            board: None,
             updates: 0,
             help_box_shown: false,
         }
@@ -74,11 +88,15 @@ impl App {
             .source_views_mod(&self.state, |s| s.extend(self.state.view_get_default()));
         // render initial frame
         term.draw(|frame| frame.render_widget(&mut *self, frame.size()))?;
+        let mut notification_poll = tokio::time::interval(NOTIFICATION_POLL_PERIOD);
         // wait for events
         loop {
             tokio::select! {
                 Some(event) = events.next() => self.step(term, UIEvent::UserEvent(event?))?,
                 Some(mid_event) = state_events.next() => self.handle_mid_event(term, mid_event)?,
+                _ = notification_poll.tick() => {
+                    self.state.notifications_poll();
+                }
                 else => break,
             }
             if self.should_exit {
@@ -119,11 +137,23 @@ impl App {
             UIEvent::UserEvent(event) => self.handle_term_event(event),
             UIEvent::StateEvent(state_event) => match state_event {
                 StateEvent::TasksUpdate => true,
-                StateEvent::PropsUpdate => todo!(),
+                StateEvent::PropsUpdate => true,
                 StateEvent::ViewsUpdate => {
                     self.task_list.rebuild_list(&self.state); // rebuild list state when views update
+                    self.task_list.fetch_tags(&mut self.state); // chips of tasks that were added to the view
+                    if let Some(calendar) = self.calendar.as_ref() {
+                        calendar.fetch(&mut self.state); // plot tasks that were added to the view
+                    }
+                    if let Some(board) = self.board.as_ref() {
+                        board.fetch(&mut self.state);
+                    }
                     true
                 }
+                StateEvent::SearchUpdate => true,
+                StateEvent::NotificationsUpdate => true,
+                StateEvent::CommentsUpdate => true,
+                StateEvent::AttachmentsUpdate => true,
+                StateEvent::TagsUpdate => true,
                 StateEvent::ScriptUpdate(_) => todo!(),
                 StateEvent::ServerStatus(_) => todo!(),
             },
@@ -133,6 +163,41 @@ impl App {
     fn handle_term_event(&mut self, event: Event) -> bool {
         use KeyCode::*;
 
+        // the notification tray takes all input while it's open
+        if self.notification_tray.shown {
+            return match self
+                .notification_tray
+                .handle_term_event(&mut self.state, &event)
+            {
+                Ok(do_render) => do_render,
+                Err(jump_to) => {
+                    self.notification_tray.shown = false;
+                    if let Some(key) = jump_to {
+                        self.task_list.select_task(key);
+                    }
+                    true
+                }
+            };
+        }
+        // the calendar replaces the task list while it's open
+        if let Some(calendar) = self.calendar.as_mut() {
+            return match calendar.handle_term_event(&mut self.state, &event) {
+                Some(do_render) => do_render,
+                None => {
+                    self.calendar = None;
+                    true
+                }
+            };
+        }
+        if let Some(board) = self.board.as_mut() {
+            return match board.handle_term_event(&mut self.state, &event) {
+                Some(do_render) => do_render,
+                None => {
+                    self.board = None;
+                    true
+                }
+            };
+        }
         // pass event to task list to check if it handles the event, if not, handle it below
         if self.task_list.handle_term_event(&mut self.state, &event) {
             return true;
@@ -153,6 +218,24 @@ impl App {
                     }
                     Char('q') => self.should_exit = true,
                     Char('h') => self.help_box_shown = !self.help_box_shown,
+                    Char('n') => self.notification_tray.open(&self.state),
+                    Char('v') => {
+                        if let Some(view_key) = self.state.view_get_default() {
+                            let calendar = calendar::Calendar::new(
+                                view_key,
+                                chrono::Local::now().date_naive(),
+                            );
+                            calendar.fetch(&mut self.state);
+                            self.calendar = Some(calendar);
+                        }
+                    }
+                    Char('b') => {
+                        if let Some(view_key) = self.state.view_get_default() {
+                            let board = board::Board::new(view_key);
+                            board.fetch(&mut self.state);
+                            self.board = Some(board);
+                        }
+                    }
                     _ => return false,
                 }
             }
@@ -168,25 +251,51 @@ pub fn report_error(error: impl std::error::Error) {
     tracing::error!("{error}");
 }
 
+// short text of a property value, for titles of groups
+fn value_title(value: &TaskPropVariant) -> String {
+    match value {
+        TaskPropVariant::Date(date) => date.format("%Y-%m-%d %H:%M").to_string(),
+        TaskPropVariant::String(s) | TaskPropVariant::Select(s) | TaskPropVariant::User(s) => {
+            s.clone()
+        }
+        TaskPropVariant::Number(n) => n.to_string(),
+        TaskPropVariant::Integer(n) => n.to_string(),
+        TaskPropVariant::Duration(duration) => duration.to_string(),
+        TaskPropVariant::Boolean(b) => if *b { "Yes" } else { "No" }.to_owned(),
+        TaskPropVariant::MultiSelect(options) => options.join(", "),
+        TaskPropVariant::TaskRef(id) => format!("#{id}"),
+        TaskPropVariant::TaskRefs(ids) => ids
+            .iter()
+            .map(|id| format!("#{id}"))
+            .collect::<Vec<_>>()
+            .join(", "),
+        TaskPropVariant::RelativeDate(date) => format!("{date:?}"),
+    }
+}
+
 impl Widget for &mut App {
     fn render(self, area: Rect, buf: &mut Buffer) {
         self.updates += 1; // record render count
 
         let title = Title::from(" Task Management ".bold());
         // bottom bar instructions
-        let instructions = Title::from(Line::from(vec![
-            " Select: ".into(),
-            "<Up>".blue().bold(),
-            "/".into(),
-            "<Down>".blue().bold(),
-            " Help: ".into(),
-            "<h> ".blue().bold(),
-            ", Quit: ".into(),
-            "<q> ".blue().bold(),
-        ]));
+        let instructions = match (self.calendar.as_ref(), self.board.as_ref()) {
+            (Some(calendar), _) => calendar.instructions(),
+            (_, Some(board)) => board.instructions(),
+            _ => Title::from(Line::from(vec![
+                " Select: ".into(),
+                "<Up>".blue().bold(),
+                "/".into(),
+                "<Down>".blue().bold(),
+                " Help: ".into(),
+                "<h> ".blue().bold(),
+                ", Quit: ".into(),
+                "<q> ".blue().bold(),
+            ])),
+        };
         // bottom right render update count
         let update_counter = Title::from(format!("Updates: {}", self.updates));
-        let block = Block::default()
+        let mut block = Block::default()
             .bg(BACKGROUND)
             .title(title.alignment(Alignment::Center))
             .title(
@@ -201,13 +310,29 @@ impl Widget for &mut App {
             )
             .borders(Borders::ALL)
             .border_set(border::ROUNDED);
+        // top right count of unread notifications
+        let unread = self.notification_tray.unread(&self.state);
+        if unread > 0 {
+            let badge = Title::from(Line::from(vec![
+                Span::styled(format!(" {unread} new "), Style::new().yellow().bold()),
+                "<n> ".blue().bold(),
+            ]));
+            block = block.title(badge.alignment(Alignment::Right));
+        }
 
-        self.task_list.render(&self.state, block, area, buf);
+        match (self.calendar.as_mut(), self.board.as_mut()) {
+            (Some(calendar), _) => calendar.render(&self.state, block, area, buf),
+            (_, Some(board)) => board.render(&self.state, block, area, buf),
+            _ => self.task_list.render(&self.state, block, area, buf),
+        }
+        if self.notification_tray.shown {
+            self.notification_tray.render(&self.state, area, buf);
+        }
 
         // render help list
         if self.help_box_shown {
             // create a centered rect of fixed vertical size that takes up 50% of the vertical area.
-            let vertical_center = Layout::vertical([Constraint::Length(7)])
+            let vertical_center = Layout::vertical([Constraint::Length(17)])
                 .flex(layout::Flex::Center)
                 .split(area);
 
@@ -243,6 +368,52 @@ impl Widget for &mut App {
                     Span::raw("Edit Task: "),
                     Span::styled("<e>", Style::new().blue().bold()),
                 ]),
+                Line::from(vec![
+                    Span::raw("Search Tasks: "),
+                    Span::styled("</>", Style::new().blue().bold()),
+                ]),
+                Line::from(vec![
+                    Span::raw("Edit Properties: "),
+                    Span::styled("<o>", Style::new().blue().bold()),
+                ]),
+                Line::from(vec![
+                    Span::raw("Notifications: "),
+                    Span::styled("<n>", Style::new().blue().bold()),
+                ]),
+                Line::from(vec![
+                    Span::raw("Calendar: "),
+                    Span::styled("<v>", Style::new().blue().bold()),
+                ]),
+                Line::from(vec![
+                    Span::raw("Board: "),
+                    Span::styled("<b>", Style::new().blue().bold()),
+                ]),
+                Line::from(vec![
+                    Span::raw("Group Tasks: "),
+                    Span::styled("<g>", Style::new().blue().bold()),
+                ]),
+                Line::from(vec![
+                    Span::raw("Outline: "),
+                    Span::styled("<t>", Style::new().blue().bold()),
+                    Span::raw(" Indent: "),
+                    Span::styled("<Tab>/<S-Tab>", Style::new().blue().bold()),
+                ]),
+                Line::from(vec![
+                    Span::raw("Move Task: "),
+                    Span::styled("<S-Up>/<S-Down>", Style::new().blue().bold()),
+                ]),
+                Line::from(vec![
+                    Span::raw("Details: "),
+                    Span::styled("<i>", Style::new().blue().bold()),
+                    Span::raw(" Edit Description: "),
+                    Span::styled("<E>", Style::new().blue().bold()),
+                ]),
+                Line::from(vec![
+                    Span::raw("Attachments: "),
+                    Span::styled("<a>", Style::new().blue().bold()),
+                    Span::raw(" Tags: "),
+                    Span::styled("<#>", Style::new().blue().bold()),
+                ]),
             ];
             // create paragraph containing current string state inside `block` & render
             Paragraph::new(text)
@@ -261,6 +432,7 @@ mod tests {
         mid::{
             init_test,
             tests::{get_event, mockito_setup},
+            ServerResponse,
         },
         ui::UIEvent::UserEvent,
     };
@@ -304,14 +476,11 @@ mod tests {
         let (state, mut receiver) = crate::mid::init(&server.url()).unwrap();
 
         let (mut app, mut term) = create_render_test(state, 55, 5);
-        app.handle_mid_event(&mut term, get_event(&mut receiver).await)
-            .unwrap(); // state update
-        app.handle_mid_event(&mut term, get_event(&mut receiver).await)
-            .unwrap(); // app update
-        app.handle_mid_event(&mut term, get_event(&mut receiver).await)
-            .unwrap(); // state update
-        app.handle_mid_event(&mut term, get_event(&mut receiver).await)
-            .unwrap(); // app update
+        // filter, schema, globals and tasks responses, each followed by an app update
+        for _ in 0..8 {
+            app.handle_mid_event(&mut term, get_event(&mut receiver).await)
+                .unwrap();
+        }
 
         app.task_list
             .source_views_mod(&app.state, |s| s.extend(app.state.view_get_default())); // set the view key as is currently done in run()
@@ -325,7 +494,7 @@ mod tests {
             "│  ✓ Eat Lunch                                        │",
             "│> ☐ Finish ABN                                       │",
             "│                                                     │",
-            "╰───── Select: <Up>/<Down> Help: <h> , Quit: <q> es: 4╯",
+            "╰───── Select: <Up>/<Down> Help: <h> , Quit: <q> es: 6╯",
         ]);
         term.backend().assert_buffer(&expected);
 
@@ -341,7 +510,7 @@ mod tests {
             "│                                                     │",
             "│                                                     │",
             "│                                                     │",
-            "╰───── Select: <Up>/<Down> Help: <h> , Quit: <q> es: 5╯",
+            "╰───── Select: <Up>/<Down> Help: <h> , Quit: <q> es: 7╯",
         ]);
         term.backend().assert_buffer(&expected);
 
@@ -360,7 +529,7 @@ mod tests {
             "│             │Finish ABNhi             │             │",
             "│             ╰─────────────────────────╯             │",
             "│                                                     │",
-            "╰───── Select: <Up>/<Down> Help: <h> , Quit: <q> s: 10╯",
+            "╰───── Select: <Up>/<Down> Help: <h> , Quit: <q> s: 12╯",
         ]);
         term.backend().assert_buffer(&expected);
 
@@ -384,7 +553,7 @@ mod tests {
             "│                                                     │",
             "│                                                     │",
             "│                                                     │",
-            "╰───── Select: <Up>/<Down> Help: <h> , Quit: <q> s: 14╯",
+            "╰───── Select: <Up>/<Down> Help: <h> , Quit: <q> s: 16╯",
         ]);
         term.backend().assert_buffer(&expected);
 
@@ -408,7 +577,7 @@ mod tests {
             "│                                                     │",
             "│                                                     │",
             "│                                                     │",
-            "╰───── Select: <Up>/<Down> Help: <h> , Quit: <q> s: 22╯",
+            "╰───── Select: <Up>/<Down> Help: <h> , Quit: <q> s: 24╯",
         ]);
         term.backend().assert_buffer(&expected);
 
@@ -425,7 +594,7 @@ mod tests {
             "│                                                     │",
             "│                                                     │",
             "│                                                     │",
-            "╰───── Select: <Up>/<Down> Help: <h> , Quit: <q> s: 25╯",
+            "╰───── Select: <Up>/<Down> Help: <h> , Quit: <q> s: 27╯",
         ]);
         term.backend().assert_buffer(&expected);
 
@@ -440,7 +609,7 @@ mod tests {
             "│                                                     │",
             "│                                                     │",
             "│                                                     │",
-            "╰───── Select: <Up>/<Down> Help: <h> , Quit: <q> s: 26╯",
+            "╰───── Select: <Up>/<Down> Help: <h> , Quit: <q> s: 28╯",
         ]);
         term.backend().assert_buffer(&expected);
         Ok(())
@@ -461,5 +630,81 @@ mod tests {
         assert!(debug_string.contains("Create Task: "));
         assert!(debug_string.contains("Delete Task: "));
         assert!(debug_string.contains("Edit Task: "));
+        assert!(debug_string.contains("Notifications: "));
+        assert!(debug_string.contains("Calendar: "));
+        assert!(debug_string.contains("Board: "));
+        assert!(debug_string.contains("Group Tasks: "));
+        assert!(debug_string.contains("Outline: "));
+        assert!(debug_string.contains("Move Task: "));
+        assert!(debug_string.contains("Details: "));
+        assert!(debug_string.contains("Attachments: "));
+        assert!(debug_string.contains(" Tags: "));
+    }
+    #[tokio::test]
+    async fn test_notification_tray() -> color_eyre::Result<()> {
+        use common::backend::{
+            GetNotificationsResponse, Notification, NotificationKind, ReadTaskShortResponse,
+        };
+
+        let (mut state, _receiver) = init_test();
+        Box::new(ReadTaskShortResponse {
+            task_id: 9,
+            name: "pay rent".into(),
+            ..Default::default()
+        })
+        .update_state(&mut state)?;
+        let fire_at = chrono::NaiveDate::from_ymd_opt(2024, 3, 1)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        Box::new(GetNotificationsResponse {
+            notifications: vec![
+                Notification {
+                    id: 4,
+                    task_id: None,
+                    kind: NotificationKind::ScriptRun(1),
+                    fire_at,
+                },
+                Notification {
+                    id: 5,
+                    task_id: Some(9),
+                    kind: NotificationKind::Overdue,
+                    fire_at,
+                },
+            ],
+            req_id: 0,
+        })
+        .update_state(&mut state)?;
+        let (mut app, mut term) = create_render_test(state, 80, 12);
+        app.step(
+            &mut term,
+            UIEvent::StateEvent(StateEvent::NotificationsUpdate),
+        )?;
+        let screen = format!("{:?}", term.backend().buffer());
+        assert!(screen.contains(" 2 new <n> "));
+
+        // opening the tray shows the newest notification first and marks them as seen
+        app.step(&mut term, UserEvent(Event::Key(KeyCode::Char('n').into())))?;
+        let screen = format!("{:?}", term.backend().buffer());
+        assert!(screen.contains("Notifications"));
+        assert!(screen.contains("03-01 09:00 pay rent is overdue"));
+        assert!(screen.contains("script 1 is scheduled to run"));
+        assert!(!screen.contains(" 2 new <n> "));
+        assert_eq!(app.notification_tray.unread(&app.state), 0);
+
+        // jump to the overdue task
+        app.step(&mut term, UserEvent(Event::Key(KeyCode::Enter.into())))?;
+        assert!(!app.notification_tray.shown);
+        let (_, task) = app.task_list.selected_task(&app.state).unwrap();
+        assert_eq!(task.name, "pay rent");
+
+        // clearing empties the tray
+        app.step(&mut term, UserEvent(Event::Key(KeyCode::Char('n').into())))?;
+        app.step(&mut term, UserEvent(Event::Key(KeyCode::Char('c').into())))?;
+        let screen = format!("{:?}", term.backend().buffer());
+        assert!(screen.contains("No Notifications"));
+        app.step(&mut term, UserEvent(Event::Key(KeyCode::Esc.into())))?;
+        assert!(!app.notification_tray.shown);
+        Ok(())
     }
 }
@@ -0,0 +1,411 @@
+use common::{GroupBy, TaskPropType, TaskPropVariant};
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    symbols::border,
+    text::Line,
+    widgets::{
+        block::Title, Block, Borders, HighlightSpacing, List, ListState, Paragraph, StatefulWidget,
+        Widget,
+    },
+};
+
+use crate::mid::{State, Task, TaskKey, ViewKey};
+
+use super::{
+    report_error, value_title, COMPLETED_TEXT_COLOR, GREYED_OUT_TEXT_COLOR, SELECTED_STYLE_FG,
+    TEXT_COLOR,
+};
+
+/// what the tasks of a column have in common
+#[derive(Debug, Clone, PartialEq)]
+enum Group {
+    Completed(bool),
+    /// value of the grouped property, None for tasks without it
+    Value(Option<TaskPropVariant>),
+}
+
+impl Group {
+    fn title(&self) -> String {
+        match self {
+            Group::Completed(false) => "Not Completed".to_owned(),
+            Group::Completed(true) => "Completed".to_owned(),
+            Group::Value(None) => "No Value".to_owned(),
+            Group::Value(Some(value)) => value_title(value),
+        }
+    }
+}
+
+struct Column<'a> {
+    group: Group,
+    tasks: Vec<(TaskKey, &'a Task)>,
+}
+
+/// Board of the tasks of a view as cards, in columns grouped by completion or the value of a property.
+/// Moving a card to another column rewrites what the task is grouped by.
+#[derive(Debug)]
+pub struct Board {
+    view_key: ViewKey,
+    /// index of the selected column
+    column: usize,
+    /// index of the selected card in the selected column
+    card: usize,
+}
+
+impl Board {
+    pub fn new(view_key: ViewKey) -> Self {
+        Self {
+            view_key,
+            column: 0,
+            card: 0,
+        }
+    }
+    /// how the board groups tasks, views that aren't grouped are grouped by completion
+    pub fn group_by(&self, state: &State) -> GroupBy {
+        match state.view_get(self.view_key).map(|view| &view.group_by) {
+            Ok(GroupBy::Property(name)) => GroupBy::Property(name.clone()),
+            _ => GroupBy::Completed,
+        }
+    }
+    /// request the grouped property of the view's tasks from the server
+    pub fn fetch(&self, state: &mut State) {
+        if let GroupBy::Property(name) = self.group_by(state) {
+            let name_key = state.prop_def_name(name);
+            state.view_fetch_prop(self.view_key, name_key);
+        }
+    }
+    /// returns Some with boolean notifying calling event handler whether to trigger re-render.
+    /// returns None if the board should be closed
+    pub fn handle_term_event(&mut self, state: &mut State, event: &Event) -> Option<bool> {
+        let Event::Key(KeyEvent { code, .. }) = event else {
+            return Some(false);
+        };
+        match code {
+            KeyCode::Esc | KeyCode::Char('b') => return None,
+            KeyCode::Left => self.select(state, self.column.saturating_sub(1), 0),
+            KeyCode::Right => self.select(state, self.column + 1, 0),
+            KeyCode::Up => self.select(state, self.column, self.card.saturating_sub(1)),
+            KeyCode::Down => self.select(state, self.column, self.card + 1),
+            KeyCode::Char('[') => self.move_card(state, -1),
+            KeyCode::Char(']') => self.move_card(state, 1),
+            KeyCode::Char('g') => self.cycle_group_by(state),
+            _ => return Some(false),
+        }
+        Some(true)
+    }
+    /// bottom bar instructions while the board is shown
+    pub fn instructions(&self) -> Title<'static> {
+        Title::from(Line::from(vec![
+            " Select: ".into(),
+            "<Arrows>".blue().bold(),
+            " Move: ".into(),
+            "<[>".blue().bold(),
+            "/".into(),
+            "<]>".blue().bold(),
+            " Group By: ".into(),
+            "<g>".blue().bold(),
+            " Close: ".into(),
+            "<b> ".blue().bold(),
+        ]))
+    }
+    // select a card, staying within the board
+    fn select(&mut self, state: &State, column: usize, card: usize) {
+        let columns = self.columns(state);
+        self.column = column.min(columns.len().saturating_sub(1));
+        let cards = columns
+            .get(self.column)
+            .map_or(0, |column| column.tasks.len());
+        self.card = card.min(cards.saturating_sub(1));
+    }
+    // move the selected card to a neighbouring column, tasks can't be moved to "No Value"
+    fn move_card(&mut self, state: &mut State, by: isize) {
+        let (task_key, group) = {
+            let columns = self.columns(state);
+            let Some(task_key) = columns
+                .get(self.column)
+                .and_then(|column| column.tasks.get(self.card))
+                .map(|(key, _)| *key)
+            else {
+                return;
+            };
+            let Some(group) = self
+                .column
+                .checked_add_signed(by)
+                .and_then(|target| columns.get(target))
+                .map(|column| column.group.clone())
+            else {
+                return;
+            };
+            (task_key, group)
+        };
+        match (self.group_by(state), group) {
+            (_, Group::Completed(completed)) => {
+                if let Err(err) = state.task_mod(task_key, |task| task.completed = completed) {
+                    report_error(err);
+                    return;
+                }
+            }
+            (GroupBy::Property(name), Group::Value(Some(value))) => {
+                let name_key = state.prop_def_name(name);
+                let res = match state.prop_get(task_key, name_key) {
+                    Ok(_) => state.prop_mod(task_key, name_key, |prop| *prop = value),
+                    Err(_) => state.prop_set(task_key, name_key, value),
+                };
+                if let Err(err) = res {
+                    report_error(err);
+                    return;
+                }
+            }
+            _ => return,
+        }
+        // follow the card to its new column
+        let columns = self.columns(state);
+        if let Some((column, card)) = columns.iter().enumerate().find_map(|(i, column)| {
+            let card = column.tasks.iter().position(|(key, _)| *key == task_key)?;
+            Some((i, card))
+        }) {
+            self.column = column;
+            self.card = card;
+        }
+    }
+    // group by completion, and then by each property of the schema
+    fn cycle_group_by(&mut self, state: &mut State) {
+        let mut options = vec![GroupBy::Completed];
+        options.extend(
+            state
+                .prop_definitions()
+                .into_iter()
+                .map(|(_, definition)| GroupBy::Property(definition.name.clone())),
+        );
+        let current = self.group_by(state);
+        let index = options
+            .iter()
+            .position(|option| *option == current)
+            .map_or(0, |i| (i + 1) % options.len());
+        let group_by = options.swap_remove(index);
+        state.view_mod(self.view_key, |view| view.group_by = group_by);
+        self.fetch(state);
+        self.column = 0;
+        self.card = 0;
+    }
+    // columns of the board, in the order of the property's options if it has any, tasks without the property last
+    fn columns<'a>(&self, state: &'a State) -> Vec<Column<'a>> {
+        let name = match self.group_by(state) {
+            GroupBy::Property(name) => name,
+            _ => {
+                let (completed, not_completed): (Vec<_>, Vec<_>) = state
+                    .view_tasks(self.view_key)
+                    .into_iter()
+                    .flatten()
+                    .partition(|(_, task)| task.completed);
+                return vec![
+                    Column {
+                        group: Group::Completed(false),
+                        tasks: not_completed,
+                    },
+                    Column {
+                        group: Group::Completed(true),
+                        tasks: completed,
+                    },
+                ];
+            }
+        };
+        let name_key = state.prop_name_key(&name);
+        // values a task can be moved to even if no task has them yet
+        let values = match name_key.and_then(|key| state.prop_definition(key)) {
+            Some(definition) if definition.typ == TaskPropType::Select => definition
+                .options
+                .iter()
+                .map(|option| TaskPropVariant::Select(option.clone()))
+                .collect(),
+            Some(definition) if definition.typ == TaskPropType::Boolean => {
+                vec![
+                    TaskPropVariant::Boolean(false),
+                    TaskPropVariant::Boolean(true),
+                ]
+            }
+            _ => vec![],
+        };
+        let mut columns = values
+            .into_iter()
+            .map(|value| Column {
+                group: Group::Value(Some(value)),
+                tasks: vec![],
+            })
+            .collect::<Vec<Column>>();
+        for (key, task) in state.view_tasks(self.view_key).into_iter().flatten() {
+            let group = Group::Value(
+                name_key
+                    .and_then(|name_key| state.prop_get(key, name_key).ok())
+                    .cloned(),
+            );
+            match columns.iter_mut().find(|column| column.group == group) {
+                Some(column) => column.tasks.push((key, task)),
+                None => columns.push(Column {
+                    group,
+                    tasks: vec![(key, task)],
+                }),
+            }
+        }
+        columns.sort_by_key(|column| column.group == Group::Value(None));
+        columns
+    }
+    // render board to buffer
+    pub fn render(&mut self, state: &State, block: Block<'_>, area: Rect, buf: &mut Buffer) {
+        let inner = block.inner(area);
+        block.render(area, buf);
+        let rows = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).split(inner);
+        let header = match self.group_by(state) {
+            GroupBy::Property(name) => format!("Grouped by {name}"),
+            _ => "Grouped by completion".to_owned(),
+        };
+        Paragraph::new(Line::styled(header, Style::new().bold()))
+            .centered()
+            .render(rows[0], buf);
+
+        let columns = self.columns(state);
+        if columns.is_empty() {
+            Paragraph::new("No Tasks to Group")
+                .centered()
+                .render(rows[1], buf);
+            return;
+        }
+        let areas = Layout::horizontal(
+            columns
+                .iter()
+                .map(|_| Constraint::Ratio(1, columns.len() as u32)),
+        )
+        .split(rows[1]);
+        for (i, (column, area)) in columns.iter().zip(areas.iter()).enumerate() {
+            let mut block = Block::default()
+                .title(format!("{} ({})", column.group.title(), column.tasks.len()))
+                .borders(Borders::ALL)
+                .border_set(border::ROUNDED);
+            if i == self.column {
+                block = block.border_style(Style::new().fg(SELECTED_STYLE_FG));
+            }
+            let lines = column
+                .tasks
+                .iter()
+                .map(|(_, task)| card_line(task))
+                .collect::<Vec<Line>>();
+            let list = List::new(lines)
+                .block(block)
+                .highlight_style(
+                    Style::default()
+                        .add_modifier(Modifier::BOLD)
+                        .add_modifier(Modifier::REVERSED)
+                        .fg(SELECTED_STYLE_FG),
+                )
+                .highlight_symbol(">")
+                .highlight_spacing(HighlightSpacing::Always);
+            let selected = (i == self.column && !column.tasks.is_empty())
+                .then(|| self.card.min(column.tasks.len() - 1));
+            let mut list_state = ListState::default().with_selected(selected);
+            StatefulWidget::render(list, *area, buf, &mut list_state);
+        }
+    }
+}
+
+fn card_line<'a>(task: &Task) -> Line<'a> {
+    let mut style: Style = if task.completed {
+        COMPLETED_TEXT_COLOR.into()
+    } else {
+        TEXT_COLOR.into()
+    };
+    if task.current_rollback.is_some() {
+        style = GREYED_OUT_TEXT_COLOR.into();
+    }
+    let mark = if task.completed { "✓" } else { "☐" };
+    Line::styled(format!("{mark} {}", task.name), style)
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{backend::GetSchemaResponse, PropertyDefinition};
+    use ratatui::{buffer::Buffer, layout::Rect};
+
+    use super::*;
+    use crate::mid::{ServerResponse, View};
+
+    #[tokio::test]
+    async fn test_board() {
+        let (mut state, _receiver) = State::new();
+        let lunch = state.task_def(Task::new("eat lunch".to_owned(), false));
+        let rent = state.task_def(Task::new("pay rent".to_owned(), false));
+        let view_key = state.view_def(View {
+            tasks: Some(vec![lunch, rent]),
+            ..View::new("Main View".to_owned())
+        });
+        let mut board = Board::new(view_key);
+        let area = Rect::new(0, 0, 80, 10);
+        let mut buffer = Buffer::empty(area);
+        board.render(&state, Block::default(), area, &mut buffer);
+        let debug_string = format!("{:?}", buffer);
+        assert!(debug_string.contains("Grouped by completion"));
+        assert!(debug_string.contains("Not Completed (2)"));
+
+        // moving a card to the completed column completes the task
+        let key = |code: KeyCode| Event::Key(code.into());
+        board.handle_term_event(&mut state, &key(KeyCode::Down));
+        assert_eq!(
+            board.handle_term_event(&mut state, &key(KeyCode::Char(']'))),
+            Some(true)
+        );
+        assert!(state.task_get(rent).unwrap().completed);
+        assert_eq!((board.column, board.card), (1, 0));
+
+        Box::new(GetSchemaResponse {
+            definitions: vec![PropertyDefinition {
+                name: "status".to_owned(),
+                typ: TaskPropType::Select,
+                description: String::new(),
+                default: None,
+                required: false,
+                allowed_values: vec![],
+                options: vec!["todo".to_owned(), "doing".to_owned(), "done".to_owned()],
+                formula: None,
+            }],
+            req_id: 0,
+        })
+        .update_state(&mut state)
+        .unwrap();
+        let status = state.prop_def_name("status");
+        state
+            .prop_set(lunch, status, TaskPropVariant::Select("todo".to_owned()))
+            .unwrap();
+        board.handle_term_event(&mut state, &key(KeyCode::Char('g')));
+        assert_eq!(
+            board.group_by(&state),
+            GroupBy::Property("status".to_owned())
+        );
+        let mut buffer = Buffer::empty(area);
+        board.render(&state, Block::default(), area, &mut buffer);
+        let debug_string = format!("{:?}", buffer);
+        assert!(debug_string.contains("todo (1)"));
+        assert!(debug_string.contains("doing (0)"));
+        assert!(debug_string.contains("No Value (1)"));
+
+        board.handle_term_event(&mut state, &key(KeyCode::Char(']')));
+        assert_eq!(
+            state.prop_get(lunch, status).unwrap(),
+            &TaskPropVariant::Select("doing".to_owned())
+        );
+        assert_eq!((board.column, board.card), (1, 0));
+        // tasks without the property get it when they are moved out of its column
+        board.handle_term_event(&mut state, &key(KeyCode::Right));
+        board.handle_term_event(&mut state, &key(KeyCode::Right));
+        board.handle_term_event(&mut state, &key(KeyCode::Char('[')));
+        assert_eq!(
+            state.prop_get(rent, status).unwrap(),
+            &TaskPropVariant::Select("done".to_owned())
+        );
+
+        assert_eq!(
+            board.handle_term_event(&mut state, &key(KeyCode::Esc)),
+            None
+        );
+    }
+}
@@ -0,0 +1,462 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveDateTime};
+use common::{TaskPropType, TaskPropVariant};
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{block::Title, Block, Paragraph, Widget},
+};
+
+use crate::mid::{PropNameKey, State, Task, TaskKey, ViewKey};
+
+use super::{
+    report_error, COMPLETED_TEXT_COLOR, GREYED_OUT_TEXT_COLOR, SELECTED_STYLE_FG, TEXT_COLOR,
+};
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// how the calendar lays out days
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum CalendarMode {
+    /// grid of the weeks of the cursor's month
+    #[default]
+    Month,
+    /// agenda of the cursor's week, one day after another
+    Week,
+}
+
+/// Calendar of the tasks of a view, plotted on one of their date properties.
+/// Tasks can be moved to another day, which updates the property.
+#[derive(Debug)]
+pub struct Calendar {
+    pub mode: CalendarMode,
+    view_key: ViewKey,
+    /// day under the cursor
+    cursor: NaiveDate,
+    /// index of the selected task among the tasks of the cursor's day
+    selected: usize,
+    today: NaiveDate,
+}
+
+impl Calendar {
+    /// create a calendar of a view with the cursor on `today`
+    pub fn new(view_key: ViewKey, today: NaiveDate) -> Self {
+        Self {
+            mode: CalendarMode::default(),
+            view_key,
+            cursor: today,
+            selected: 0,
+            today,
+        }
+    }
+    /// date property plotted, the one chosen for the view or the first date property of the schema
+    pub fn date_prop(&self, state: &State) -> Option<PropNameKey> {
+        state
+            .view_get(self.view_key)
+            .ok()?
+            .date_prop
+            .or_else(|| date_props(state).first().copied())
+    }
+    /// request the plotted property of the view's tasks from the server
+    pub fn fetch(&self, state: &mut State) {
+        if let Some(name_key) = self.date_prop(state) {
+            state.view_fetch_prop(self.view_key, name_key);
+        }
+    }
+    /// returns Some with boolean notifying calling event handler whether to trigger re-render.
+    /// returns None if the calendar should be closed
+    pub fn handle_term_event(&mut self, state: &mut State, event: &Event) -> Option<bool> {
+        let Event::Key(KeyEvent { code, .. }) = event else {
+            return Some(false);
+        };
+        match code {
+            KeyCode::Esc | KeyCode::Char('v') => return None,
+            KeyCode::Left => self.move_cursor(Duration::days(-1)),
+            KeyCode::Right => self.move_cursor(Duration::days(1)),
+            KeyCode::Up => self.move_cursor(Duration::weeks(-1)),
+            KeyCode::Down => self.move_cursor(Duration::weeks(1)),
+            KeyCode::Char('t') => self.move_cursor(self.today - self.cursor),
+            KeyCode::Tab => self.selected += 1,
+            KeyCode::Char('[') => self.move_selected(state, Duration::days(-1)),
+            KeyCode::Char(']') => self.move_selected(state, Duration::days(1)),
+            KeyCode::Char('w') => {
+                self.mode = match self.mode {
+                    CalendarMode::Month => CalendarMode::Week,
+                    CalendarMode::Week => CalendarMode::Month,
+                }
+            }
+            KeyCode::Char('p') => self.cycle_date_prop(state),
+            _ => return Some(false),
+        }
+        Some(true)
+    }
+    /// bottom bar instructions while the calendar is shown
+    pub fn instructions(&self) -> Title<'static> {
+        Title::from(Line::from(vec![
+            " Day: ".into(),
+            "<Left>".blue().bold(),
+            "/".into(),
+            "<Right>".blue().bold(),
+            " Move: ".into(),
+            "<[>".blue().bold(),
+            "/".into(),
+            "<]>".blue().bold(),
+            " Mode: ".into(),
+            "<w>".blue().bold(),
+            " Close: ".into(),
+            "<v> ".blue().bold(),
+        ]))
+    }
+    fn move_cursor(&mut self, by: Duration) {
+        self.cursor += by;
+        self.selected = 0;
+    }
+    // the selected task of the cursor's day, wrapping around the day's tasks
+    fn selected_task(&self, state: &State, name_key: PropNameKey) -> Option<TaskKey> {
+        let tasks = self.day_tasks(state, name_key, self.cursor);
+        tasks
+            .get(self.selected.checked_rem(tasks.len())?)
+            .map(|(_, key, _)| *key)
+    }
+    // move the selected task and the cursor to another day, keeping the task's time of day
+    fn move_selected(&mut self, state: &mut State, by: Duration) {
+        let Some(name_key) = self.date_prop(state) else {
+            return;
+        };
+        let Some(task_key) = self.selected_task(state, name_key) else {
+            return;
+        };
+        let res = state.prop_mod(task_key, name_key, |prop| {
+            if let TaskPropVariant::Date(date) = prop {
+                *date += by;
+            }
+        });
+        if let Err(err) = res {
+            report_error(err);
+            return;
+        }
+        self.cursor += by;
+        // keep the moved task selected on its new day
+        self.selected = self
+            .day_tasks(state, name_key, self.cursor)
+            .iter()
+            .position(|(_, key, _)| *key == task_key)
+            .unwrap_or(0);
+    }
+    // plot the next date property of the schema and remember it for the view
+    fn cycle_date_prop(&mut self, state: &mut State) {
+        let props = date_props(state);
+        let index = self
+            .date_prop(state)
+            .and_then(|current| props.iter().position(|key| *key == current))
+            .map_or(0, |i| (i + 1) % props.len());
+        let Some(name_key) = props.get(index).copied() else {
+            return;
+        };
+        state.view_mod(self.view_key, |view| view.date_prop = Some(name_key));
+        state.view_fetch_prop(self.view_key, name_key);
+        self.selected = 0;
+    }
+    // tasks of the view dated from `from` until (excluding) `to`, by day, ordered by time and then name
+    fn tasks_by_day<'a>(
+        &self,
+        state: &'a State,
+        name_key: PropNameKey,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> BTreeMap<NaiveDate, Vec<(NaiveDateTime, TaskKey, &'a Task)>> {
+        let mut days: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for (key, task) in state.view_tasks(self.view_key).into_iter().flatten() {
+            if let Ok(TaskPropVariant::Date(date)) = state.prop_get(key, name_key) {
+                if (from..to).contains(&date.date()) {
+                    days.entry(date.date())
+                        .or_default()
+                        .push((*date, key, task));
+                }
+            }
+        }
+        for tasks in days.values_mut() {
+            tasks.sort_by(|(a_date, _, a), (b_date, _, b)| {
+                a_date.cmp(b_date).then_with(|| a.name.cmp(&b.name))
+            });
+        }
+        days
+    }
+    fn day_tasks<'a>(
+        &self,
+        state: &'a State,
+        name_key: PropNameKey,
+        day: NaiveDate,
+    ) -> Vec<(NaiveDateTime, TaskKey, &'a Task)> {
+        self.tasks_by_day(state, name_key, day, day + Duration::days(1))
+            .remove(&day)
+            .unwrap_or_default()
+    }
+    // line of a task, highlighted if it's the selected task of the cursor's day
+    fn task_line<'a>(&self, task: &Task, prefix: String, selected: bool) -> Line<'a> {
+        let mut style: Style = if task.completed {
+            COMPLETED_TEXT_COLOR.into()
+        } else {
+            TEXT_COLOR.into()
+        };
+        if task.current_rollback.is_some() {
+            style = GREYED_OUT_TEXT_COLOR.into();
+        }
+        if selected {
+            style = style.add_modifier(Modifier::REVERSED).fg(SELECTED_STYLE_FG);
+        }
+        let mark = if task.completed { "✓" } else { "☐" };
+        Line::styled(format!("{prefix}{mark} {}", task.name), style)
+    }
+    // style of a day's header
+    fn day_style(&self, day: NaiveDate) -> Style {
+        let mut style = Style::new().bold();
+        if day.month() != self.cursor.month() && self.mode == CalendarMode::Month {
+            style = style.fg(GREYED_OUT_TEXT_COLOR);
+        }
+        if day == self.today {
+            style = style.add_modifier(Modifier::UNDERLINED);
+        }
+        if day == self.cursor {
+            style = style.add_modifier(Modifier::REVERSED).fg(SELECTED_STYLE_FG);
+        }
+        style
+    }
+    // render calendar to buffer
+    pub fn render(&mut self, state: &State, block: Block<'_>, area: Rect, buf: &mut Buffer) {
+        let inner = block.inner(area);
+        block.render(area, buf);
+        let Some(name_key) = self.date_prop(state) else {
+            Paragraph::new("No Date Properties to Plot")
+                .centered()
+                .render(inner, buf);
+            return;
+        };
+        let rows = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).split(inner);
+        let name = state
+            .prop_definition(name_key)
+            .map_or("", |definition| definition.name.as_str());
+        let header = match self.mode {
+            CalendarMode::Month => format!("{} · {name}", self.cursor.format("%B %Y")),
+            CalendarMode::Week => format!(
+                "Week of {} · {name}",
+                self.week_start().format("%B %-d, %Y")
+            ),
+        };
+        Paragraph::new(Line::styled(header, Style::new().bold()))
+            .centered()
+            .render(rows[0], buf);
+        match self.mode {
+            CalendarMode::Month => self.render_month(state, name_key, rows[1], buf),
+            CalendarMode::Week => self.render_week(state, name_key, rows[1], buf),
+        }
+    }
+    fn week_start(&self) -> NaiveDate {
+        self.cursor - Duration::days(self.cursor.weekday().num_days_from_monday().into())
+    }
+    // grid of the weeks of the cursor's month, each cell lists the day's tasks
+    fn render_month(&self, state: &State, name_key: PropNameKey, area: Rect, buf: &mut Buffer) {
+        let first = self
+            .cursor
+            .with_day(1)
+            .expect("every month has a first day");
+        let start = first - Duration::days(first.weekday().num_days_from_monday().into());
+        let end = first + Months::new(1);
+        let weeks = ((end - start).num_days() + 6) / 7;
+        let rows = Layout::vertical(
+            std::iter::once(Constraint::Length(1))
+                .chain((0..weeks).map(|_| Constraint::Ratio(1, weeks as u32))),
+        )
+        .split(area);
+        let columns = |row: Rect| Layout::horizontal([Constraint::Ratio(1, 7); 7]).split(row);
+        for (weekday, cell) in WEEKDAYS.iter().zip(columns(rows[0]).iter()) {
+            Paragraph::new(*weekday)
+                .centered()
+                .style(Style::new().bold())
+                .render(*cell, buf);
+        }
+        let mut days = self.tasks_by_day(state, name_key, start, start + Duration::weeks(weeks));
+        for (week, row) in rows.iter().skip(1).enumerate() {
+            for (weekday, cell) in columns(*row).iter().enumerate() {
+                let day = start + Duration::days((week * 7 + weekday) as i64);
+                let tasks = days.remove(&day).unwrap_or_default();
+                let mut lines = vec![Line::styled(
+                    format!("{:>2}", day.day()),
+                    self.day_style(day),
+                )];
+                let shown = tasks.len().min((cell.height as usize).saturating_sub(1));
+                // make room for the overflow count
+                let shown = if shown < tasks.len() {
+                    shown.saturating_sub(1)
+                } else {
+                    shown
+                };
+                let selected = self.selected.checked_rem(tasks.len());
+                lines.extend(
+                    tasks
+                        .iter()
+                        .take(shown)
+                        .enumerate()
+                        .map(|(i, (_, _, task))| {
+                            self.task_line(
+                                task,
+                                String::new(),
+                                day == self.cursor && selected == Some(i),
+                            )
+                        }),
+                );
+                if shown < tasks.len() {
+                    lines.push(Line::styled(
+                        format!("+{} more", tasks.len() - shown),
+                        Style::new().fg(GREYED_OUT_TEXT_COLOR),
+                    ));
+                }
+                Paragraph::new(lines).render(*cell, buf);
+            }
+        }
+    }
+    // agenda of the cursor's week, tasks listed with their time under each day
+    fn render_week(&self, state: &State, name_key: PropNameKey, area: Rect, buf: &mut Buffer) {
+        let start = self.week_start();
+        let mut days = self.tasks_by_day(state, name_key, start, start + Duration::weeks(1));
+        let mut lines = vec![];
+        let mut cursor_line = 0;
+        for day in start.iter_days().take(7) {
+            if day == self.cursor {
+                cursor_line = lines.len();
+            }
+            lines.push(Line::styled(
+                day.format("%a %m-%d").to_string(),
+                self.day_style(day),
+            ));
+            let tasks = days.remove(&day).unwrap_or_default();
+            let selected = self.selected.checked_rem(tasks.len());
+            lines.extend(tasks.iter().enumerate().map(|(i, (date, _, task))| {
+                self.task_line(
+                    task,
+                    format!("  {} ", date.format("%H:%M")),
+                    day == self.cursor && selected == Some(i),
+                )
+            }));
+        }
+        // keep the cursor's day on screen
+        let scroll = if cursor_line < area.height as usize {
+            0
+        } else {
+            cursor_line as u16
+        };
+        Paragraph::new(lines).scroll((scroll, 0)).render(area, buf);
+    }
+}
+
+// date properties of the schema, ordered by name
+fn date_props(state: &State) -> Vec<PropNameKey> {
+    state
+        .prop_definitions()
+        .into_iter()
+        .filter(|(_, definition)| definition.typ == TaskPropType::Date)
+        .map(|(key, _)| key)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{backend::GetSchemaResponse, PropertyDefinition};
+    use ratatui::{buffer::Buffer, layout::Rect};
+
+    use super::*;
+    use crate::mid::{ServerResponse, View};
+
+    fn date(day: u32, hour: u32) -> TaskPropVariant {
+        TaskPropVariant::Date(
+            NaiveDate::from_ymd_opt(2024, 3, day)
+                .unwrap()
+                .and_hms_opt(hour, 0, 0)
+                .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_calendar() {
+        let (mut state, _receiver) = State::new();
+        let lunch = state.task_def(Task::new("eat lunch".to_owned(), false));
+        let rent = state.task_def(Task::new("pay rent".to_owned(), false));
+        let view_key = state.view_def(View {
+            tasks: Some(vec![lunch, rent]),
+            ..View::new("Main View".to_owned())
+        });
+        let mut calendar = Calendar::new(view_key, NaiveDate::from_ymd_opt(2024, 3, 4).unwrap());
+        let area = Rect::new(0, 0, 84, 20);
+        let mut buffer = Buffer::empty(area);
+        calendar.render(&state, Block::default(), area, &mut buffer);
+        assert!(format!("{:?}", buffer).contains("No Date Properties to Plot"));
+
+        let definition = |name: &str| PropertyDefinition {
+            name: name.to_owned(),
+            typ: TaskPropType::Date,
+            description: String::new(),
+            default: None,
+            required: false,
+            allowed_values: vec![],
+            options: vec![],
+            formula: None,
+        };
+        Box::new(GetSchemaResponse {
+            definitions: vec![definition("due"), definition("start")],
+            req_id: 0,
+        })
+        .update_state(&mut state)
+        .unwrap();
+        let due = state.prop_def_name("due");
+        let start = state.prop_def_name("start");
+        state.prop_set(lunch, due, date(4, 12)).unwrap();
+        state.prop_set(rent, due, date(4, 9)).unwrap();
+        state.prop_set(rent, start, date(1, 9)).unwrap();
+
+        let mut buffer = Buffer::empty(area);
+        calendar.render(&state, Block::default(), area, &mut buffer);
+        let debug_string = format!("{:?}", buffer);
+        assert!(debug_string.contains("March 2024 · due"));
+        assert!(debug_string.contains("Mon"));
+        assert!(debug_string.contains("☐ pay rent"));
+
+        // tasks of a day are ordered by time, move the second one to the next day
+        let key = |code: KeyCode| Event::Key(code.into());
+        assert_eq!(
+            calendar.handle_term_event(&mut state, &key(KeyCode::Tab)),
+            Some(true)
+        );
+        calendar.handle_term_event(&mut state, &key(KeyCode::Char(']')));
+        assert_eq!(state.prop_get(lunch, due).unwrap(), &date(5, 12));
+        assert_eq!(state.prop_get(rent, due).unwrap(), &date(4, 9));
+        assert_eq!(
+            calendar.cursor,
+            NaiveDate::from_ymd_opt(2024, 3, 5).unwrap()
+        );
+        assert_eq!(calendar.selected_task(&state, due), Some(lunch));
+
+        calendar.handle_term_event(&mut state, &key(KeyCode::Char('w')));
+        calendar.handle_term_event(&mut state, &key(KeyCode::Char('p')));
+        assert_eq!(calendar.date_prop(&state), Some(start));
+        let mut buffer = Buffer::empty(area);
+        calendar.render(&state, Block::default(), area, &mut buffer);
+        let debug_string = format!("{:?}", buffer);
+        assert!(debug_string.contains("Week of March 4, 2024 · start"));
+        assert!(debug_string.contains("Tue 03-05"));
+        assert!(!debug_string.contains("pay rent")); // starts the week before
+
+        calendar.handle_term_event(&mut state, &key(KeyCode::Up));
+        let mut buffer = Buffer::empty(area);
+        calendar.render(&state, Block::default(), area, &mut buffer);
+        assert!(format!("{:?}", buffer).contains("09:00 ☐ pay rent"));
+
+        assert_eq!(
+            calendar.handle_term_event(&mut state, &key(KeyCode::Esc)),
+            None
+        );
+    }
+}
@@ -0,0 +1,336 @@
+//! Rendering of Markdown task descriptions into styled lines.
+//! Supports headings, bullet, numbered and task lists, block quotes, rules, fenced and inline code,
+//! emphasis and links. Task list checkboxes can be toggled in the source text.
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+
+use super::{COMPLETED_TEXT_COLOR, SELECTED_STYLE_FG};
+
+const HEADING_COLOR: Color = Color::Cyan;
+const CODE_COLOR: Color = Color::Yellow;
+const LINK_COLOR: Color = Color::Blue;
+const QUOTE_COLOR: Color = Color::Gray;
+
+/// lines of the text with the byte offset they start at and whether they are part of a fenced code block,
+/// fences included
+fn source_lines(text: &str) -> impl Iterator<Item = (usize, &str, bool)> {
+    let mut offset = 0;
+    let mut in_code = false;
+    text.split_inclusive('\n').map(move |line| {
+        let start = offset;
+        offset += line.len();
+        let line = line.trim_end_matches(['\n', '\r']);
+        let fence = line.trim_start().starts_with("```");
+        if fence {
+            in_code = !in_code;
+        }
+        (start, line, in_code || fence)
+    })
+}
+
+/// a task list item, e.g. "- [x] buy milk"
+struct Checkbox<'a> {
+    indent: &'a str,
+    checked: bool,
+    /// byte offset of the mark between the brackets in the line
+    mark: usize,
+    text: &'a str,
+}
+
+fn checkbox(line: &str) -> Option<Checkbox> {
+    let (indent, item) = bullet(line)?;
+    let checked = match item.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    let text = &item[3..];
+    if !text.is_empty() && !text.starts_with(' ') {
+        return None;
+    }
+    Some(Checkbox {
+        indent,
+        checked,
+        mark: indent.len() + 3,
+        text: text.trim_start(),
+    })
+}
+
+/// indentation and text of a bullet list item
+fn bullet(line: &str) -> Option<(&str, &str)> {
+    let item = line.trim_start();
+    let indent = &line[..line.len() - item.len()];
+    let text = item.strip_prefix(['-', '*', '+'])?.strip_prefix(' ')?;
+    Some((indent, text))
+}
+
+/// indentation, number and text of a numbered list item
+fn numbered(line: &str) -> Option<(&str, &str, &str)> {
+    let item = line.trim_start();
+    let indent = &line[..line.len() - item.len()];
+    let (number, text) = item.split_once(". ")?;
+    (!number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))
+        .then_some((indent, number, text))
+}
+
+/// level and text of a heading
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.bytes().take_while(|b| *b == b'#').count();
+    let text = line[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then_some((level, text))
+}
+
+fn is_rule(line: &str) -> bool {
+    let line = line.trim();
+    line.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|c| line.chars().all(|l| l == *c))
+}
+
+/// number of checkboxes in the text
+pub fn checkbox_count(text: &str) -> usize {
+    source_lines(text)
+        .filter(|(_, line, code)| !code && checkbox(line).is_some())
+        .count()
+}
+
+/// the text with its `index`th checkbox checked or unchecked, None if it has fewer checkboxes
+pub fn toggle_checkbox(text: &str, index: usize) -> Option<String> {
+    let (start, checkbox) = source_lines(text)
+        .filter(|(_, _, code)| !code)
+        .filter_map(|(start, line, _)| Some((start, checkbox(line)?)))
+        .nth(index)?;
+    let mark = if checkbox.checked { " " } else { "x" };
+    let mut text = text.to_owned();
+    text.replace_range(start + checkbox.mark..start + checkbox.mark + 1, mark);
+    Some(text)
+}
+
+/// render the text, highlighting the `selected`th checkbox
+pub fn render(text: &str, selected: Option<usize>) -> Vec<Line<'static>> {
+    let mut lines = vec![];
+    let mut checkboxes = 0;
+    for (_, line, code) in source_lines(text) {
+        if code {
+            if !line.trim_start().starts_with("```") {
+                lines.push(Line::styled(
+                    format!("  {line}"),
+                    Style::new().fg(CODE_COLOR),
+                ));
+            }
+            continue;
+        }
+        let rendered = if let Some(checkbox) = checkbox(line) {
+            let mut mark_style = Style::new();
+            if checkbox.checked {
+                mark_style = mark_style.fg(COMPLETED_TEXT_COLOR);
+            }
+            if selected == Some(checkboxes) {
+                mark_style = mark_style
+                    .fg(SELECTED_STYLE_FG)
+                    .add_modifier(Modifier::REVERSED);
+            }
+            checkboxes += 1;
+            let mark = if checkbox.checked { "✓" } else { "☐" };
+            let mut spans = vec![
+                Span::raw(checkbox.indent.to_owned()),
+                Span::styled(mark, mark_style),
+                Span::raw(" "),
+            ];
+            spans.extend(inline(checkbox.text, Style::new()));
+            Line::from(spans)
+        } else if let Some((level, text)) = heading(line) {
+            let mut style = Style::new().fg(HEADING_COLOR).add_modifier(Modifier::BOLD);
+            if level == 1 {
+                style = style.add_modifier(Modifier::UNDERLINED);
+            }
+            Line::from(inline(text, style))
+        } else if let Some((indent, text)) = bullet(line) {
+            let mut spans = vec![Span::raw(format!("{indent}• "))];
+            spans.extend(inline(text, Style::new()));
+            Line::from(spans)
+        } else if let Some((indent, number, text)) = numbered(line) {
+            let mut spans = vec![Span::raw(format!("{indent}{number}. "))];
+            spans.extend(inline(text, Style::new()));
+            Line::from(spans)
+        } else if let Some(text) = line.strip_prefix('>') {
+            let style = Style::new().fg(QUOTE_COLOR).add_modifier(Modifier::ITALIC);
+            let mut spans = vec![Span::styled("│ ", Style::new().fg(QUOTE_COLOR))];
+            spans.extend(inline(text.trim_start(), style));
+            Line::from(spans)
+        } else if is_rule(line) {
+            Line::styled("─".repeat(20), Style::new().fg(QUOTE_COLOR))
+        } else {
+            Line::from(inline(line, Style::new()))
+        };
+        lines.push(rendered);
+    }
+    lines
+}
+
+/// text of a link and where it points to, followed by the text after it
+fn link(text: &str) -> Option<(&str, &str, &str)> {
+    let (label, rest) = text.strip_prefix('[')?.split_once("](")?;
+    let (url, rest) = rest.split_once(')')?;
+    (!label.contains(']')).then_some((label, url, rest))
+}
+
+/// spans of a line of text with inline code, emphasis and links
+fn inline(text: &str, base: Style) -> Vec<Span<'static>> {
+    let mut spans = vec![];
+    let mut plain = String::new();
+    let (mut bold, mut italic) = (false, false);
+    let style = |bold: bool, italic: bool| {
+        let mut style = base;
+        if bold {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if italic {
+            style = style.add_modifier(Modifier::ITALIC);
+        }
+        style
+    };
+    let flush = |spans: &mut Vec<Span<'static>>, plain: &mut String, style: Style| {
+        if !plain.is_empty() {
+            spans.push(Span::styled(std::mem::take(plain), style));
+        }
+    };
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("**") {
+            flush(&mut spans, &mut plain, style(bold, italic));
+            bold = !bold;
+            rest = after;
+            continue;
+        }
+        // a lone `*` only opens emphasis right before a word, e.g. not in "2 * 3"
+        let after = &rest[c.len_utf8()..];
+        if c == '*' && (italic || after.starts_with(|c: char| !c.is_whitespace())) {
+            flush(&mut spans, &mut plain, style(bold, italic));
+            italic = !italic;
+            rest = after;
+            continue;
+        }
+        if c == '`' {
+            if let Some((code, after)) = after.split_once('`') {
+                flush(&mut spans, &mut plain, style(bold, italic));
+                spans.push(Span::styled(code.to_owned(), base.fg(CODE_COLOR)));
+                rest = after;
+                continue;
+            }
+        }
+        if c == '[' {
+            if let Some((label, url, after)) = link(rest) {
+                flush(&mut spans, &mut plain, style(bold, italic));
+                let link_style = style(bold, italic)
+                    .fg(LINK_COLOR)
+                    .add_modifier(Modifier::UNDERLINED);
+                spans.push(Span::styled(label.to_owned(), link_style));
+                if label != url {
+                    spans.push(Span::styled(
+                        format!(" ({url})"),
+                        Style::new().fg(QUOTE_COLOR),
+                    ));
+                }
+                rest = after;
+                continue;
+            }
+        }
+        plain.push(c);
+        rest = after;
+    }
+    flush(&mut spans, &mut plain, style(bold, italic));
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESCRIPTION: &str = "# Trip\n\
+        Book **early**, see [the site](https://example.com).\n\
+        - [ ] flights\n\
+        - [x] hotel\n\
+        ```\n\
+        - [ ] not a checkbox\n\
+        ```\n\
+        1. pack `bags`\n\
+        - go";
+
+    fn line_text(line: &Line) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn test_render() {
+        let lines = render(DESCRIPTION, Some(1));
+        let text = lines.iter().map(line_text).collect::<Vec<_>>();
+        assert_eq!(
+            text,
+            vec![
+                "Trip",
+                "Book early, see the site (https://example.com).",
+                "☐ flights",
+                "✓ hotel",
+                "  - [ ] not a checkbox",
+                "1. pack bags",
+                "• go",
+            ]
+        );
+        assert!(lines[0].spans[0]
+            .style
+            .add_modifier
+            .contains(Modifier::BOLD));
+        assert_eq!(lines[1].spans[1].content, "early");
+        assert!(lines[1].spans[1]
+            .style
+            .add_modifier
+            .contains(Modifier::BOLD));
+        assert_eq!(lines[1].spans[3].style.fg, Some(LINK_COLOR));
+        // the selected checkbox is highlighted
+        assert!(!lines[2].spans[1]
+            .style
+            .add_modifier
+            .contains(Modifier::REVERSED));
+        assert!(lines[3].spans[1]
+            .style
+            .add_modifier
+            .contains(Modifier::REVERSED));
+        assert_eq!(lines[5].spans[2].style.fg, Some(CODE_COLOR));
+    }
+
+    #[test]
+    fn test_inline_emphasis() {
+        let text = |line: &str| {
+            inline(line, Style::new())
+                .iter()
+                .map(|span| span.content.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            text("an *important* step"),
+            vec!["an ", "important", " step"]
+        );
+        assert_eq!(text("2 * 3 = 6"), vec!["2 * 3 = 6"]);
+        assert_eq!(text("[unclosed link"), vec!["[unclosed link"]);
+    }
+
+    #[test]
+    fn test_toggle_checkbox() {
+        // checkboxes in code blocks are skipped
+        assert_eq!(checkbox_count(DESCRIPTION), 2);
+        let toggled = toggle_checkbox(DESCRIPTION, 0).unwrap();
+        assert!(toggled.contains("- [x] flights\n"));
+        let toggled = toggle_checkbox(&toggled, 1).unwrap();
+        assert!(toggled.contains("- [ ] hotel\n"));
+        assert!(toggled.contains("- [ ] not a checkbox\n"));
+        assert_eq!(toggled.len(), DESCRIPTION.len());
+        assert_eq!(toggle_checkbox(DESCRIPTION, 2), None);
+    }
+}
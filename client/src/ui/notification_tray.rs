@@ -0,0 +1,167 @@
+use common::backend::{Notification, NotificationKind};
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    symbols::border,
+    text::{Line, Span},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Clear, HighlightSpacing, List, ListState, Paragraph, StatefulWidget,
+        Widget,
+    },
+};
+
+use crate::mid::{State, TaskKey};
+
+use super::SELECTED_STYLE_FG;
+
+const OVERDUE_TEXT_COLOR: Color = Color::Red;
+
+/// Tray listing the notifications fired by the server's scheduler, newest first.
+/// <Enter> jumps to the task of the selected notification.
+#[derive(Debug, Default)]
+pub struct NotificationTray {
+    /// whether the tray is open
+    pub shown: bool,
+    /// number of received notifications the user has seen
+    seen: usize,
+    list_state: ListState,
+}
+
+impl NotificationTray {
+    /// number of notifications received since the tray was last open
+    pub fn unread(&self, state: &State) -> usize {
+        state.notifications().len().saturating_sub(self.seen)
+    }
+    /// open the tray with the newest notification selected
+    pub fn open(&mut self, state: &State) {
+        self.shown = true;
+        self.seen = state.notifications().len();
+        self.list_state.select(Some(0));
+    }
+    /// returns Ok with boolean notifying calling event handler whether to trigger re-render.
+    /// returns Err with the task to jump to (if any) if the tray should be closed
+    pub fn handle_term_event(
+        &mut self,
+        state: &mut State,
+        event: &Event,
+    ) -> Result<bool, Option<TaskKey>> {
+        let Event::Key(KeyEvent { code, .. }) = event else {
+            return Ok(false);
+        };
+        let len = state.notifications().len();
+        match code {
+            KeyCode::Esc | KeyCode::Char('n') => return Err(None),
+            KeyCode::Enter => {
+                let selected = self.list_state.selected().unwrap_or(0);
+                // newest notification is shown first
+                let key = len
+                    .checked_sub(selected + 1)
+                    .and_then(|i| state.notifications()[i].0);
+                return Err(key);
+            }
+            KeyCode::Char('c') => {
+                state.notifications_clear();
+                self.seen = 0;
+            }
+            KeyCode::Up => {
+                let index = self.list_state.selected().unwrap_or(0).saturating_sub(1);
+                self.list_state.select(Some(index));
+            }
+            KeyCode::Down => {
+                let index = self.list_state.selected().map_or(0, |i| i + 1);
+                self.list_state
+                    .select(Some(index.min(len.saturating_sub(1))));
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+    pub fn render(&mut self, state: &State, area: Rect, buf: &mut Buffer) {
+        // notifications that come in while the tray is open are seen
+        self.seen = state.notifications().len();
+
+        // create a centered rect that takes up 60% of the area
+        let vertical_center = Layout::vertical([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .split(area);
+        let popup_area = Layout::horizontal([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .split(vertical_center[0])[0];
+
+        Clear.render(popup_area, buf); // clear background of popup area
+
+        let instructions = Title::from(Line::from(vec![
+            " Jump: ".into(),
+            "<Enter>".blue().bold(),
+            " Clear: ".into(),
+            "<c> ".blue().bold(),
+        ]));
+        let block = Block::default()
+            .title("Notifications")
+            .title(instructions.position(Position::Bottom))
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED);
+        if state.notifications().is_empty() {
+            Paragraph::new("No Notifications")
+                .centered()
+                .block(block)
+                .render(popup_area, buf);
+            return;
+        }
+        let lines = state
+            .notifications()
+            .iter()
+            .rev()
+            .map(|(key, notification)| notification_line(state, *key, notification))
+            .collect::<Vec<Line>>();
+        let list = List::new(lines)
+            .block(block)
+            .highlight_style(
+                Style::default()
+                    .add_modifier(Modifier::REVERSED)
+                    .fg(SELECTED_STYLE_FG),
+            )
+            .highlight_spacing(HighlightSpacing::Always)
+            .highlight_symbol(">");
+        StatefulWidget::render(list, popup_area, buf, &mut self.list_state);
+    }
+}
+
+// time the event fired at, followed by what happened
+fn notification_line<'a>(
+    state: &'a State,
+    key: Option<TaskKey>,
+    notification: &'a Notification,
+) -> Line<'a> {
+    let task = key
+        .and_then(|key| state.task_get(key).ok())
+        .map_or("", |task| task.name.as_str());
+    let mut spans = vec![Span::styled(
+        format!("{} ", notification.fire_at.format("%m-%d %H:%M")),
+        Style::new().italic(),
+    )];
+    match &notification.kind {
+        NotificationKind::Reminder { property } => {
+            spans.push(Span::styled(task, Style::new().bold()));
+            spans.push(format!(" ({property})").into());
+        }
+        NotificationKind::Overdue => {
+            spans.push(Span::styled(task, Style::new().bold()));
+            spans.push(Span::styled(
+                " is overdue",
+                Style::new().fg(OVERDUE_TEXT_COLOR),
+            ));
+        }
+        NotificationKind::ScriptRun(script_id) => {
+            spans.push(format!("script {script_id} is scheduled to run").into());
+        }
+        NotificationKind::Comment { author, .. } => {
+            spans.push(format!("{author} commented on ").into());
+            spans.push(Span::styled(task, Style::new().bold()));
+        }
+    }
+    Line::from(spans)
+}
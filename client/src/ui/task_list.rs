@@ -1,33 +1,71 @@
+mod attachment_popup;
+mod comment_thread;
+mod detail_pane;
+mod search_popup;
+mod select_popup;
+mod tag_picker;
 mod task_popup;
 
-use std::collections::BTreeSet;
+use std::collections::{HashMap, HashSet};
 
-use crossterm::event::{Event, KeyCode};
+use common::{
+    backend::{GroupKey, TaskGroup},
+    GroupBy, Summary, TaskPropType, TaskPropVariant,
+};
+use crossterm::event::{Event, KeyCode, KeyModifiers};
 use ratatui::{
     buffer::Buffer,
-    layout::Rect,
+    layout::{Constraint, Layout, Rect},
     style::{Modifier, Style},
-    text::{Line, Text},
+    text::{Line, Span, Text},
     widgets::{Block, HighlightSpacing, List, ListState, Paragraph, StatefulWidget, Widget},
 };
 
 use crate::{
-    mid::{State, Task, TaskKey, ViewKey},
-    ui::{report_error, task_list::task_popup::CloseError},
+    mid::{State, Task, TaskKey, View, ViewKey},
+    ui::{report_error, task_list::task_popup::CloseError, value_title},
 };
 
+use attachment_popup::AttachmentPopup;
+use detail_pane::DetailPane;
+use search_popup::SearchPopup;
+use select_popup::SelectPopup;
+use tag_picker::{tag_chips, TagPicker};
 use task_popup::TaskPopup;
 
 use super::{COMPLETED_TEXT_COLOR, GREYED_OUT_TEXT_COLOR, SELECTED_STYLE_FG, TEXT_COLOR};
 
+/// how close the selection has to be to the end of the list before more tasks are requested
+const LOAD_MORE_MARGIN: usize = 10;
+
+/// a line of the task list
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Row {
+    /// header of a group, by index in the groups of the grouping view
+    Group(usize),
+    Task(TaskKey),
+}
+
 #[derive(Default, Debug)]
 /// Task list widget
 pub struct TaskList {
     pub list_state: ListState,
     /// views that we source the task list from
     source_views: Vec<ViewKey>,
-    shown_tasks: Vec<TaskKey>,
+    rows: Vec<Row>,
+    /// groups whose tasks are hidden
+    collapsed: Vec<GroupKey>,
+    /// whether subtasks are shown indented under their parent
+    outline: bool,
+    /// how deep each shown task is nested in the outline
+    depths: HashMap<TaskKey, usize>,
     task_popup: Option<TaskPopup>,
+    search_popup: Option<SearchPopup>,
+    select_popup: Option<SelectPopup>,
+    attachment_popup: Option<AttachmentPopup>,
+    tag_picker: Option<TagPicker>,
+    /// description of the selected task, shown beside the list
+    detail_pane: Option<DetailPane>,
 }
 impl TaskList {
     /// remove unused items
@@ -35,14 +73,11 @@ impl TaskList {
         // keep track of number of items removed so we can adjust selected item (if something is currently selected)
         let mut removed_count = 0;
         let mut did_switch = false;
-        let current_task = self
-            .list_state
-            .selected()
-            .and_then(|s| self.shown_tasks.get(s).cloned());
-        self.shown_tasks
-            .extract_if(|k| state.task_get(*k).is_err())
-            .for_each(|key| {
-                if Some(key) == current_task {
+        let current_row = self.selected_row();
+        self.rows
+            .extract_if(|row| matches!(row, Row::Task(k) if state.task_get(*k).is_err()))
+            .for_each(|row| {
+                if Some(row) == current_row {
                     did_switch = true;
                 };
                 if !did_switch {
@@ -50,7 +85,7 @@ impl TaskList {
                 }
             });
 
-        let len = self.shown_tasks.len();
+        let len = self.rows.len();
         if len == 0 {
             // reset selection if neeeded
             self.list_state.select(None);
@@ -67,34 +102,251 @@ impl TaskList {
         func(&mut self.source_views);
         self.rebuild_list(state);
     }
+    /// the first source view that is grouped, once its groups have been received
+    fn grouping_view<'a>(&self, state: &'a State) -> Option<&'a View> {
+        self.source_views
+            .iter()
+            .flat_map(|key| state.view_get(*key).ok())
+            .find(|view| view.group_by != GroupBy::None && !view.groups.is_empty())
+    }
     /// recreate the shown list
     pub fn rebuild_list(&mut self, state: &State) {
-        let mut set = BTreeSet::new();
-        // collect all items from source views into set
-        self.source_views
+        let current_row = self.selected_row();
+        let mut set = HashSet::new();
+        // collect all items from source views in view order, skipping duplicates
+        let tasks = self
+            .source_views
             .iter()
             .flat_map(|key| state.view_get(*key).ok())
-            .map(|view| set.extend(view.tasks.iter().flatten()))
-            .last();
-        // clear tasks and extend it with the generated set
-        self.shown_tasks.clear();
-        self.shown_tasks.extend(set.iter());
-        self.list_state.select(None); // clear selection
-    }
-    /// get currently selected task
+            .flat_map(|view| view.tasks.iter().flatten())
+            .filter(|key| set.insert(**key))
+            .cloned()
+            .collect::<Vec<TaskKey>>();
+        let grouping_view = self.grouping_view(state);
+        self.rows.clear();
+        self.depths.clear();
+        let outline = self.outline;
+        let depths = &mut self.depths;
+        let mut order = |tasks: Vec<TaskKey>| {
+            let tasks = if outline {
+                outline_order(state, tasks, depths)
+            } else {
+                tasks
+            };
+            tasks.into_iter().map(Row::Task).collect::<Vec<Row>>()
+        };
+        match grouping_view {
+            None => self.rows.extend(order(tasks)),
+            Some(view) => {
+                // tasks under the header of their group, in view order. tasks whose group isn't known yet go last
+                let mut grouped = vec![vec![]; view.groups.len()];
+                let mut ungrouped = vec![];
+                for key in tasks {
+                    match view.task_groups.get(&key).and_then(|i| grouped.get_mut(*i)) {
+                        Some(group) => group.push(key),
+                        None => ungrouped.push(key),
+                    }
+                }
+                for (index, tasks) in grouped.into_iter().enumerate() {
+                    self.rows.push(Row::Group(index));
+                    if !self.collapsed.contains(&view.groups[index].key) {
+                        self.rows.extend(order(tasks));
+                    }
+                }
+                self.rows.extend(order(ungrouped));
+            }
+        }
+        // keep selection on the same row if it is still shown
+        let selected = current_row.and_then(|row| self.rows.iter().position(|r| *r == row));
+        self.list_state.select(selected);
+    }
+    /// hide or show the tasks of a group
+    fn toggle_collapsed(&mut self, state: &State, index: usize) {
+        let Some(group) = self
+            .grouping_view(state)
+            .and_then(|view| view.groups.get(index))
+        else {
+            return;
+        };
+        match self.collapsed.iter().position(|key| *key == group.key) {
+            Some(i) => {
+                self.collapsed.swap_remove(i);
+            }
+            None => self.collapsed.push(group.key.clone()),
+        }
+        self.rebuild_list(state);
+    }
+    /// group the first source view by nothing, completion, each property of the schema and then dependency root in turn.
+    /// groups are summarized by the first number and date properties unless the view has its own summaries
+    fn cycle_group_by(&mut self, state: &mut State) {
+        let Some(view_key) = self.source_views.first().copied() else {
+            return;
+        };
+        let Ok(view) = state.view_get(view_key) else {
+            return;
+        };
+        let definitions = state.prop_definitions();
+        let mut options = vec![GroupBy::None, GroupBy::Completed];
+        options.extend(
+            definitions
+                .iter()
+                .map(|(_, definition)| GroupBy::Property(definition.name.clone())),
+        );
+        options.push(GroupBy::DependencyRoot);
+        let index = options
+            .iter()
+            .position(|option| *option == view.group_by)
+            .map_or(0, |i| (i + 1) % options.len());
+        let group_by = options.swap_remove(index);
+        let mut summaries = view.summaries.clone();
+        if summaries.is_empty() {
+            let first_of = |typ: TaskPropType| {
+                definitions
+                    .iter()
+                    .find(|(_, definition)| definition.typ == typ)
+                    .map(|(_, definition)| definition.name.clone())
+            };
+            if let Some(name) = first_of(TaskPropType::Number) {
+                summaries.push(Summary::Sum(name.clone()));
+                summaries.push(Summary::Average(name));
+            }
+            if let Some(name) = first_of(TaskPropType::Date) {
+                summaries.push(Summary::Earliest(name));
+            }
+        }
+        let grouped = group_by != GroupBy::None;
+        state.view_mod(view_key, |view| {
+            view.group_by = group_by;
+            view.summaries = summaries;
+            view.groups.clear();
+            view.task_groups.clear();
+        });
+        if grouped {
+            state.view_aggregate(view_key);
+        }
+        self.collapsed.clear();
+        self.rebuild_list(state);
+    }
+    /// nest the selected task under the task above it at the same depth, as its last subtask
+    fn indent_selected(&mut self, state: &mut State) {
+        let Some((key, sibling)) = self.selected_sibling(state, true) else {
+            return;
+        };
+        if let Err(err) = state.task_set_parent(key, Some(sibling), usize::MAX) {
+            report_error(err);
+        }
+        self.rebuild_list(state);
+    }
+    /// the selected task and the closest task above or below it at the same depth within its group and parent
+    fn selected_sibling(&mut self, state: &State, above: bool) -> Option<(TaskKey, TaskKey)> {
+        // selecting the task prunes the list, so the index is read after
+        let (key, _) = self.selected_task(state)?;
+        let index = self.list_state.selected()?;
+        let depth = self.depths.get(&key).copied().unwrap_or(0);
+        let rows: Box<dyn Iterator<Item = &Row>> = if above {
+            Box::new(self.rows[..index].iter().rev())
+        } else {
+            Box::new(self.rows[index + 1..].iter())
+        };
+        let (sibling, _) = rows
+            .map_while(|row| match row {
+                Row::Task(k) => Some((*k, self.depths.get(k).copied().unwrap_or(0))),
+                Row::Group(_) => None,
+            })
+            .take_while(|(_, d)| *d >= depth)
+            .find(|(_, d)| *d == depth)?;
+        Some((key, sibling))
+    }
+    /// swap the selected task with the task above or below it. subtasks shown under their parent are
+    /// reordered among their siblings, other tasks in the manual order of the first source view showing them
+    fn move_selected(&mut self, state: &mut State, up: bool) {
+        let Some((key, sibling)) = self.selected_sibling(state, up) else {
+            return;
+        };
+        if self.depths.get(&key).is_some_and(|depth| *depth > 0) {
+            let parent = state.task_get(key).ok().and_then(|task| task.parent);
+            let position = parent
+                .and_then(|parent| state.task_get(parent).ok())
+                .and_then(|parent| parent.subtasks.iter().position(|k| *k == sibling));
+            if let Some(position) = position {
+                if let Err(err) = state.task_set_parent(key, parent, position) {
+                    report_error(err);
+                }
+            }
+        } else {
+            let view_key = self.source_views.iter().copied().find(|view_key| {
+                state
+                    .view_task_keys(*view_key)
+                    .is_some_and(|mut keys| keys.any(|k| k == key))
+            });
+            if let Some(view_key) = view_key {
+                state.view_move_task(view_key, key, sibling, !up);
+            }
+        }
+        self.rebuild_list(state);
+    }
+    /// move the selected task out of its parent, placing it right after the parent
+    fn outdent_selected(&mut self, state: &mut State) {
+        let Some((key, task)) = self.selected_task(state) else {
+            return;
+        };
+        let Some(parent) = task.parent.and_then(|p| state.task_get(p).ok()) else {
+            return;
+        };
+        let grandparent = parent.parent;
+        let position = grandparent
+            .and_then(|g| state.task_get(g).ok())
+            .and_then(|g| g.subtasks.iter().position(|k| Some(*k) == task.parent))
+            .map_or(0, |i| i + 1);
+        if let Err(err) = state.task_set_parent(key, grandparent, position) {
+            report_error(err);
+        }
+        self.rebuild_list(state);
+    }
+    /// request more tasks from source views if the selection is close to the end of the list
+    pub fn load_more(&self, state: &mut State) {
+        let Some(selected) = self.list_state.selected() else {
+            return;
+        };
+        if selected + LOAD_MORE_MARGIN >= self.rows.len() {
+            for view_key in self.source_views.iter() {
+                state.view_load_more(*view_key);
+            }
+        }
+    }
+    /// request the tags of tasks in source views that weren't requested yet
+    pub fn fetch_tags(&self, state: &mut State) {
+        for view_key in self.source_views.iter() {
+            state.view_fetch_tags(*view_key);
+        }
+    }
+    /// select a task, adding it to the end of the shown tasks if it isn't shown
+    pub fn select_task(&mut self, key: TaskKey) {
+        let index = match self.rows.iter().position(|row| *row == Row::Task(key)) {
+            Some(index) => index,
+            None => {
+                self.rows.push(Row::Task(key));
+                self.rows.len() - 1
+            }
+        };
+        self.list_state.select(Some(index));
+    }
+    fn selected_row(&self) -> Option<Row> {
+        self.list_state
+            .selected()
+            .and_then(|s| self.rows.get(s).cloned())
+    }
+    /// get currently selected task, None if nothing or a group header is selected
     pub fn selected_task<'a>(&mut self, state: &'a State) -> Option<(TaskKey, &'a Task)> {
         self.prune_list(state);
-        if self.shown_tasks.is_empty() {
-            return None;
-        } // error if no tasks
-        self.list_state.selected().and_then(|r| {
-            let key = self.shown_tasks[r];
-            state.task_get(key).ok().map(|t| (key, t))
-        })
+        match self.selected_row()? {
+            Row::Task(key) => state.task_get(key).ok().map(|t| (key, t)),
+            Row::Group(_) => None,
+        }
     }
     // move current selection by amt in either direction, wrapping optionally
     pub fn shift(&mut self, amt: isize, wrap: bool) {
-        let len = self.shown_tasks.len();
+        let len = self.rows.len();
         // ensure we have at least 1 item
         if len == 0 {
             return;
@@ -137,17 +389,66 @@ impl TaskList {
                             CloseError::ModifyTaskError(err) => {
                                 log::error!("attempted to modify a task but got error: {err:?}")
                             }
-                            CloseError::AddTask(t) => self.shown_tasks.push(t),
+                            CloseError::PropDataError(err) => {
+                                log::error!("attempted to set a description but got error: {err:?}")
+                            }
+                            CloseError::AddTask(t) => self.rows.push(Row::Task(t)),
                         }
                     }
                     true
                 }
             };
         }
+        if let Some(search_popup) = &mut self.search_popup {
+            return match search_popup.handle_term_event(state, event) {
+                Ok(do_render) => do_render,
+                Err(jump_to) => {
+                    self.search_popup = None;
+                    if let Some(key) = jump_to {
+                        self.select_task(key);
+                    }
+                    true
+                }
+            };
+        }
+        if let Some(select_popup) = &mut self.select_popup {
+            return select_popup
+                .handle_term_event(state, event)
+                .unwrap_or_else(|| {
+                    self.select_popup = None;
+                    true
+                });
+        }
+        if let Some(attachment_popup) = &mut self.attachment_popup {
+            return attachment_popup
+                .handle_term_event(state, event)
+                .unwrap_or_else(|| {
+                    self.attachment_popup = None;
+                    true
+                });
+        }
+        if let Some(tag_picker) = &mut self.tag_picker {
+            return tag_picker
+                .handle_term_event(state, event)
+                .unwrap_or_else(|| {
+                    self.tag_picker = None;
+                    true
+                });
+        }
+        let selected = self.selected_task(state).map(|(key, _)| key);
+        if let Some(detail_pane) = &mut self.detail_pane {
+            if detail_pane.handle_term_event(state, selected, event) {
+                return true;
+            }
+        }
         let Event::Key(key_event) = event else {
             return false;
         };
         match key_event.code {
+            Char('/') => {
+                state.search(""); // clear results of previous search
+                self.search_popup = Some(SearchPopup::default());
+            }
             Char('c') => self.task_popup = Some(TaskPopup::Create(Default::default())), // create task
             Char('d') => {
                 // delete task
@@ -160,10 +461,57 @@ impl TaskList {
                     self.task_popup = TaskPopup::edit(selection, state);
                 }
             }
+            Char('E') => {
+                if let Some((selection, _)) = self.selected_task(state) {
+                    self.task_popup = TaskPopup::describe(selection, state);
+                }
+            }
+            Char('i') => {
+                self.detail_pane = match self.detail_pane {
+                    Some(_) => None,
+                    None => {
+                        DetailPane::fetch(state, &self.source_views);
+                        Some(DetailPane::default())
+                    }
+                }
+            }
+            Char('o') => {
+                if let Some((selection, _)) = self.selected_task(state) {
+                    self.select_popup = Some(SelectPopup::new(selection));
+                }
+            }
+            Char('a') => {
+                if let Some((selection, _)) = self.selected_task(state) {
+                    self.attachment_popup = Some(AttachmentPopup::new(selection, state));
+                }
+            }
+            Char('#') => {
+                if let Some((selection, _)) = self.selected_task(state) {
+                    self.tag_picker = Some(TagPicker::new(selection));
+                }
+            }
+            Char('g') => self.cycle_group_by(state),
+            Char('t') => {
+                self.outline = !self.outline;
+                self.rebuild_list(state);
+            }
+            Tab if self.outline => self.indent_selected(state),
+            BackTab if self.outline => self.outdent_selected(state),
+            Up if key_event.modifiers.contains(KeyModifiers::SHIFT) => {
+                self.move_selected(state, true)
+            }
+            Down if key_event.modifiers.contains(KeyModifiers::SHIFT) => {
+                self.move_selected(state, false)
+            }
             Up => self.shift(-1, false),
-            Down => self.shift(1, false),
+            Down => {
+                self.shift(1, false);
+                self.load_more(state);
+            }
             Enter => {
-                if let Some((selected_key, _)) = self.selected_task(state) {
+                if let Some(Row::Group(index)) = self.selected_row() {
+                    self.toggle_collapsed(state, index);
+                } else if let Some((selected_key, _)) = self.selected_task(state) {
                     let res = state.task_mod(selected_key, |t| t.completed = !t.completed);
                     if let Err(err) = res {
                         report_error(err);
@@ -172,20 +520,50 @@ impl TaskList {
             }
             _ => return false,
         }
+        // keep the detail pane on the task the list moved to
+        let selected = self.selected_task(state).map(|(key, _)| key);
+        if let Some(detail_pane) = &mut self.detail_pane {
+            detail_pane.show(state, selected);
+        }
         true // assume if didn't explicitly return false, that we should re-render
     }
     // render task list to buffer
     pub fn render(&mut self, state: &State, block: Block<'_>, area: Rect, buf: &mut Buffer) {
         // flat_map current tasks to make sure they're valid
         self.prune_list(state);
-        let valid_tasks = self
-            .shown_tasks
-            .iter()
-            .flat_map(|key| state.task_get(*key).ok().map(|t| (key, t)));
+        // the detail pane takes the right side of the area, popups still cover all of it
+        let full_area = area;
+        let selected = match self.selected_row() {
+            Some(Row::Task(key)) => Some(key),
+            _ => None,
+        };
+        let area = if let Some(detail_pane) = self.detail_pane.as_mut() {
+            let [list_area, pane_area] =
+                Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)])
+                    .areas(area);
+            detail_pane.render(state, selected, pane_area, buf);
+            list_area
+        } else {
+            area
+        };
+        let grouping_view = self.grouping_view(state);
+        let valid_rows = self.rows.iter().flat_map(|row| match row {
+            Row::Group(index) => {
+                grouping_view.and_then(|view| Some(Err((view, view.groups.get(*index)?))))
+            }
+            Row::Task(key) => state.task_get(*key).ok().map(|task| Ok((key, task))),
+        });
 
         // take items from the current view and render them into a list
-        let lines = valid_tasks
-            .map(|(_key, task)| {
+        let lines = valid_rows
+            .map(|row| {
+                let (key, task) = match row {
+                    Ok(task) => task,
+                    Err((view, group)) => {
+                        let collapsed = self.collapsed.contains(&group.key);
+                        return group_header(state, view, group, collapsed);
+                    }
+                };
                 let mut text_style: Style = if task.completed {
                     COMPLETED_TEXT_COLOR.into()
                 } else {
@@ -206,7 +584,14 @@ impl TaskList {
                     mark = "✓";
                 }
 
-                Line::styled(format!(" {mark} {}", task.name), text_style)
+                let indent = "  ".repeat(self.depths.get(key).copied().unwrap_or(0));
+                let mut spans = vec![Span::styled(
+                    format!(" {indent}{mark} {}", task.name),
+                    text_style,
+                )];
+                spans.extend(durations(state, *key));
+                spans.extend(tag_chips(state, *key));
+                Line::from(spans)
             })
             .collect::<Vec<Line>>();
 
@@ -238,7 +623,262 @@ impl TaskList {
         }
         // popup rendering
         if let Some(popup) = self.task_popup.as_mut() {
-            popup.render(area, buf)
+            popup.render(full_area, buf)
+        }
+        if let Some(popup) = self.search_popup.as_mut() {
+            popup.render(state, full_area, buf)
+        }
+        if let Some(popup) = self.select_popup.as_mut() {
+            popup.render(state, full_area, buf)
+        }
+        if let Some(popup) = self.attachment_popup.as_mut() {
+            popup.render(state, full_area, buf)
+        }
+        if let Some(popup) = self.tag_picker.as_mut() {
+            popup.render(state, full_area, buf)
+        }
+    }
+}
+
+/// order tasks depth-first so subtasks follow their parent in sibling order, recording the depth of each task.
+/// tasks whose parent isn't among the given tasks are roots
+fn outline_order(
+    state: &State,
+    tasks: Vec<TaskKey>,
+    depths: &mut HashMap<TaskKey, usize>,
+) -> Vec<TaskKey> {
+    let shown = tasks.iter().copied().collect::<HashSet<TaskKey>>();
+    let parent_shown = |key: &TaskKey| {
+        state
+            .task_get(*key)
+            .ok()
+            .and_then(|task| task.parent)
+            .is_some_and(|parent| shown.contains(&parent))
+    };
+    let mut visited = HashSet::new();
+    let mut ordered = Vec::with_capacity(tasks.len());
+    for root in tasks.iter().filter(|key| !parent_shown(key)) {
+        let mut stack = vec![(*root, 0)];
+        while let Some((key, depth)) = stack.pop() {
+            if !visited.insert(key) {
+                continue;
+            }
+            ordered.push(key);
+            depths.insert(key, depth);
+            if let Ok(task) = state.task_get(key) {
+                let subtasks = task.subtasks.iter().rev().filter(|k| shown.contains(k));
+                stack.extend(subtasks.map(|k| (*k, depth + 1)));
+            }
         }
     }
+    // tasks whose parents are waiting on the server to confirm a move may not be reachable from a root
+    ordered.extend(tasks.into_iter().filter(|key| visited.insert(*key)));
+    ordered
+}
+
+// header of a group: its title, number of tasks and summaries, e.g. "▼ todo (2) · sum estimate: 6"
+fn group_header<'a>(state: &State, view: &View, group: &TaskGroup, collapsed: bool) -> Line<'a> {
+    let title = match &group.key {
+        GroupKey::All => "All Tasks".to_owned(),
+        GroupKey::Completed(true) => "Completed".to_owned(),
+        GroupKey::Completed(false) => "Not Completed".to_owned(),
+        GroupKey::Value(Some(value)) => value_title(value),
+        GroupKey::Value(None) => "No Value".to_owned(),
+        GroupKey::Root(task_id) => state
+            .task_key(*task_id)
+            .and_then(|key| state.task_get(key).ok())
+            .map_or_else(|| format!("#{task_id}"), |task| task.name.clone()),
+    };
+    let arrow = if collapsed { "▶" } else { "▼" };
+    let mut header = format!("{arrow} {title} ({})", group.count);
+    for (summary, value) in view.summaries.iter().zip(group.summaries.iter()) {
+        let Some(value) = value else {
+            continue;
+        };
+        let label = match summary {
+            Summary::Sum(name) => format!("sum {name}"),
+            Summary::Average(name) => format!("avg {name}"),
+            Summary::Earliest(name) => format!("earliest {name}"),
+        };
+        let value = match value {
+            // averages don't need every digit
+            TaskPropVariant::Number(n) => format!("{}", (n * 100.0).round() / 100.0),
+            value => value_title(value),
+        };
+        header.push_str(&format!(" · {label}: {value}"));
+    }
+    Line::styled(header, Style::new().add_modifier(Modifier::BOLD))
+}
+
+/// loaded duration properties of a task, written like "2h30m"
+fn durations(state: &State, key: TaskKey) -> Vec<Span<'static>> {
+    state
+        .prop_definitions()
+        .into_iter()
+        .filter(|(_, definition)| definition.typ == TaskPropType::Duration)
+        .filter_map(|(name_key, _)| match state.prop_get(key, name_key) {
+            Ok(value @ TaskPropVariant::Duration(_)) => Some(Span::styled(
+                format!(" {}", value_title(value)),
+                Style::new().add_modifier(Modifier::DIM),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use common::{backend::GetSchemaResponse, PropertyDefinition, TaskDuration};
+    use ratatui::{buffer::Buffer, layout::Rect};
+
+    use super::*;
+    use crate::mid::ServerResponse;
+
+    #[tokio::test]
+    async fn test_group_headers() {
+        let (mut state, _receiver) = State::new();
+        let lunch = state.task_def(Task::new("eat lunch".to_owned(), false));
+        let rent = state.task_def(Task::new("pay rent".to_owned(), true));
+        let view_key = state.view_def(View {
+            tasks: Some(vec![lunch, rent]),
+            group_by: GroupBy::Completed,
+            summaries: vec![Summary::Sum("estimate".to_owned())],
+            // the server counts tasks of the view that haven't been loaded yet
+            groups: vec![
+                TaskGroup {
+                    key: GroupKey::Completed(false),
+                    count: 40,
+                    summaries: vec![Some(TaskPropVariant::Number(12.5))],
+                },
+                TaskGroup {
+                    key: GroupKey::Completed(true),
+                    count: 1,
+                    summaries: vec![None],
+                },
+            ],
+            task_groups: HashMap::from([(lunch, 0), (rent, 1)]),
+            ..View::new("Main View".to_owned())
+        });
+        let mut task_list = TaskList::default();
+        task_list.source_views_mod(&state, |views| views.push(view_key));
+        assert_eq!(
+            task_list.rows,
+            vec![
+                Row::Group(0),
+                Row::Task(lunch),
+                Row::Group(1),
+                Row::Task(rent)
+            ]
+        );
+        let area = Rect::new(0, 0, 60, 6);
+        let mut buffer = Buffer::empty(area);
+        task_list.render(&state, Block::default(), area, &mut buffer);
+        let debug_string = format!("{:?}", buffer);
+        assert!(debug_string.contains("Not Completed (40)"));
+        assert!(debug_string.contains("sum estimate: 12.5"));
+        assert!(debug_string.contains("Completed (1)"));
+
+        // <Enter> on a header collapses its group instead of completing a task
+        let key = |code: KeyCode| Event::Key(code.into());
+        task_list.list_state.select(Some(0));
+        task_list.handle_term_event(&mut state, &key(KeyCode::Enter));
+        assert_eq!(
+            task_list.rows,
+            vec![Row::Group(0), Row::Group(1), Row::Task(rent)]
+        );
+        assert!(task_list.selected_task(&state).is_none());
+        assert!(!state.task_get(lunch).unwrap().completed);
+        task_list.handle_term_event(&mut state, &key(KeyCode::Enter));
+        assert_eq!(task_list.rows.len(), 4);
+
+        // the view's groups are requested again when it is regrouped
+        task_list.handle_term_event(&mut state, &key(KeyCode::Char('g')));
+        let view = state.view_get(view_key).unwrap();
+        assert_eq!(view.group_by, GroupBy::DependencyRoot);
+        assert_eq!(view.summaries, vec![Summary::Sum("estimate".to_owned())]);
+        assert_eq!(task_list.rows, vec![Row::Task(lunch), Row::Task(rent)]);
+    }
+
+    #[tokio::test]
+    async fn test_outline() {
+        let (mut state, _receiver) = State::new();
+        let trip = state.task_def(Task::new("plan trip".to_owned(), false));
+        let flights = state.task_def(Task::new("book flights".to_owned(), false));
+        let seats = state.task_def(Task::new("pick seats".to_owned(), false));
+        let lunch = state.task_def(Task::new("eat lunch".to_owned(), false));
+        state
+            .task_mod(trip, |t| t.subtasks = vec![flights])
+            .unwrap();
+        state
+            .task_mod(flights, |t| {
+                t.parent = Some(trip);
+                t.subtasks = vec![seats];
+            })
+            .unwrap();
+        state.task_mod(seats, |t| t.parent = Some(flights)).unwrap();
+        let view_key = state.view_def(View {
+            tasks: Some(vec![seats, lunch, flights, trip]),
+            ..View::new("Main View".to_owned())
+        });
+        let mut task_list = TaskList::default();
+        task_list.source_views_mod(&state, |views| views.push(view_key));
+        let rows = |keys: [TaskKey; 4]| keys.map(Row::Task).to_vec();
+        assert_eq!(task_list.rows, rows([seats, lunch, flights, trip]));
+
+        // subtasks follow their parent, indented by their depth
+        let key = |code: KeyCode| Event::Key(code.into());
+        task_list.handle_term_event(&mut state, &key(KeyCode::Char('t')));
+        assert_eq!(task_list.rows, rows([lunch, trip, flights, seats]));
+        let area = Rect::new(0, 0, 40, 6);
+        let mut buffer = Buffer::empty(area);
+        task_list.render(&state, Block::default(), area, &mut buffer);
+        let debug_string = format!("{:?}", buffer);
+        assert!(debug_string.contains("\"  ☐ plan trip"));
+        assert!(debug_string.contains("\"    ☐ book flights"));
+        assert!(debug_string.contains("\"      ☐ pick seats"));
+
+        task_list.handle_term_event(&mut state, &key(KeyCode::Char('t')));
+        assert_eq!(task_list.rows, rows([seats, lunch, flights, trip]));
+    }
+
+    #[tokio::test]
+    async fn test_durations() {
+        let (mut state, _receiver) = State::new();
+        let lunch = state.task_def(Task::new("eat lunch".to_owned(), false));
+        Box::new(GetSchemaResponse {
+            definitions: vec![PropertyDefinition {
+                name: "estimate".to_owned(),
+                typ: TaskPropType::Duration,
+                description: String::new(),
+                default: None,
+                required: false,
+                allowed_values: vec![],
+                options: vec![],
+                formula: None,
+            }],
+            req_id: 0,
+        })
+        .update_state(&mut state)
+        .unwrap();
+        let estimate = state.prop_def_name("estimate");
+        state
+            .prop_set(
+                lunch,
+                estimate,
+                TaskPropVariant::Duration(TaskDuration(5400)),
+            )
+            .unwrap();
+        let view_key = state.view_def(View {
+            tasks: Some(vec![lunch]),
+            ..View::new("Main View".to_owned())
+        });
+        let mut task_list = TaskList::default();
+        task_list.source_views_mod(&state, |views| views.push(view_key));
+        let area = Rect::new(0, 0, 40, 3);
+        let mut buffer = Buffer::empty(area);
+        task_list.render(&state, Block::default(), area, &mut buffer);
+        assert!(format!("{:?}", buffer).contains("☐ eat lunch 1h30m"));
+    }
 }
@@ -0,0 +1,283 @@
+use std::path::PathBuf;
+
+use common::backend::Attachment;
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    symbols::border,
+    text::{Line, Span},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Clear, HighlightSpacing, List, ListState, Paragraph, StatefulWidget,
+        Widget,
+    },
+};
+use tui_textarea::{TextArea, TextAreaWidget};
+
+use crate::{
+    mid::{State, TaskKey},
+    ui::GREYED_OUT_TEXT_COLOR,
+};
+
+/// Popup listing the files attached to a task. <o> opens the selected file with `$OPENER`,
+/// <s> asks for a path to save it to, prefilled with its name
+#[derive(Debug)]
+pub struct AttachmentPopup {
+    task_key: TaskKey,
+    list_state: ListState,
+    /// path the selected file will be saved to, while it's being typed
+    save_path: Option<Box<TextArea>>,
+}
+
+impl AttachmentPopup {
+    /// list the attachments of a task, requesting them from the server
+    pub fn new(task_key: TaskKey, state: &mut State) -> Self {
+        state.attachments_fetch(task_key);
+        Self {
+            task_key,
+            list_state: ListState::default().with_selected(Some(0)),
+            save_path: None,
+        }
+    }
+    fn selected<'a>(&self, state: &'a State) -> Option<&'a Attachment> {
+        let attachments = state.attachments(self.task_key)?;
+        attachments.get(self.list_state.selected()?)
+    }
+    /// returns Some with boolean notifying calling event handler whether to trigger re-render.
+    /// returns None if popup should be closed
+    pub fn handle_term_event(&mut self, state: &mut State, event: &Event) -> Option<bool> {
+        let Event::Key(KeyEvent { code, .. }) = event else {
+            return Some(false);
+        };
+        if let Some(textarea) = &mut self.save_path {
+            match code {
+                KeyCode::Esc => self.save_path = None,
+                KeyCode::Enter => {
+                    let path = textarea.lines().join("");
+                    if let (Some(attachment), false) =
+                        (self.selected(state), path.trim().is_empty())
+                    {
+                        state.attachment_save(attachment.id, PathBuf::from(path.trim()));
+                    }
+                    self.save_path = None;
+                }
+                _ => {
+                    textarea.input(event.clone());
+                }
+            }
+            return Some(true);
+        }
+        let count = state
+            .attachments(self.task_key)
+            .map_or(0, <[Attachment]>::len);
+        match code {
+            KeyCode::Esc => return None,
+            KeyCode::Up => {
+                let index = self.list_state.selected().unwrap_or_default();
+                self.list_state.select(Some(index.saturating_sub(1)));
+            }
+            KeyCode::Down => {
+                let index = self.list_state.selected().unwrap_or_default();
+                self.list_state
+                    .select(Some((index + 1).min(count.saturating_sub(1))));
+            }
+            KeyCode::Char('o') => {
+                let Some(attachment) = self.selected(state) else {
+                    return Some(false);
+                };
+                state.attachment_open(attachment);
+            }
+            KeyCode::Char('s') => {
+                let Some(attachment) = self.selected(state) else {
+                    return Some(false);
+                };
+                let mut textarea = TextArea::default();
+                textarea.set_cursor_line_style(Style::default());
+                textarea.insert_str(&attachment.name);
+                self.save_path = Some(Box::new(textarea));
+            }
+            _ => return Some(false),
+        }
+        Some(true)
+    }
+    pub fn render(&mut self, state: &State, area: Rect, buf: &mut Buffer) {
+        // create a centered rect that takes up 60% of the area
+        let vertical_center = Layout::vertical([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .split(area);
+        let popup_area = Layout::horizontal([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .split(vertical_center[0])[0];
+
+        Clear.render(popup_area, buf); // clear background of popup area
+
+        let list_area = match &mut self.save_path {
+            Some(textarea) => {
+                let [list_area, path_area] =
+                    Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(popup_area);
+                let instructions = Title::from(Line::from(vec![
+                    " Save: ".into(),
+                    "<Enter>".blue().bold(),
+                    " Cancel: ".into(),
+                    "<Esc> ".blue().bold(),
+                ]));
+                let block = Block::default()
+                    .title("Save To")
+                    .title(instructions.position(Position::Bottom))
+                    .borders(Borders::ALL)
+                    .border_set(border::ROUNDED);
+                let widget = TextAreaWidget::new().block(block);
+                StatefulWidget::render(widget, path_area, buf, textarea);
+                list_area
+            }
+            None => popup_area,
+        };
+
+        let title = state
+            .task_get(self.task_key)
+            .map(|t| format!("Attachments of {}", t.name))
+            .unwrap_or_default();
+        let instructions = Title::from(Line::from(vec![
+            " Open: ".into(),
+            "<o>".blue().bold(),
+            " Save: ".into(),
+            "<s>".blue().bold(),
+            " Close: ".into(),
+            "<Esc> ".blue().bold(),
+        ]));
+        let block = Block::default()
+            .title(title)
+            .title(instructions.position(Position::Bottom))
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED);
+        let text = match state.attachments(self.task_key) {
+            None => "Loading Attachments...",
+            Some([]) => "No Attachments",
+            Some(attachments) => {
+                let lines = attachments
+                    .iter()
+                    .map(|attachment| {
+                        Line::from(vec![
+                            Span::raw(attachment.name.clone()),
+                            Span::styled(
+                                format!(" · {} · {}", attachment.mime, human_size(attachment.size)),
+                                Style::new().fg(GREYED_OUT_TEXT_COLOR),
+                            ),
+                        ])
+                    })
+                    .collect::<Vec<Line>>();
+                let list = List::new(lines)
+                    .block(block)
+                    .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+                    .highlight_symbol(">")
+                    .highlight_spacing(HighlightSpacing::Always);
+                StatefulWidget::render(list, list_area, buf, &mut self.list_state);
+                return;
+            }
+        };
+        Paragraph::new(text)
+            .centered()
+            .block(block)
+            .render(list_area, buf);
+    }
+}
+
+/// size in bytes with a binary unit, e.g. "1.5 KiB"
+fn human_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if size < 1024 {
+        return format!("{size} B");
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+    use common::backend::{GetAttachmentsResponse, ReadTaskShortResponse};
+
+    use super::*;
+    use crate::mid::ServerResponse;
+
+    #[test]
+    fn test_human_size() {
+        assert_eq!(human_size(512), "512 B");
+        assert_eq!(human_size(1536), "1.5 KiB");
+        assert_eq!(human_size(10 * 1024 * 1024), "10.0 MiB");
+    }
+
+    #[tokio::test]
+    async fn test_attachment_popup() {
+        let (mut state, _receiver) = State::new();
+        Box::new(ReadTaskShortResponse {
+            task_id: 7,
+            name: "fix crash".to_owned(),
+            completed: false,
+            props: vec![],
+            deps: vec![],
+            scripts: vec![],
+            parent: None,
+            subtasks: vec![],
+            last_edited: NaiveDateTime::default(),
+            req_id: 0,
+        })
+        .update_state(&mut state)
+        .unwrap();
+        let task_key = state.task_key(7).unwrap();
+        let mut popup = AttachmentPopup::new(task_key, &mut state);
+        let area = Rect::new(0, 0, 80, 20);
+        let mut buffer = Buffer::empty(area);
+        popup.render(&state, area, &mut buffer);
+        assert!(format!("{:?}", buffer).contains("Loading Attachments..."));
+
+        let attachment = |id, name: &str| Attachment {
+            id,
+            task_id: 7,
+            name: name.to_owned(),
+            mime: "text/plain".to_owned(),
+            size: 2048,
+            hash: String::new(),
+            uploaded_at: NaiveDateTime::default(),
+        };
+        Box::new(GetAttachmentsResponse {
+            task_id: 7,
+            attachments: vec![attachment(1, "crash.log"), attachment(2, "spec.md")],
+            req_id: 0,
+        })
+        .update_state(&mut state)
+        .unwrap();
+        popup.render(&state, area, &mut buffer);
+        let debug_string = format!("{:?}", buffer);
+        assert!(debug_string.contains("Attachments of fix crash"));
+        assert!(debug_string.contains("crash.log · text/plain · 2.0 KiB"));
+
+        let key = |code: KeyCode| Event::Key(code.into());
+        popup.handle_term_event(&mut state, &key(KeyCode::Down));
+        popup.handle_term_event(&mut state, &key(KeyCode::Down));
+        assert_eq!(popup.list_state.selected(), Some(1));
+        // the path to save to starts as the name of the file
+        popup.handle_term_event(&mut state, &key(KeyCode::Char('s')));
+        popup.render(&state, area, &mut buffer);
+        let debug_string = format!("{:?}", buffer);
+        assert!(debug_string.contains("Save To"));
+        assert!(debug_string.contains("│spec.md"));
+        // keys go to the path until it's closed
+        assert_eq!(
+            popup.handle_term_event(&mut state, &key(KeyCode::Esc)),
+            Some(true)
+        );
+        assert!(popup.save_path.is_none());
+        assert_eq!(
+            popup.handle_term_event(&mut state, &key(KeyCode::Esc)),
+            None
+        );
+    }
+}
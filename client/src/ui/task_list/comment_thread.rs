@@ -0,0 +1,216 @@
+use common::backend::Comment;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    symbols::border,
+    text::{Line, Span},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Paragraph, StatefulWidget, Widget, Wrap,
+    },
+};
+use tui_textarea::{TextArea, TextAreaWidget};
+
+use crate::{
+    mid::{State, TaskKey},
+    ui::{markdown, GREYED_OUT_TEXT_COLOR},
+};
+
+/// lines scrolled by <PgUp>/<PgDn>
+const SCROLL_STEP: u16 = 5;
+
+/// Discussion thread of a task, shown under its description. Comments are written in Markdown,
+/// <m> opens a box to compose one and <C-s> posts it
+#[derive(Debug, Default)]
+pub struct CommentThread {
+    /// lines scrolled past at the top of the thread
+    scroll: u16,
+    /// comment being written, if any
+    compose: Option<Box<TextArea>>,
+}
+
+impl CommentThread {
+    /// start over at the top of the thread of another task, discarding any comment being written
+    pub fn reset(&mut self) {
+        self.scroll = 0;
+        self.compose = None;
+    }
+    /// returns whether the event was handled, every key is handled while composing
+    pub fn handle_term_event(&mut self, state: &mut State, key: TaskKey, event: &Event) -> bool {
+        let Event::Key(KeyEvent {
+            code, modifiers, ..
+        }) = event
+        else {
+            return false;
+        };
+        if let Some(textarea) = &mut self.compose {
+            match code {
+                KeyCode::Esc => self.compose = None,
+                KeyCode::Char('s') if modifiers.contains(KeyModifiers::CONTROL) => {
+                    state.comment_add(key, textarea.lines().join("\n"));
+                    self.compose = None;
+                }
+                _ => {
+                    textarea.input(event.clone());
+                }
+            }
+            return true;
+        }
+        match code {
+            KeyCode::Char('m') => {
+                let mut textarea = TextArea::default();
+                textarea.set_cursor_line_style(Style::default());
+                self.compose = Some(Box::new(textarea));
+            }
+            KeyCode::PageDown => {
+                let lines = state
+                    .comments(key)
+                    .map_or(0, |thread| thread_lines(thread).len());
+                self.scroll = self
+                    .scroll
+                    .saturating_add(SCROLL_STEP)
+                    .min(u16::try_from(lines.saturating_sub(1)).unwrap_or(u16::MAX));
+            }
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(SCROLL_STEP),
+            _ => return false,
+        }
+        true
+    }
+    pub fn render(&mut self, state: &State, key: Option<TaskKey>, area: Rect, buf: &mut Buffer) {
+        let thread = key.and_then(|key| state.comments(key));
+        let area = match &mut self.compose {
+            Some(textarea) => {
+                let [thread_area, compose_area] =
+                    Layout::vertical([Constraint::Min(0), Constraint::Length(5)]).areas(area);
+                let instructions = Title::from(Line::from(vec![
+                    " Send: ".into(),
+                    "<C-s>".blue().bold(),
+                    " Cancel: ".into(),
+                    "<Esc> ".blue().bold(),
+                ]));
+                let block = Block::default()
+                    .title("New Comment")
+                    .title(instructions.position(Position::Bottom))
+                    .borders(Borders::ALL)
+                    .border_set(border::ROUNDED);
+                let widget = TextAreaWidget::new().block(block);
+                StatefulWidget::render(widget, compose_area, buf, textarea);
+                thread_area
+            }
+            None => area,
+        };
+        let instructions = Title::from(Line::from(vec![
+            " Comment: ".into(),
+            "<m>".blue().bold(),
+            " Scroll: ".into(),
+            "<PgUp>/<PgDn> ".blue().bold(),
+        ]));
+        let block = Block::default()
+            .title(format!("Comments ({})", thread.map_or(0, <[Comment]>::len)))
+            .title(instructions.position(Position::Bottom))
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED);
+        let text = match thread {
+            Some(thread) if !thread.is_empty() => thread_lines(thread),
+            _ => vec![Line::from("No Comments").italic()],
+        };
+        Paragraph::new(text)
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0))
+            .block(block)
+            .render(area, buf);
+    }
+}
+
+/// each comment as a header with its author and when it was posted, followed by its text
+fn thread_lines(thread: &[Comment]) -> Vec<Line<'static>> {
+    let mut lines = vec![];
+    for comment in thread {
+        let mut header = vec![
+            Span::raw(comment.author.clone()).bold(),
+            Span::styled(
+                format!(" · {}", comment.created_at.format("%m-%d %H:%M")),
+                Style::new().fg(GREYED_OUT_TEXT_COLOR),
+            ),
+        ];
+        if comment.edited_at.is_some() {
+            header.push(Span::styled(
+                " (edited)",
+                Style::new().fg(GREYED_OUT_TEXT_COLOR).italic(),
+            ));
+        }
+        lines.push(Line::from(header));
+        lines.extend(markdown::render(&comment.body, None));
+        lines.push(Line::default());
+    }
+    lines.pop(); // no gap after the last comment
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+
+    use super::*;
+
+    #[test]
+    fn test_thread_lines() {
+        let comment = |id, author: &str, body: &str| Comment {
+            id,
+            task_id: 1,
+            author: author.to_owned(),
+            body: body.to_owned(),
+            created_at: NaiveDateTime::default(),
+            edited_at: None,
+        };
+        let thread = [
+            comment(1, "ana", "is this **blocked**?"),
+            Comment {
+                edited_at: Some(NaiveDateTime::default()),
+                ..comment(2, "bo", "- [x] unblocked")
+            },
+        ];
+        let text = thread_lines(&thread)
+            .iter()
+            .map(|line| {
+                line.spans
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect()
+            })
+            .collect::<Vec<String>>();
+        assert_eq!(
+            text,
+            vec![
+                "ana · 01-01 00:00",
+                "is this blocked?",
+                "",
+                "bo · 01-01 00:00 (edited)",
+                "✓ unblocked",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compose() {
+        let (mut state, _receiver) = State::new();
+        let key = TaskKey::default();
+        let press = |code: KeyCode| Event::Key(code.into());
+        let mut thread = CommentThread::default();
+        assert!(!thread.handle_term_event(&mut state, key, &press(KeyCode::Char('x'))));
+        assert!(thread.handle_term_event(&mut state, key, &press(KeyCode::Char('m'))));
+        // keys go to the compose box until it's closed
+        assert!(thread.handle_term_event(&mut state, key, &press(KeyCode::Char('x'))));
+        let area = Rect::new(0, 0, 40, 12);
+        let mut buffer = Buffer::empty(area);
+        thread.render(&state, Some(key), area, &mut buffer);
+        let debug_string = format!("{:?}", buffer);
+        assert!(debug_string.contains("Comments (0)"));
+        assert!(debug_string.contains("New Comment"));
+        assert!(debug_string.contains("│x"));
+        assert!(thread.handle_term_event(&mut state, key, &press(KeyCode::Esc)));
+        assert!(thread.compose.is_none());
+    }
+}
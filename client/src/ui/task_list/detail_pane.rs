@@ -0,0 +1,173 @@
+use common::{TaskPropVariant, DESCRIPTION_PROPERTY};
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::Stylize,
+    symbols::border,
+    text::Line,
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Paragraph, Widget, Wrap,
+    },
+};
+
+use crate::{
+    mid::{State, TaskKey, ViewKey},
+    ui::{markdown, report_error},
+};
+
+use super::comment_thread::CommentThread;
+
+/// Pane beside the task list showing the description of the selected task, rendered from Markdown,
+/// above its comments. <[>/<]> select a checkbox of the description and <x> checks or unchecks it
+#[derive(Debug, Default)]
+pub struct DetailPane {
+    /// task the pane is showing
+    task: Option<TaskKey>,
+    /// selected checkbox, by index among the checkboxes of the description
+    checkbox: Option<usize>,
+    thread: CommentThread,
+}
+
+/// the description of a task, if it has been loaded
+pub fn description(state: &State, key: TaskKey) -> Option<&str> {
+    let name_key = state.prop_name_key(DESCRIPTION_PROPERTY)?;
+    match state.prop_get(key, name_key).ok()? {
+        TaskPropVariant::String(text) => Some(text),
+        _ => None,
+    }
+}
+
+impl DetailPane {
+    /// request the descriptions of the loaded tasks of the views
+    pub fn fetch(state: &mut State, views: &[ViewKey]) {
+        let name_key = state.prop_def_name(DESCRIPTION_PROPERTY);
+        for view_key in views {
+            state.view_fetch_prop(*view_key, name_key);
+        }
+    }
+    /// show a task, its comments are requested when it changes
+    pub fn show(&mut self, state: &mut State, key: Option<TaskKey>) {
+        if self.task != key {
+            self.task = key;
+            self.checkbox = None;
+            self.thread.reset();
+            if let Some(key) = key {
+                state.comments_fetch(key);
+            }
+        }
+    }
+    /// returns whether the event was handled
+    pub fn handle_term_event(
+        &mut self,
+        state: &mut State,
+        key: Option<TaskKey>,
+        event: &Event,
+    ) -> bool {
+        self.show(state, key);
+        let (Some(key), Event::Key(KeyEvent { code, .. })) = (key, event) else {
+            return false;
+        };
+        if self.thread.handle_term_event(state, key, event) {
+            return true;
+        }
+        let text = description(state, key).unwrap_or_default();
+        let count = markdown::checkbox_count(text);
+        match code {
+            KeyCode::Char(']') if count > 0 => {
+                self.checkbox = Some(self.checkbox.map_or(0, |i| (i + 1) % count));
+            }
+            KeyCode::Char('[') if count > 0 => {
+                self.checkbox = Some(self.checkbox.map_or(count - 1, |i| (i + count - 1) % count));
+            }
+            KeyCode::Char('x') => {
+                let Some(toggled) = self
+                    .checkbox
+                    .and_then(|index| markdown::toggle_checkbox(text, index))
+                else {
+                    return false;
+                };
+                let name_key = state.prop_def_name(DESCRIPTION_PROPERTY);
+                if let Err(err) = state.prop_set(key, name_key, TaskPropVariant::String(toggled)) {
+                    report_error(err);
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+    pub fn render(&mut self, state: &State, key: Option<TaskKey>, area: Rect, buf: &mut Buffer) {
+        let [area, thread_area] =
+            Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(area);
+        self.thread.render(state, key, thread_area, buf);
+        let task = key.and_then(|key| state.task_get(key).ok());
+        let instructions = Title::from(Line::from(vec![
+            " Checkbox: ".into(),
+            "<[>/<]>".blue().bold(),
+            " Toggle: ".into(),
+            "<x>".blue().bold(),
+            " Edit: ".into(),
+            "<E> ".blue().bold(),
+        ]));
+        let block = Block::default()
+            .title(task.map_or("Details", |task| task.name.as_str()))
+            .title(instructions.position(Position::Bottom))
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED);
+        let text = match (key, task) {
+            (Some(key), Some(_)) => match description(state, key) {
+                Some(text) if !text.trim().is_empty() => {
+                    let checkbox = self.checkbox.filter(|_| self.task == Some(key));
+                    markdown::render(text, checkbox)
+                }
+                _ => vec![Line::from("No Description").italic()],
+            },
+            _ => vec![Line::from("No Task Selected").italic()],
+        };
+        Paragraph::new(text)
+            .wrap(Wrap { trim: false })
+            .block(block)
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mid::Task;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_toggle_checkbox() {
+        let (mut state, _receiver) = State::new();
+        let key = state.task_def(Task::new("plan trip".to_owned(), false));
+        let name_key = state.prop_def_name(DESCRIPTION_PROPERTY);
+        let text = "## Packing\n- [ ] passport\n- [ ] charger";
+        state
+            .prop_set(key, name_key, TaskPropVariant::String(text.to_owned()))
+            .unwrap();
+        let mut pane = DetailPane::default();
+        let area = Rect::new(0, 0, 40, 12);
+        let mut buffer = Buffer::empty(area);
+        pane.render(&state, Some(key), area, &mut buffer);
+        let debug_string = format!("{:?}", buffer);
+        assert!(debug_string.contains("plan trip"));
+        assert!(debug_string.contains("│Packing"));
+        assert!(debug_string.contains("│☐ passport"));
+
+        // <x> does nothing until a checkbox is selected
+        let press = |code: KeyCode| Event::Key(code.into());
+        assert!(!pane.handle_term_event(&mut state, Some(key), &press(KeyCode::Char('x'))));
+        pane.handle_term_event(&mut state, Some(key), &press(KeyCode::Char('[')));
+        pane.handle_term_event(&mut state, Some(key), &press(KeyCode::Char('x')));
+        assert_eq!(
+            description(&state, key),
+            Some("## Packing\n- [ ] passport\n- [x] charger")
+        );
+
+        // selection is reset when another task is shown
+        pane.handle_term_event(&mut state, None, &press(KeyCode::Char(']')));
+        assert_eq!(pane.checkbox, None);
+    }
+}
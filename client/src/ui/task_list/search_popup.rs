@@ -0,0 +1,201 @@
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    symbols::border,
+    text::{Line, Span},
+    widgets::{
+        Block, Borders, Clear, HighlightSpacing, List, ListState, Paragraph, StatefulWidget, Widget,
+    },
+};
+
+use crate::mid::{State, TaskKey};
+
+use super::super::SELECTED_STYLE_FG;
+
+/// Popup for full-text searching tasks.
+/// Results are updated as the query is typed, <Enter> jumps to the selected result.
+#[derive(Debug, Default)]
+pub struct SearchPopup {
+    query: String,
+    list_state: ListState,
+}
+
+impl SearchPopup {
+    /// returns Ok with boolean notifying calling event handler whether to trigger re-render.
+    /// returns Err with the task to jump to (if any) if popup should be closed
+    pub fn handle_term_event(
+        &mut self,
+        state: &mut State,
+        event: &Event,
+    ) -> Result<bool, Option<TaskKey>> {
+        let Event::Key(KeyEvent { code, .. }) = event else {
+            return Ok(false);
+        };
+        match code {
+            KeyCode::Esc => return Err(None),
+            KeyCode::Enter => {
+                let selected = self.list_state.selected().unwrap_or(0);
+                return Err(state.search_results().get(selected).map(|(key, _)| *key));
+            }
+            KeyCode::Up => self.shift(state, -1),
+            KeyCode::Down => self.shift(state, 1),
+            KeyCode::Char(c) => {
+                self.query.push(*c);
+                self.update_query(state);
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.update_query(state);
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+    // search for the new query, results come in as a SearchUpdate
+    fn update_query(&mut self, state: &mut State) {
+        state.search(&self.query);
+        self.list_state.select(None);
+    }
+    // move selection within the results, without wrapping
+    fn shift(&mut self, state: &State, amt: isize) {
+        let len = state.search_results().len();
+        if len == 0 {
+            return;
+        }
+        let index = match self.list_state.selected() {
+            Some(i) => i.saturating_add_signed(amt).min(len - 1),
+            None => 0,
+        };
+        self.list_state.select(Some(index));
+    }
+    pub fn render(&mut self, state: &State, area: Rect, buf: &mut Buffer) {
+        // create a centered rect that takes up 60% of the area
+        let vertical_center = Layout::vertical([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .split(area);
+        let popup_area = Layout::horizontal([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .split(vertical_center[0])[0];
+        let areas = Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).split(popup_area);
+
+        Clear.render(popup_area, buf); // clear background of popup area
+
+        let block = Block::default()
+            .title("Search Tasks")
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED);
+        Paragraph::new(self.query.as_str())
+            .block(block)
+            .render(areas[0], buf);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED);
+        let results = state.search_results();
+        if results.is_empty() {
+            let text = if self.query.trim().is_empty() {
+                "Type to search titles and properties"
+            } else {
+                "No Results"
+            };
+            Paragraph::new(text)
+                .centered()
+                .block(block)
+                .render(areas[1], buf);
+            return;
+        }
+        let lines = results
+            .iter()
+            .map(|(key, result)| {
+                let mut spans = vec![];
+                // show which task the snippet belongs to if it isn't from the title
+                if let Some(field) = &result.field {
+                    let name = state.task_get(*key).map(|t| t.name.as_str()).unwrap_or("");
+                    spans.push(Span::styled(format!("{name} "), Style::new().bold()));
+                    spans.push(Span::styled(format!("{field}: "), Style::new().italic()));
+                }
+                spans.extend(highlight_spans(&result.snippet, &result.highlights));
+                Line::from(spans)
+            })
+            .collect::<Vec<Line>>();
+        let list = List::new(lines)
+            .block(block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">")
+            .highlight_spacing(HighlightSpacing::Always);
+        StatefulWidget::render(list, areas[1], buf, &mut self.list_state);
+    }
+}
+
+/// split a snippet into spans, styling the highlighted byte ranges. Invalid ranges are ignored.
+fn highlight_spans<'a>(snippet: &'a str, highlights: &[(usize, usize)]) -> Vec<Span<'a>> {
+    let mut spans = vec![];
+    let mut pos = 0;
+    for (start, end) in highlights.iter().cloned() {
+        let (Some(before), Some(matched)) = (snippet.get(pos..start), snippet.get(start..end))
+        else {
+            continue;
+        };
+        spans.push(Span::raw(before));
+        spans.push(Span::styled(
+            matched,
+            Style::new().fg(SELECTED_STYLE_FG).bold(),
+        ));
+        pos = end;
+    }
+    spans.push(Span::raw(snippet.get(pos..).unwrap_or("")));
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{Event, KeyCode};
+    use ratatui::{buffer::Buffer, layout::Rect};
+
+    use super::*;
+
+    #[test]
+    fn test_highlight_spans() {
+        let spans = highlight_spans("walk the dog", &[(9, 12)]);
+        assert_eq!(
+            spans.iter().map(|s| s.content.as_ref()).collect::<Vec<_>>(),
+            vec!["walk the ", "dog", ""]
+        );
+        assert_eq!(spans[1].style.fg, Some(SELECTED_STYLE_FG));
+        // out of bounds and non-char-boundary ranges are skipped
+        let spans = highlight_spans("café", &[(4, 5), (2, 40)]);
+        assert_eq!(
+            spans.iter().map(|s| s.content.as_ref()).collect::<Vec<_>>(),
+            vec!["café"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_popup() {
+        let (mut state, _receiver) = State::new();
+        let mut popup = SearchPopup::default();
+        for c in "dog".chars() {
+            assert_eq!(
+                popup.handle_term_event(&mut state, &Event::Key(KeyCode::Char(c).into())),
+                Ok(true)
+            );
+        }
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 60, 20));
+        popup.render(&state, Rect::new(0, 0, 60, 20), &mut buffer);
+        assert!(format!("{:?}", buffer).contains("Search Tasks"));
+        assert!(format!("{:?}", buffer).contains("dog"));
+        assert!(format!("{:?}", buffer).contains("No Results"));
+
+        // nothing to jump to
+        assert_eq!(
+            popup.handle_term_event(&mut state, &Event::Key(KeyCode::Enter.into())),
+            Err(None)
+        );
+        assert_eq!(
+            popup.handle_term_event(&mut state, &Event::Key(KeyCode::Esc.into())),
+            Err(None)
+        );
+    }
+}
@@ -0,0 +1,435 @@
+use common::{TaskDuration, TaskPropType, TaskPropVariant};
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    symbols::border,
+    text::Line,
+    widgets::{
+        Block, Borders, Clear, HighlightSpacing, List, ListState, Paragraph, StatefulWidget, Widget,
+    },
+};
+
+use crate::mid::{PropNameKey, State, TaskKey};
+
+/// row of the picker: the name of a select property, one of its options,
+/// or an integer or duration property typed in as text
+#[derive(Debug, PartialEq)]
+enum Row {
+    Header(String),
+    Option(PropNameKey, String),
+    Value(PropNameKey),
+}
+
+/// Popup for picking the options of a task's select and multi-select properties.
+/// <Space>/<Enter> picks the option under the cursor, toggling it for multi-selects.
+/// On an integer or duration property <Enter> starts typing its value, durations read like "2h30m"
+#[derive(Debug)]
+pub struct SelectPopup {
+    task_key: TaskKey,
+    list_state: ListState,
+    /// text typed for the property under the cursor
+    input: Option<String>,
+}
+
+/// parse text typed for a property of the type, None if it isn't a value of the type
+fn parse_value(typ: TaskPropType, text: &str) -> Option<TaskPropVariant> {
+    match typ {
+        TaskPropType::Integer => text.trim().parse().ok().map(TaskPropVariant::Integer),
+        TaskPropType::Duration => text
+            .parse::<TaskDuration>()
+            .ok()
+            .map(TaskPropVariant::Duration),
+        _ => None,
+    }
+}
+
+impl SelectPopup {
+    pub fn new(task_key: TaskKey) -> Self {
+        Self {
+            task_key,
+            list_state: ListState::default(),
+            input: None,
+        }
+    }
+    /// returns Some with boolean notifying calling event handler whether to trigger re-render.
+    /// returns None if popup should be closed
+    pub fn handle_term_event(&mut self, state: &mut State, event: &Event) -> Option<bool> {
+        let Event::Key(KeyEvent { code, .. }) = event else {
+            return Some(false);
+        };
+        if let Some(input) = &mut self.input {
+            match code {
+                KeyCode::Esc => self.input = None,
+                KeyCode::Char(c) => input.push(*c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => self.submit(state),
+                _ => return Some(false),
+            }
+            return Some(true);
+        }
+        match code {
+            KeyCode::Esc => return None,
+            KeyCode::Up => self.shift(state, -1),
+            KeyCode::Down => self.shift(state, 1),
+            KeyCode::Char(' ') | KeyCode::Enter => self.pick(state),
+            _ => return Some(false),
+        }
+        Some(true)
+    }
+    // select and multi-select properties of the workspace with their options,
+    // and integer and duration properties, in name order
+    fn rows(state: &State) -> Vec<Row> {
+        let mut rows = vec![];
+        for (name_key, definition) in state.prop_definitions() {
+            match definition.typ {
+                TaskPropType::Select | TaskPropType::MultiSelect => {}
+                TaskPropType::Integer | TaskPropType::Duration => {
+                    rows.push(Row::Value(name_key));
+                    continue;
+                }
+                _ => continue,
+            }
+            rows.push(Row::Header(definition.name.clone()));
+            rows.extend(
+                definition
+                    .options
+                    .iter()
+                    .map(|option| Row::Option(name_key, option.clone())),
+            );
+        }
+        rows
+    }
+    // move selection to the next option in the direction of amt, skipping headers
+    fn shift(&mut self, state: &State, amt: isize) {
+        let rows = Self::rows(state);
+        let mut index = self.list_state.selected();
+        loop {
+            let next = match index {
+                Some(i) => i.checked_add_signed(amt),
+                None => Some(0),
+            };
+            match next {
+                Some(i) if i < rows.len() => {
+                    index = Some(i);
+                    if let Row::Option(..) | Row::Value(_) = rows[i] {
+                        self.list_state.select(index);
+                        return;
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+    // set the option under the cursor on the task, or start typing the value under the cursor
+    fn pick(&mut self, state: &mut State) {
+        let rows = Self::rows(state);
+        let (name_key, option) = match self.list_state.selected().and_then(|i| rows.get(i)) {
+            Some(Row::Option(name_key, option)) => (name_key, option),
+            Some(Row::Value(name_key)) => {
+                let current = state.prop_get(self.task_key, *name_key).ok();
+                self.input = Some(match current {
+                    Some(TaskPropVariant::Integer(n)) => n.to_string(),
+                    Some(TaskPropVariant::Duration(duration)) => duration.to_string(),
+                    _ => String::new(),
+                });
+                return;
+            }
+            _ => return,
+        };
+        let current = state.prop_get(self.task_key, *name_key).ok();
+        let value = match state.prop_definition(*name_key).map(|d| d.typ) {
+            Some(TaskPropType::MultiSelect) => {
+                let mut options = match current {
+                    Some(TaskPropVariant::MultiSelect(options)) => options.clone(),
+                    _ => vec![],
+                };
+                match options.iter().position(|o| o == option) {
+                    Some(i) => {
+                        options.remove(i);
+                    }
+                    None => options.push(option.clone()),
+                }
+                TaskPropVariant::MultiSelect(options)
+            }
+            _ => TaskPropVariant::Select(option.clone()),
+        };
+        if let Err(err) = state.prop_set(self.task_key, *name_key, value) {
+            log::error!("failed to pick option: {err}");
+        }
+    }
+    // set the typed value on the task, text that doesn't parse stays to be corrected
+    fn submit(&mut self, state: &mut State) {
+        let rows = Self::rows(state);
+        let Some(Row::Value(name_key)) = self.list_state.selected().and_then(|i| rows.get(i))
+        else {
+            self.input = None;
+            return;
+        };
+        let Some(typ) = state.prop_definition(*name_key).map(|d| d.typ) else {
+            self.input = None;
+            return;
+        };
+        let Some(value) = self
+            .input
+            .as_deref()
+            .and_then(|text| parse_value(typ, text))
+        else {
+            return;
+        };
+        self.input = None;
+        if let Err(err) = state.prop_set(self.task_key, *name_key, value) {
+            log::error!("failed to set value: {err}");
+        }
+    }
+    pub fn render(&mut self, state: &State, area: Rect, buf: &mut Buffer) {
+        // create a centered rect that takes up 60% of the area
+        let vertical_center = Layout::vertical([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .split(area);
+        let popup_area = Layout::horizontal([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .split(vertical_center[0])[0];
+
+        Clear.render(popup_area, buf); // clear background of popup area
+
+        let title = state
+            .task_get(self.task_key)
+            .map(|t| format!("Options of {}", t.name))
+            .unwrap_or_default();
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED);
+        let rows = Self::rows(state);
+        if rows.is_empty() {
+            Paragraph::new("No Editable Properties Defined")
+                .centered()
+                .block(block)
+                .render(popup_area, buf);
+            return;
+        }
+        let selected = self.list_state.selected();
+        let lines = rows
+            .into_iter()
+            .enumerate()
+            .map(|(i, row)| match row {
+                Row::Header(name) => Line::styled(name, Style::new().bold()),
+                Row::Option(name_key, option) => {
+                    let marker = match state.prop_get(self.task_key, name_key) {
+                        Ok(TaskPropVariant::Select(selected)) if *selected == option => "(•)",
+                        Ok(TaskPropVariant::MultiSelect(selected))
+                            if selected.contains(&option) =>
+                        {
+                            "[x]"
+                        }
+                        _ => match state.prop_definition(name_key).map(|d| d.typ) {
+                            Some(TaskPropType::MultiSelect) => "[ ]",
+                            _ => "( )",
+                        },
+                    };
+                    Line::raw(format!("  {marker} {option}"))
+                }
+                Row::Value(name_key) => {
+                    let name = state
+                        .prop_definition(name_key)
+                        .map(|d| d.name.as_str())
+                        .unwrap_or_default();
+                    let value = match (&self.input, state.prop_get(self.task_key, name_key)) {
+                        (Some(input), _) if selected == Some(i) => format!("{input}_"),
+                        (_, Ok(TaskPropVariant::Integer(n))) => n.to_string(),
+                        (_, Ok(TaskPropVariant::Duration(duration))) => duration.to_string(),
+                        _ => "-".to_owned(),
+                    };
+                    Line::styled(format!("{name}: {value}"), Style::new().bold())
+                }
+            })
+            .collect::<Vec<Line>>();
+        let list = List::new(lines)
+            .block(block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">")
+            .highlight_spacing(HighlightSpacing::Always);
+        StatefulWidget::render(list, popup_area, buf, &mut self.list_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{backend::GetSchemaResponse, PropertyDefinition};
+    use crossterm::event::{Event, KeyCode};
+    use ratatui::{buffer::Buffer, layout::Rect};
+
+    use super::*;
+    use crate::mid::{ServerResponse, Task};
+
+    #[tokio::test]
+    async fn test_select_popup() {
+        let (mut state, _receiver) = State::new();
+        let task_key = state.task_def(Task::new("walk the dog".to_owned(), false));
+        let mut popup = SelectPopup::new(task_key);
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 60, 20));
+        popup.render(&state, Rect::new(0, 0, 60, 20), &mut buffer);
+        assert!(format!("{:?}", buffer).contains("No Editable Properties Defined"));
+
+        let definition = |name: &str, typ, options: [&str; 2]| PropertyDefinition {
+            name: name.to_owned(),
+            typ,
+            description: String::new(),
+            default: None,
+            required: false,
+            allowed_values: vec![],
+            options: options.map(str::to_owned).to_vec(),
+            formula: None,
+        };
+        Box::new(GetSchemaResponse {
+            definitions: vec![
+                definition("status", TaskPropType::Select, ["todo", "done"]),
+                definition("labels", TaskPropType::MultiSelect, ["home", "work"]),
+            ],
+            req_id: 0,
+        })
+        .update_state(&mut state)
+        .unwrap();
+        let status = state.prop_def_name("status");
+        let labels = state.prop_def_name("labels");
+        let key = |code: KeyCode| Event::Key(code.into());
+        // headers are skipped, "labels" sorts first
+        assert_eq!(
+            popup.handle_term_event(&mut state, &key(KeyCode::Down)),
+            Some(true)
+        );
+        assert_eq!(popup.list_state.selected(), Some(1));
+        assert_eq!(
+            popup.handle_term_event(&mut state, &key(KeyCode::Char(' '))),
+            Some(true)
+        );
+        popup.handle_term_event(&mut state, &key(KeyCode::Down));
+        popup.handle_term_event(&mut state, &key(KeyCode::Enter));
+        assert_eq!(
+            state.prop_get(task_key, labels).unwrap(),
+            &TaskPropVariant::MultiSelect(vec!["home".to_owned(), "work".to_owned()])
+        );
+        // toggling again removes the option
+        popup.handle_term_event(&mut state, &key(KeyCode::Char(' ')));
+        assert_eq!(
+            state.prop_get(task_key, labels).unwrap(),
+            &TaskPropVariant::MultiSelect(vec!["home".to_owned()])
+        );
+        popup.handle_term_event(&mut state, &key(KeyCode::Down));
+        assert_eq!(popup.list_state.selected(), Some(4));
+        popup.handle_term_event(&mut state, &key(KeyCode::Down));
+        popup.handle_term_event(&mut state, &key(KeyCode::Down));
+        assert_eq!(popup.list_state.selected(), Some(5));
+        popup.handle_term_event(&mut state, &key(KeyCode::Enter));
+        assert_eq!(
+            state.prop_get(task_key, status).unwrap(),
+            &TaskPropVariant::Select("done".to_owned())
+        );
+
+        popup.render(&state, Rect::new(0, 0, 60, 20), &mut buffer);
+        assert!(format!("{:?}", buffer).contains("Options of walk the dog"));
+        assert!(format!("{:?}", buffer).contains("[x] home"));
+        assert!(format!("{:?}", buffer).contains("(•) done"));
+        assert!(format!("{:?}", buffer).contains("( ) todo"));
+
+        assert_eq!(
+            popup.handle_term_event(&mut state, &key(KeyCode::Esc)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(
+            parse_value(TaskPropType::Duration, "2h30m"),
+            Some(TaskPropVariant::Duration(TaskDuration(9000)))
+        );
+        assert_eq!(
+            parse_value(TaskPropType::Integer, " 42 "),
+            Some(TaskPropVariant::Integer(42))
+        );
+        assert_eq!(parse_value(TaskPropType::Duration, "2 hours"), None);
+        assert_eq!(parse_value(TaskPropType::Integer, "4.5"), None);
+        assert_eq!(parse_value(TaskPropType::String, "text"), None);
+    }
+
+    #[tokio::test]
+    async fn test_type_values() {
+        let (mut state, _receiver) = State::new();
+        let task_key = state.task_def(Task::new("walk the dog".to_owned(), false));
+        let definition = |name: &str, typ| PropertyDefinition {
+            name: name.to_owned(),
+            typ,
+            description: String::new(),
+            default: None,
+            required: false,
+            allowed_values: vec![],
+            options: vec![],
+            formula: None,
+        };
+        Box::new(GetSchemaResponse {
+            definitions: vec![
+                definition("estimate", TaskPropType::Duration),
+                definition("points", TaskPropType::Integer),
+            ],
+            req_id: 0,
+        })
+        .update_state(&mut state)
+        .unwrap();
+        let estimate = state.prop_def_name("estimate");
+        let points = state.prop_def_name("points");
+        let key = |code: KeyCode| Event::Key(code.into());
+        let mut popup = SelectPopup::new(task_key);
+        let type_text = |popup: &mut SelectPopup, state: &mut State, text: &str| {
+            for c in text.chars() {
+                popup.handle_term_event(state, &key(KeyCode::Char(c)));
+            }
+            popup.handle_term_event(state, &key(KeyCode::Enter));
+        };
+        popup.handle_term_event(&mut state, &key(KeyCode::Down));
+        popup.handle_term_event(&mut state, &key(KeyCode::Enter));
+        type_text(&mut popup, &mut state, "2h30m");
+        assert_eq!(
+            state.prop_get(task_key, estimate).unwrap(),
+            &TaskPropVariant::Duration(TaskDuration(9000))
+        );
+        // text that isn't a duration stays in the input to be corrected
+        popup.handle_term_event(&mut state, &key(KeyCode::Enter));
+        assert_eq!(popup.input.as_deref(), Some("2h30m"));
+        type_text(&mut popup, &mut state, "x");
+        assert_eq!(popup.input.as_deref(), Some("2h30mx"));
+        popup.handle_term_event(&mut state, &key(KeyCode::Backspace));
+        type_text(&mut popup, &mut state, "15s");
+        assert_eq!(
+            state.prop_get(task_key, estimate).unwrap(),
+            &TaskPropVariant::Duration(TaskDuration(9015))
+        );
+
+        popup.handle_term_event(&mut state, &key(KeyCode::Down));
+        popup.handle_term_event(&mut state, &key(KeyCode::Enter));
+        type_text(&mut popup, &mut state, "8");
+        assert_eq!(
+            state.prop_get(task_key, points).unwrap(),
+            &TaskPropVariant::Integer(8)
+        );
+
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 60, 20));
+        popup.render(&state, Rect::new(0, 0, 60, 20), &mut buffer);
+        assert!(format!("{:?}", buffer).contains("estimate: 2h30m15s"));
+        assert!(format!("{:?}", buffer).contains("points: 8"));
+        // Esc leaves the input before closing the popup
+        popup.handle_term_event(&mut state, &key(KeyCode::Enter));
+        assert_eq!(
+            popup.handle_term_event(&mut state, &key(KeyCode::Esc)),
+            Some(true)
+        );
+        assert_eq!(
+            popup.handle_term_event(&mut state, &key(KeyCode::Esc)),
+            None
+        );
+    }
+}
@@ -0,0 +1,373 @@
+use common::{backend::Tag, TagID};
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    symbols::border,
+    text::{Line, Span},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Clear, HighlightSpacing, List, ListState, Paragraph, StatefulWidget,
+        Widget,
+    },
+};
+use tui_textarea::{TextArea, TextAreaWidget};
+
+use crate::{
+    mid::{State, TaskKey},
+    ui::GREYED_OUT_TEXT_COLOR,
+};
+
+/// colors handed out to new top-level tags in turn, nested tags take the color of their parent
+const PALETTE: [&str; 6] = [
+    "#1e90ff", "#2e8b57", "#daa520", "#cd5c5c", "#9370db", "#20b2aa",
+];
+
+/// Popup listing every tag as an outline, marking the ones on a task. <Space> tags or untags the task,
+/// <n> asks for the name of a new top-level tag and <c> for a tag nested under the selected one
+#[derive(Debug)]
+pub struct TagPicker {
+    task_key: TaskKey,
+    list_state: ListState,
+    /// parent and name of the tag being created, while it's being typed
+    new_tag: Option<(Option<TagID>, Box<TextArea>)>,
+}
+
+impl TagPicker {
+    /// pick the tags of a task
+    pub fn new(task_key: TaskKey) -> Self {
+        Self {
+            task_key,
+            list_state: ListState::default().with_selected(Some(0)),
+            new_tag: None,
+        }
+    }
+    fn selected<'a>(&self, state: &'a State) -> Option<&'a Tag> {
+        let tags = tag_outline(state);
+        tags.get(self.list_state.selected()?).map(|(_, tag)| *tag)
+    }
+    /// returns Some with boolean notifying calling event handler whether to trigger re-render.
+    /// returns None if popup should be closed
+    pub fn handle_term_event(&mut self, state: &mut State, event: &Event) -> Option<bool> {
+        let Event::Key(KeyEvent { code, .. }) = event else {
+            return Some(false);
+        };
+        if let Some((parent, textarea)) = &mut self.new_tag {
+            match code {
+                KeyCode::Esc => self.new_tag = None,
+                KeyCode::Enter => {
+                    let name = textarea.lines().join("").trim().to_owned();
+                    let color = match parent.and_then(|parent| state.tag(parent)) {
+                        Some(parent) => parent.color.clone(),
+                        None => PALETTE[state.tags().count() % PALETTE.len()].to_owned(),
+                    };
+                    state.tag_create(name, *parent, color);
+                    self.new_tag = None;
+                }
+                _ => {
+                    textarea.input(event.clone());
+                }
+            }
+            return Some(true);
+        }
+        let count = state.tags().count();
+        match code {
+            KeyCode::Esc => return None,
+            KeyCode::Up => {
+                let index = self.list_state.selected().unwrap_or_default();
+                self.list_state.select(Some(index.saturating_sub(1)));
+            }
+            KeyCode::Down => {
+                let index = self.list_state.selected().unwrap_or_default();
+                self.list_state
+                    .select(Some((index + 1).min(count.saturating_sub(1))));
+            }
+            KeyCode::Char(' ') | KeyCode::Enter => {
+                let Some(tag_id) = self.selected(state).map(|tag| tag.id) else {
+                    return Some(false);
+                };
+                return Some(state.task_tag_toggle(self.task_key, tag_id));
+            }
+            KeyCode::Char('n') => self.new_tag = Some((None, new_textarea())),
+            KeyCode::Char('c') => {
+                let Some(tag_id) = self.selected(state).map(|tag| tag.id) else {
+                    return Some(false);
+                };
+                self.new_tag = Some((Some(tag_id), new_textarea()));
+            }
+            KeyCode::Char('d') => {
+                let Some(tag_id) = self.selected(state).map(|tag| tag.id) else {
+                    return Some(false);
+                };
+                state.tag_rm(tag_id);
+            }
+            _ => return Some(false),
+        }
+        Some(true)
+    }
+    pub fn render(&mut self, state: &State, area: Rect, buf: &mut Buffer) {
+        // create a centered rect that takes up 60% of the area
+        let vertical_center = Layout::vertical([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .split(area);
+        let popup_area = Layout::horizontal([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .split(vertical_center[0])[0];
+
+        Clear.render(popup_area, buf); // clear background of popup area
+
+        let list_area = match &mut self.new_tag {
+            Some((parent, textarea)) => {
+                let [list_area, name_area] =
+                    Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(popup_area);
+                let title = match parent.and_then(|parent| state.tag(parent)) {
+                    Some(parent) => format!("New Tag Under {}", parent.name),
+                    None => "New Tag".to_owned(),
+                };
+                let instructions = Title::from(Line::from(vec![
+                    " Create: ".into(),
+                    "<Enter>".blue().bold(),
+                    " Cancel: ".into(),
+                    "<Esc> ".blue().bold(),
+                ]));
+                let block = Block::default()
+                    .title(title)
+                    .title(instructions.position(Position::Bottom))
+                    .borders(Borders::ALL)
+                    .border_set(border::ROUNDED);
+                let widget = TextAreaWidget::new().block(block);
+                StatefulWidget::render(widget, name_area, buf, textarea);
+                list_area
+            }
+            None => popup_area,
+        };
+
+        let title = state
+            .task_get(self.task_key)
+            .map(|t| format!("Tags of {}", t.name))
+            .unwrap_or_default();
+        let instructions = Title::from(Line::from(vec![
+            " Toggle: ".into(),
+            "<Space>".blue().bold(),
+            " New: ".into(),
+            "<n>".blue().bold(),
+            " Nested: ".into(),
+            "<c>".blue().bold(),
+            " Delete: ".into(),
+            "<d>".blue().bold(),
+            " Close: ".into(),
+            "<Esc> ".blue().bold(),
+        ]));
+        let block = Block::default()
+            .title(title)
+            .title(instructions.position(Position::Bottom))
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED);
+        let tags = tag_outline(state);
+        if tags.is_empty() {
+            Paragraph::new("No Tags, Create One With <n>")
+                .centered()
+                .block(block)
+                .render(list_area, buf);
+            return;
+        }
+        let task_tags = state.task_tags(self.task_key).unwrap_or_default();
+        let lines = tags
+            .into_iter()
+            .map(|(depth, tag)| {
+                let mark = if task_tags.contains(&tag.id) {
+                    "[x]"
+                } else {
+                    "[ ]"
+                };
+                Line::from(vec![
+                    Span::raw(format!("{}{mark} ", "  ".repeat(depth))),
+                    chip(tag),
+                ])
+            })
+            .collect::<Vec<Line>>();
+        let list = List::new(lines)
+            .block(block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">")
+            .highlight_spacing(HighlightSpacing::Always);
+        StatefulWidget::render(list, list_area, buf, &mut self.list_state);
+    }
+}
+
+fn new_textarea() -> Box<TextArea<'static>> {
+    let mut textarea = TextArea::default();
+    textarea.set_cursor_line_style(Style::default());
+    Box::new(textarea)
+}
+
+/// every tag depth-first, siblings by name, with how deep it's nested.
+/// tags whose parent isn't known are shown at the top level
+fn tag_outline(state: &State) -> Vec<(usize, &Tag)> {
+    let mut tags = state.tags().collect::<Vec<&Tag>>();
+    tags.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+    let mut outline = Vec::with_capacity(tags.len());
+    // walk down from the roots, pushing children in reverse so they're popped in name order
+    let mut stack = tags
+        .iter()
+        .rev()
+        .filter(|tag| tag.parent.and_then(|parent| state.tag(parent)).is_none())
+        .map(|tag| (0, *tag))
+        .collect::<Vec<_>>();
+    while let Some((depth, tag)) = stack.pop() {
+        outline.push((depth, tag));
+        stack.extend(
+            tags.iter()
+                .rev()
+                .filter(|child| child.parent == Some(tag.id))
+                .map(|child| (depth + 1, *child)),
+        );
+    }
+    outline
+}
+
+/// the name of a tag on its color, in black or white, whichever reads better
+fn chip(tag: &Tag) -> Span<'static> {
+    let name = format!(" {} ", tag.name);
+    match tag.rgb() {
+        Some((r, g, b)) => {
+            let luminance = (299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000;
+            let fg = if luminance > 128 {
+                Color::Black
+            } else {
+                Color::White
+            };
+            Span::styled(name, Style::new().fg(fg).bg(Color::Rgb(r, g, b)))
+        }
+        None => Span::styled(name, Style::new().fg(GREYED_OUT_TEXT_COLOR)),
+    }
+}
+
+/// colored chips for the tags of a task, each led by a space, to follow its name
+pub fn tag_chips(state: &State, key: TaskKey) -> Vec<Span<'static>> {
+    let Some(tags) = state.task_tags(key) else {
+        return vec![];
+    };
+    tags.iter()
+        .filter_map(|tag_id| state.tag(*tag_id))
+        .flat_map(|tag| [Span::raw(" "), chip(tag)])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+    use common::backend::{GetTagsResponse, GetTaskTagsResponse, ReadTaskShortResponse, TaskTags};
+
+    use super::*;
+    use crate::mid::ServerResponse;
+
+    fn tag(id: TagID, name: &str, parent: Option<TagID>, color: &str) -> Tag {
+        Tag {
+            id,
+            name: name.to_owned(),
+            parent,
+            color: color.to_owned(),
+        }
+    }
+
+    fn tagged_state() -> (State, TaskKey) {
+        let (mut state, _receiver) = State::new();
+        Box::new(ReadTaskShortResponse {
+            task_id: 7,
+            name: "fix crash".to_owned(),
+            completed: false,
+            props: vec![],
+            deps: vec![],
+            scripts: vec![],
+            parent: None,
+            subtasks: vec![],
+            last_edited: NaiveDateTime::default(),
+            req_id: 0,
+        })
+        .update_state(&mut state)
+        .unwrap();
+        Box::new(GetTagsResponse {
+            tags: vec![
+                tag(1, "work", None, "#000080"),
+                tag(2, "frontend", Some(1), "#ffff00"),
+                tag(3, "backend", Some(1), "#000080"),
+                tag(4, "home", None, "teal"),
+            ],
+            req_id: 0,
+        })
+        .update_state(&mut state)
+        .unwrap();
+        Box::new(GetTaskTagsResponse {
+            tasks: vec![TaskTags {
+                task_id: 7,
+                tags: vec![2, 4],
+            }],
+            req_id: 0,
+        })
+        .update_state(&mut state)
+        .unwrap();
+        let key = state.task_key(7).unwrap();
+        (state, key)
+    }
+
+    #[tokio::test]
+    async fn test_tag_outline() {
+        let (state, _) = tagged_state();
+        assert_eq!(
+            tag_outline(&state)
+                .into_iter()
+                .map(|(depth, tag)| (depth, tag.name.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(0, "home"), (0, "work"), (1, "backend"), (1, "frontend")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tag_chips() {
+        let (state, key) = tagged_state();
+        let chips = tag_chips(&state, key);
+        assert_eq!(chips.len(), 4);
+        assert_eq!(chips[1].content, " frontend ");
+        // dark text on light colors, and colors that can't be parsed aren't drawn
+        assert_eq!(chips[1].style.fg, Some(Color::Black));
+        assert_eq!(chips[1].style.bg, Some(Color::Rgb(255, 255, 0)));
+        assert_eq!(chips[3].style.fg, Some(GREYED_OUT_TEXT_COLOR));
+        assert_eq!(chips[3].style.bg, None);
+        assert_eq!(
+            chip(&tag(1, "work", None, "#000080")).style.fg,
+            Some(Color::White)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tag_picker() {
+        let (mut state, key) = tagged_state();
+        let mut picker = TagPicker::new(key);
+        let area = Rect::new(0, 0, 100, 20);
+        let mut buffer = Buffer::empty(area);
+        picker.render(&state, area, &mut buffer);
+        let debug_string = format!("{:?}", buffer);
+        assert!(debug_string.contains("Tags of fix crash"));
+        assert!(debug_string.contains("[x]  home "));
+        assert!(debug_string.contains("  [ ]  backend "));
+
+        let key_event = |code: KeyCode| Event::Key(code.into());
+        picker.handle_term_event(&mut state, &key_event(KeyCode::Down));
+        assert_eq!(picker.selected(&state).unwrap().name, "work");
+        // the name of a nested tag is typed under its parent
+        picker.handle_term_event(&mut state, &key_event(KeyCode::Char('c')));
+        picker.render(&state, area, &mut buffer);
+        assert!(format!("{:?}", buffer).contains("New Tag Under work"));
+        assert_eq!(
+            picker.handle_term_event(&mut state, &key_event(KeyCode::Esc)),
+            Some(true)
+        );
+        assert!(picker.new_tag.is_none());
+        assert_eq!(
+            picker.handle_term_event(&mut state, &key_event(KeyCode::Esc)),
+            None
+        );
+    }
+}
@@ -1,22 +1,30 @@
-use crossterm::event::{Event, KeyCode, KeyEvent};
+use common::{TaskPropVariant, DESCRIPTION_PROPERTY};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Flex, Layout, Rect},
     style::{Style, Stylize},
     symbols::border,
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, Paragraph, StatefulWidget, Widget},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Clear, Paragraph, StatefulWidget, Widget,
+    },
 };
 use thiserror::Error;
 use tui_textarea::{TextArea, TextAreaWidget};
 
-use crate::mid::{ModifyTaskError, NoTaskError, State, Task, TaskKey};
+use crate::mid::{ModifyTaskError, NoTaskError, PropDataError, State, Task, TaskKey};
+
+use super::detail_pane::description;
 
 #[derive(Debug)]
 pub enum TaskPopup {
     Create(String),
     Delete(TaskKey, String),
     Edit(TaskKey, Box<TextArea>),
+    /// multi-line editor for the Markdown description of a task
+    Description(TaskKey, Box<TextArea>),
 }
 
 #[derive(Debug, Error)]
@@ -25,6 +33,8 @@ pub enum CloseError {
     NoTaskError(#[from] NoTaskError),
     #[error(transparent)]
     ModifyTaskError(#[from] ModifyTaskError),
+    #[error(transparent)]
+    PropDataError(#[from] PropDataError),
     #[error("should handle to make sure task exists in shown tasks")]
     AddTask(TaskKey),
 }
@@ -38,6 +48,14 @@ impl TaskPopup {
 
         Some(Self::Edit(key, Box::new(textarea)))
     }
+    pub fn describe(key: TaskKey, state: &State) -> Option<Self> {
+        state.task_get(key).ok()?;
+        let text = description(state, key).unwrap_or_default();
+        let mut textarea = TextArea::from(text.lines());
+        textarea.set_cursor_line_style(Style::default());
+
+        Some(Self::Description(key, Box::new(textarea)))
+    }
 
     /// returns Ok with boolean notifying calling event handler whether to trigger re-render.
     /// returns Err with optional error if popup should be closed
@@ -99,16 +117,35 @@ impl TaskPopup {
                     textarea.input(event.clone());
                 }
             }
+            Self::Description(key, textarea) => {
+                // Enter starts a new line, so saving needs a modifier
+                if let Event::Key(KeyEvent {
+                    code: KeyCode::Char('s'),
+                    modifiers,
+                    ..
+                }) = event
+                {
+                    if modifiers.contains(KeyModifiers::CONTROL) {
+                        let name_key = state.prop_def_name(DESCRIPTION_PROPERTY);
+                        let text = TaskPropVariant::String(textarea.lines().join("\n"));
+                        return Err(state.prop_set(*key, name_key, text).err().map(Into::into));
+                    }
+                }
+                textarea.input(event.clone());
+            }
         }
         Ok(true)
     }
     pub fn render(&mut self, area: Rect, buf: &mut Buffer) {
         // create a centered rect of fixed vertical size that takes up 50% of the vertical area.
-        let vertical_center = Layout::vertical([Constraint::Length(3)])
-            .flex(Flex::Center)
-            .split(area);
+        // descriptions span several lines so they get a larger rect
+        let (height, width) = match self {
+            Self::Description(..) => (Constraint::Percentage(60), Constraint::Percentage(60)),
+            _ => (Constraint::Length(3), Constraint::Percentage(50)),
+        };
+        let vertical_center = Layout::vertical([height]).flex(Flex::Center).split(area);
 
-        let popup_area = Layout::horizontal([Constraint::Percentage(50)])
+        let popup_area = Layout::horizontal([width])
             .flex(Flex::Center)
             .split(vertical_center[0])[0];
 
@@ -151,6 +188,21 @@ impl TaskPopup {
                 let widget = TextAreaWidget::new().block(block);
                 StatefulWidget::render(widget, popup_area, buf, textarea)
             }
+            Self::Description(_key, textarea) => {
+                let instructions = Title::from(Line::from(vec![
+                    " Save: ".into(),
+                    "<C-s>".blue().bold(),
+                    " Cancel: ".into(),
+                    "<Esc> ".blue().bold(),
+                ]));
+                let block = Block::default()
+                    .title("Edit Description")
+                    .title(instructions.position(Position::Bottom))
+                    .borders(Borders::ALL)
+                    .border_set(border::ROUNDED);
+                let widget = TextAreaWidget::new().block(block);
+                StatefulWidget::render(widget, popup_area, buf, textarea)
+            }
         };
     }
 }
@@ -166,6 +218,7 @@ mod tests {
 mod task_popup_tests {
     mod render_tests {
         use ratatui::{buffer::Buffer, layout::Rect};
+        use tui_textarea::TextArea;
 
         use crate::mid::TaskKey;
 
@@ -190,6 +243,19 @@ mod task_popup_tests {
             assert!(format!("{:?}", buffer).contains("Delete Task")); // Check if the buffer contains the string "Delete Task"
             assert!(format!("{:?}", buffer).contains("Test Task")); // Check if the buffer contains the string "Test Task"
         }
+        #[test]
+        fn test_description() {
+            let textarea = TextArea::from(["# Plan", "- [ ] call"]);
+            let mut task_popup: TaskPopup =
+                TaskPopup::Description(TaskKey::default(), Box::new(textarea));
+            let mut buffer = Buffer::empty(Rect::new(0, 0, 100, 20));
+            let rect = Rect::new(0, 0, 100, 20);
+            task_popup.render(rect, &mut buffer);
+            let debug_string = format!("{:?}", buffer);
+            assert!(debug_string.contains("Edit Description"));
+            assert!(debug_string.contains("# Plan"));
+            assert!(debug_string.contains("- [ ] call"));
+        }
     }
     /*
         mod term_events_tests {
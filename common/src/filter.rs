@@ -0,0 +1,1031 @@
+//! In-process evaluation of [`Filter`]s.
+//!
+//! The server turns filters into SQL in `construct_filter`, the functions here run the same
+//! filter against a single task held in memory so the middleware can tell whether a locally
+//! edited task still belongs to a view without asking the server.
+
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::{Comparator, DependencyFilter, Filter, Operator, PrimitiveField, TaskPropVariant};
+
+/// Task data that a [`Filter`] can be evaluated against.
+pub trait FilterTarget {
+    /// title of the task
+    fn title(&self) -> &str;
+    /// completion status of the task
+    fn completed(&self) -> bool;
+    /// last time the task was edited
+    fn last_edited(&self) -> chrono::NaiveDateTime;
+    /// look up a property of the task by name, `None` if the task doesn't have it.
+    fn prop(&self, name: &str) -> Option<&TaskPropVariant>;
+    /// look up a global property by name, `None` if it isn't set.
+    /// Targets that don't know the global properties never match global leaves.
+    fn global(&self, _name: &str) -> Option<&TaskPropVariant> {
+        None
+    }
+}
+
+/// Reasons a filter can't be evaluated, these are the same filters the server refuses to run.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum FilterError {
+    /// comparator can't be used with values of this type
+    InvalidComparator {
+        /// the offending comparator
+        comparator: Comparator,
+        /// type string of the immediate
+        typ: String,
+    },
+    /// immediate has the wrong type for a primitive field
+    InvalidType {
+        /// the primitive field
+        field: PrimitiveField,
+        /// type string of the immediate
+        typ: String,
+    },
+    /// NOT operator without a child
+    MissingOperand,
+    /// immediate of a REGEX or IREGEX comparison isn't a valid regular expression
+    InvalidRegex {
+        /// the offending pattern
+        pattern: String,
+        /// why the pattern couldn't be parsed
+        reason: String,
+    },
+    /// filter depends on other tasks (e.g. dependency leaves) so only the server can evaluate it
+    RequiresServer,
+}
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterError::InvalidComparator { comparator, typ } => {
+                write!(f, "invalid comparator {comparator:?} for type {typ}")
+            }
+            FilterError::InvalidType { field, typ } => {
+                write!(f, "invalid type {typ} for field {field:?}")
+            }
+            FilterError::MissingOperand => write!(f, "NOT operator requires a child filter"),
+            FilterError::InvalidRegex { pattern, reason } => {
+                write!(f, "invalid regex {pattern:?}: {reason}")
+            }
+            FilterError::RequiresServer => {
+                write!(f, "filter can only be evaluated by the server")
+            }
+        }
+    }
+}
+impl std::error::Error for FilterError {}
+
+impl Filter {
+    /// Check that the filter can be evaluated, without looking at any task.
+    pub fn validate(&self) -> Result<(), FilterError> {
+        match self {
+            Filter::Leaf {
+                comparator,
+                immediate,
+                ..
+            } => check_comparator(comparator, immediate),
+            Filter::LeafPrimitive {
+                field,
+                comparator,
+                immediate,
+            } => {
+                let valid_type = matches!(
+                    (field, immediate),
+                    (PrimitiveField::TITLE, TaskPropVariant::String(_))
+                        | (PrimitiveField::COMPLETED, TaskPropVariant::Boolean(_))
+                        | (PrimitiveField::LASTEDITED, TaskPropVariant::Date(_))
+                        | (PrimitiveField::LASTEDITED, TaskPropVariant::RelativeDate(_))
+                );
+                if !valid_type {
+                    return Err(FilterError::InvalidType {
+                        field: field.clone(),
+                        typ: immediate.type_string().to_owned(),
+                    });
+                }
+                check_comparator(comparator, immediate)
+            }
+            Filter::LeafProperty { comparator, .. } => check_ordering(comparator, "property"),
+            Filter::LeafGlobal { comparator, .. } => check_ordering(comparator, "global"),
+            Filter::LeafDependency(
+                DependencyFilter::DependencyCount { comparator, .. }
+                | DependencyFilter::DependentCount { comparator, .. },
+            ) => check_ordering(comparator, "count"),
+            Filter::LeafDependency(_) | Filter::LeafTag { .. } => Ok(()),
+            Filter::Operator { op, childs } => {
+                if let (Operator::NOT, None) = (op, childs.first()) {
+                    return Err(FilterError::MissingOperand);
+                }
+                childs.iter().try_for_each(Filter::validate)
+            }
+            Filter::None => Ok(()),
+        }
+    }
+    /// Evaluate the filter against a task.
+    /// A property leaf only matches if the task has a property of that name with the same type as the immediate,
+    /// so `NOT` of a leaf matches tasks that don't have the property at all (same as the server).
+    /// Note: string ordering (LT, GT, ...) is bytewise here, postgres may use a different collation.
+    /// Regexes are limited by `validate` to the syntax the `regex` crate and postgres read the same way.
+    /// Dependency and tag leaves need the rest of the task graph or the tag tree, so filters containing them are
+    /// [`FilterError::RequiresServer`].
+    pub fn matches<T: FilterTarget + ?Sized>(&self, task: &T) -> Result<bool, FilterError> {
+        self.matches_at(task, Utc::now())
+    }
+    /// Evaluate the filter against a task, resolving relative dates against `now`.
+    pub fn matches_at<T: FilterTarget + ?Sized>(
+        &self,
+        task: &T,
+        now: DateTime<Utc>,
+    ) -> Result<bool, FilterError> {
+        self.validate()?;
+        if self.requires_server() {
+            return Err(FilterError::RequiresServer);
+        }
+        Ok(self.eval(task, now))
+    }
+    // whether the filter contains leaves that can't be evaluated from a single task,
+    // tags are left to the server too since matching descendants needs the whole tag tree
+    fn requires_server(&self) -> bool {
+        match self {
+            Filter::LeafDependency(_) | Filter::LeafTag { .. } => true,
+            Filter::Operator { childs, .. } => childs.iter().any(Filter::requires_server),
+            _ => false,
+        }
+    }
+    // evaluate an already validated filter
+    fn eval<T: FilterTarget + ?Sized>(&self, task: &T, now: DateTime<Utc>) -> bool {
+        match self {
+            Filter::Leaf {
+                field,
+                comparator,
+                immediate,
+            } => task
+                .prop(field)
+                .is_some_and(|value| compare(comparator, value, immediate, now)),
+            Filter::LeafPrimitive {
+                field,
+                comparator,
+                immediate,
+            } => {
+                let value = match field {
+                    PrimitiveField::TITLE => TaskPropVariant::String(task.title().to_owned()),
+                    PrimitiveField::COMPLETED => TaskPropVariant::Boolean(task.completed()),
+                    PrimitiveField::LASTEDITED => TaskPropVariant::Date(task.last_edited()),
+                };
+                match (field, immediate) {
+                    // last edited is recorded in UTC, unlike date properties
+                    (PrimitiveField::LASTEDITED, TaskPropVariant::RelativeDate(date)) => {
+                        let immediate = TaskPropVariant::Date(date.resolve_utc(now));
+                        compare(comparator, &value, &immediate, now)
+                    }
+                    _ => compare(comparator, &value, immediate, now),
+                }
+            }
+            Filter::LeafProperty {
+                left,
+                comparator,
+                right,
+            } => match (task.prop(left), task.prop(right)) {
+                (Some(left), Some(right)) => compare_props(comparator, left, right),
+                _ => false,
+            },
+            Filter::LeafGlobal {
+                field,
+                comparator,
+                global,
+            } => match (task.prop(field), task.global(global)) {
+                (Some(value), Some(global)) => compare_props(comparator, value, global),
+                _ => false,
+            },
+            Filter::LeafDependency(_) | Filter::LeafTag { .. } => {
+                unreachable!("dependency and tag filters are evaluated by the server")
+            }
+            Filter::Operator { op, childs } => match op {
+                Operator::AND => childs.iter().all(|c| c.eval(task, now)),
+                Operator::OR => childs.iter().any(|c| c.eval(task, now)),
+                Operator::NOT => !childs[0].eval(task, now),
+            },
+            Filter::None => true,
+        }
+    }
+}
+
+// make sure comparator is supported for the type of the immediate, mirrors the arms of construct_filter
+fn check_comparator(
+    comparator: &Comparator,
+    immediate: &TaskPropVariant,
+) -> Result<(), FilterError> {
+    use Comparator::*;
+    let valid = match immediate {
+        TaskPropVariant::String(_) if matches!(comparator, IN | CONTAINSANY | CONTAINSALL) => false,
+        TaskPropVariant::String(pattern) => {
+            if let REGEX | IREGEX = comparator {
+                // postgres runs the pattern on the server, so only accept what it reads the same way as the crate
+                portable_regex(pattern)
+                    .and_then(|_| regex(comparator, pattern).map_err(|err| err.to_string()))
+                    .map_err(|reason| FilterError::InvalidRegex {
+                        pattern: pattern.clone(),
+                        reason,
+                    })?;
+            }
+            true
+        }
+        TaskPropVariant::Number(_)
+        | TaskPropVariant::Integer(_)
+        | TaskPropVariant::Duration(_)
+        | TaskPropVariant::Date(_)
+        | TaskPropVariant::RelativeDate(_) => {
+            matches!(comparator, LT | LEQ | GT | GEQ | EQ | NEQ)
+        }
+        TaskPropVariant::Boolean(_)
+        | TaskPropVariant::Select(_)
+        | TaskPropVariant::TaskRef(_)
+        | TaskPropVariant::User(_) => matches!(comparator, EQ | NEQ),
+        // IN compares a select value (or task reference) to the list, the rest compare lists
+        TaskPropVariant::MultiSelect(_) | TaskPropVariant::TaskRefs(_) => {
+            matches!(comparator, IN | CONTAINSANY | CONTAINSALL | EQ | NEQ)
+        }
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(FilterError::InvalidComparator {
+            comparator: comparator.clone(),
+            typ: immediate.type_string().to_owned(),
+        })
+    }
+}
+
+// make sure comparator only orders its operands, for leaves without an immediate
+fn check_ordering(comparator: &Comparator, typ: &str) -> Result<(), FilterError> {
+    use Comparator::*;
+    if matches!(comparator, LT | LEQ | GT | GEQ | EQ | NEQ) {
+        Ok(())
+    } else {
+        Err(FilterError::InvalidComparator {
+            comparator: comparator.clone(),
+            typ: typ.to_owned(),
+        })
+    }
+}
+
+// compare two property values, values of a different type never match
+fn compare_props(comparator: &Comparator, left: &TaskPropVariant, right: &TaskPropVariant) -> bool {
+    use TaskPropVariant::*;
+    match (left, right) {
+        (String(l), String(r)) => compare_ord(comparator, l, r),
+        (Number(l), Number(r)) => compare_ord(comparator, l, r),
+        (Integer(l), Integer(r)) => compare_ord(comparator, l, r),
+        (Duration(l), Duration(r)) => compare_ord(comparator, l, r),
+        (Date(l), Date(r)) => compare_ord(comparator, l, r),
+        (Boolean(l), Boolean(r)) => compare_ord(comparator, l, r),
+        (Select(l), Select(r)) => compare_ord(comparator, l, r),
+        (TaskRef(l), TaskRef(r)) => compare_ord(comparator, l, r),
+        (User(l), User(r)) => compare_ord(comparator, l, r),
+        _ => false,
+    }
+}
+
+// compare a value to an immediate, values of a different type never match
+fn compare(
+    comparator: &Comparator,
+    value: &TaskPropVariant,
+    immediate: &TaskPropVariant,
+    now: DateTime<Utc>,
+) -> bool {
+    use TaskPropVariant::*;
+    match (value, immediate) {
+        (String(v), String(imm)) => match comparator {
+            Comparator::CONTAINS => like(v, &format!("%{imm}%")),
+            Comparator::NOTCONTAINS => !like(v, &format!("%{imm}%")),
+            Comparator::LIKE => like(v, imm),
+            Comparator::IEQ => v.to_lowercase() == imm.to_lowercase(),
+            Comparator::ICONTAINS => like(&v.to_lowercase(), &format!("%{}%", imm.to_lowercase())),
+            Comparator::ILIKE => like(&v.to_lowercase(), &imm.to_lowercase()),
+            Comparator::REGEX | Comparator::IREGEX => {
+                regex(comparator, imm).is_ok_and(|re| re.is_match(v))
+            }
+            _ => compare_ord(comparator, v, imm),
+        },
+        (Number(v), Number(imm)) => compare_ord(comparator, v, imm),
+        (Integer(v), Integer(imm)) => compare_ord(comparator, v, imm),
+        (Duration(v), Duration(imm)) => compare_ord(comparator, v, imm),
+        (Date(v), Date(imm)) => compare_ord(comparator, v, imm),
+        (Date(v), RelativeDate(imm)) => compare_ord(comparator, v, &imm.resolve(now)),
+        (Boolean(v), Boolean(imm)) => compare_ord(comparator, v, imm),
+        (Select(v), Select(imm)) => compare_ord(comparator, v, imm),
+        (Select(v), MultiSelect(imm)) => *comparator == Comparator::IN && imm.contains(v),
+        (MultiSelect(v), MultiSelect(imm)) => compare_lists(comparator, v, imm),
+        (TaskRef(v), TaskRef(imm)) => compare_ord(comparator, v, imm),
+        (TaskRef(v), TaskRefs(imm)) => *comparator == Comparator::IN && imm.contains(v),
+        (TaskRefs(v), TaskRefs(imm)) => compare_lists(comparator, v, imm),
+        (User(v), User(imm)) => compare_ord(comparator, v, imm),
+        _ => false,
+    }
+}
+
+// compare multi-select options or task references as sets
+fn compare_lists<T: PartialEq>(comparator: &Comparator, value: &[T], imm: &[T]) -> bool {
+    match comparator {
+        Comparator::CONTAINSANY => imm.iter().any(|o| value.contains(o)),
+        Comparator::CONTAINSALL => imm.iter().all(|o| value.contains(o)),
+        // order of the options doesn't matter
+        Comparator::EQ => same_options(value, imm),
+        Comparator::NEQ => !same_options(value, imm),
+        _ => false,
+    }
+}
+
+fn same_options<T: PartialEq>(left: &[T], right: &[T]) -> bool {
+    left.iter().all(|o| right.contains(o)) && right.iter().all(|o| left.contains(o))
+}
+
+fn compare_ord<T: PartialOrd>(comparator: &Comparator, value: &T, imm: &T) -> bool {
+    match comparator {
+        Comparator::LT => value < imm,
+        Comparator::LEQ => value <= imm,
+        Comparator::GT => value > imm,
+        Comparator::GEQ => value >= imm,
+        Comparator::EQ => value == imm,
+        Comparator::NEQ => value != imm,
+        _ => false,
+    }
+}
+
+/// escapes that mean the same to the `regex` crate and postgres, other letters and digits after a `\`
+/// differ (e.g. `\b` is a word boundary to the crate but a backspace to postgres) or only exist on one side
+const PORTABLE_ESCAPES: &[char] = &['d', 'D', 's', 'S', 'w', 'W', 'n', 'r', 't'];
+
+// reject the parts of the `regex` crate's syntax postgres reads differently: escapes outside PORTABLE_ESCAPES,
+// inline flags and the crate's nested classes and class set operations. Escaped punctuation is a literal on both sides
+fn portable_regex(pattern: &str) -> Result<(), String> {
+    let mut chars = pattern.chars().peekable();
+    let mut in_class = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(e) if e.is_ascii_alphanumeric() && !PORTABLE_ESCAPES.contains(&e) => {
+                    return Err(format!("\\{e} means something else to the server"));
+                }
+                // postgres doesn't allow negated shorthands in a class
+                Some(e @ ('D' | 'S' | 'W')) if in_class => {
+                    return Err(format!("\\{e} isn't allowed in a class by the server"));
+                }
+                _ => {}
+            },
+            '(' if !in_class && chars.peek() == Some(&'?') => {
+                chars.next();
+                if chars.next() != Some(':') {
+                    return Err("only (?:...) groups are supported by the server".to_owned());
+                }
+            }
+            '[' if !in_class => {
+                in_class = true;
+                chars.next_if_eq(&'^');
+                chars.next_if_eq(&']'); // a leading ] is a literal
+            }
+            '[' if chars.peek() == Some(&':') => {
+                // a POSIX class like [:alpha:], which both sides know
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                }
+            }
+            '[' => return Err("nested classes aren't supported by the server".to_owned()),
+            ']' if in_class => in_class = false,
+            '&' | '-' | '~' if in_class && chars.peek() == Some(&c) => {
+                return Err(format!(
+                    "class operator {c}{c} isn't supported by the server"
+                ));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// compile the pattern of a REGEX or IREGEX comparison. `.` matches newlines, as it does in postgres
+fn regex(comparator: &Comparator, pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(*comparator == Comparator::IREGEX)
+        .dot_matches_new_line(true)
+        .build()
+}
+
+/// SQL `LIKE` matching: `%` matches any sequence, `_` matches any single character and `\` escapes the next character.
+pub fn like(value: &str, pattern: &str) -> bool {
+    let value = value.chars().collect::<Vec<char>>();
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            '%' => LikeToken::Any,
+            '_' => LikeToken::One,
+            // a trailing backslash is a literal
+            '\\' => LikeToken::Char(chars.next().unwrap_or('\\')),
+            c => LikeToken::Char(c),
+        });
+    }
+    // match greedily, and on a mismatch let the last % swallow one more character and retry from there.
+    // Earlier %s never need to swallow more, so this takes at most value × pattern steps
+    let (mut v, mut p) = (0, 0);
+    let mut last_any: Option<(usize, usize)> = None;
+    while v < value.len() {
+        match tokens.get(p) {
+            Some(LikeToken::Any) => {
+                last_any = Some((p + 1, v));
+                p += 1;
+            }
+            Some(LikeToken::One) => (v, p) = (v + 1, p + 1),
+            Some(LikeToken::Char(c)) if *c == value[v] => (v, p) = (v + 1, p + 1),
+            _ => match last_any {
+                Some((after, swallowed)) => {
+                    last_any = Some((after, swallowed + 1));
+                    (v, p) = (swallowed + 1, after);
+                }
+                None => return false,
+            },
+        }
+    }
+    tokens[p..].iter().all(|token| *token == LikeToken::Any)
+}
+#[derive(PartialEq)]
+enum LikeToken {
+    /// `%`
+    Any,
+    /// `_`
+    One,
+    Char(char),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{DateAnchor, RelativeDate, TaskDuration, TaskID};
+
+    struct TestTask {
+        title: String,
+        completed: bool,
+        last_edited: chrono::NaiveDateTime,
+        props: HashMap<String, TaskPropVariant>,
+        globals: HashMap<String, TaskPropVariant>,
+    }
+    impl FilterTarget for TestTask {
+        fn title(&self) -> &str {
+            &self.title
+        }
+        fn completed(&self) -> bool {
+            self.completed
+        }
+        fn last_edited(&self) -> chrono::NaiveDateTime {
+            self.last_edited
+        }
+        fn prop(&self, name: &str) -> Option<&TaskPropVariant> {
+            self.props.get(name)
+        }
+        fn global(&self, name: &str) -> Option<&TaskPropVariant> {
+            self.globals.get(name)
+        }
+    }
+
+    fn date(month: u32, day: u32) -> chrono::NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, month, day)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+    }
+    fn task() -> TestTask {
+        TestTask {
+            title: "a dude".to_owned(),
+            completed: true,
+            last_edited: date(1, 1),
+            props: HashMap::from([
+                ("dogs".to_owned(), TaskPropVariant::Number(1.0)),
+                (
+                    "name".to_owned(),
+                    TaskPropVariant::String("a dude".to_owned()),
+                ),
+                ("due".to_owned(), TaskPropVariant::Date(date(2, 1))),
+                ("ez".to_owned(), TaskPropVariant::Boolean(true)),
+            ]),
+            globals: HashMap::new(),
+        }
+    }
+    fn leaf(field: &str, comparator: Comparator, immediate: TaskPropVariant) -> Filter {
+        Filter::Leaf {
+            field: field.to_owned(),
+            comparator,
+            immediate,
+        }
+    }
+
+    #[test]
+    fn test_like() {
+        assert!(like("a dude", "%dude%"));
+        assert!(like("a dude", "a_dude"));
+        assert!(like("a dude", "%"));
+        assert!(!like("a dude", "dude"));
+        assert!(!like("a dude", "a_"));
+        assert!(like("100%", "100\\%"));
+        assert!(!like("1000", "100\\%"));
+        assert!(like("", ""));
+        assert!(like("a%b_c", "a\\%b\\_c"));
+        assert!(like("abcb", "%b"));
+        assert!(!like("abc", "%b"));
+        // many %s don't backtrack exponentially
+        assert!(!like(&"a".repeat(500), "%a%a%a%a%a%a%a%a%b"));
+    }
+
+    #[test]
+    fn test_leaf_number() {
+        let task = task();
+        let num = |c| leaf("dogs", c, TaskPropVariant::Number(1.0)).matches(&task);
+        assert_eq!(num(Comparator::EQ), Ok(true));
+        assert_eq!(num(Comparator::NEQ), Ok(false));
+        assert_eq!(num(Comparator::LEQ), Ok(true));
+        assert_eq!(num(Comparator::LT), Ok(false));
+        assert_eq!(num(Comparator::GEQ), Ok(true));
+        assert_eq!(num(Comparator::GT), Ok(false));
+        assert!(num(Comparator::LIKE).is_err());
+    }
+
+    #[test]
+    fn test_leaf_integer_duration() {
+        let mut task = task();
+        task.props
+            .insert("points".to_owned(), TaskPropVariant::Integer(3));
+        task.props.insert(
+            "estimate".to_owned(),
+            TaskPropVariant::Duration(TaskDuration(9000)),
+        );
+        let points = |c, imm| leaf("points", c, TaskPropVariant::Integer(imm)).matches(&task);
+        assert_eq!(points(Comparator::EQ, 3), Ok(true));
+        assert_eq!(points(Comparator::GT, 2), Ok(true));
+        assert_eq!(points(Comparator::LT, 3), Ok(false));
+        assert!(points(Comparator::CONTAINS, 3).is_err());
+        // integers and decimals are different types
+        assert_eq!(
+            leaf("points", Comparator::EQ, TaskPropVariant::Number(3.0)).matches(&task),
+            Ok(false)
+        );
+        let estimate = |c, imm: &str| {
+            leaf(
+                "estimate",
+                c,
+                TaskPropVariant::Duration(imm.parse().unwrap()),
+            )
+            .matches(&task)
+        };
+        assert_eq!(estimate(Comparator::EQ, "150m"), Ok(true));
+        assert_eq!(estimate(Comparator::LEQ, "2h"), Ok(false));
+        assert_eq!(estimate(Comparator::GT, "1d"), Ok(false));
+    }
+
+    #[test]
+    fn test_leaf_string() {
+        let task = task();
+        let s = |c, imm: &str| {
+            leaf("name", c, TaskPropVariant::String(imm.to_owned()))
+                .matches(&task)
+                .unwrap()
+        };
+        assert!(s(Comparator::EQ, "a dude"));
+        assert!(s(Comparator::LT, "b"));
+        assert!(!s(Comparator::GT, "b"));
+        assert!(s(Comparator::CONTAINS, "dude"));
+        assert!(!s(Comparator::NOTCONTAINS, "dude"));
+        assert!(s(Comparator::NOTCONTAINS, "cat"));
+        assert!(s(Comparator::LIKE, "%dude%"));
+        assert!(!s(Comparator::LIKE, "dude"));
+        assert!(s(Comparator::IEQ, "A Dude"));
+        assert!(!s(Comparator::EQ, "A Dude"));
+        assert!(s(Comparator::ICONTAINS, "DUDE"));
+        assert!(!s(Comparator::CONTAINS, "DUDE"));
+        assert!(s(Comparator::ILIKE, "A_D%"));
+        assert!(!s(Comparator::LIKE, "A_D%"));
+    }
+
+    #[test]
+    fn test_leaf_regex() {
+        let task = task();
+        let s =
+            |c, imm: &str| leaf("name", c, TaskPropVariant::String(imm.to_owned())).matches(&task);
+        assert_eq!(s(Comparator::REGEX, "^a d.de$"), Ok(true));
+        assert_eq!(s(Comparator::REGEX, "du"), Ok(true));
+        assert_eq!(s(Comparator::REGEX, "DUDE"), Ok(false));
+        assert_eq!(s(Comparator::IREGEX, "DUDE"), Ok(true));
+        assert_eq!(s(Comparator::IREGEX, "^dude"), Ok(false));
+        // invalid patterns are rejected even when the task doesn't have the property
+        let invalid = leaf(
+            "cats",
+            Comparator::REGEX,
+            TaskPropVariant::String("(".to_owned()),
+        );
+        assert!(matches!(
+            invalid.validate(),
+            Err(FilterError::InvalidRegex { pattern, .. }) if pattern == "("
+        ));
+        // lookaround isn't supported by both sides
+        assert!(s(Comparator::IREGEX, "a(?=dude)").is_err());
+        // neither is syntax postgres reads differently from the crate
+        for pattern in [
+            r"\bdude", r"\ydude", r"\p{L}", "(?i)dude", r"[\D]", "[a[b]]", "[a-z&&b]",
+        ] {
+            assert!(
+                matches!(
+                    s(Comparator::REGEX, pattern),
+                    Err(FilterError::InvalidRegex { .. })
+                ),
+                "{pattern}"
+            );
+        }
+        assert_eq!(s(Comparator::REGEX, r"^a\sd(?:u|o)de\.?$"), Ok(true));
+        assert_eq!(s(Comparator::REGEX, r"[[:alpha:]\d-]+$"), Ok(true));
+        assert_eq!(s(Comparator::REGEX, r"[]a]\s"), Ok(true));
+        // . matches newlines in postgres too
+        assert!(regex(&Comparator::REGEX, "a.b").unwrap().is_match("a\nb"));
+        // regex only applies to strings
+        assert!(
+            leaf("dogs", Comparator::REGEX, TaskPropVariant::Number(1.0))
+                .matches(&task)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_leaf_date_bool() {
+        let task = task();
+        assert_eq!(
+            leaf("due", Comparator::LT, TaskPropVariant::Date(date(2, 5))).matches(&task),
+            Ok(true)
+        );
+        assert_eq!(
+            leaf("ez", Comparator::NEQ, TaskPropVariant::Boolean(false)).matches(&task),
+            Ok(true)
+        );
+        assert!(leaf("ez", Comparator::LT, TaskPropVariant::Boolean(false))
+            .matches(&task)
+            .is_err());
+        assert!(leaf(
+            "due",
+            Comparator::CONTAINS,
+            TaskPropVariant::Date(date(2, 5))
+        )
+        .matches(&task)
+        .is_err());
+    }
+
+    #[test]
+    fn test_leaf_select() {
+        let mut task = task();
+        task.props.insert(
+            "status".to_owned(),
+            TaskPropVariant::Select("doing".to_owned()),
+        );
+        task.props.insert(
+            "labels".to_owned(),
+            TaskPropVariant::MultiSelect(vec!["bug".to_owned(), "ui".to_owned()]),
+        );
+        let options = |options: &[&str]| {
+            TaskPropVariant::MultiSelect(options.iter().map(|o| o.to_string()).collect())
+        };
+        let status = |c, imm| leaf("status", c, imm).matches(&task);
+        assert_eq!(
+            status(Comparator::EQ, TaskPropVariant::Select("doing".to_owned())),
+            Ok(true)
+        );
+        assert_eq!(
+            status(Comparator::NEQ, TaskPropVariant::Select("doing".to_owned())),
+            Ok(false)
+        );
+        assert_eq!(
+            status(Comparator::IN, options(&["todo", "doing"])),
+            Ok(true)
+        );
+        assert_eq!(status(Comparator::IN, options(&["done"])), Ok(false));
+        // a select is never equal to a string
+        assert_eq!(
+            status(Comparator::EQ, TaskPropVariant::String("doing".to_owned())),
+            Ok(false)
+        );
+        assert!(status(
+            Comparator::CONTAINS,
+            TaskPropVariant::Select("doing".to_owned())
+        )
+        .is_err());
+
+        let labels = |c, imm: &[&str]| leaf("labels", c, options(imm)).matches(&task);
+        assert_eq!(labels(Comparator::CONTAINSANY, &["docs", "ui"]), Ok(true));
+        assert_eq!(labels(Comparator::CONTAINSANY, &["docs"]), Ok(false));
+        assert_eq!(labels(Comparator::CONTAINSALL, &["ui", "bug"]), Ok(true));
+        assert_eq!(labels(Comparator::CONTAINSALL, &["ui", "docs"]), Ok(false));
+        assert_eq!(labels(Comparator::EQ, &["ui", "bug"]), Ok(true));
+        assert_eq!(labels(Comparator::NEQ, &["ui"]), Ok(true));
+        assert_eq!(labels(Comparator::IN, &["ui"]), Ok(false));
+        assert!(labels(Comparator::LT, &["ui"]).is_err());
+        assert!(leaf(
+            "name",
+            Comparator::CONTAINSANY,
+            TaskPropVariant::String("dude".to_owned())
+        )
+        .matches(&task)
+        .is_err());
+    }
+
+    #[test]
+    fn test_leaf_references() {
+        let mut task = task();
+        task.props
+            .insert("duplicate of".to_owned(), TaskPropVariant::TaskRef(4));
+        task.props.insert(
+            "blocked by".to_owned(),
+            TaskPropVariant::TaskRefs(vec![2, 3]),
+        );
+        task.props.insert(
+            "assignee".to_owned(),
+            TaskPropVariant::User("alex".to_owned()),
+        );
+        let duplicate = |c, imm| leaf("duplicate of", c, imm).matches(&task);
+        assert_eq!(
+            duplicate(Comparator::EQ, TaskPropVariant::TaskRef(4)),
+            Ok(true)
+        );
+        assert_eq!(
+            duplicate(Comparator::NEQ, TaskPropVariant::TaskRef(4)),
+            Ok(false)
+        );
+        assert_eq!(
+            duplicate(Comparator::IN, TaskPropVariant::TaskRefs(vec![1, 4])),
+            Ok(true)
+        );
+        // a reference is never equal to a number
+        assert_eq!(
+            duplicate(Comparator::EQ, TaskPropVariant::Number(4.0)),
+            Ok(false)
+        );
+        assert!(duplicate(Comparator::LT, TaskPropVariant::TaskRef(4)).is_err());
+
+        let blocked = |c, imm: &[TaskID]| {
+            leaf("blocked by", c, TaskPropVariant::TaskRefs(imm.to_vec())).matches(&task)
+        };
+        assert_eq!(blocked(Comparator::CONTAINSANY, &[3, 5]), Ok(true));
+        assert_eq!(blocked(Comparator::CONTAINSALL, &[3, 5]), Ok(false));
+        assert_eq!(blocked(Comparator::EQ, &[3, 2]), Ok(true));
+        assert_eq!(blocked(Comparator::NEQ, &[3, 2]), Ok(false));
+
+        let assignee = |c, imm: &str| {
+            leaf("assignee", c, TaskPropVariant::User(imm.to_owned())).matches(&task)
+        };
+        assert_eq!(assignee(Comparator::EQ, "alex"), Ok(true));
+        assert_eq!(assignee(Comparator::NEQ, "sam"), Ok(true));
+        assert!(assignee(Comparator::CONTAINS, "al").is_err());
+    }
+
+    #[test]
+    fn test_leaf_relative_date() {
+        let task = task();
+        // due 2024-02-01, last edited 2024-01-01
+        let now = date(2, 3).and_utc();
+        let relative = |anchor, days: i64| {
+            TaskPropVariant::RelativeDate(RelativeDate {
+                anchor,
+                offset_secs: days * 86400,
+                timezone: None,
+            })
+        };
+        let due = |comparator, immediate| leaf("due", comparator, immediate).matches_at(&task, now);
+        // due this week (week of monday 2024-01-29)
+        let this_week = Filter::Operator {
+            op: Operator::AND,
+            childs: vec![
+                leaf("due", Comparator::GEQ, relative(DateAnchor::StartOfWeek, 0)),
+                leaf("due", Comparator::LT, relative(DateAnchor::StartOfWeek, 7)),
+            ],
+        };
+        assert_eq!(this_week.matches_at(&task, now), Ok(true));
+        // a week later the same filter no longer matches
+        assert_eq!(
+            this_week.matches_at(&task, date(2, 10).and_utc()),
+            Ok(false)
+        );
+        assert_eq!(due(Comparator::GT, relative(DateAnchor::Now, -3)), Ok(true));
+        assert_eq!(
+            due(Comparator::GEQ, relative(DateAnchor::StartOfMonth, 0)),
+            Ok(true)
+        );
+        assert!(due(Comparator::LIKE, relative(DateAnchor::Now, 0)).is_err());
+        // relative dates only compare with dates
+        assert_eq!(
+            leaf("dogs", Comparator::LT, relative(DateAnchor::Now, 0)).matches_at(&task, now),
+            Ok(false)
+        );
+        // edited in the last 3 days
+        let edited = Filter::LeafPrimitive {
+            field: PrimitiveField::LASTEDITED,
+            comparator: Comparator::GEQ,
+            immediate: relative(DateAnchor::StartOfDay, -3),
+        };
+        assert_eq!(edited.matches_at(&task, now), Ok(false));
+        assert_eq!(edited.matches_at(&task, date(1, 2).and_utc()), Ok(true));
+        // last edited is in UTC, so the time zone only moves the anchor: edited two hours ago isn't in the last hour
+        let edited = Filter::LeafPrimitive {
+            field: PrimitiveField::LASTEDITED,
+            comparator: Comparator::GEQ,
+            immediate: TaskPropVariant::RelativeDate(RelativeDate {
+                anchor: DateAnchor::Now,
+                offset_secs: -3600,
+                timezone: Some(chrono_tz::America::New_York),
+            }),
+        };
+        let two_hours_later = (date(1, 1) + chrono::Duration::hours(2)).and_utc();
+        assert_eq!(edited.matches_at(&task, two_hours_later), Ok(false));
+    }
+
+    #[test]
+    fn test_leaf_property() {
+        let mut task = task();
+        task.props
+            .insert("cats".to_owned(), TaskPropVariant::Number(3.0));
+        let prop = |left: &str, comparator, right: &str| {
+            Filter::LeafProperty {
+                left: left.to_owned(),
+                comparator,
+                right: right.to_owned(),
+            }
+            .matches(&task)
+        };
+        assert_eq!(prop("cats", Comparator::GT, "dogs"), Ok(true));
+        assert_eq!(prop("cats", Comparator::LEQ, "dogs"), Ok(false));
+        assert_eq!(prop("dogs", Comparator::EQ, "dogs"), Ok(true));
+        // missing or differently typed properties never match
+        assert_eq!(prop("cats", Comparator::NEQ, "birds"), Ok(false));
+        assert_eq!(prop("cats", Comparator::NEQ, "name"), Ok(false));
+        assert!(prop("cats", Comparator::CONTAINS, "dogs").is_err());
+    }
+
+    #[test]
+    fn test_leaf_global() {
+        let mut task = task();
+        task.globals
+            .insert("sprint end".to_owned(), TaskPropVariant::Date(date(3, 1)));
+        task.globals
+            .insert("wip limit".to_owned(), TaskPropVariant::Number(1.0));
+        let global = |field: &str, comparator, global: &str| {
+            Filter::LeafGlobal {
+                field: field.to_owned(),
+                comparator,
+                global: global.to_owned(),
+            }
+            .matches(&task)
+        };
+        assert_eq!(global("due", Comparator::LT, "sprint end"), Ok(true));
+        assert_eq!(global("due", Comparator::GEQ, "sprint end"), Ok(false));
+        assert_eq!(global("dogs", Comparator::EQ, "wip limit"), Ok(true));
+        // missing or differently typed globals never match
+        assert_eq!(global("due", Comparator::NEQ, "wip limit"), Ok(false));
+        assert_eq!(global("due", Comparator::NEQ, "release"), Ok(false));
+        assert!(global("due", Comparator::LIKE, "sprint end").is_err());
+    }
+
+    #[test]
+    fn test_leaf_dependency() {
+        let task = task();
+        let incomplete = Filter::LeafDependency(DependencyFilter::HasIncompleteDependency);
+        assert_eq!(incomplete.validate(), Ok(()));
+        assert_eq!(incomplete.matches(&task), Err(FilterError::RequiresServer));
+        let nested = Filter::Operator {
+            op: Operator::OR,
+            childs: vec![Filter::None, incomplete],
+        };
+        assert_eq!(nested.matches(&task), Err(FilterError::RequiresServer));
+        let count = Filter::LeafDependency(DependencyFilter::DependentCount {
+            comparator: Comparator::LIKE,
+            count: 3,
+        });
+        assert!(matches!(
+            count.validate(),
+            Err(FilterError::InvalidComparator { .. })
+        ));
+    }
+
+    #[test]
+    fn test_leaf_tag() {
+        let tagged = Filter::LeafTag {
+            tag: 1,
+            descendants: true,
+        };
+        assert_eq!(tagged.validate(), Ok(()));
+        assert_eq!(tagged.matches(&task()), Err(FilterError::RequiresServer));
+    }
+
+    #[test]
+    fn test_leaf_missing_or_mistyped_prop() {
+        let task = task();
+        // property doesn't exist
+        let missing = leaf("cats", Comparator::NEQ, TaskPropVariant::Number(1.0));
+        assert_eq!(missing.matches(&task), Ok(false));
+        // property exists but has a different type
+        let mistyped = leaf(
+            "dogs",
+            Comparator::NEQ,
+            TaskPropVariant::String("1".to_owned()),
+        );
+        assert_eq!(mistyped.matches(&task), Ok(false));
+        // NOT of a missing property matches
+        let not = Filter::Operator {
+            op: Operator::NOT,
+            childs: vec![missing],
+        };
+        assert_eq!(not.matches(&task), Ok(true));
+    }
+
+    #[test]
+    fn test_leaf_primitive() {
+        let task = task();
+        let prim = |field, comparator, immediate| {
+            Filter::LeafPrimitive {
+                field,
+                comparator,
+                immediate,
+            }
+            .matches(&task)
+        };
+        assert_eq!(
+            prim(
+                PrimitiveField::TITLE,
+                Comparator::CONTAINS,
+                TaskPropVariant::String("dude".to_owned())
+            ),
+            Ok(true)
+        );
+        assert_eq!(
+            prim(
+                PrimitiveField::COMPLETED,
+                Comparator::EQ,
+                TaskPropVariant::Boolean(false)
+            ),
+            Ok(false)
+        );
+        assert_eq!(
+            prim(
+                PrimitiveField::LASTEDITED,
+                Comparator::GEQ,
+                TaskPropVariant::Date(date(1, 1))
+            ),
+            Ok(true)
+        );
+        assert!(prim(
+            PrimitiveField::TITLE,
+            Comparator::EQ,
+            TaskPropVariant::Boolean(true)
+        )
+        .is_err());
+        assert!(prim(
+            PrimitiveField::COMPLETED,
+            Comparator::LIKE,
+            TaskPropVariant::Boolean(true)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_operators() {
+        let task = task();
+        let yes = leaf("dogs", Comparator::EQ, TaskPropVariant::Number(1.0));
+        let no = leaf("ez", Comparator::EQ, TaskPropVariant::Boolean(false));
+        let op = |op, childs| Filter::Operator { op, childs }.matches(&task);
+        assert_eq!(op(Operator::AND, vec![yes.clone(), yes.clone()]), Ok(true));
+        assert_eq!(op(Operator::AND, vec![yes.clone(), no.clone()]), Ok(false));
+        assert_eq!(op(Operator::OR, vec![yes.clone(), no.clone()]), Ok(true));
+        assert_eq!(op(Operator::OR, vec![no.clone()]), Ok(false));
+        assert_eq!(op(Operator::NOT, vec![no.clone()]), Ok(true));
+        assert_eq!(op(Operator::NOT, vec![]), Err(FilterError::MissingOperand));
+        // an invalid child is an error even if it wouldn't need to be evaluated
+        let invalid = leaf("ez", Comparator::LIKE, TaskPropVariant::Boolean(false));
+        assert!(op(Operator::OR, vec![yes, invalid]).is_err());
+        assert_eq!(Filter::None.matches(&task), Ok(true));
+    }
+
+    #[test]
+    fn test_filter_error_display() {
+        let err = FilterError::InvalidComparator {
+            comparator: Comparator::LIKE,
+            typ: "number".to_owned(),
+        };
+        assert_eq!(err.to_string(), "invalid comparator LIKE for type number");
+        assert!(!FilterError::MissingOperand.to_string().is_empty());
+        let err = FilterError::InvalidRegex {
+            pattern: "(".to_owned(),
+            reason: "unclosed group".to_owned(),
+        };
+        assert_eq!(err.to_string(), "invalid regex \"(\": unclosed group");
+    }
+}
@@ -0,0 +1,427 @@
+//! Expression language of computed properties.
+//!
+//! A [`PropertyDefinition`](crate::PropertyDefinition) with a formula isn't stored on tasks, the server
+//! evaluates the formula whenever the property is read or filtered on. Formulas are numbers combined
+//! with `+ - * /`, parentheses and unary minus, where a number is one of
+//! - a literal, `2.5`
+//! - `prop("estimate")`: number, integer or duration (in seconds) property of the task, computed properties included
+//! - `days_until("due")`: fractional days from now until the date property of the task, negative once it's past
+//! - `count(dependencies)` / `done(dependencies)`: number of related tasks, and of completed related tasks
+//! - `sum(dependencies, "estimate")`, `avg`, `min` and `max`: aggregate of a property of the related tasks
+//!
+//! Related tasks are either `dependencies` (the tasks the task depends on) or `dependents`.
+//! A formula is missing for a task if a property it refers to is missing, or it divides by zero.
+//! Aggregates skip related tasks missing the property, sums over no values are 0 and the others are missing.
+//! e.g. percent of dependencies completed: `done(dependencies) / count(dependencies) * 100`
+
+use std::{fmt, str::FromStr};
+
+use crate::PropName;
+
+/// Parsed formula of a computed property.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Formula {
+    /// literal number
+    Number(f64),
+    /// numeric property of the task
+    Prop(PropName),
+    /// days until the date property of the task
+    DaysUntil(PropName),
+    /// number of related tasks
+    Count(Relation),
+    /// number of completed related tasks
+    Done(Relation),
+    /// aggregate of a property over the related tasks
+    Rollup {
+        /// how values are combined
+        aggregate: Aggregate,
+        /// which tasks are related
+        relation: Relation,
+        /// property of the related tasks
+        prop: PropName,
+    },
+    /// negation
+    Neg(Box<Formula>),
+    /// arithmetic on two formulas
+    Binary {
+        /// the operation
+        op: BinaryOp,
+        /// left operand
+        left: Box<Formula>,
+        /// right operand
+        right: Box<Formula>,
+    },
+}
+
+/// Tasks a rollup is taken over.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Relation {
+    /// tasks the task depends on
+    Dependencies,
+    /// tasks that depend on the task
+    Dependents,
+}
+
+/// How a rollup combines the values of the related tasks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregate {
+    /// total, 0 without values
+    Sum,
+    /// mean
+    Avg,
+    /// smallest value
+    Min,
+    /// largest value
+    Max,
+}
+
+/// Arithmetic operators.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    /// `+`
+    Add,
+    /// `-`
+    Sub,
+    /// `*`
+    Mul,
+    /// `/`
+    Div,
+}
+
+/// Reason a formula couldn't be parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormulaError {
+    /// byte offset into the formula where parsing failed
+    pub position: usize,
+    /// what was wrong
+    pub reason: String,
+}
+impl fmt::Display for FormulaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.reason, self.position)
+    }
+}
+impl std::error::Error for FormulaError {}
+
+impl Formula {
+    /// names of the properties the formula reads, of the task or of its related tasks
+    pub fn references(&self) -> Vec<&str> {
+        match self {
+            Formula::Number(_) | Formula::Count(_) | Formula::Done(_) => vec![],
+            Formula::Prop(name) | Formula::DaysUntil(name) | Formula::Rollup { prop: name, .. } => {
+                vec![name.as_str()]
+            }
+            Formula::Neg(formula) => formula.references(),
+            Formula::Binary { left, right, .. } => {
+                let mut references = left.references();
+                references.extend(right.references());
+                references
+            }
+        }
+    }
+}
+
+/// how deep parentheses, negations and chains of operators may nest
+const MAX_DEPTH: usize = 128;
+
+impl FromStr for Formula {
+    type Err = FormulaError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            src: s,
+            pos: 0,
+            depth: 0,
+        };
+        let formula = parser.expr()?;
+        parser.skip_whitespace();
+        if parser.pos < s.len() {
+            return Err(parser.error("unexpected input"));
+        }
+        Ok(formula)
+    }
+}
+
+// recursive descent parser, one method per precedence level
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    // nesting of the formula being parsed, bounded by MAX_DEPTH so parsing doesn't overflow the stack
+    depth: usize,
+}
+impl Parser<'_> {
+    fn error(&self, reason: &str) -> FormulaError {
+        FormulaError {
+            position: self.pos,
+            reason: reason.to_owned(),
+        }
+    }
+    fn rest(&self) -> &str {
+        &self.src[self.pos..]
+    }
+    fn skip_whitespace(&mut self) {
+        self.pos = self.src.len() - self.rest().trim_start().len();
+    }
+    // consume `c` if it's the next non-whitespace character
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+    fn expect(&mut self, c: char) -> Result<(), FormulaError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{c}'")))
+        }
+    }
+    // enter one more level of nesting
+    fn nest(&mut self) -> Result<(), FormulaError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.error("formula nested too deeply"));
+        }
+        Ok(())
+    }
+    // take the longest prefix of characters satisfying `f`
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &str {
+        let start = self.pos;
+        let len = self.rest().find(|c| !f(c)).unwrap_or(self.rest().len());
+        self.pos += len;
+        &self.src[start..self.pos]
+    }
+    // expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Result<Formula, FormulaError> {
+        // every operator nests the formula before it one level deeper
+        let outer = self.depth;
+        let mut left = self.term()?;
+        loop {
+            let op = if self.eat('+') {
+                BinaryOp::Add
+            } else if self.eat('-') {
+                BinaryOp::Sub
+            } else {
+                self.depth = outer;
+                return Ok(left);
+            };
+            self.nest()?;
+            let right = self.term()?;
+            left = Formula::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+    }
+    // term := factor (('*' | '/') factor)*
+    fn term(&mut self) -> Result<Formula, FormulaError> {
+        // every operator nests the formula before it one level deeper
+        let outer = self.depth;
+        let mut left = self.factor()?;
+        loop {
+            let op = if self.eat('*') {
+                BinaryOp::Mul
+            } else if self.eat('/') {
+                BinaryOp::Div
+            } else {
+                self.depth = outer;
+                return Ok(left);
+            };
+            self.nest()?;
+            let right = self.factor()?;
+            left = Formula::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+    }
+    // factor := number | '-' factor | '(' expr ')' | function '(' args ')'
+    fn factor(&mut self) -> Result<Formula, FormulaError> {
+        if self.eat('-') {
+            self.nest()?;
+            let formula = self.factor()?;
+            self.depth -= 1;
+            return Ok(Formula::Neg(Box::new(formula)));
+        }
+        if self.eat('(') {
+            self.nest()?;
+            let formula = self.expr()?;
+            self.expect(')')?;
+            self.depth -= 1;
+            return Ok(formula);
+        }
+        let start = self.pos;
+        if self
+            .rest()
+            .starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        {
+            let number = self.take_while(|c| c.is_ascii_digit() || c == '.');
+            return match number.parse() {
+                Ok(number) => Ok(Formula::Number(number)),
+                Err(_) => {
+                    self.pos = start;
+                    Err(self.error("invalid number"))
+                }
+            };
+        }
+        let function = self
+            .take_while(|c| c.is_ascii_alphabetic() || c == '_')
+            .to_owned();
+        if function.is_empty() {
+            return Err(self.error("expected a number, '(' or a function"));
+        }
+        self.expect('(')?;
+        let formula = match function.as_str() {
+            "prop" => Formula::Prop(self.string()?),
+            "days_until" => Formula::DaysUntil(self.string()?),
+            "count" => Formula::Count(self.relation()?),
+            "done" => Formula::Done(self.relation()?),
+            "sum" | "avg" | "min" | "max" => {
+                let relation = self.relation()?;
+                self.expect(',')?;
+                Formula::Rollup {
+                    aggregate: match function.as_str() {
+                        "sum" => Aggregate::Sum,
+                        "avg" => Aggregate::Avg,
+                        "min" => Aggregate::Min,
+                        _ => Aggregate::Max,
+                    },
+                    relation,
+                    prop: self.string()?,
+                }
+            }
+            _ => {
+                self.pos = start;
+                return Err(self.error(&format!("unknown function {function}")));
+            }
+        };
+        self.expect(')')?;
+        Ok(formula)
+    }
+    // property name in double quotes
+    fn string(&mut self) -> Result<PropName, FormulaError> {
+        self.expect('"')?;
+        let name = self.take_while(|c| c != '"').to_owned();
+        self.expect('"')?;
+        Ok(name)
+    }
+    fn relation(&mut self) -> Result<Relation, FormulaError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.take_while(|c| c.is_ascii_alphabetic()) {
+            "dependencies" => Ok(Relation::Dependencies),
+            "dependents" => Ok(Relation::Dependents),
+            _ => {
+                self.pos = start;
+                Err(self.error("expected dependencies or dependents"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binary(op: BinaryOp, left: Formula, right: Formula) -> Formula {
+        Formula::Binary {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("2.5".parse(), Ok(Formula::Number(2.5)));
+        // precedence and associativity
+        assert_eq!(
+            "1 - 2 - 3 * prop(\"wip limit\")".parse(),
+            Ok(binary(
+                BinaryOp::Sub,
+                binary(BinaryOp::Sub, Formula::Number(1.0), Formula::Number(2.0)),
+                binary(
+                    BinaryOp::Mul,
+                    Formula::Number(3.0),
+                    Formula::Prop("wip limit".to_owned())
+                ),
+            ))
+        );
+        assert_eq!(
+            "done(dependencies) / count( dependencies ) * 100".parse(),
+            Ok(binary(
+                BinaryOp::Mul,
+                binary(
+                    BinaryOp::Div,
+                    Formula::Done(Relation::Dependencies),
+                    Formula::Count(Relation::Dependencies)
+                ),
+                Formula::Number(100.0),
+            ))
+        );
+        assert_eq!(
+            "-(sum(dependents, \"estimate\") + days_until(\"due\"))".parse(),
+            Ok(Formula::Neg(Box::new(binary(
+                BinaryOp::Add,
+                Formula::Rollup {
+                    aggregate: Aggregate::Sum,
+                    relation: Relation::Dependents,
+                    prop: "estimate".to_owned(),
+                },
+                Formula::DaysUntil("due".to_owned()),
+            ))))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |s: &str| s.parse::<Formula>().unwrap_err();
+        assert_eq!(
+            error("1 +"),
+            FormulaError {
+                position: 3,
+                reason: "expected a number, '(' or a function".to_owned()
+            }
+        );
+        assert_eq!(error("1.2.3").position, 0);
+        assert_eq!(
+            error("total(dependencies)").reason,
+            "unknown function total"
+        );
+        assert_eq!(error("count(children)").position, 6);
+        assert_eq!(error("prop(due)").reason, "expected '\"'");
+        assert_eq!(error("(1 + 2").reason, "expected ')'");
+        assert_eq!(error("1 2").reason, "unexpected input");
+        assert_eq!(error("1 2").to_string(), "unexpected input at 2");
+        // deep nesting is rejected instead of overflowing the stack
+        let deep = format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000));
+        assert_eq!(error(&deep).reason, "formula nested too deeply");
+        assert_eq!(
+            error(&"-".repeat(100_000)).reason,
+            "formula nested too deeply"
+        );
+        assert_eq!(
+            error(&["1"; 100_000].join(" + ")).reason,
+            "formula nested too deeply"
+        );
+        let nested = format!("{}1{}", "(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert_eq!(nested.parse(), Ok(Formula::Number(1.0)));
+        assert!(["(1 + 2)"; MAX_DEPTH / 2]
+            .join(" * ")
+            .parse::<Formula>()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_references() {
+        let formula: Formula =
+            "prop(\"a\") / days_until(\"due\") - -sum(dependents, \"b\") * count(dependencies)"
+                .parse()
+                .unwrap();
+        assert_eq!(formula.references(), vec!["a", "due", "b"]);
+    }
+}
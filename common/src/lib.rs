@@ -6,7 +6,12 @@
 #![allow(unused)]
 
 pub mod backend;
+pub mod filter;
+pub mod formula;
+pub mod recurrence;
 
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 
 /// Database Primary key for tasks
@@ -21,8 +26,17 @@ pub type ScriptID = i32;
 /// Note: Database should ensure IDs are never re-used.
 pub type ViewID = i32;
 
+/// Database Primary key for tags
+/// Note: Database should ensure IDs are never re-used.
+pub type TagID = i32;
+
 /// Identification of a property, from database
 pub type PropName = String;
+
+/// Name of the date property holding when a task is due
+pub const DUE_PROPERTY: &str = "due";
+/// Name of the string property holding a task's description, written in Markdown
+pub const DESCRIPTION_PROPERTY: &str = "description";
 /// Data stored in Database representing a view.
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct ViewData {
@@ -34,6 +48,18 @@ pub struct ViewData {
     pub filter: Filter,
     /// Properties shown in view
     pub props: Vec<String>,
+    /// Sort order of the view's tasks
+    #[serde(default)]
+    pub sort: Vec<SortKey>,
+    /// Maximum number of tasks shown in the view, if any
+    #[serde(default)]
+    pub max_tasks: Option<u64>,
+    /// How the view's tasks are grouped, e.g. into the columns of a board
+    #[serde(default)]
+    pub group_by: GroupBy,
+    /// Values summarizing each group of the view's tasks
+    #[serde(default)]
+    pub summaries: Vec<Summary>,
 }
 
 /// Primary Task Data (doesn't include properties)
@@ -81,9 +107,23 @@ pub enum Comparator {
     /// Does not contain
     NOTCONTAINS,
     /// Regular expression match
-    // REGEX,
+    REGEX,
     /// Like SQL query
     LIKE,
+    /// Case insensitive equal to
+    IEQ,
+    /// Case insensitive contains
+    ICONTAINS,
+    /// Case insensitive regular expression match
+    IREGEX,
+    /// Case insensitive like SQL query
+    ILIKE,
+    /// Select value is one of the options of a multi-select immediate
+    IN,
+    /// Multi-select value has at least one of the options of the immediate
+    CONTAINSANY,
+    /// Multi-select value has every option of the immediate
+    CONTAINSALL,
 }
 
 /// Operator that combines multiple Filters
@@ -105,22 +145,367 @@ pub enum TaskPropVariant {
     Date(chrono::NaiveDateTime),
     /// String variant
     String(String),
-    /// Decimal variant (NOTE: should we have an integer variant?)
+    /// Decimal variant, approximate. Counts and amounts that are summed should be integers.
     Number(f64),
+    /// Integer variant, exact
+    Integer(i64),
+    /// Length of time, e.g. a time estimate
+    Duration(TaskDuration),
     /// Boolean variant
     Boolean(bool),
+    /// One option out of the option set defined in the property's schema
+    Select(String),
+    /// Any number of options out of the option set defined in the property's schema, without duplicates
+    MultiSelect(Vec<String>),
+    /// Reference to another task, e.g. "duplicate of"
+    TaskRef(TaskID),
+    /// References to any number of other tasks, without duplicates
+    TaskRefs(Vec<TaskID>),
+    /// Reference to a person, by user name
+    User(String),
+    /// Date relative to when it is used, only valid as a filter immediate.
+    /// Resolved when the filter is evaluated so saved views don't go stale.
+    RelativeDate(RelativeDate),
 }
 impl TaskPropVariant {
+    /// get the type of a property, relative dates are dates
+    pub fn prop_type(&self) -> TaskPropType {
+        match self {
+            TaskPropVariant::Date(_) | TaskPropVariant::RelativeDate(_) => TaskPropType::Date,
+            TaskPropVariant::String(_) => TaskPropType::String,
+            TaskPropVariant::Number(_) => TaskPropType::Number,
+            TaskPropVariant::Integer(_) => TaskPropType::Integer,
+            TaskPropVariant::Duration(_) => TaskPropType::Duration,
+            TaskPropVariant::Boolean(_) => TaskPropType::Boolean,
+            TaskPropVariant::Select(_) => TaskPropType::Select,
+            TaskPropVariant::MultiSelect(_) => TaskPropType::MultiSelect,
+            TaskPropVariant::TaskRef(_) => TaskPropType::TaskRef,
+            TaskPropVariant::TaskRefs(_) => TaskPropType::TaskRefs,
+            TaskPropVariant::User(_) => TaskPropType::User,
+        }
+    }
     /// get the string name of a proprty typer
     pub fn type_string(&self) -> &'static str {
+        self.prop_type().as_str()
+    }
+}
+
+/// The types a task property can have, each is stored in its own table by the server.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum TaskPropType {
+    /// [`TaskPropVariant::Date`]
+    Date,
+    /// [`TaskPropVariant::String`]
+    String,
+    /// [`TaskPropVariant::Number`]
+    Number,
+    /// [`TaskPropVariant::Integer`]
+    Integer,
+    /// [`TaskPropVariant::Duration`]
+    Duration,
+    /// [`TaskPropVariant::Boolean`]
+    Boolean,
+    /// [`TaskPropVariant::Select`]
+    Select,
+    /// [`TaskPropVariant::MultiSelect`]
+    MultiSelect,
+    /// [`TaskPropVariant::TaskRef`]
+    TaskRef,
+    /// [`TaskPropVariant::TaskRefs`]
+    TaskRefs,
+    /// [`TaskPropVariant::User`]
+    User,
+}
+impl TaskPropType {
+    /// every property type
+    pub const ALL: [TaskPropType; 11] = [
+        TaskPropType::Date,
+        TaskPropType::String,
+        TaskPropType::Number,
+        TaskPropType::Integer,
+        TaskPropType::Duration,
+        TaskPropType::Boolean,
+        TaskPropType::Select,
+        TaskPropType::MultiSelect,
+        TaskPropType::TaskRef,
+        TaskPropType::TaskRefs,
+        TaskPropType::User,
+    ];
+    /// name of the type, this is what the server stores for the property
+    pub fn as_str(&self) -> &'static str {
         match self {
-            TaskPropVariant::Date(_) => "date",
-            TaskPropVariant::String(_) => "string",
-            TaskPropVariant::Number(_) => "number",
-            TaskPropVariant::Boolean(_) => "boolean",
+            TaskPropType::Date => "date",
+            TaskPropType::String => "string",
+            TaskPropType::Number => "number",
+            TaskPropType::Integer => "integer",
+            TaskPropType::Duration => "duration",
+            TaskPropType::Boolean => "boolean",
+            TaskPropType::Select => "select",
+            TaskPropType::MultiSelect => "multiselect",
+            TaskPropType::TaskRef => "taskref",
+            TaskPropType::TaskRefs => "taskrefs",
+            TaskPropType::User => "user",
         }
     }
 }
+impl std::str::FromStr for TaskPropType {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TaskPropType::ALL
+            .into_iter()
+            .find(|typ| typ.as_str() == s)
+            .ok_or_else(|| format!("unknown property type {s}"))
+    }
+}
+/// Workspace-level definition of a task property, every write to a property with this name must fit it.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct PropertyDefinition {
+    /// name of the property
+    pub name: PropName,
+    /// type every value of the property has
+    pub typ: TaskPropType,
+    /// what the property is for
+    #[serde(default)]
+    pub description: String,
+    /// value given to the property when a task is created
+    #[serde(default)]
+    pub default: Option<TaskPropVariant>,
+    /// whether every task must have the property, required properties can't be removed from a task
+    #[serde(default)]
+    pub required: bool,
+    /// values the property is restricted to, any value of the right type is allowed if empty
+    #[serde(default)]
+    pub allowed_values: Vec<TaskPropVariant>,
+    /// options of a select or multi-select property, in display order
+    #[serde(default)]
+    pub options: Vec<String>,
+    /// [`formula::Formula`] of a computed property. Computed properties are numbers evaluated by the
+    /// server on read and can't be written.
+    #[serde(default)]
+    pub formula: Option<String>,
+}
+impl PropertyDefinition {
+    /// check that a value can be written to the property
+    pub fn check_value(&self, value: &TaskPropVariant) -> Result<(), String> {
+        if self.formula.is_some() {
+            return Err(format!(
+                "property {} is computed and can't be written",
+                self.name
+            ));
+        }
+        if let TaskPropVariant::RelativeDate(_) = value {
+            return Err("relative dates can only be used in filters".to_owned());
+        }
+        if value.prop_type() != self.typ {
+            return Err(format!(
+                "property {} has wrong type (expecting {})",
+                self.name,
+                self.typ.as_str()
+            ));
+        }
+        let selected = match value {
+            TaskPropVariant::Select(option) => std::slice::from_ref(option),
+            TaskPropVariant::MultiSelect(options) => options.as_slice(),
+            _ => &[],
+        };
+        if let Some(option) = selected.iter().find(|o| !self.options.contains(o)) {
+            return Err(format!(
+                "{} is not an option of property {}",
+                option, self.name
+            ));
+        }
+        if let TaskPropVariant::MultiSelect(options) = value {
+            if options
+                .iter()
+                .enumerate()
+                .any(|(i, o)| options[..i].contains(o))
+            {
+                return Err(format!("options of property {} repeat", self.name));
+            }
+        }
+        if let TaskPropVariant::TaskRefs(tasks) = value {
+            if tasks
+                .iter()
+                .enumerate()
+                .any(|(i, t)| tasks[..i].contains(t))
+            {
+                return Err(format!("tasks referenced by property {} repeat", self.name));
+            }
+        }
+        if !self.allowed_values.is_empty() && !self.allowed_values.contains(value) {
+            return Err(format!(
+                "value {:?} is not allowed for property {}",
+                value, self.name
+            ));
+        }
+        Ok(())
+    }
+    /// check that the definition is consistent with itself: allowed values and the default fit the type,
+    /// required properties have a default and select properties have options.
+    /// Computed properties must be numbers with a valid formula, and nothing else set.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(formula) = &self.formula {
+            formula
+                .parse::<formula::Formula>()
+                .map_err(|e| format!("invalid formula of property {}: {}", self.name, e))?;
+            if self.typ != TaskPropType::Number {
+                return Err(format!("computed property {} must be a number", self.name));
+            }
+            if self.default.is_some()
+                || self.required
+                || !self.allowed_values.is_empty()
+                || !self.options.is_empty()
+            {
+                return Err(format!(
+                    "computed property {} can't have a default, be required or restrict its values",
+                    self.name
+                ));
+            }
+            return Ok(());
+        }
+        if let TaskPropType::Select | TaskPropType::MultiSelect = self.typ {
+            if self.options.is_empty() {
+                return Err(format!("property {} has no options", self.name));
+            }
+        }
+        let unrestricted = PropertyDefinition {
+            allowed_values: vec![],
+            ..self.clone()
+        };
+        for value in self.allowed_values.iter() {
+            unrestricted.check_value(value)?;
+        }
+        match &self.default {
+            Some(default) => self.check_value(default),
+            None if self.required => Err(format!(
+                "required property {} must have a default",
+                self.name
+            )),
+            None => Ok(()),
+        }
+    }
+}
+/// Point in time a [`RelativeDate`] is measured from.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum DateAnchor {
+    /// the moment of evaluation
+    Now,
+    /// midnight of the current day
+    StartOfDay,
+    /// midnight of the monday of the current week
+    StartOfWeek,
+    /// midnight of the first day of the current month
+    StartOfMonth,
+}
+
+/// A date expressed relative to when it is evaluated, e.g. `StartOfWeek + 7 days` or `Now - 3 days`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RelativeDate {
+    /// where to start from
+    pub anchor: DateAnchor,
+    /// seconds to add to the anchor, negative for the past
+    #[serde(default)]
+    pub offset_secs: i64,
+    /// time zone the anchor is computed in, and that dates are compared in. UTC if not set.
+    #[serde(default)]
+    pub timezone: Option<Tz>,
+}
+impl RelativeDate {
+    /// Resolve to an absolute local date in `timezone`, given the current time.
+    pub fn resolve(&self, now: DateTime<Utc>) -> NaiveDateTime {
+        let now = now
+            .with_timezone(&self.timezone.unwrap_or(Tz::UTC))
+            .naive_local();
+        let today = now.date();
+        let anchor = match self.anchor {
+            DateAnchor::Now => now,
+            DateAnchor::StartOfDay => today.into(),
+            DateAnchor::StartOfWeek => {
+                (today - Duration::days(today.weekday().num_days_from_monday().into())).into()
+            }
+            DateAnchor::StartOfMonth => today.with_day(1).unwrap_or(today).into(),
+        };
+        anchor + Duration::seconds(self.offset_secs)
+    }
+    /// Resolve to an absolute UTC date, for comparing against timestamps the server records (which are in UTC).
+    /// The anchor is still computed in `timezone`, so "start of day" is midnight there.
+    pub fn resolve_utc(&self, now: DateTime<Utc>) -> NaiveDateTime {
+        let local = self.resolve(now);
+        match self.timezone {
+            // a local time skipped by a DST change has no UTC equivalent, keep it as is
+            Some(tz) => tz
+                .from_local_datetime(&local)
+                .earliest()
+                .map_or(local, |date| date.naive_utc()),
+            None => local,
+        }
+    }
+}
+
+/// Exact length of time in seconds, written like `2h30m`.
+/// Serialized as the number of seconds.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct TaskDuration(pub i64);
+
+// largest unit first, so formatting and parsing agree
+const DURATION_UNITS: [(char, i64); 4] = [('d', 86400), ('h', 3600), ('m', 60), ('s', 1)];
+
+impl std::fmt::Display for TaskDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0 == 0 {
+            return write!(f, "0s");
+        }
+        if self.0 < 0 {
+            write!(f, "-")?;
+        }
+        let mut rest = self.0.unsigned_abs();
+        for (unit, secs) in DURATION_UNITS {
+            let amount = rest / secs as u64;
+            if amount > 0 {
+                write!(f, "{amount}{unit}")?;
+            }
+            rest %= secs as u64;
+        }
+        Ok(())
+    }
+}
+impl std::str::FromStr for TaskDuration {
+    type Err = String;
+    /// parse amounts followed by a unit (`d`, `h`, `m` or `s`), e.g. `1d 4h` or `-90m`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let (sign, mut rest) = match trimmed.strip_prefix('-') {
+            Some(rest) => (-1, rest),
+            None => (1, trimmed),
+        };
+        if rest.trim().is_empty() {
+            return Err(format!("empty duration {s:?}"));
+        }
+        let mut total: i64 = 0;
+        while !rest.trim().is_empty() {
+            rest = rest.trim_start();
+            let digits = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let amount = rest[..digits]
+                .parse::<i64>()
+                .map_err(|_| format!("expected an amount in duration {s:?}"))?;
+            let unit = rest[digits..].chars().next();
+            let secs = DURATION_UNITS
+                .iter()
+                .find(|(u, _)| Some(*u) == unit)
+                .map(|(_, secs)| *secs)
+                .ok_or_else(|| format!("expected a unit (d, h, m or s) in duration {s:?}"))?;
+            total = amount
+                .checked_mul(secs)
+                .and_then(|secs| total.checked_add(secs))
+                .ok_or_else(|| format!("duration {s:?} is too long"))?;
+            rest = &rest[digits + 1..];
+        }
+        Ok(TaskDuration(sign * total))
+    }
+}
+
 /// A task property and its corresponding name.
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct TaskProp {
@@ -141,6 +526,82 @@ pub enum PrimitiveField {
     LASTEDITED,
 }
 
+/// field that the results of a filter can be sorted by
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum SortField {
+    /// field of the task itself
+    Primitive(PrimitiveField),
+    /// any task property, tasks are grouped by the type of the property and then ordered by value
+    Property(PropName),
+    /// place of the task in the manual order of a view, tasks that were never moved in it come last
+    Rank(ViewID),
+}
+
+/// how the tasks of a view are grouped
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Clone)]
+pub enum GroupBy {
+    /// tasks aren't grouped
+    #[default]
+    None,
+    /// completed and not completed tasks
+    Completed,
+    /// one group per distinct value of a property, tasks without the property form their own group
+    Property(PropName),
+    /// tasks are grouped under the task that (transitively) depends on them and that nothing depends on.
+    /// a task with several of them is grouped under the one with the lowest id
+    DependencyRoot,
+}
+
+/// value computed over the tasks of a group
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum Summary {
+    /// sum of a number property
+    Sum(PropName),
+    /// average of a number property, over the tasks that have it
+    Average(PropName),
+    /// earliest value of a date property
+    Earliest(PropName),
+}
+
+/// a single sort key, filters are sorted by a list of these in order of precedence
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct SortKey {
+    /// field to sort by
+    pub field: SortField,
+    /// sort in descending order instead of ascending
+    pub descending: bool,
+    /// tasks that don't have the field are placed first instead of last
+    pub nulls_first: bool,
+}
+
+/// Facts about a task's place in the dependency graph that a filter can test.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum DependencyFilter {
+    /// task depends on at least one task that isn't completed
+    HasIncompleteDependency,
+    /// number of tasks this task depends on
+    DependencyCount {
+        /// the comparator, only ordering comparators are supported
+        comparator: Comparator,
+        /// what the count is compared against
+        count: u64,
+    },
+    /// number of tasks that depend on this task
+    DependentCount {
+        /// the comparator, only ordering comparators are supported
+        comparator: Comparator,
+        /// what the count is compared against
+        count: u64,
+    },
+    /// task depends on `task`
+    DependsOn {
+        /// the dependency
+        task: TaskID,
+        /// also match tasks that depend on `task` through other dependencies
+        transitive: bool,
+    },
+}
+
 /// Represents a filter on tasks using their properties that the database computes.
 #[derive(Debug, Default, Serialize, Deserialize, PartialEq, Clone)]
 pub enum Filter {
@@ -163,6 +624,35 @@ pub enum Filter {
         /// what we compare against
         immediate: TaskPropVariant,
     },
+    /// Filter leaf comparing two properties of the same task, e.g. `spent > estimate`.
+    /// Only matches tasks that have both properties with the same type.
+    LeafProperty {
+        /// property on the left of the comparator
+        left: PropName,
+        /// the comparator, only ordering comparators are supported
+        comparator: Comparator,
+        /// property on the right of the comparator
+        right: PropName,
+    },
+    /// Filter leaf comparing a property of the task to a global property, e.g. `due < sprint end`.
+    /// Only matches tasks whose property has the same type as the global.
+    LeafGlobal {
+        /// property of the task on the left of the comparator
+        field: PropName,
+        /// the comparator, only ordering comparators are supported
+        comparator: Comparator,
+        /// global property on the right of the comparator
+        global: PropName,
+    },
+    /// Filter leaf on facts derived from the task's dependencies.
+    LeafDependency(DependencyFilter),
+    /// Filter leaf matching tasks tagged with `tag`.
+    LeafTag {
+        /// the tag
+        tag: TagID,
+        /// also match tasks tagged with a tag nested under `tag`, at any depth
+        descendants: bool,
+    },
     /// Filter branch, combines multiple leaves based on Operator.
     Operator {
         /// operator used to combined a set of nested filters
@@ -208,6 +698,259 @@ mod tests {
             op: Operator::AND,
             childs: vec![],
         });
+        test_serde_commutes(Filter::LeafProperty {
+            left: "spent".to_owned(),
+            comparator: Comparator::GT,
+            right: "estimate".to_owned(),
+        });
+        test_serde_commutes(Filter::LeafGlobal {
+            field: "due".to_owned(),
+            comparator: Comparator::LT,
+            global: "sprint end".to_owned(),
+        });
+        test_serde_commutes(Filter::LeafDependency(DependencyFilter::DependsOn {
+            task: 1,
+            transitive: true,
+        }));
+        test_serde_commutes(Filter::LeafTag {
+            tag: 3,
+            descendants: true,
+        });
+    }
+
+    #[test]
+    fn test_prop_type_names() {
+        for typ in TaskPropType::ALL {
+            assert_eq!(typ.as_str().parse::<TaskPropType>(), Ok(typ));
+        }
+        assert!("real".parse::<TaskPropType>().is_err());
+        assert_eq!(TaskPropVariant::Number(1.0).type_string(), "number");
+        assert_eq!(
+            TaskPropVariant::Date(chrono::NaiveDateTime::default()).prop_type(),
+            TaskPropType::Date
+        );
+    }
+
+    #[test]
+    fn test_property_definition() {
+        let mut def = PropertyDefinition {
+            name: "status".to_owned(),
+            typ: TaskPropType::String,
+            description: String::new(),
+            default: None,
+            required: false,
+            allowed_values: vec![],
+            options: vec![],
+            formula: None,
+        };
+        assert_eq!(def.validate(), Ok(()));
+        assert_eq!(
+            def.check_value(&TaskPropVariant::String("anything".to_owned())),
+            Ok(())
+        );
+        assert!(def.check_value(&TaskPropVariant::Number(1.0)).is_err());
+
+        def.allowed_values = vec![
+            TaskPropVariant::String("todo".to_owned()),
+            TaskPropVariant::String("done".to_owned()),
+        ];
+        assert_eq!(
+            def.check_value(&TaskPropVariant::String("done".to_owned())),
+            Ok(())
+        );
+        assert!(def
+            .check_value(&TaskPropVariant::String("anything".to_owned()))
+            .is_err());
+
+        // required properties need a default that is allowed
+        def.required = true;
+        assert!(def.validate().is_err());
+        def.default = Some(TaskPropVariant::String("doing".to_owned()));
+        assert!(def.validate().is_err());
+        def.default = Some(TaskPropVariant::String("todo".to_owned()));
+        assert_eq!(def.validate(), Ok(()));
+
+        def.allowed_values.push(TaskPropVariant::Boolean(true));
+        assert!(def.validate().is_err());
+
+        // select values must be options
+        let mut def = PropertyDefinition {
+            name: "labels".to_owned(),
+            typ: TaskPropType::MultiSelect,
+            description: String::new(),
+            default: None,
+            required: false,
+            allowed_values: vec![],
+            options: vec![],
+            formula: None,
+        };
+        assert!(def.validate().is_err());
+        def.options = vec!["bug".to_owned(), "ui".to_owned()];
+        assert_eq!(def.validate(), Ok(()));
+        let labels = |labels: &[&str]| {
+            TaskPropVariant::MultiSelect(labels.iter().map(|l| l.to_string()).collect())
+        };
+        assert_eq!(def.check_value(&labels(&[])), Ok(()));
+        assert_eq!(def.check_value(&labels(&["ui", "bug"])), Ok(()));
+        assert!(def.check_value(&labels(&["ui", "docs"])).is_err());
+        assert!(def.check_value(&labels(&["ui", "ui"])).is_err());
+        assert!(def
+            .check_value(&TaskPropVariant::Select("ui".to_owned()))
+            .is_err());
+
+        // task references can't repeat either
+        let def = PropertyDefinition {
+            name: "duplicate of".to_owned(),
+            typ: TaskPropType::TaskRefs,
+            ..def
+        };
+        assert_eq!(
+            def.check_value(&TaskPropVariant::TaskRefs(vec![2, 1])),
+            Ok(())
+        );
+        assert!(def
+            .check_value(&TaskPropVariant::TaskRefs(vec![2, 2]))
+            .is_err());
+        assert!(def.check_value(&TaskPropVariant::TaskRef(2)).is_err());
+
+        // computed properties are numbers with a formula and can't be written
+        let mut def = PropertyDefinition {
+            name: "progress".to_owned(),
+            typ: TaskPropType::Number,
+            options: vec![],
+            formula: Some("done(dependencies) / count(dependencies) * 100".to_owned()),
+            ..def
+        };
+        assert_eq!(def.validate(), Ok(()));
+        assert!(def.check_value(&TaskPropVariant::Number(50.0)).is_err());
+        def.default = Some(TaskPropVariant::Number(0.0));
+        assert!(def.validate().is_err());
+        def.default = None;
+        def.formula = Some("done(dependencies) /".to_owned());
+        assert!(def.validate().is_err());
+
+        // defaults are optional when deserializing
+        let def: PropertyDefinition =
+            serde_json::from_str(r#"{"name":"estimate","typ":"Number"}"#).unwrap();
+        assert!(!def.required);
+        assert_eq!(def.default, None);
+        test_serde_commutes(def);
+    }
+
+    #[test]
+    fn test_task_duration() {
+        let duration = |s: &str| s.parse::<TaskDuration>();
+        assert_eq!(duration("2h30m"), Ok(TaskDuration(9000)));
+        assert_eq!(duration(" 1d 4h "), Ok(TaskDuration(100800)));
+        assert_eq!(duration("90m"), Ok(TaskDuration(5400)));
+        assert_eq!(duration("-45s"), Ok(TaskDuration(-45)));
+        assert!(duration("").is_err());
+        assert!(duration("2").is_err());
+        assert!(duration("h").is_err());
+        assert!(duration("2w").is_err());
+        assert!(duration("99999999999999999d").is_err());
+
+        assert_eq!(TaskDuration(9000).to_string(), "2h30m");
+        assert_eq!(TaskDuration(5400).to_string(), "1h30m");
+        assert_eq!(TaskDuration(-90061).to_string(), "-1d1h1m1s");
+        assert_eq!(TaskDuration(0).to_string(), "0s");
+        for secs in [0, 59, 3600, -86401, i64::MAX / 2] {
+            assert_eq!(
+                duration(&TaskDuration(secs).to_string()),
+                Ok(TaskDuration(secs))
+            );
+        }
+        // serialized as seconds
+        assert_eq!(serde_json::to_string(&TaskDuration(60)).unwrap(), "60");
+        test_serde_commutes(TaskPropVariant::Duration(TaskDuration(60)));
+        test_serde_commutes(TaskPropVariant::Integer(i64::MAX));
+    }
+
+    #[test]
+    fn serde_relative_date() {
+        test_serde_commutes(TaskPropVariant::RelativeDate(RelativeDate {
+            anchor: DateAnchor::StartOfWeek,
+            offset_secs: -60,
+            timezone: Some(chrono_tz::America::New_York),
+        }));
+        // offset and timezone are optional
+        let date: RelativeDate = serde_json::from_str(r#"{"anchor":"Now"}"#).unwrap();
+        assert_eq!(date.offset_secs, 0);
+        assert_eq!(date.timezone, None);
+    }
+
+    #[test]
+    fn test_relative_date_resolve() {
+        // wednesday 2024-05-15 03:30 UTC, still tuesday in new york
+        let now = DateTime::parse_from_rfc3339("2024-05-15T03:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let resolve = |anchor, offset_secs, timezone| {
+            RelativeDate {
+                anchor,
+                offset_secs,
+                timezone,
+            }
+            .resolve(now)
+            .to_string()
+        };
+        assert_eq!(resolve(DateAnchor::Now, 0, None), "2024-05-15 03:30:00");
+        assert_eq!(
+            resolve(DateAnchor::Now, -3 * 86400, None),
+            "2024-05-12 03:30:00"
+        );
+        assert_eq!(
+            resolve(DateAnchor::StartOfDay, 0, None),
+            "2024-05-15 00:00:00"
+        );
+        assert_eq!(
+            resolve(DateAnchor::StartOfWeek, 0, None),
+            "2024-05-13 00:00:00"
+        );
+        assert_eq!(
+            resolve(DateAnchor::StartOfMonth, 3600, None),
+            "2024-05-01 01:00:00"
+        );
+        let new_york = Some(chrono_tz::America::New_York);
+        assert_eq!(resolve(DateAnchor::Now, 0, new_york), "2024-05-14 23:30:00");
+        assert_eq!(
+            resolve(DateAnchor::StartOfDay, 0, new_york),
+            "2024-05-14 00:00:00"
+        );
+        // midnight in new york, as a UTC timestamp
+        let date = RelativeDate {
+            anchor: DateAnchor::StartOfDay,
+            offset_secs: 0,
+            timezone: new_york,
+        };
+        assert_eq!(date.resolve_utc(now).to_string(), "2024-05-14 04:00:00");
+    }
+
+    #[test]
+    fn serde_sort_key() {
+        test_serde_commutes(SortKey {
+            field: SortField::Primitive(PrimitiveField::TITLE),
+            descending: false,
+            nulls_first: false,
+        });
+        test_serde_commutes(SortKey {
+            field: SortField::Property("due".to_owned()),
+            descending: true,
+            nulls_first: true,
+        });
+    }
+
+    #[test]
+    fn serde_group_by() {
+        test_serde_commutes(GroupBy::Completed);
+        test_serde_commutes(GroupBy::Property("status".to_owned()));
+        test_serde_commutes(GroupBy::DependencyRoot);
+        test_serde_commutes(Summary::Earliest(DUE_PROPERTY.to_owned()));
+        // views saved before grouping existed aren't grouped
+        let view: ViewData =
+            serde_json::from_str(r#"{"view_id":1,"name":"","filter":"None","props":[]}"#).unwrap();
+        assert_eq!(view.group_by, GroupBy::None);
+        assert!(view.summaries.is_empty());
     }
 
     #[test]
@@ -0,0 +1,329 @@
+//! Recurrence rules of repeating tasks.
+//!
+//! Rules are written as a subset of RFC 5545 `RRULE`s:
+//! - `FREQ=DAILY;INTERVAL=2`: every other day
+//! - `FREQ=WEEKLY;BYDAY=MO,TH`: every monday and thursday, `INTERVAL` skips weeks
+//! - `FREQ=MONTHLY;BYMONTHDAY=31`: on the 31st every month, clamped to the end of shorter months.
+//!   Without `BYMONTHDAY` on the day of the due date, the server anchors a series to the day of its first instance
+//! - `FREQ=DAILY;INTERVAL=3;X-FROM=COMPLETION`: three days after the last instance was completed
+//!
+//! `INTERVAL` defaults to 1. When a recurring task is completed, the server spawns its next instance
+//! due at [`Recurrence::next`].
+
+use std::{fmt, str::FromStr};
+
+use chrono::{Datelike, Duration, Months, NaiveDateTime, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// How often a task repeats. Serialized as its `RRULE`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Recurrence {
+    /// every `interval` days after the due date
+    Daily {
+        /// days between instances
+        interval: u32,
+    },
+    /// on `days` every `interval` weeks, on the weekday of the due date if `days` is empty
+    Weekly {
+        /// weeks between instances
+        interval: u32,
+        /// days of the week the task is due, in order
+        days: Vec<Weekday>,
+    },
+    /// same day of the month every `interval` months
+    Monthly {
+        /// months between instances
+        interval: u32,
+        /// day of the month the task is due, the day of the due date if None
+        day: Option<u32>,
+    },
+    /// `days` days after the previous instance was completed, regardless of when it was due
+    AfterCompletion {
+        /// days from completion to the next instance
+        days: u32,
+    },
+}
+
+impl Recurrence {
+    /// due date of the instance after one that was due at `due` and completed at `completed`
+    pub fn next(&self, due: NaiveDateTime, completed: NaiveDateTime) -> NaiveDateTime {
+        match self {
+            Recurrence::Daily { interval } => due + Duration::days(*interval as i64),
+            Recurrence::Weekly { interval, days } => {
+                let weekday = due.weekday().num_days_from_monday() as i64;
+                let days = days.iter().map(|day| day.num_days_from_monday() as i64);
+                // a later day in the same week, otherwise the first day `interval` weeks later
+                match days.clone().filter(|day| *day > weekday).min() {
+                    Some(day) => due + Duration::days(day - weekday),
+                    None => {
+                        let first = days.min().unwrap_or(weekday);
+                        due + Duration::days(7 * *interval as i64 + first - weekday)
+                    }
+                }
+            }
+            Recurrence::Monthly { interval, day } => {
+                let day = day.unwrap_or(due.day());
+                // step from the first of the month, so a day clamped in a short month isn't carried on
+                let Some(first) = due
+                    .with_day(1)
+                    .and_then(|first| first.checked_add_months(Months::new(*interval)))
+                else {
+                    return NaiveDateTime::MAX;
+                };
+                let days_in_month = first
+                    .checked_add_months(Months::new(1))
+                    .map_or(31, |next| (next - first).num_days() as u32);
+                first.with_day(day.min(days_in_month)).unwrap_or(first)
+            }
+            Recurrence::AfterCompletion { days } => completed + Duration::days(*days as i64),
+        }
+    }
+    /// the rule with monthly recurrences fixed to the day of `due`, so later instances keep that day
+    pub fn anchor(&self, due: NaiveDateTime) -> Recurrence {
+        match self {
+            Recurrence::Monthly {
+                interval,
+                day: None,
+            } => Recurrence::Monthly {
+                interval: *interval,
+                day: Some(due.day()),
+            },
+            recurrence => recurrence.clone(),
+        }
+    }
+}
+
+// RFC 5545 two letter weekday names
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("MO", Weekday::Mon),
+    ("TU", Weekday::Tue),
+    ("WE", Weekday::Wed),
+    ("TH", Weekday::Thu),
+    ("FR", Weekday::Fri),
+    ("SA", Weekday::Sat),
+    ("SU", Weekday::Sun),
+];
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Daily { interval } => write!(f, "FREQ=DAILY;INTERVAL={interval}"),
+            Recurrence::Weekly { interval, days } => {
+                write!(f, "FREQ=WEEKLY;INTERVAL={interval}")?;
+                if !days.is_empty() {
+                    let days = days
+                        .iter()
+                        .map(|day| WEEKDAYS[day.num_days_from_monday() as usize].0)
+                        .collect::<Vec<_>>();
+                    write!(f, ";BYDAY={}", days.join(","))?;
+                }
+                Ok(())
+            }
+            Recurrence::Monthly { interval, day } => {
+                write!(f, "FREQ=MONTHLY;INTERVAL={interval}")?;
+                if let Some(day) = day {
+                    write!(f, ";BYMONTHDAY={day}")?;
+                }
+                Ok(())
+            }
+            Recurrence::AfterCompletion { days } => {
+                write!(f, "FREQ=DAILY;INTERVAL={days};X-FROM=COMPLETION")
+            }
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s.strip_prefix("RRULE:").unwrap_or(s);
+        let (mut freq, mut interval, mut days, mut from_completion) = (None, 1, vec![], false);
+        let mut month_day = None;
+        for part in s.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("expected KEY=VALUE, got {part}"))?;
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => freq = Some(value.to_ascii_uppercase()),
+                "INTERVAL" => {
+                    interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| format!("invalid interval {value}"))?
+                }
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        let day = WEEKDAYS
+                            .iter()
+                            .find(|(name, _)| name.eq_ignore_ascii_case(day))
+                            .ok_or_else(|| format!("invalid weekday {day}"))?
+                            .1;
+                        if !days.contains(&day) {
+                            days.push(day);
+                        }
+                    }
+                }
+                "BYMONTHDAY" => {
+                    month_day = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|day| (1..=31).contains(day))
+                            .ok_or_else(|| format!("invalid day of the month {value}"))?,
+                    )
+                }
+                "X-FROM" if value.eq_ignore_ascii_case("COMPLETION") => from_completion = true,
+                _ => return Err(format!("unsupported rule part {part}")),
+            }
+        }
+        days.sort_by_key(|day| day.num_days_from_monday());
+        match (freq.as_deref(), from_completion) {
+            (Some("MONTHLY"), false) if days.is_empty() => Ok(Recurrence::Monthly {
+                interval,
+                day: month_day,
+            }),
+            _ if month_day.is_some() => Err(format!("unsupported rule {s}")),
+            (Some("DAILY"), true) if days.is_empty() => {
+                Ok(Recurrence::AfterCompletion { days: interval })
+            }
+            (Some("DAILY"), false) if days.is_empty() => Ok(Recurrence::Daily { interval }),
+            (Some("WEEKLY"), false) => Ok(Recurrence::Weekly { interval, days }),
+            (None, _) => Err("missing FREQ".to_owned()),
+            _ => Err(format!("unsupported rule {s}")),
+        }
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = String;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+impl From<Recurrence> for String {
+    fn from(value: Recurrence) -> Self {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, month, day)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("FREQ=DAILY".parse(), Ok(Recurrence::Daily { interval: 1 }));
+        assert_eq!(
+            "RRULE:freq=weekly;byday=TH,mo,TH;interval=2".parse(),
+            Ok(Recurrence::Weekly {
+                interval: 2,
+                days: vec![Weekday::Mon, Weekday::Thu]
+            })
+        );
+        assert_eq!(
+            "FREQ=DAILY;INTERVAL=3;X-FROM=COMPLETION".parse(),
+            Ok(Recurrence::AfterCompletion { days: 3 })
+        );
+        assert!("INTERVAL=2".parse::<Recurrence>().is_err());
+        assert!("FREQ=YEARLY".parse::<Recurrence>().is_err());
+        assert!("FREQ=DAILY;INTERVAL=0".parse::<Recurrence>().is_err());
+        assert!("FREQ=MONTHLY;BYDAY=MO".parse::<Recurrence>().is_err());
+        assert!("FREQ=WEEKLY;BYDAY=XX".parse::<Recurrence>().is_err());
+        assert!("FREQ=DAILY;COUNT=4".parse::<Recurrence>().is_err());
+        assert!("FREQ=MONTHLY;BYMONTHDAY=32".parse::<Recurrence>().is_err());
+        assert!("FREQ=WEEKLY;BYMONTHDAY=3".parse::<Recurrence>().is_err());
+
+        // serialized as the rule, which parses back to the same recurrence
+        for recurrence in [
+            Recurrence::Daily { interval: 2 },
+            Recurrence::Weekly {
+                interval: 1,
+                days: vec![Weekday::Tue, Weekday::Sun],
+            },
+            Recurrence::Weekly {
+                interval: 3,
+                days: vec![],
+            },
+            Recurrence::Monthly {
+                interval: 6,
+                day: None,
+            },
+            Recurrence::Monthly {
+                interval: 1,
+                day: Some(31),
+            },
+            Recurrence::AfterCompletion { days: 10 },
+        ] {
+            let json = serde_json::to_string(&recurrence).unwrap();
+            assert_eq!(json, format!("\"{}\"", recurrence));
+            assert_eq!(
+                serde_json::from_str::<Recurrence>(&json).unwrap(),
+                recurrence
+            );
+        }
+        assert!(serde_json::from_str::<Recurrence>("\"FREQ=HOURLY\"").is_err());
+    }
+
+    #[test]
+    fn test_anchor() {
+        let monthly = Recurrence::Monthly {
+            interval: 1,
+            day: None,
+        };
+        assert_eq!(
+            monthly.anchor(date(1, 31)).to_string(),
+            "FREQ=MONTHLY;INTERVAL=1;BYMONTHDAY=31"
+        );
+        // anchored rules keep their day
+        let anchored = monthly.anchor(date(1, 31));
+        assert_eq!(anchored.anchor(date(2, 29)), anchored);
+        let daily = Recurrence::Daily { interval: 1 };
+        assert_eq!(daily.anchor(date(1, 31)), daily);
+    }
+
+    #[test]
+    fn test_next() {
+        let completed = date(3, 20);
+        let next = |recurrence: &str, due| {
+            recurrence
+                .parse::<Recurrence>()
+                .unwrap()
+                .next(due, completed)
+        };
+        assert_eq!(next("FREQ=DAILY;INTERVAL=2", date(2, 28)), date(3, 1));
+        // 2024-03-04 is a monday
+        let weekly = "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH";
+        assert_eq!(next(weekly, date(3, 4)), date(3, 7));
+        assert_eq!(next(weekly, date(3, 7)), date(3, 18));
+        assert_eq!(next(weekly, date(3, 9)), date(3, 18));
+        assert_eq!(next("FREQ=WEEKLY", date(3, 6)), date(3, 13));
+        assert_eq!(next("FREQ=MONTHLY", date(1, 31)), date(2, 29));
+        // the anchored day comes back after a short month
+        let monthly = "FREQ=MONTHLY;BYMONTHDAY=31";
+        assert_eq!(next(monthly, date(1, 31)), date(2, 29));
+        assert_eq!(next(monthly, date(2, 29)), date(3, 31));
+        assert_eq!(next(monthly, date(3, 31)), date(4, 30));
+        assert_eq!(
+            next("FREQ=MONTHLY;INTERVAL=2;BYMONTHDAY=30", date(12, 30)).month(),
+            2
+        );
+        assert_eq!(next("FREQ=MONTHLY;INTERVAL=12", date(3, 4)).year(), 2025);
+        // the due date doesn't matter once the task is completed
+        assert_eq!(
+            next("FREQ=DAILY;INTERVAL=5;X-FROM=COMPLETION", date(1, 1)),
+            date(3, 25)
+        );
+    }
+}
@@ -21,6 +21,16 @@ CREATE TABLE IF NOT EXISTS "task_property" (
     FOREIGN KEY ("task_id") REFERENCES "task"("id") ON DELETE CASCADE,
     PRIMARY KEY ("task_id", "name")
 );
+CREATE TABLE IF NOT EXISTS "property_schema" (
+    "name" varchar(255) PRIMARY KEY,
+    "type" TEXT NOT NULL,
+    "description" TEXT NOT NULL DEFAULT '',
+    "default_value" TEXT,
+    "required" BOOLEAN NOT NULL DEFAULT FALSE,
+    "allowed_values" TEXT NOT NULL DEFAULT '[]',
+    "options" TEXT NOT NULL DEFAULT '[]',
+    "formula" TEXT
+);
 CREATE TABLE IF NOT EXISTS "task_string_property" (
     "task_id" INT NOT NULL,
     "task_property_name" varchar(255) NOT NULL,
@@ -35,6 +45,21 @@ CREATE TABLE IF NOT EXISTS "task_num_property" (
     PRIMARY KEY ("task_id", "task_property_name"),
     FOREIGN KEY ("task_id", "task_property_name") REFERENCES "task_property"("task_id", "name") ON DELETE CASCADE
 );
+CREATE TABLE IF NOT EXISTS "task_int_property" (
+    "task_id" INT NOT NULL,
+    "task_property_name" varchar(255) NOT NULL,
+    "value" BIGINT NOT NULL,
+    PRIMARY KEY ("task_id", "task_property_name"),
+    FOREIGN KEY ("task_id", "task_property_name") REFERENCES "task_property"("task_id", "name") ON DELETE CASCADE
+);
+-- durations are stored as a number of seconds
+CREATE TABLE IF NOT EXISTS "task_duration_property" (
+    "task_id" INT NOT NULL,
+    "task_property_name" varchar(255) NOT NULL,
+    "value" BIGINT NOT NULL,
+    PRIMARY KEY ("task_id", "task_property_name"),
+    FOREIGN KEY ("task_id", "task_property_name") REFERENCES "task_property"("task_id", "name") ON DELETE CASCADE
+);
 CREATE TABLE IF NOT EXISTS "task_date_property" (
     "task_id" INT NOT NULL,
     "task_property_name" varchar(255) NOT NULL,
@@ -49,6 +74,56 @@ CREATE TABLE IF NOT EXISTS "task_bool_property" (
     PRIMARY KEY ("task_id", "task_property_name"),
     FOREIGN KEY ("task_id", "task_property_name") REFERENCES "task_property"("task_id", "name") ON DELETE CASCADE
 );
+CREATE TABLE IF NOT EXISTS "task_select_property" (
+    "task_id" INT NOT NULL,
+    "task_property_name" varchar(255) NOT NULL,
+    "value" TEXT NOT NULL,
+    PRIMARY KEY ("task_id", "task_property_name"),
+    FOREIGN KEY ("task_id", "task_property_name") REFERENCES "task_property"("task_id", "name") ON DELETE CASCADE
+);
+CREATE TABLE IF NOT EXISTS "task_multiselect_property" (
+    "task_id" INT NOT NULL,
+    "task_property_name" varchar(255) NOT NULL,
+    "value" TEXT[] NOT NULL,
+    PRIMARY KEY ("task_id", "task_property_name"),
+    FOREIGN KEY ("task_id", "task_property_name") REFERENCES "task_property"("task_id", "name") ON DELETE CASCADE
+);
+-- a reference is deleted along with the task it points to, like a dependency
+CREATE TABLE IF NOT EXISTS "task_ref_property" (
+    "task_id" INT NOT NULL,
+    "task_property_name" varchar(255) NOT NULL,
+    "value" INT NOT NULL,
+    PRIMARY KEY ("task_id", "task_property_name"),
+    FOREIGN KEY ("task_id", "task_property_name") REFERENCES "task_property"("task_id", "name") ON DELETE CASCADE,
+    FOREIGN KEY ("value") REFERENCES "task"("id") ON DELETE CASCADE
+);
+CREATE TABLE IF NOT EXISTS "task_refs_property" (
+    "task_id" INT NOT NULL,
+    "task_property_name" varchar(255) NOT NULL,
+    "value" INT NOT NULL,
+    PRIMARY KEY ("task_id", "task_property_name", "value"),
+    FOREIGN KEY ("task_id", "task_property_name") REFERENCES "task_property"("task_id", "name") ON DELETE CASCADE,
+    FOREIGN KEY ("value") REFERENCES "task"("id") ON DELETE CASCADE
+);
+CREATE TABLE IF NOT EXISTS "task_user_property" (
+    "task_id" INT NOT NULL,
+    "task_property_name" varchar(255) NOT NULL,
+    "value" varchar(255) NOT NULL,
+    PRIMARY KEY ("task_id", "task_property_name"),
+    FOREIGN KEY ("task_id", "task_property_name") REFERENCES "task_property"("task_id", "name") ON DELETE CASCADE
+);
+-- instances of a recurring task link back to the one they were spawned from, so its history can be walked
+CREATE TABLE IF NOT EXISTS "task_recurrence" (
+    "task_id" INT PRIMARY KEY REFERENCES "task"("id") ON DELETE CASCADE,
+    "rule" TEXT,
+    "previous_id" INT UNIQUE REFERENCES "task"("id") ON DELETE SET NULL
+);
+-- a task is a subtask of at most one parent, subtasks are ordered among their siblings by position
+CREATE TABLE IF NOT EXISTS "subtask" (
+    "task_id" INT PRIMARY KEY REFERENCES "task"("id") ON DELETE CASCADE,
+    "parent_id" INT NOT NULL REFERENCES "task"("id") ON DELETE CASCADE,
+    "position" INT NOT NULL
+);
 CREATE TABLE IF NOT EXISTS "scripts" (
     "id" SERIAL,
     "name" varchar(255) NOT NULL,
@@ -61,6 +136,60 @@ CREATE TABLE IF NOT EXISTS "task_scripts" (
     "event" varchar(255) NOT NULL,
     PRIMARY KEY ("task_id", "script_id")
 );
+-- reminders fire `before` seconds ahead of the date in a task's date property
+CREATE TABLE IF NOT EXISTS "reminder" (
+    "task_id" INT NOT NULL,
+    "property_name" varchar(255) NOT NULL,
+    "before" BIGINT NOT NULL,
+    PRIMARY KEY ("task_id", "property_name"),
+    FOREIGN KEY ("task_id", "property_name") REFERENCES "task_property"("task_id", "name") ON DELETE CASCADE
+);
+CREATE TABLE IF NOT EXISTS "script_schedule" (
+    "script_id" INT PRIMARY KEY REFERENCES "scripts"("id") ON DELETE CASCADE,
+    "next_run" timestamp NOT NULL,
+    "rule" TEXT
+);
+-- events fired by the scheduler, an event fires once for each task, kind and time
+CREATE TABLE IF NOT EXISTS "notification" (
+    "id" SERIAL PRIMARY KEY,
+    "task_id" INT REFERENCES "task"("id") ON DELETE CASCADE,
+    "kind" TEXT NOT NULL,
+    "fire_at" timestamp NOT NULL,
+    UNIQUE ("task_id", "kind", "fire_at")
+);
+-- discussion thread of a task, comments are shown in the order they were posted
+CREATE TABLE IF NOT EXISTS "comment" (
+    "id" SERIAL PRIMARY KEY,
+    "task_id" INT NOT NULL REFERENCES "task"("id") ON DELETE CASCADE,
+    "author" varchar(255) NOT NULL,
+    "body" TEXT NOT NULL,
+    "created_at" timestamp NOT NULL,
+    "edited_at" timestamp
+);
+-- files attached to tasks, contents live in the blob directory under their SHA-256 hash
+CREATE TABLE IF NOT EXISTS "attachment" (
+    "id" SERIAL PRIMARY KEY,
+    "task_id" INT NOT NULL REFERENCES "task"("id") ON DELETE CASCADE,
+    "name" varchar(255) NOT NULL,
+    "mime" varchar(255) NOT NULL,
+    "size" BIGINT NOT NULL,
+    "hash" char(64) NOT NULL,
+    "uploaded_at" timestamp NOT NULL
+);
+-- tags nest under an optional parent tag, deleting a tag deletes the tags nested under it.
+-- Sibling tags have distinct names, top level tags included
+CREATE TABLE IF NOT EXISTS "tag" (
+    "id" SERIAL PRIMARY KEY,
+    "name" varchar(255) NOT NULL,
+    "parent_id" INT REFERENCES "tag"("id") ON DELETE CASCADE,
+    "color" char(7) NOT NULL,
+    UNIQUE NULLS NOT DISTINCT ("parent_id", "name")
+);
+CREATE TABLE IF NOT EXISTS "task_tag" (
+    "task_id" INT NOT NULL REFERENCES "task"("id") ON DELETE CASCADE,
+    "tag_id" INT NOT NULL REFERENCES "tag"("id") ON DELETE CASCADE,
+    PRIMARY KEY ("task_id", "tag_id")
+);
 CREATE TABLE IF NOT EXISTS "global_property" (
     "name" varchar(255) NOT NULL,
     "type" TEXT NOT NULL,
@@ -73,7 +202,7 @@ CREATE TABLE IF NOT EXISTS "global_string_property" (
 );
 CREATE TABLE IF NOT EXISTS "global_num_property" (
     "property_name" varchar(255) NOT NULL REFERENCES "global_property"("name") ON DELETE CASCADE,
-    "value" REAL NOT NULL,
+    "value" NUMERIC NOT NULL,
     PRIMARY KEY ("property_name")
 );
 CREATE TABLE IF NOT EXISTS "global_date_property" (
@@ -88,10 +217,9 @@ CREATE TABLE IF NOT EXISTS "global_bool_property" (
 );
 CREATE OR REPLACE FUNCTION check_cycle() RETURNS TRIGGER AS $$
 DECLARE cycle BOOLEAN;
-BEGIN WITH RECURSIVE cte AS (
+BEGIN WITH RECURSIVE cte ("task_id", "depends_on_id") AS (
     SELECT NEW.task_id,
         NEW.depends_on_id
-    FROM NEW
     UNION
     SELECT cte.task_id,
         d.depends_on_id
@@ -108,82 +236,97 @@ END IF;
 RETURN NEW;
 END;
 $$ LANGUAGE plpgsql;
+DROP TRIGGER IF EXISTS dependency_insert_update_trigger ON dependency;
 CREATE TRIGGER dependency_insert_update_trigger BEFORE
 INSERT
     OR
 UPDATE ON dependency FOR EACH ROW EXECUTE FUNCTION check_cycle();
-DO $$ BEGIN IF NOT EXISTS (
-    SELECT 1
-    FROM pg_class c
-        JOIN pg_namespace n ON n.oid = c.relnamespace
-    WHERE c.relname = 'task_depend_on_index'
-        AND n.nspname = 'public'
-) THEN CREATE INDEX task_depend_on_index ON dependency (depends_on_id);
-END IF;
-END;
-$$;
-DO $$ BEGIN IF NOT EXISTS (
-    SELECT 1
-    FROM pg_class c
-        JOIN pg_namespace n ON n.oid = c.relnamespace
-    WHERE c.relname = 'task_date_property_value_index'
-        AND n.nspname = 'public'
-) THEN CREATE INDEX task_date_property_value_index ON task_date_property (value);
-END IF;
-END;
-$$;
-DO $$ BEGIN IF NOT EXISTS (
-    SELECT 1
-    FROM pg_class c
-        JOIN pg_namespace n ON n.oid = c.relnamespace
-    WHERE c.relname = 'task_num_property_value_index'
-        AND n.nspname = 'public'
-) THEN CREATE INDEX task_num_property_value_index ON task_num_property (value);
-END IF;
-END;
-$$;
-DO $$ BEGIN IF NOT EXISTS (
-    SELECT 1
-    FROM pg_class c
-        JOIN pg_namespace n ON n.oid = c.relnamespace
-    WHERE c.relname = 'task_string_property_value_index'
-        AND n.nspname = 'public'
-) THEN CREATE INDEX task_string_property_value_index ON task_string_property (value);
-END IF;
-END;
-$$;
-DO $$ BEGIN IF NOT EXISTS (
-    SELECT 1
-    FROM pg_class c
-        JOIN pg_namespace n ON n.oid = c.relnamespace
-    WHERE c.relname = 'task_bool_property_value_index'
-        AND n.nspname = 'public'
-) THEN CREATE INDEX task_bool_property_value_index ON task_bool_property (value);
+CREATE OR REPLACE FUNCTION check_subtask_cycle() RETURNS TRIGGER AS $$
+DECLARE cycle BOOLEAN;
+BEGIN WITH RECURSIVE cte ("task_id", "parent_id") AS (
+    SELECT NEW.task_id,
+        NEW.parent_id
+    UNION
+    SELECT cte.task_id,
+        s.parent_id
+    FROM cte
+        JOIN subtask s ON cte.parent_id = s.task_id
+)
+SELECT EXISTS (
+        SELECT 1
+        FROM cte
+        WHERE cte.task_id = cte.parent_id
+    ) INTO cycle;
+IF cycle THEN RAISE EXCEPTION 'Subtask cycle detected';
 END IF;
+RETURN NEW;
 END;
-$$;
----CREATE INDEX task_property_type_index on task_property (jsonb_typeof(value));
-CREATE OR REPLACE FUNCTION check_property() RETURNS TRIGGER AS $$ BEGIN IF EXISTS (
+$$ LANGUAGE plpgsql;
+DROP TRIGGER IF EXISTS subtask_insert_update_trigger ON subtask;
+CREATE TRIGGER subtask_insert_update_trigger BEFORE
+INSERT
+    OR
+UPDATE ON subtask FOR EACH ROW EXECUTE FUNCTION check_subtask_cycle();
+CREATE OR REPLACE FUNCTION check_tag_cycle() RETURNS TRIGGER AS $$
+DECLARE cycle BOOLEAN;
+BEGIN WITH RECURSIVE cte ("id", "parent_id") AS (
+    SELECT NEW.id,
+        NEW.parent_id
+    UNION
+    SELECT cte.id,
+        t.parent_id
+    FROM cte
+        JOIN tag t ON cte.parent_id = t.id
+)
+SELECT EXISTS (
         SELECT 1
-        FROM task_property
-        WHERE task_id = NEW.task_id
-            AND name = NEW.task_property_name
-    ) THEN RAISE EXCEPTION 'Primary key already exists in task_property table';
-ELSE
-INSERT INTO task_property(task_id, name, type)
-VALUES (NEW.task_id, NEW.task_property_name, TG_ARGV [0]);
-RETURN NEW;
+        FROM cte
+        WHERE cte.id = cte.parent_id
+    ) INTO cycle;
+IF cycle THEN RAISE EXCEPTION 'Tag cycle detected';
 END IF;
+RETURN NEW;
 END;
 $$ LANGUAGE plpgsql;
-CREATE TRIGGER string_property_trigger BEFORE
-INSERT ON task_string_property FOR EACH ROW EXECUTE FUNCTION check_property("string");
-CREATE TRIGGER num_property_trigger BEFORE
-INSERT ON task_num_property FOR EACH ROW EXECUTE FUNCTION check_property("real");
-CREATE TRIGGER date_property_trigger BEFORE
-INSERT ON task_date_property FOR EACH ROW EXECUTE FUNCTION check_property("timestamp");
-CREATE TRIGGER bool_property_trigger BEFORE
-INSERT ON task_bool_property FOR EACH ROW EXECUTE FUNCTION check_property("boolean");
+DROP TRIGGER IF EXISTS tag_update_trigger ON tag;
+CREATE TRIGGER tag_update_trigger BEFORE
+UPDATE ON tag FOR EACH ROW EXECUTE FUNCTION check_tag_cycle();
+CREATE INDEX IF NOT EXISTS task_depend_on_index ON dependency (depends_on_id);
+CREATE INDEX IF NOT EXISTS task_date_property_value_index ON task_date_property (value);
+CREATE INDEX IF NOT EXISTS task_num_property_value_index ON task_num_property (value);
+CREATE INDEX IF NOT EXISTS task_string_property_value_index ON task_string_property (value);
+CREATE INDEX IF NOT EXISTS task_bool_property_value_index ON task_bool_property (value);
+CREATE INDEX IF NOT EXISTS task_title_search_index ON task USING GIN (to_tsvector('english', title));
+CREATE INDEX IF NOT EXISTS task_string_property_search_index ON task_string_property USING GIN (to_tsvector('english', value));
+CREATE INDEX IF NOT EXISTS task_select_property_value_index ON task_select_property (value);
+CREATE INDEX IF NOT EXISTS task_multiselect_property_value_index ON task_multiselect_property USING GIN (value);
+CREATE INDEX IF NOT EXISTS task_int_property_value_index ON task_int_property (value);
+CREATE INDEX IF NOT EXISTS task_duration_property_value_index ON task_duration_property (value);
+CREATE INDEX IF NOT EXISTS task_ref_property_value_index ON task_ref_property (value);
+CREATE INDEX IF NOT EXISTS task_refs_property_value_index ON task_refs_property (value);
+CREATE INDEX IF NOT EXISTS task_user_property_value_index ON task_user_property (value);
+-- a task spawns at most one next instance, tables created before previous_id was unique get the index of its constraint
+CREATE UNIQUE INDEX IF NOT EXISTS task_recurrence_previous_id_key ON task_recurrence (previous_id);
+DROP INDEX IF EXISTS task_recurrence_previous_index;
+CREATE INDEX IF NOT EXISTS subtask_parent_index ON subtask (parent_id, position);
+CREATE INDEX IF NOT EXISTS script_schedule_next_run_index ON script_schedule (next_run);
+CREATE INDEX IF NOT EXISTS comment_task_index ON comment (task_id, id);
+CREATE INDEX IF NOT EXISTS attachment_task_index ON attachment (task_id);
+CREATE INDEX IF NOT EXISTS attachment_hash_index ON attachment (hash);
+-- tables created before sibling names were unique get the index of the constraint, which also serves
+-- lookups by parent
+CREATE UNIQUE INDEX IF NOT EXISTS tag_parent_id_name_key ON tag (parent_id, name) NULLS NOT DISTINCT;
+DROP INDEX IF EXISTS tag_parent_index;
+CREATE INDEX IF NOT EXISTS task_tag_tag_index ON task_tag (tag_id);
+---CREATE INDEX task_property_type_index on task_property (jsonb_typeof(value));
+-- property types are checked by the API now, drop the triggers databases created before that still have
+DROP TRIGGER IF EXISTS string_property_trigger ON task_string_property;
+DROP TRIGGER IF EXISTS num_property_trigger ON task_num_property;
+DROP TRIGGER IF EXISTS date_property_trigger ON task_date_property;
+DROP TRIGGER IF EXISTS bool_property_trigger ON task_bool_property;
+DROP FUNCTION IF EXISTS check_property;
+-- global numbers were REAL before they matched task numbers, keep existing databases exact too
+ALTER TABLE global_num_property ALTER COLUMN value TYPE NUMERIC;
 /*CREATE OR REPLACE FUNCTION update_last_edited() RETURNS TRIGGER AS $$ BEGIN
 UPDATE task
 SET last_edited = NOW()
@@ -213,16 +356,40 @@ SELECT task_id_tables();
 
 */
 
+-- example tasks for a new database
 INSERT INTO task (completed, title)
-VALUES (
-        true,
-        'give ABN an A for their Alpha Release!'
+SELECT *
+FROM (
+        VALUES (
+                true,
+                'give ABN an A for their Alpha Release!'
+            ),
+            (false, 'make dinner')
+    ) AS example
+WHERE NOT EXISTS (
+        SELECT 1
+        FROM task
     );
-INSERT INTO task (completed, title)
-VALUES (false, 'make dinner');
 CREATE TABLE IF NOT EXISTS "view" (
     "id" SERIAL PRIMARY KEY,
     "name" text,
     "properties" text[]  NOT NULL,
-    "filter" jsonb NOT NULL
+    "filter" jsonb NOT NULL,
+    "sort" jsonb NOT NULL DEFAULT '[]',
+    "max_tasks" BIGINT,
+    "group_by" jsonb NOT NULL DEFAULT '"None"',
+    "summaries" jsonb NOT NULL DEFAULT '[]'
+);
+-- views created before sorting, paging, grouping and summaries get their columns
+ALTER TABLE "view" ADD COLUMN IF NOT EXISTS "sort" jsonb NOT NULL DEFAULT '[]';
+ALTER TABLE "view" ADD COLUMN IF NOT EXISTS "max_tasks" BIGINT;
+ALTER TABLE "view" ADD COLUMN IF NOT EXISTS "group_by" jsonb NOT NULL DEFAULT '"None"';
+ALTER TABLE "view" ADD COLUMN IF NOT EXISTS "summaries" jsonb NOT NULL DEFAULT '[]';
+-- manual order of the tasks of a view, ranks are fractional indices compared byte by byte
+CREATE TABLE IF NOT EXISTS "view_rank" (
+    "view_id" INT NOT NULL REFERENCES "view"(id) ON DELETE CASCADE,
+    "task_id" INT NOT NULL REFERENCES task(id) ON DELETE CASCADE,
+    "rank" text COLLATE "C" NOT NULL,
+    PRIMARY KEY ("view_id", "task_id"),
+    UNIQUE ("view_id", "rank")
 );
@@ -1,15 +1,37 @@
-use crate::database::*;
-use actix_web::error::{ErrorInternalServerError, ErrorNotFound};
+use crate::{
+    blob::{detect_mime, BlobStore},
+    database::*,
+};
+use actix_web::error::{
+    ErrorBadRequest, ErrorConflict, ErrorInternalServerError, ErrorNotFound, ErrorPayloadTooLarge,
+    InternalError,
+};
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
 #[allow(unused)]
-use actix_web::{delete, get, post, put, web, Responder, Result};
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder, Result};
 use common::{
-    backend::{*}, Comparator, Filter, Operator, PrimitiveField, TaskID, TaskPropVariant, ViewData,
+    backend::*,
+    filter::FilterError,
+    formula::{Aggregate, BinaryOp, Formula, Relation},
+    recurrence::Recurrence,
+    Comparator, DependencyFilter, Filter, GroupBy, Operator, PrimitiveField, PropertyDefinition,
+    SortField, SortKey, Summary, TagID, TaskDuration, TaskID, TaskProp, TaskPropType,
+    TaskPropVariant, ViewData, DUE_PROPERTY,
 };
-use log::info;
+use log::{info, warn};
 use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
 use sea_orm::{
-    entity::prelude::*, ActiveValue::NotSet, Condition, IntoActiveModel, QuerySelect, Set,
+    entity::prelude::*,
+    sea_query::{
+        extension::postgres::{PgBinOper, PgExpr},
+        Alias, Asterisk, Expr, Func, JoinType, NullOrdering, OnConflict, Order, Query, SimpleExpr,
+    },
+    ActiveValue::NotSet,
+    Condition, DbBackend, FromQueryResult, IntoActiveModel, IntoSimpleExpr, QueryOrder,
+    QueryResult, QuerySelect, QueryTrait, Set, SqlErr, Statement, TransactionTrait,
 };
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::Bound;
 
 /// get /task endpoint for retrieving a single TaskShort
 #[get("/task")]
@@ -25,20 +47,47 @@ async fn get_task_request(
         .map_err(|e| ErrorInternalServerError(format!("SQL error: {}", e)))?; // TODO handle this error better, if it does not exist then it should be a http 204 error
     info!("get_task_request, found_task: {:?}", task);
     match task {
-        Some(model) => Ok(web::Json(ReadTaskShortResponse {
-            task_id: model.id,
-            name: model.title,
-            completed: model.completed,
-            props: Vec::new(),   //TODO 26mar24 Mrknox: implement properties
-            deps: Vec::new(),    //TODO 26mar24 Mrknox: implement dependencies
-            scripts: Vec::new(), //TODO 26mar24 Mrknox: implement scripts
-            last_edited: model.last_edited,
-            req_id: req.req_id,
-        })),
+        Some(model) => {
+            let (parent, subtasks) = read_hierarchy(db.as_ref(), model.id)
+                .await
+                .map_err(|e| ErrorInternalServerError(format!("SQL error: {}", e)))?;
+            Ok(web::Json(ReadTaskShortResponse {
+                task_id: model.id,
+                name: model.title,
+                completed: model.completed,
+                props: Vec::new(),   //TODO 26mar24 Mrknox: implement properties
+                deps: Vec::new(),    //TODO 26mar24 Mrknox: implement dependencies
+                scripts: Vec::new(), //TODO 26mar24 Mrknox: implement scripts
+                parent,
+                subtasks,
+                last_edited: model.last_edited,
+                req_id: req.req_id,
+            }))
+        }
         None => Err(ErrorNotFound("task not found by ID")),
     }
 }
 
+/// the task a task is a subtask of, and its own subtasks in order
+async fn read_hierarchy(
+    db: &DatabaseConnection,
+    task_id: TaskID,
+) -> Result<(Option<TaskID>, Vec<TaskID>), DbErr> {
+    let parent = subtask::Entity::find_by_id(task_id)
+        .one(db)
+        .await?
+        .map(|model| model.parent_id);
+    let subtasks = subtask::Entity::find()
+        .filter(subtask::Column::ParentId.eq(task_id))
+        .order_by_asc(subtask::Column::Position)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|model| model.task_id)
+        .collect();
+    Ok((parent, subtasks))
+}
+
 /// get /tasks endpoint for retrieving some number of TaskShorts
 #[get("/tasks")]
 async fn get_tasks_request(
@@ -54,16 +103,24 @@ async fn get_tasks_request(
             .await
             .map_err(|e| ErrorInternalServerError(format!("couldn't fetch tasks: {}", e)))?;
         match task {
-            Some(model) => res.push(Ok(ReadTaskShortResponse {
-                task_id: model.id,
-                name: model.title,
-                completed: model.completed,
-                props: Vec::new(),
-                deps: Vec::new(),
-                scripts: Vec::new(),
-                last_edited: model.last_edited,
-                req_id: taskreq.req_id,
-            })),
+            Some(model) => {
+                let (parent, subtasks) =
+                    read_hierarchy(data.as_ref(), model.id).await.map_err(|e| {
+                        ErrorInternalServerError(format!("couldn't fetch tasks: {}", e))
+                    })?;
+                res.push(Ok(ReadTaskShortResponse {
+                    task_id: model.id,
+                    name: model.title,
+                    completed: model.completed,
+                    props: Vec::new(),
+                    deps: Vec::new(),
+                    scripts: Vec::new(),
+                    parent,
+                    subtasks,
+                    last_edited: model.last_edited,
+                    req_id: taskreq.req_id,
+                }))
+            }
             None => res.push(Err("task not found by ID".to_string())),
         }
     }
@@ -78,13 +135,22 @@ pub async fn create_task(db: &DatabaseConnection, req: &CreateTaskRequest) -> Re
         id: NotSet,
         title: Set(req.name.clone()),
         completed: Set(req.completed),
-        last_edited: Set(chrono::Local::now().naive_local()),
+        last_edited: Set(chrono::Utc::now().naive_utc()),
     };
     let result_task = task::Entity::insert(task_model)
         .exec(db)
         .await
         .map_err(|e| ErrorInternalServerError(format!("task not inserted: {}", e)))?; //TODO handle this error better, for example for unique constraint violation
     info!("create_task, result_task: {:?}", result_task);
+    for definition in get_schema(db).await? {
+        if let Some(default) = &definition.default {
+            let prop = TaskProp {
+                name: definition.name.clone(),
+                value: default.clone(),
+            };
+            write_property(db, result_task.last_insert_id, &prop, Some(&definition)).await?;
+        }
+    }
     Ok(result_task.last_insert_id)
 }
 
@@ -119,14 +185,314 @@ async fn create_tasks_request(
     Ok(web::Json(res))
 }
 
-/// put /task updates one task
+/// Create or overwrite a property of a task.
+/// The first write of a name records its type in `task_property`, later writes must have the same type.
+/// If the property has a workspace definition, the value must also fit it.
+/// Select and multi-select properties must have a definition, it holds their options.
+/// The type and the value are written in one transaction.
+async fn write_property<C: ConnectionTrait + TransactionTrait>(
+    db: &C,
+    task_id: TaskID,
+    prop: &TaskProp,
+    definition: Option<&PropertyDefinition>,
+) -> Result<()> {
+    if let TaskPropVariant::RelativeDate(_) = prop.value {
+        return Err(ErrorBadRequest(
+            "relative dates can only be used in filters",
+        ));
+    }
+    match definition {
+        Some(definition) => definition
+            .check_value(&prop.value)
+            .map_err(ErrorBadRequest)?,
+        None if matches!(
+            prop.value,
+            TaskPropVariant::Select(_) | TaskPropVariant::MultiSelect(_)
+        ) =>
+        {
+            return Err(ErrorBadRequest(format!(
+                "property {} has no options defined in the schema",
+                prop.name
+            )))
+        }
+        None => {}
+    }
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't write property: {}", e)))?;
+    write_property_value(&txn, task_id, prop).await?;
+    txn.commit()
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't write property: {}", e)))
+}
+
+// record the type of the property if the task doesn't have it yet, and write its value to the typed table
+async fn write_property_value<C: ConnectionTrait>(
+    db: &C,
+    task_id: TaskID,
+    prop: &TaskProp,
+) -> Result<()> {
+    let typ = prop.value.prop_type();
+    let model = task_property::Entity::find()
+        .filter(
+            Condition::all()
+                .add(task_property::Column::TaskId.eq(task_id))
+                .add(task_property::Column::Name.eq(prop.name.to_owned())),
+        )
+        .one(db)
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't fetch property: {}", e)))?;
+    match model {
+        Some(model) if model.typ != typ.as_str() => {
+            return Err(ErrorInternalServerError(format!(
+                "property {} has wrong type (expecting {})",
+                prop.name, model.typ
+            )));
+        }
+        Some(_) => {}
+        None => {
+            task_property::Entity::insert(task_property::ActiveModel {
+                task_id: Set(task_id),
+                name: Set(prop.name.to_owned()),
+                typ: Set(typ.as_str().to_owned()),
+            })
+            .exec_without_returning(db)
+            .await
+            .map_err(|e| ErrorInternalServerError(format!("couldn't create property: {}", e)))?;
+        }
+    }
+
+    match &prop.value {
+        TaskPropVariant::String(val) => {
+            upsert_property_value(
+                db,
+                task_string_property::ActiveModel {
+                    task_id: Set(task_id),
+                    task_property_name: Set(prop.name.to_owned()),
+                    value: Set(val.to_owned()),
+                },
+                [
+                    task_string_property::Column::TaskId,
+                    task_string_property::Column::TaskPropertyName,
+                ],
+                task_string_property::Column::Value,
+            )
+            .await
+        }
+        TaskPropVariant::Number(val) => {
+            let value = Decimal::from_f64(*val)
+                .ok_or_else(|| ErrorInternalServerError(format!("can't store number {}", val)))?;
+            upsert_property_value(
+                db,
+                task_num_property::ActiveModel {
+                    task_id: Set(task_id),
+                    task_property_name: Set(prop.name.to_owned()),
+                    value: Set(value),
+                },
+                [
+                    task_num_property::Column::TaskId,
+                    task_num_property::Column::TaskPropertyName,
+                ],
+                task_num_property::Column::Value,
+            )
+            .await
+        }
+        TaskPropVariant::Integer(val) => {
+            upsert_property_value(
+                db,
+                task_int_property::ActiveModel {
+                    task_id: Set(task_id),
+                    task_property_name: Set(prop.name.to_owned()),
+                    value: Set(*val),
+                },
+                [
+                    task_int_property::Column::TaskId,
+                    task_int_property::Column::TaskPropertyName,
+                ],
+                task_int_property::Column::Value,
+            )
+            .await
+        }
+        TaskPropVariant::Duration(val) => {
+            upsert_property_value(
+                db,
+                task_duration_property::ActiveModel {
+                    task_id: Set(task_id),
+                    task_property_name: Set(prop.name.to_owned()),
+                    value: Set(val.0),
+                },
+                [
+                    task_duration_property::Column::TaskId,
+                    task_duration_property::Column::TaskPropertyName,
+                ],
+                task_duration_property::Column::Value,
+            )
+            .await
+        }
+        TaskPropVariant::Date(val) => {
+            upsert_property_value(
+                db,
+                task_date_property::ActiveModel {
+                    task_id: Set(task_id),
+                    task_property_name: Set(prop.name.to_owned()),
+                    value: Set(*val),
+                },
+                [
+                    task_date_property::Column::TaskId,
+                    task_date_property::Column::TaskPropertyName,
+                ],
+                task_date_property::Column::Value,
+            )
+            .await
+        }
+        TaskPropVariant::Boolean(val) => {
+            upsert_property_value(
+                db,
+                task_bool_property::ActiveModel {
+                    task_id: Set(task_id),
+                    task_property_name: Set(prop.name.to_owned()),
+                    value: Set(*val),
+                },
+                [
+                    task_bool_property::Column::TaskId,
+                    task_bool_property::Column::TaskPropertyName,
+                ],
+                task_bool_property::Column::Value,
+            )
+            .await
+        }
+        TaskPropVariant::Select(val) => {
+            upsert_property_value(
+                db,
+                task_select_property::ActiveModel {
+                    task_id: Set(task_id),
+                    task_property_name: Set(prop.name.to_owned()),
+                    value: Set(val.to_owned()),
+                },
+                [
+                    task_select_property::Column::TaskId,
+                    task_select_property::Column::TaskPropertyName,
+                ],
+                task_select_property::Column::Value,
+            )
+            .await
+        }
+        TaskPropVariant::MultiSelect(val) => {
+            upsert_property_value(
+                db,
+                task_multiselect_property::ActiveModel {
+                    task_id: Set(task_id),
+                    task_property_name: Set(prop.name.to_owned()),
+                    value: Set(val.to_owned()),
+                },
+                [
+                    task_multiselect_property::Column::TaskId,
+                    task_multiselect_property::Column::TaskPropertyName,
+                ],
+                task_multiselect_property::Column::Value,
+            )
+            .await
+        }
+        TaskPropVariant::TaskRef(val) => {
+            upsert_property_value(
+                db,
+                task_ref_property::ActiveModel {
+                    task_id: Set(task_id),
+                    task_property_name: Set(prop.name.to_owned()),
+                    value: Set(*val),
+                },
+                [
+                    task_ref_property::Column::TaskId,
+                    task_ref_property::Column::TaskPropertyName,
+                ],
+                task_ref_property::Column::Value,
+            )
+            .await
+        }
+        TaskPropVariant::TaskRefs(val) => write_task_refs(db, task_id, &prop.name, val).await,
+        TaskPropVariant::User(val) => {
+            upsert_property_value(
+                db,
+                task_user_property::ActiveModel {
+                    task_id: Set(task_id),
+                    task_property_name: Set(prop.name.to_owned()),
+                    value: Set(val.to_owned()),
+                },
+                [
+                    task_user_property::Column::TaskId,
+                    task_user_property::Column::TaskPropertyName,
+                ],
+                task_user_property::Column::Value,
+            )
+            .await
+        }
+        TaskPropVariant::RelativeDate(_) => unreachable!("relative dates are rejected above"),
+    }
+}
+
+/// replace the tasks referenced by a property, each reference is its own row so it can be a foreign key
+async fn write_task_refs<C: ConnectionTrait>(
+    db: &C,
+    task_id: TaskID,
+    name: &str,
+    tasks: &[TaskID],
+) -> Result<()> {
+    task_refs_property::Entity::delete_many()
+        .filter(task_refs_property::Column::TaskId.eq(task_id))
+        .filter(task_refs_property::Column::TaskPropertyName.eq(name))
+        .exec(db)
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't write property: {}", e)))?;
+    let mut tasks = tasks.to_vec();
+    tasks.sort_unstable();
+    tasks.dedup();
+    if tasks.is_empty() {
+        return Ok(());
+    }
+    task_refs_property::Entity::insert_many(tasks.into_iter().map(|value| {
+        task_refs_property::ActiveModel {
+            task_id: Set(task_id),
+            task_property_name: Set(name.to_owned()),
+            value: Set(value),
+        }
+    }))
+    .exec_without_returning(db)
+    .await
+    .map_err(|e| ErrorInternalServerError(format!("couldn't write property: {}", e)))?;
+    Ok(())
+}
+
+/// insert the value of a property into its typed table, overwriting the value if the task already has it
+async fn upsert_property_value<A, C: ConnectionTrait>(
+    db: &C,
+    model: A,
+    key: [<A::Entity as EntityTrait>::Column; 2],
+    value: <A::Entity as EntityTrait>::Column,
+) -> Result<()>
+where
+    A: ActiveModelTrait + Send,
+    <A::Entity as EntityTrait>::Model: IntoActiveModel<A>,
+{
+    <A::Entity as EntityTrait>::insert(model)
+        .on_conflict(OnConflict::columns(key).update_column(value).to_owned())
+        .exec_without_returning(db)
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't write property: {}", e)))?;
+    Ok(())
+}
+
+/// put /task updates one task. All of the update, cascades included, happens in one transaction
 pub async fn update_task(db: &DatabaseConnection, req: &UpdateTaskRequest) -> Result<TaskID> {
+    let error = |e: DbErr| ErrorInternalServerError(format!("couldn't update task: {}", e));
+    let txn = db.begin().await.map_err(error)?;
     let task = task::Entity::find_by_id(req.task_id)
-        .one(db)
+        .one(&txn)
         .await
         .map_err(|e| ErrorInternalServerError(format!("couldn't fetch tasks: {}", e)))?
         .ok_or("no task by id")
         .map_err(ErrorInternalServerError)?;
+    let was_completed = task.completed;
     let mut task: task::ActiveModel = task.into();
     if req.name.is_some() {
         task.title = Set(req.name.to_owned().unwrap());
@@ -134,191 +500,58 @@ pub async fn update_task(db: &DatabaseConnection, req: &UpdateTaskRequest) -> Re
     if req.checked.is_some() {
         task.completed = Set(req.checked.unwrap());
     }
-    if req.checked.is_some() || req.name.is_some() {
-        task.update(db).await.map_err(ErrorInternalServerError)?;
+    let edited = req.name.is_some()
+        || req.checked.is_some()
+        || !req.props_to_add.is_empty()
+        || !req.props_to_remove.is_empty()
+        || !req.deps_to_add.is_empty()
+        || !req.deps_to_remove.is_empty()
+        || req.parent.is_some();
+    // any edit counts, so "edited within" filters see property, dependency and hierarchy changes too
+    if edited {
+        task.last_edited = Set(chrono::Utc::now().naive_utc());
+        task.update(&txn).await.map_err(ErrorInternalServerError)?;
     }
+    let schema = if req.props_to_add.is_empty() && req.props_to_remove.is_empty() {
+        Vec::new()
+    } else {
+        get_schema(&txn).await?
+    };
+    let definition = |name: &str| schema.iter().find(|definition| definition.name == name);
     for prop in req.props_to_add.iter() {
-        let model = task_property::Entity::find()
-            .filter(
-                Condition::all()
-                    .add(task_property::Column::TaskId.eq(req.task_id))
-                    .add(task_property::Column::Name.eq(req.name.to_owned())),
-            )
-            .one(db)
-            .await
-            .map_err(|e| ErrorInternalServerError(format!("couldn't fetch property: {}", e)))?;
-
-        //model exists but type is wrong
-        if model
-            .as_ref()
-            .is_some_and(|m| m.typ != prop.value.type_string())
-        {
-            return Err(ErrorInternalServerError(format!(
-                "property {} has wrong type (expecting {})",
-                prop.name,
-                model.unwrap().typ
+        write_property(&txn, req.task_id, prop, definition(&prop.name)).await?;
+    }
+    for prop in req.props_to_remove.iter() {
+        if definition(prop).is_some_and(|definition| definition.required) {
+            return Err(ErrorBadRequest(format!(
+                "property {} is required and can't be removed",
+                prop
             )));
         }
-
-        //model already exists and we can just update
-        if model.is_some() {
-            match &prop.value {
-                TaskPropVariant::String(val) => {
-                    let p = task_string_property::Entity::find()
-                        .filter(
-                            Condition::all()
-                                .add(task_string_property::Column::TaskId.eq(req.task_id))
-                                .add(
-                                    task_string_property::Column::TaskPropertyName
-                                        .eq(prop.name.to_owned()),
-                                ),
-                        )
-                        .one(db)
-                        .await
-                        .map_err(|e| {
-                            ErrorInternalServerError(format!("couldn't fetch property: {}", e))
-                        })?
-                        .ok_or("couldn't find property by name")
-                        .map_err(ErrorInternalServerError)?;
-                    let mut p = p.into_active_model();
-                    p.value = Set(val.to_owned());
-                }
-                TaskPropVariant::Date(val) => {
-                    let p = task_date_property::Entity::find()
-                        .filter(
-                            Condition::all()
-                                .add(task_date_property::Column::TaskId.eq(req.task_id))
-                                .add(
-                                    task_date_property::Column::TaskPropertyName
-                                        .eq(prop.name.to_owned()),
-                                ),
-                        )
-                        .one(db)
-                        .await
-                        .map_err(|e| {
-                            ErrorInternalServerError(format!("couldn't fetch property: {}", e))
-                        })?
-                        .ok_or("couldn't find property by name")
-                        .map_err(ErrorInternalServerError)?;
-                    let mut p = p.into_active_model();
-                    p.value = Set(*val);
-                }
-                TaskPropVariant::Number(val) => {
-                    let p = task_num_property::Entity::find()
-                        .filter(
-                            Condition::all()
-                                .add(task_num_property::Column::TaskId.eq(req.task_id))
-                                .add(
-                                    task_num_property::Column::TaskPropertyName
-                                        .eq(prop.name.to_owned()),
-                                ),
-                        )
-                        .one(db)
-                        .await
-                        .map_err(|e| {
-                            ErrorInternalServerError(format!("couldn't fetch property: {}", e))
-                        })?
-                        .ok_or("couldn't find property by name")
-                        .map_err(ErrorInternalServerError)?;
-                    let mut p = p.into_active_model();
-                    p.value = Set(Decimal::from_f64(*val).unwrap());
-                }
-                TaskPropVariant::Boolean(val) => {
-                    let p = task_bool_property::Entity::find()
-                        .filter(
-                            Condition::all()
-                                .add(task_bool_property::Column::TaskId.eq(req.task_id))
-                                .add(
-                                    task_bool_property::Column::TaskPropertyName
-                                        .eq(prop.name.to_owned()),
-                                ),
-                        )
-                        .one(db)
-                        .await
-                        .map_err(|e| {
-                            ErrorInternalServerError(format!("couldn't fetch property: {}", e))
-                        })?
-                        .ok_or("couldn't find property by name")
-                        .map_err(ErrorInternalServerError)?;
-                    let mut p = p.into_active_model();
-                    p.value = Set(*val);
-                }
-            }
-
-            continue;
+        if definition(prop).is_some_and(|definition| definition.formula.is_some()) {
+            return Err(ErrorBadRequest(format!(
+                "property {} is computed and can't be removed",
+                prop
+            )));
         }
-
-        //otherwise we create a new property
-        match &prop.value {
-            TaskPropVariant::String(val) => {
-                task_string_property::Entity::insert(task_string_property::ActiveModel {
-                    task_id: Set(req.task_id),
-                    task_property_name: Set(prop.name.to_owned()),
-                    value: Set(val.to_string()),
-                })
-                .exec(db)
-                .await
-                .map_err(|e| {
-                    ErrorInternalServerError(format!("couldn't create property: {}", e))
-                })?;
-            }
-            TaskPropVariant::Number(val) => {
-                task_num_property::Entity::insert(task_num_property::ActiveModel {
-                    task_id: Set(req.task_id),
-                    task_property_name: Set(prop.name.to_owned()),
-                    value: Set(Decimal::from_f64(*val).unwrap()),
-                })
-                .exec(db)
-                .await
-                .map_err(|e| {
-                    ErrorInternalServerError(format!("couldn't create property: {}", e))
-                })?;
-            }
-            TaskPropVariant::Date(val) => {
-                task_date_property::Entity::insert(task_date_property::ActiveModel {
-                    task_id: Set(req.task_id),
-                    task_property_name: Set(prop.name.to_owned()),
-                    value: Set(val.to_owned()),
-                })
-                .exec(db)
-                .await
-                .map_err(|e| {
-                    ErrorInternalServerError(format!("couldn't create property: {}", e))
-                })?;
-            }
-            TaskPropVariant::Boolean(val) => {
-                task_bool_property::Entity::insert(task_bool_property::ActiveModel {
-                    task_id: Set(req.task_id),
-                    task_property_name: Set(prop.name.to_owned()),
-                    value: Set(*val),
-                })
-                .exec(db)
-                .await
-                .map_err(|e| {
-                    ErrorInternalServerError(format!("couldn't create property: {}", e))
-                })?;
-            }
-        };
-    }
-    for prop in req.props_to_remove.iter() {
         task_property::Entity::find()
             .filter(
                 Condition::all()
                     .add(task_property::Column::TaskId.eq(req.task_id))
                     .add(task_property::Column::Name.eq(prop)),
             )
-            .one(db)
+            .one(&txn)
             .await
             .map_err(|e| ErrorInternalServerError(format!("couldn't fetch property: {}", e)))?
             .ok_or("no property by name")
             .map_err(ErrorInternalServerError)?
-            .delete(db)
+            .delete(&txn)
             .await
             .map_err(ErrorInternalServerError)?;
     }
     for dep in req.deps_to_add.iter() {
         if task::Entity::find_by_id(*dep)
-            .one(db)
+            .one(&txn)
             .await
             .map_err(|e| ErrorInternalServerError(format!("couldn't fetch task: {}", e)))?
             .is_none()
@@ -333,7 +566,7 @@ pub async fn update_task(db: &DatabaseConnection, req: &UpdateTaskRequest) -> Re
             task_id: Set(req.task_id),
             depends_on_id: Set(*dep),
         })
-        .exec(db)
+        .exec(&txn)
         .await
         .map_err(|e| ErrorInternalServerError(format!("couldn't create dependancy: {}", e)))?;
     }
@@ -344,12 +577,12 @@ pub async fn update_task(db: &DatabaseConnection, req: &UpdateTaskRequest) -> Re
                     .add(dependency::Column::TaskId.eq(req.task_id))
                     .add(dependency::Column::DependsOnId.eq(*dep)),
             )
-            .one(db)
+            .one(&txn)
             .await
             .map_err(|e| ErrorInternalServerError(format!("couldn't fetch dependancy: {}", e)))?
             .ok_or("dependency couldn't be found")
             .map_err(ErrorInternalServerError)?
-            .delete(db)
+            .delete(&txn)
             .await
             .map_err(|e| ErrorInternalServerError(format!("couldn't delete dependancy: {}", e)))?;
     }
@@ -359,10 +592,168 @@ pub async fn update_task(db: &DatabaseConnection, req: &UpdateTaskRequest) -> Re
     for _script in req.scripts_to_remove.iter() {
         //TODO: implement scripts
     }*/
+    let old_parent = match &req.parent {
+        Some(change) => set_parent(&txn, req.task_id, change).await?,
+        None => None,
+    };
+    if req.checked == Some(true) && !was_completed {
+        spawn_next_instance(&txn, req.task_id).await?;
+    }
+    let completion_changed = req.checked.is_some_and(|checked| checked != was_completed);
+    let new_parent = match req.parent {
+        Some(ParentChange::Set { parent, .. }) => Some(parent),
+        _ => None,
+    };
+    if completion_changed || new_parent.is_some() {
+        let parent = subtask::Entity::find_by_id(req.task_id)
+            .one(&txn)
+            .await
+            .map_err(|e| ErrorInternalServerError(format!("couldn't fetch parent: {}", e)))?
+            .map(|link| link.parent_id);
+        cascade_completion(&txn, parent).await?;
+    }
+    // the parent the task left may have only completed subtasks now
+    if old_parent != new_parent {
+        cascade_completion(&txn, old_parent).await?;
+    }
+    txn.commit().await.map_err(error)?;
 
     info!("update_task, updated task: {:?}", req.task_id);
     Ok(req.task_id)
 }
+
+/// move a task to a position among the subtasks of a new parent, or make it top-level, in the transaction
+/// `txn`. The subtasks it leaves and joins are renumbered to keep their order. Returns the parent the task left
+async fn set_parent<C: ConnectionTrait>(
+    txn: &C,
+    task_id: TaskID,
+    change: &ParentChange,
+) -> Result<Option<TaskID>> {
+    let error = |e: DbErr| ErrorInternalServerError(format!("couldn't change parent: {}", e));
+    let old_parent = subtask::Entity::find_by_id(task_id)
+        .one(txn)
+        .await
+        .map_err(error)?;
+    if let Some(link) = &old_parent {
+        subtask::Entity::delete_by_id(task_id)
+            .exec(txn)
+            .await
+            .map_err(error)?;
+        subtask::Entity::update_many()
+            .col_expr(
+                subtask::Column::Position,
+                Expr::col(subtask::Column::Position).sub(1),
+            )
+            .filter(subtask::Column::ParentId.eq(link.parent_id))
+            .filter(subtask::Column::Position.gt(link.position))
+            .exec(txn)
+            .await
+            .map_err(error)?;
+    }
+    let old_parent = old_parent.map(|link| link.parent_id);
+    let ParentChange::Set { parent, position } = change else {
+        return Ok(old_parent);
+    };
+    if task::Entity::find_by_id(*parent)
+        .one(txn)
+        .await
+        .map_err(error)?
+        .is_none()
+    {
+        return Err(ErrorNotFound(format!("no task with id {}", parent)));
+    }
+    let siblings = subtask::Entity::find()
+        .filter(subtask::Column::ParentId.eq(*parent))
+        .count(txn)
+        .await
+        .map_err(error)?;
+    let position = (*position as u64).min(siblings) as i32;
+    subtask::Entity::update_many()
+        .col_expr(
+            subtask::Column::Position,
+            Expr::col(subtask::Column::Position).add(1),
+        )
+        .filter(subtask::Column::ParentId.eq(*parent))
+        .filter(subtask::Column::Position.gte(position))
+        .exec(txn)
+        .await
+        .map_err(error)?;
+    // the database rejects parents that are the task or one of its subtasks
+    let inserted = subtask::Entity::insert(subtask::ActiveModel {
+        task_id: Set(task_id),
+        parent_id: Set(*parent),
+        position: Set(position),
+    })
+    .exec_without_returning(txn)
+    .await;
+    match inserted {
+        Err(e) if e.to_string().contains("Subtask cycle detected") => {
+            return Err(ErrorBadRequest(format!(
+                "task {} can't be a subtask of itself or of its subtasks",
+                task_id
+            )));
+        }
+        inserted => inserted.map_err(error)?,
+    };
+    Ok(old_parent)
+}
+
+/// complete a parent once all of its subtasks are completed, and reopen it once one of them isn't.
+/// Changes cascade up the hierarchy from `parent_id`, and parents completed this way spawn their next
+/// instance if they recur. Tasks that have no subtasks are left as they are.
+/// Each parent is locked before its subtasks are counted, so subtasks completed at the same time in
+/// other transactions are counted after they commit, and the last one to complete sees no open sibling
+async fn cascade_completion<C: ConnectionTrait + TransactionTrait>(
+    db: &C,
+    parent_id: Option<TaskID>,
+) -> Result<()> {
+    let error = |e: DbErr| ErrorInternalServerError(format!("couldn't cascade completion: {}", e));
+    let mut parent_id = parent_id;
+    while let Some(id) = parent_id {
+        let parent = task::Entity::find_by_id(id)
+            .lock_exclusive()
+            .one(db)
+            .await
+            .map_err(error)?
+            .ok_or("no task by id")
+            .map_err(ErrorInternalServerError)?;
+        let subtasks = task::Entity::find().filter(
+            task::Column::Id.in_subquery(
+                Query::select()
+                    .column(subtask::Column::TaskId)
+                    .from(subtask::Entity)
+                    .and_where(subtask::Column::ParentId.eq(id))
+                    .to_owned(),
+            ),
+        );
+        let open = subtasks
+            .clone()
+            .filter(task::Column::Completed.eq(false))
+            .count(db)
+            .await
+            .map_err(error)?;
+        if open == 0 && subtasks.count(db).await.map_err(error)? == 0 {
+            break;
+        }
+        let completed = open == 0;
+        if parent.completed == completed {
+            break;
+        }
+        let mut parent: task::ActiveModel = parent.into();
+        parent.completed = Set(completed);
+        parent.update(db).await.map_err(error)?;
+        if completed {
+            spawn_next_instance(db, id).await?;
+        }
+        parent_id = subtask::Entity::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(error)?
+            .map(|link| link.parent_id);
+    }
+    Ok(())
+}
+
 #[put("/task")]
 async fn update_task_request(
     data: web::Data<DatabaseConnection>,
@@ -394,183 +785,1571 @@ async fn update_tasks_request(
     Ok(web::Json(res))
 }
 
-async fn delete_task(
-    db: &DatabaseConnection,
-    req: &DeleteTaskRequest,
-) -> Result<web::Json<DeleteTaskResponse>> {
-    info!("delete_task, req: {:?}", req);
-    task::Entity::find_by_id(req.task_id)
+/// typed property tables, copied to the next instance of a recurring task
+const TYPED_PROPERTY_TABLES: [&str; 11] = [
+    "task_string_property",
+    "task_num_property",
+    "task_int_property",
+    "task_duration_property",
+    "task_date_property",
+    "task_bool_property",
+    "task_select_property",
+    "task_multiselect_property",
+    "task_ref_property",
+    "task_refs_property",
+    "task_user_property",
+];
+
+/// copy the properties and dependencies of a recurring task that was just completed to a new instance,
+/// due at the next date of its rule. Nothing happens if the task doesn't recur, or already has a next
+/// instance from an earlier completion. The instance is spawned in one transaction, nested in the caller's
+/// if it has one, which is rolled back if a concurrent completion spawned one first.
+async fn spawn_next_instance<C: ConnectionTrait + TransactionTrait>(
+    db: &C,
+    task_id: TaskID,
+) -> Result<Option<TaskID>> {
+    let error = |e: DbErr| ErrorInternalServerError(format!("couldn't spawn next instance: {}", e));
+    let recurrence = match task_recurrence::Entity::find_by_id(task_id)
         .one(db)
         .await
-        .map_err(|e| ErrorInternalServerError(format!("couldn't find task: {}", e)))?
-        .ok_or("couldn't find task by id")
-        .map_err(ErrorInternalServerError)?
-        .delete(db)
+        .map_err(error)?
+        .and_then(|model| model.rule)
+    {
+        Some(rule) => rule
+            .parse::<Recurrence>()
+            .map_err(ErrorInternalServerError)?,
+        None => return Ok(None),
+    };
+    let spawned = task_recurrence::Entity::find()
+        .filter(task_recurrence::Column::PreviousId.eq(task_id))
+        .count(db)
         .await
-        .map_err(|e| ErrorInternalServerError(format!("couldn't delete task: {}", e)))?;
-    info!("delete_task, deleted task: {:?}", req.task_id);
-    Ok(web::Json(req.req_id))
+        .map_err(error)?;
+    if spawned > 0 {
+        return Ok(None);
+    }
+    let task = task::Entity::find_by_id(task_id)
+        .one(db)
+        .await
+        .map_err(error)?
+        .ok_or("no task by id")
+        .map_err(ErrorInternalServerError)?;
+    let now = chrono::Utc::now().naive_utc();
+    let due = match get_property_or_err(db, &DUE_PROPERTY.to_owned(), task_id).await {
+        Ok(Some(TaskPropVariant::Date(due))) => due,
+        _ => now,
+    };
+    let schema = get_schema(db).await?;
+    let definition = schema
+        .iter()
+        .find(|definition| definition.name == DUE_PROPERTY);
+
+    let txn = db.begin().await.map_err(error)?;
+    let next_id = task::Entity::insert(task::ActiveModel {
+        id: NotSet,
+        title: Set(task.title),
+        completed: Set(false),
+        last_edited: Set(now),
+    })
+    .exec(&txn)
+    .await
+    .map_err(error)?
+    .last_insert_id;
+    let copy = |sql: String| {
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            [next_id.into(), task_id.into()],
+        ))
+    };
+    copy(
+        r#"INSERT INTO "task_property" ("task_id", "name", "type")
+        SELECT $1, "name", "type" FROM "task_property" WHERE "task_id" = $2"#
+            .to_owned(),
+    )
+    .await
+    .map_err(error)?;
+    for table in TYPED_PROPERTY_TABLES {
+        copy(format!(
+            r#"INSERT INTO "{table}" ("task_id", "task_property_name", "value")
+            SELECT $1, "task_property_name", "value" FROM "{table}" WHERE "task_id" = $2"#
+        ))
+        .await
+        .map_err(error)?;
+    }
+    copy(
+        r#"INSERT INTO "dependency" ("task_id", "depends_on_id")
+        SELECT $1, "depends_on_id" FROM "dependency" WHERE "task_id" = $2"#
+            .to_owned(),
+    )
+    .await
+    .map_err(error)?;
+
+    let next_due = TaskProp {
+        name: DUE_PROPERTY.to_owned(),
+        value: TaskPropVariant::Date(recurrence.next(due, now)),
+    };
+    write_property(&txn, next_id, &next_due, definition).await?;
+    // monthly rules keep the day of the instance the series started from
+    let spawned = task_recurrence::Entity::insert(task_recurrence::ActiveModel {
+        task_id: Set(next_id),
+        rule: Set(Some(recurrence.anchor(due).to_string())),
+        previous_id: Set(Some(task_id)),
+    })
+    .exec_without_returning(&txn)
+    .await;
+    match spawned {
+        Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+            info!(
+                "spawn_next_instance, {} already spawned an instance",
+                task_id
+            );
+            return Ok(None);
+        }
+        spawned => spawned.map_err(error)?,
+    };
+    txn.commit().await.map_err(error)?;
+    info!("spawn_next_instance, spawned {} from {}", next_id, task_id);
+    Ok(Some(next_id))
 }
 
-#[delete("/task")]
-async fn delete_task_request(
+/// make a task recur, or stop it recurring. Tasks that stopped keep their place in the history.
+pub async fn set_recurrence(
+    db: &DatabaseConnection,
+    task_id: TaskID,
+    recurrence: Option<&Recurrence>,
+) -> Result<()> {
+    task::Entity::find_by_id(task_id)
+        .one(db)
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't fetch task: {}", e)))?
+        .ok_or_else(|| ErrorNotFound(format!("no task with id {}", task_id)))?;
+    task_recurrence::Entity::insert(task_recurrence::ActiveModel {
+        task_id: Set(task_id),
+        rule: Set(recurrence.map(Recurrence::to_string)),
+        previous_id: Set(None),
+    })
+    .on_conflict(
+        OnConflict::column(task_recurrence::Column::TaskId)
+            .update_column(task_recurrence::Column::Rule)
+            .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await
+    .map_err(|e| ErrorInternalServerError(format!("couldn't set recurrence: {}", e)))?;
+    Ok(())
+}
+
+/// put /recurrence endpoint sets the recurrence rule of a task
+#[put("/recurrence")]
+async fn set_recurrence_request(
     data: web::Data<DatabaseConnection>,
-    req: web::Json<DeleteTaskRequest>,
-) -> Result<web::Json<DeleteTaskResponse>> {
-    info!("delete_task_request, req: {:?}", req);
-    delete_task(&data, &req).await
+    req: web::Json<SetRecurrenceRequest>,
+) -> Result<web::Json<SetRecurrenceResponse>> {
+    info!("set_recurrence_request, req: {:?}", req);
+    set_recurrence(&data, req.task_id, req.recurrence.as_ref()).await?;
+    Ok(web::Json(req.req_id))
 }
-#[delete("/tasks")]
-async fn delete_tasks_request(
+
+// earlier instances of $1, most recent first
+const RECURRENCE_HISTORY: &str = r#"
+WITH RECURSIVE "instance" ("task_id", "previous_id", "depth") AS (
+    SELECT "task_id", "previous_id", 0 FROM "task_recurrence" WHERE "task_id" = $1
+    UNION ALL
+    SELECT "task_recurrence"."task_id", "task_recurrence"."previous_id", "instance"."depth" + 1
+    FROM "task_recurrence" JOIN "instance" ON "task_recurrence"."task_id" = "instance"."previous_id"
+)
+SELECT "task_id" FROM "instance" WHERE "depth" > 0 ORDER BY "depth"
+"#;
+
+#[derive(Debug, FromQueryResult)]
+struct InstanceRow {
+    task_id: i32,
+}
+
+/// get the recurrence rule of a task, along with the instance after it and the ones before it
+pub async fn get_recurrence(
+    db: &DatabaseConnection,
+    req: &GetRecurrenceRequest,
+) -> Result<GetRecurrenceResponse> {
+    let error = |e: DbErr| ErrorInternalServerError(format!("couldn't fetch recurrence: {}", e));
+    let recurrence = task_recurrence::Entity::find_by_id(req.task_id)
+        .one(db)
+        .await
+        .map_err(error)?
+        .and_then(|model| model.rule)
+        .map(|rule| rule.parse::<Recurrence>())
+        .transpose()
+        .map_err(ErrorInternalServerError)?;
+    let next_instance = task_recurrence::Entity::find()
+        .filter(task_recurrence::Column::PreviousId.eq(req.task_id))
+        .one(db)
+        .await
+        .map_err(error)?
+        .map(|model| model.task_id);
+    let history = InstanceRow::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        RECURRENCE_HISTORY,
+        [req.task_id.into()],
+    ))
+    .all(db)
+    .await
+    .map_err(error)?
+    .into_iter()
+    .map(|row| row.task_id)
+    .collect();
+    Ok(GetRecurrenceResponse {
+        recurrence,
+        next_instance,
+        history,
+        req_id: req.req_id,
+    })
+}
+
+/// get /recurrence endpoint returns the rule and history of a recurring task
+#[get("/recurrence")]
+async fn get_recurrence_request(
     data: web::Data<DatabaseConnection>,
-    req: web::Json<DeleteTasksRequest>,
-) -> Result<web::Json<DeleteTasksResponse>> {
-    info!("delete_tasks_request, req: {:?}", req);
-    let mut res: Vec<u64> = vec![];
-    for task in req.iter() {
-        delete_task(&data, task).await?;
-        res.push(task.req_id);
-    }
-    info!("delete_tasks_request, completed res: {:?}", res);
-    Ok(web::Json(res))
+    req: web::Json<GetRecurrenceRequest>,
+) -> Result<web::Json<GetRecurrenceResponse>> {
+    Ok(web::Json(get_recurrence(&data, &req).await?))
 }
 
-fn construct_filter(filter: &Filter) -> actix_web::Result<Condition> {
+/// put /reminder endpoint sets or removes the reminder on a date property of a task
+#[put("/reminder")]
+async fn set_reminder_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<SetReminderRequest>,
+) -> Result<web::Json<SetReminderResponse>> {
+    info!("set_reminder_request, req: {:?}", req);
+    let db = data.as_ref();
+    let error = |e: DbErr| ErrorInternalServerError(format!("couldn't set reminder: {}", e));
+    let property = task_property::Entity::find_by_id((req.task_id, req.property.clone()))
+        .one(db)
+        .await
+        .map_err(error)?;
+    match property {
+        Some(property) if property.typ == TaskPropType::Date.as_str() => {}
+        _ => {
+            return Err(ErrorBadRequest(format!(
+                "task {} has no date property {}",
+                req.task_id, req.property
+            )))
+        }
+    }
+    match req.before {
+        Some(before) => {
+            reminder::Entity::insert(reminder::ActiveModel {
+                task_id: Set(req.task_id),
+                property_name: Set(req.property.clone()),
+                before: Set(before.0),
+            })
+            .on_conflict(
+                OnConflict::columns([reminder::Column::TaskId, reminder::Column::PropertyName])
+                    .update_column(reminder::Column::Before)
+                    .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await
+            .map_err(error)?;
+        }
+        None => {
+            reminder::Entity::delete_by_id((req.task_id, req.property.clone()))
+                .exec(db)
+                .await
+                .map_err(error)?;
+        }
+    }
+    Ok(web::Json(req.req_id))
+}
+
+/// put /schedule endpoint schedules or unschedules runs of a script
+#[put("/schedule")]
+async fn schedule_script_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<ScheduleScriptRequest>,
+) -> Result<web::Json<ScheduleScriptResponse>> {
+    info!("schedule_script_request, req: {:?}", req);
+    let db = data.as_ref();
+    let error = |e: DbErr| ErrorInternalServerError(format!("couldn't schedule script: {}", e));
+    match req.at {
+        Some(at) => {
+            script_schedule::Entity::insert(script_schedule::ActiveModel {
+                script_id: Set(req.script_id),
+                next_run: Set(at),
+                rule: Set(req.recurrence.as_ref().map(Recurrence::to_string)),
+            })
+            .on_conflict(
+                OnConflict::column(script_schedule::Column::ScriptId)
+                    .update_columns([
+                        script_schedule::Column::NextRun,
+                        script_schedule::Column::Rule,
+                    ])
+                    .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await
+            .map_err(error)?;
+        }
+        None => {
+            script_schedule::Entity::delete_by_id(req.script_id)
+                .exec(db)
+                .await
+                .map_err(error)?;
+        }
+    }
+    Ok(web::Json(req.req_id))
+}
+
+/// get notifications fired by the scheduler after `req.after`, or the latest ones
+pub async fn get_notifications(
+    db: &DatabaseConnection,
+    req: &GetNotificationsRequest,
+) -> Result<GetNotificationsResponse> {
+    let mut select = notification::Entity::find();
+    if let Some(after) = req.after {
+        select = select
+            .filter(notification::Column::Id.gt(after))
+            .order_by_asc(notification::Column::Id);
+    } else {
+        select = select.order_by_desc(notification::Column::Id);
+    }
+    if let Some(limit) = req.limit {
+        select = select.limit(limit);
+    }
+    let mut models = select
+        .all(db)
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't fetch notifications: {}", e)))?;
+    if req.after.is_none() {
+        models.reverse();
+    }
+    let notifications = models
+        .into_iter()
+        .map(|model| {
+            Ok(Notification {
+                id: model.id,
+                task_id: model.task_id,
+                kind: serde_json::from_str(&model.kind).map_err(ErrorInternalServerError)?,
+                fire_at: model.fire_at,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(GetNotificationsResponse {
+        notifications,
+        req_id: req.req_id,
+    })
+}
+
+/// get /notifications endpoint, polled by clients for events fired by the scheduler
+#[get("/notifications")]
+async fn get_notifications_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<GetNotificationsRequest>,
+) -> Result<web::Json<GetNotificationsResponse>> {
+    Ok(web::Json(get_notifications(&data, &req).await?))
+}
+
+fn comment_from_model(model: comment::Model) -> Comment {
+    Comment {
+        id: model.id,
+        task_id: model.task_id,
+        author: model.author,
+        body: model.body,
+        created_at: model.created_at,
+        edited_at: model.edited_at,
+    }
+}
+
+/// get /comments endpoint returns the thread of a task, oldest comment first
+#[get("/comments")]
+async fn get_comments_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<GetCommentsRequest>,
+) -> Result<web::Json<GetCommentsResponse>> {
+    info!("get_comments_request, req: {:?}", req);
+    let comments = comment::Entity::find()
+        .filter(comment::Column::TaskId.eq(req.task_id))
+        .order_by_asc(comment::Column::Id)
+        .all(data.as_ref())
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't fetch comments: {}", e)))?;
+    Ok(web::Json(GetCommentsResponse {
+        task_id: req.task_id,
+        comments: comments.into_iter().map(comment_from_model).collect(),
+        req_id: req.req_id,
+    }))
+}
+
+/// add a comment to the thread of a task, and fire a notification so polling clients pick it up.
+/// Both are written in one transaction. The author is the one the client names, there are no accounts to check it
+pub async fn create_comment(
+    db: &DatabaseConnection,
+    req: &CreateCommentRequest,
+) -> Result<CreateCommentResponse> {
+    let error = |e: DbErr| ErrorInternalServerError(format!("couldn't post comment: {}", e));
+    if req.author.trim().is_empty() || req.body.trim().is_empty() {
+        return Err(ErrorBadRequest("comments need an author and a body"));
+    }
+    task::Entity::find_by_id(req.task_id)
+        .one(db)
+        .await
+        .map_err(error)?
+        .ok_or_else(|| ErrorNotFound(format!("task {} doesn't exist", req.task_id)))?;
+    let txn = db.begin().await.map_err(error)?;
+    let model = comment::ActiveModel {
+        id: NotSet,
+        task_id: Set(req.task_id),
+        author: Set(req.author.clone()),
+        body: Set(req.body.clone()),
+        created_at: Set(chrono::Utc::now().naive_utc()),
+        edited_at: Set(None),
+    }
+    .insert(&txn)
+    .await
+    .map_err(error)?;
+    let kind = NotificationKind::Comment {
+        comment_id: model.id,
+        author: model.author.clone(),
+    };
+    notification::Entity::insert(notification::ActiveModel {
+        id: NotSet,
+        task_id: Set(Some(model.task_id)),
+        kind: Set(serde_json::to_string(&kind).expect("notification kinds serialize")),
+        fire_at: Set(model.created_at),
+    })
+    .exec_without_returning(&txn)
+    .await
+    .map_err(error)?;
+    txn.commit().await.map_err(error)?;
+    Ok(CreateCommentResponse {
+        comment: comment_from_model(model),
+        req_id: req.req_id,
+    })
+}
+
+/// post /comment endpoint adds a comment to the thread of a task
+#[post("/comment")]
+async fn create_comment_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<CreateCommentRequest>,
+) -> Result<web::Json<CreateCommentResponse>> {
+    info!("create_comment_request, req: {:?}", req);
+    Ok(web::Json(create_comment(&data, &req).await?))
+}
+
+/// put /comment endpoint replaces the text of a comment
+#[put("/comment")]
+async fn update_comment_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<UpdateCommentRequest>,
+) -> Result<web::Json<UpdateCommentResponse>> {
+    info!("update_comment_request, req: {:?}", req);
+    let db = data.as_ref();
+    let error = |e: DbErr| ErrorInternalServerError(format!("couldn't edit comment: {}", e));
+    if req.body.trim().is_empty() {
+        return Err(ErrorBadRequest("comments need a body"));
+    }
+    let mut model: comment::ActiveModel = comment::Entity::find_by_id(req.comment_id)
+        .one(db)
+        .await
+        .map_err(error)?
+        .ok_or_else(|| ErrorNotFound(format!("comment {} doesn't exist", req.comment_id)))?
+        .into();
+    model.body = Set(req.body.clone());
+    model.edited_at = Set(Some(chrono::Utc::now().naive_utc()));
+    let model = model.update(db).await.map_err(error)?;
+    Ok(web::Json(UpdateCommentResponse {
+        comment: comment_from_model(model),
+        req_id: req.req_id,
+    }))
+}
+
+/// delete /comment endpoint removes a comment from its thread
+#[delete("/comment")]
+async fn delete_comment_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<DeleteCommentRequest>,
+) -> Result<web::Json<DeleteCommentResponse>> {
+    info!("delete_comment_request, req: {:?}", req);
+    let db = data.as_ref();
+    let error = |e: DbErr| ErrorInternalServerError(format!("couldn't delete comment: {}", e));
+    let model = comment::Entity::find_by_id(req.comment_id)
+        .one(db)
+        .await
+        .map_err(error)?
+        .ok_or_else(|| ErrorNotFound(format!("comment {} doesn't exist", req.comment_id)))?;
+    let task_id = model.task_id;
+    model.delete(db).await.map_err(error)?;
+    Ok(web::Json(DeleteCommentResponse {
+        task_id,
+        comment_id: req.comment_id,
+        req_id: req.req_id,
+    }))
+}
+
+fn attachment_from_model(model: attachment::Model) -> Attachment {
+    Attachment {
+        id: model.id,
+        task_id: model.task_id,
+        name: model.name,
+        mime: model.mime,
+        size: model.size as u64,
+        hash: model.hash,
+        uploaded_at: model.uploaded_at,
+    }
+}
+
+/// hold a lock on some content until the transaction ends, so attaching the content and removing its
+/// unused file can't interleave and leave an attachment without its blob
+async fn lock_blob<C: ConnectionTrait>(db: &C, hash: &str) -> std::result::Result<(), DbErr> {
+    // the first 16 digits of the hash are key enough
+    let key = hash
+        .get(..16)
+        .and_then(|digits| u64::from_str_radix(digits, 16).ok())
+        .unwrap_or_default() as i64;
+    db.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "SELECT pg_advisory_xact_lock($1)",
+        [key.into()],
+    ))
+    .await?;
+    Ok(())
+}
+
+/// delete the content of attachments once their deletion is committed, unless another attachment still
+/// has it. The content is locked, in hash order so cleanups can't deadlock, until its files are gone.
+/// A file left behind is only wasted space, while one removed too early breaks its attachments, so
+/// failures are logged rather than undoing the deletion
+async fn remove_unshared_blobs(
+    db: &DatabaseConnection,
+    blobs: &BlobStore,
+    mut hashes: Vec<String>,
+) {
+    hashes.sort_unstable();
+    hashes.dedup();
+    let locked = async {
+        let txn = db.begin().await?;
+        for hash in &hashes {
+            lock_blob(&txn, hash).await?;
+        }
+        let shared = attachment::Entity::find()
+            .filter(attachment::Column::Hash.is_in(hashes.clone()))
+            .all(&txn)
+            .await?;
+        Ok::<_, DbErr>((txn, shared))
+    };
+    let (txn, shared) = match locked.await {
+        Ok(locked) => locked,
+        Err(e) => {
+            warn!("couldn't check which attachment contents are unused: {}", e);
+            return;
+        }
+    };
+    for hash in hashes {
+        if shared.iter().any(|attachment| attachment.hash == hash) {
+            continue;
+        }
+        if let Err(e) = blobs.remove(&hash).await {
+            warn!("couldn't delete attachment content {}: {}", hash, e);
+        }
+    }
+    // nothing was written, committing only releases the locks
+    if let Err(e) = txn.commit().await {
+        warn!("couldn't release attachment contents: {}", e);
+    }
+}
+
+/// store content as a file attached to a task
+pub async fn upload_attachment(
+    db: &DatabaseConnection,
+    blobs: &BlobStore,
+    query: &UploadAttachmentQuery,
+    content: &[u8],
+) -> Result<Attachment> {
+    let error = |e: DbErr| ErrorInternalServerError(format!("couldn't attach file: {}", e));
+    // clients may send a whole path, only its last component names the file
+    let name = query
+        .name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .trim();
+    if name.is_empty() {
+        return Err(ErrorBadRequest("attachments need a file name"));
+    }
+    if content.len() as u64 > blobs.max_size {
+        return Err(ErrorPayloadTooLarge(format!(
+            "attachments can be at most {} bytes",
+            blobs.max_size
+        )));
+    }
+    task::Entity::find_by_id(query.task_id)
+        .one(db)
+        .await
+        .map_err(error)?
+        .ok_or_else(|| ErrorNotFound(format!("task {} doesn't exist", query.task_id)))?;
+    let store_error =
+        |e: std::io::Error| ErrorInternalServerError(format!("couldn't store file: {}", e));
+    let hash = BlobStore::hash(content);
+    let txn = db.begin().await.map_err(error)?;
+    lock_blob(&txn, &hash).await.map_err(error)?;
+    // content stored before belongs to other attachments, only a file written here is ours to remove
+    let stored = blobs.contains(&hash).await.map_err(store_error)?;
+    blobs.put(content).await.map_err(store_error)?;
+    let inserted = attachment::ActiveModel {
+        id: NotSet,
+        task_id: Set(query.task_id),
+        name: Set(name.to_owned()),
+        mime: Set(detect_mime(name, content).to_owned()),
+        size: Set(content.len() as i64),
+        hash: Set(hash.clone()),
+        uploaded_at: Set(chrono::Utc::now().naive_utc()),
+    }
+    .insert(&txn)
+    .await;
+    let model = match inserted {
+        Err(e) => {
+            // still locked, so no other upload is relying on the file yet
+            if !stored {
+                if let Err(e) = blobs.remove(&hash).await {
+                    warn!("couldn't delete unattached content {}: {}", hash, e);
+                }
+            }
+            return Err(error(e));
+        }
+        Ok(model) => model,
+    };
+    txn.commit().await.map_err(error)?;
+    Ok(attachment_from_model(model))
+}
+
+/// post /attachment endpoint attaches the request body to a task as a file
+#[post("/attachment")]
+async fn upload_attachment_request(
+    data: web::Data<DatabaseConnection>,
+    blobs: web::Data<BlobStore>,
+    query: web::Query<UploadAttachmentQuery>,
+    body: web::Bytes,
+) -> Result<web::Json<UploadAttachmentResponse>> {
+    info!(
+        "upload_attachment_request, query: {:?}, size: {}",
+        query,
+        body.len()
+    );
+    Ok(web::Json(
+        upload_attachment(&data, &blobs, &query, &body).await?,
+    ))
+}
+
+/// get /attachments endpoint lists the files attached to a task, oldest first
+#[get("/attachments")]
+async fn get_attachments_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<GetAttachmentsRequest>,
+) -> Result<web::Json<GetAttachmentsResponse>> {
+    info!("get_attachments_request, req: {:?}", req);
+    let attachments = attachment::Entity::find()
+        .filter(attachment::Column::TaskId.eq(req.task_id))
+        .order_by_asc(attachment::Column::Id)
+        .all(data.as_ref())
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't fetch attachments: {}", e)))?;
+    Ok(web::Json(GetAttachmentsResponse {
+        task_id: req.task_id,
+        attachments: attachments.into_iter().map(attachment_from_model).collect(),
+        req_id: req.req_id,
+    }))
+}
+
+/// get /attachment endpoint responds with the content of a file, typed by its detected media type
+#[get("/attachment")]
+async fn download_attachment_request(
+    data: web::Data<DatabaseConnection>,
+    blobs: web::Data<BlobStore>,
+    query: web::Query<DownloadAttachmentQuery>,
+) -> Result<HttpResponse> {
+    info!("download_attachment_request, query: {:?}", query);
+    let model = attachment::Entity::find_by_id(query.attachment_id)
+        .one(data.as_ref())
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't fetch attachment: {}", e)))?
+        .ok_or_else(|| {
+            ErrorNotFound(format!("attachment {} doesn't exist", query.attachment_id))
+        })?;
+    let content = blobs
+        .get(&model.hash)
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't read file: {}", e)))?;
+    Ok(HttpResponse::Ok()
+        .content_type(model.mime)
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(model.name)],
+        })
+        .body(content))
+}
+
+/// delete /attachment endpoint removes a file from its task,
+/// its content is deleted once no attachment refers to it
+#[delete("/attachment")]
+async fn delete_attachment_request(
+    data: web::Data<DatabaseConnection>,
+    blobs: web::Data<BlobStore>,
+    req: web::Json<DeleteAttachmentRequest>,
+) -> Result<web::Json<DeleteAttachmentResponse>> {
+    info!("delete_attachment_request, req: {:?}", req);
+    let db = data.as_ref();
+    let error = |e: DbErr| ErrorInternalServerError(format!("couldn't delete attachment: {}", e));
+    let model = attachment::Entity::find_by_id(req.attachment_id)
+        .one(db)
+        .await
+        .map_err(error)?
+        .ok_or_else(|| ErrorNotFound(format!("attachment {} doesn't exist", req.attachment_id)))?;
+    let hash = model.hash.clone();
+    model.delete(db).await.map_err(error)?;
+    remove_unshared_blobs(db, &blobs, vec![hash]).await;
+    Ok(web::Json(req.req_id))
+}
+
+/// a sibling created by someone else since the tag was checked is a conflict, like one found by
+/// `check_tag`
+fn tag_error(action: &str, name: &str, e: DbErr) -> actix_web::Error {
+    match e.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => {
+            ErrorConflict(format!("a sibling tag is already named {}", name.trim()))
+        }
+        _ => ErrorInternalServerError(format!("couldn't {} tag: {}", action, e)),
+    }
+}
+
+fn tag_from_model(model: tag::Model) -> Tag {
+    Tag {
+        id: model.id,
+        name: model.name,
+        parent: model.parent_id,
+        color: model.color,
+    }
+}
+
+/// reject tags without a name or with a malformed color, under a parent that doesn't exist,
+/// or named like one of their siblings. `tag_id` is the tag being changed, if it exists already
+async fn check_tag(
+    db: &DatabaseConnection,
+    tag_id: Option<TagID>,
+    name: &str,
+    parent: Option<TagID>,
+    color: &str,
+) -> Result<()> {
+    let error = |e: DbErr| ErrorInternalServerError(format!("couldn't check tag: {}", e));
+    if name.trim().is_empty() {
+        return Err(ErrorBadRequest("tags need a name"));
+    }
+    if parse_color(color).is_none() {
+        return Err(ErrorBadRequest(format!(
+            "tag color {:?} isn't a \"#rrggbb\" hex code",
+            color
+        )));
+    }
+    if let Some(parent) = parent {
+        tag::Entity::find_by_id(parent)
+            .one(db)
+            .await
+            .map_err(error)?
+            .ok_or_else(|| ErrorNotFound(format!("tag {} doesn't exist", parent)))?;
+    }
+    let mut siblings = tag::Entity::find().filter(tag::Column::Name.eq(name.trim()));
+    siblings = match parent {
+        Some(parent) => siblings.filter(tag::Column::ParentId.eq(parent)),
+        None => siblings.filter(tag::Column::ParentId.is_null()),
+    };
+    if let Some(tag_id) = tag_id {
+        siblings = siblings.filter(tag::Column::Id.ne(tag_id));
+    }
+    if siblings.one(db).await.map_err(error)?.is_some() {
+        return Err(ErrorConflict(format!(
+            "a sibling tag is already named {}",
+            name.trim()
+        )));
+    }
+    Ok(())
+}
+
+/// get /tags endpoint returns every tag of the workspace
+#[get("/tags")]
+async fn get_tags_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<GetTagsRequest>,
+) -> Result<web::Json<GetTagsResponse>> {
+    info!("get_tags_request, req: {:?}", req);
+    let tags = tag::Entity::find()
+        .order_by_asc(tag::Column::Id)
+        .all(data.as_ref())
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't fetch tags: {}", e)))?;
+    Ok(web::Json(GetTagsResponse {
+        tags: tags.into_iter().map(tag_from_model).collect(),
+        req_id: *req,
+    }))
+}
+
+/// post /tag endpoint creates a tag, nested under another one if it has a parent
+#[post("/tag")]
+async fn create_tag_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<CreateTagRequest>,
+) -> Result<web::Json<CreateTagResponse>> {
+    info!("create_tag_request, req: {:?}", req);
+    let db = data.as_ref();
+    check_tag(db, None, &req.name, req.parent, &req.color).await?;
+    let model = tag::ActiveModel {
+        id: NotSet,
+        name: Set(req.name.trim().to_owned()),
+        parent_id: Set(req.parent),
+        color: Set(req.color.to_ascii_lowercase()),
+    }
+    .insert(db)
+    .await
+    .map_err(|e| tag_error("create", &req.name, e))?;
+    Ok(web::Json(CreateTagResponse {
+        tag: tag_from_model(model),
+        req_id: req.req_id,
+    }))
+}
+
+/// put /tag endpoint renames, moves or recolors a tag
+#[put("/tag")]
+async fn update_tag_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<UpdateTagRequest>,
+) -> Result<web::Json<UpdateTagResponse>> {
+    info!("update_tag_request, req: {:?}", req);
+    let db = data.as_ref();
+    let error = |e: DbErr| ErrorInternalServerError(format!("couldn't update tag: {}", e));
+    let mut model: tag::ActiveModel = tag::Entity::find_by_id(req.tag_id)
+        .one(db)
+        .await
+        .map_err(error)?
+        .ok_or_else(|| ErrorNotFound(format!("tag {} doesn't exist", req.tag_id)))?
+        .into();
+    if req.parent == Some(req.tag_id) {
+        return Err(ErrorBadRequest("a tag can't be nested under itself"));
+    }
+    check_tag(db, Some(req.tag_id), &req.name, req.parent, &req.color).await?;
+    model.name = Set(req.name.trim().to_owned());
+    model.parent_id = Set(req.parent);
+    model.color = Set(req.color.to_ascii_lowercase());
+    // the database rejects parents that are nested under the tag
+    let model = match model.update(db).await {
+        Err(e) if e.to_string().contains("Tag cycle detected") => {
+            return Err(ErrorBadRequest(format!(
+                "tag {} can't be nested under a tag nested under it",
+                req.tag_id
+            )));
+        }
+        updated => updated.map_err(|e| tag_error("update", &req.name, e))?,
+    };
+    Ok(web::Json(UpdateTagResponse {
+        tag: tag_from_model(model),
+        req_id: req.req_id,
+    }))
+}
+
+/// delete /tag endpoint deletes a tag and the tags nested under it, untagging their tasks
+#[delete("/tag")]
+async fn delete_tag_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<DeleteTagRequest>,
+) -> Result<web::Json<DeleteTagResponse>> {
+    info!("delete_tag_request, req: {:?}", req);
+    let db = data.as_ref();
+    let error = |e: DbErr| ErrorInternalServerError(format!("couldn't delete tag: {}", e));
+    tag::Entity::find_by_id(req.tag_id)
+        .one(db)
+        .await
+        .map_err(error)?
+        .ok_or_else(|| ErrorNotFound(format!("tag {} doesn't exist", req.tag_id)))?
+        .delete(db)
+        .await
+        .map_err(error)?;
+    Ok(web::Json(DeleteTagResponse {
+        tag_id: req.tag_id,
+        req_id: req.req_id,
+    }))
+}
+
+/// get /task_tags endpoint returns the tags of some tasks
+#[get("/task_tags")]
+async fn get_task_tags_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<GetTaskTagsRequest>,
+) -> Result<web::Json<GetTaskTagsResponse>> {
+    info!("get_task_tags_request, req: {:?}", req);
+    let rows = task_tag::Entity::find()
+        .filter(task_tag::Column::TaskId.is_in(req.task_ids.iter().cloned()))
+        .order_by_asc(task_tag::Column::TagId)
+        .all(data.as_ref())
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't fetch tags: {}", e)))?;
+    let mut tags: HashMap<TaskID, Vec<TagID>> = HashMap::new();
+    for row in rows {
+        tags.entry(row.task_id).or_default().push(row.tag_id);
+    }
+    let mut task_ids = req.task_ids.clone();
+    task_ids.sort_unstable();
+    task_ids.dedup();
+    Ok(web::Json(GetTaskTagsResponse {
+        tasks: task_ids
+            .into_iter()
+            .map(|task_id| TaskTags {
+                task_id,
+                tags: tags.remove(&task_id).unwrap_or_default(),
+            })
+            .collect(),
+        req_id: req.req_id,
+    }))
+}
+
+/// put /task_tags endpoint replaces the tags of a task, in one transaction so the task is never
+/// left untagged
+#[put("/task_tags")]
+async fn set_task_tags_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<SetTaskTagsRequest>,
+) -> Result<web::Json<SetTaskTagsResponse>> {
+    info!("set_task_tags_request, req: {:?}", req);
+    let db = data.as_ref();
+    let error = |e: DbErr| ErrorInternalServerError(format!("couldn't tag task: {}", e));
+    task::Entity::find_by_id(req.task_id)
+        .one(db)
+        .await
+        .map_err(error)?
+        .ok_or_else(|| ErrorNotFound(format!("task {} doesn't exist", req.task_id)))?;
+    let mut tags = req.tags.clone();
+    tags.sort_unstable();
+    tags.dedup();
+    if !tags.is_empty() {
+        let found = tag::Entity::find()
+            .filter(tag::Column::Id.is_in(tags.iter().cloned()))
+            .all(db)
+            .await
+            .map_err(error)?;
+        if found.len() != tags.len() {
+            return Err(ErrorNotFound("some of the tags don't exist"));
+        }
+    }
+    let txn = db.begin().await.map_err(error)?;
+    task_tag::Entity::delete_many()
+        .filter(task_tag::Column::TaskId.eq(req.task_id))
+        .exec(&txn)
+        .await
+        .map_err(error)?;
+    if !tags.is_empty() {
+        task_tag::Entity::insert_many(tags.iter().map(|tag_id| task_tag::ActiveModel {
+            task_id: Set(req.task_id),
+            tag_id: Set(*tag_id),
+        }))
+        .exec_without_returning(&txn)
+        .await
+        .map_err(error)?;
+    }
+    txn.commit().await.map_err(error)?;
+    Ok(web::Json(SetTaskTagsResponse {
+        task: TaskTags {
+            task_id: req.task_id,
+            tags,
+        },
+        req_id: req.req_id,
+    }))
+}
+
+async fn delete_task(
+    db: &DatabaseConnection,
+    blobs: &BlobStore,
+    req: &DeleteTaskRequest,
+) -> Result<web::Json<DeleteTaskResponse>> {
+    info!("delete_task, req: {:?}", req);
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't delete task: {}", e)))?;
+    let task = task::Entity::find_by_id(req.task_id)
+        .one(&txn)
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't find task: {}", e)))?
+        .ok_or("couldn't find task by id")
+        .map_err(ErrorInternalServerError)?;
+    // attachments cascade away with the task, their content goes too once that's committed, unless
+    // another task has it
+    let hashes: Vec<String> = attachment::Entity::find()
+        .filter(attachment::Column::TaskId.eq(req.task_id))
+        .all(&txn)
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't fetch attachments: {}", e)))?
+        .into_iter()
+        .map(|attachment| attachment.hash)
+        .collect();
+    // references to the task cascade away with it, a single reference would leave its property without a
+    // value so the property goes too. Lists of references just get shorter
+    task_property::Entity::delete_many()
+        .filter(task_property::Column::Typ.eq(TaskPropType::TaskRef.as_str()))
+        .filter(
+            Expr::tuple([
+                Expr::col(task_property::Column::TaskId).into(),
+                Expr::col(task_property::Column::Name).into(),
+            ])
+            .in_subquery(
+                Query::select()
+                    .columns([
+                        task_ref_property::Column::TaskId,
+                        task_ref_property::Column::TaskPropertyName,
+                    ])
+                    .from(task_ref_property::Entity)
+                    .and_where(task_ref_property::Column::Value.eq(req.task_id))
+                    .to_owned(),
+            ),
+        )
+        .exec(&txn)
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't delete references: {}", e)))?;
+    task.delete(&txn)
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't delete task: {}", e)))?;
+    txn.commit()
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't delete task: {}", e)))?;
+    if !hashes.is_empty() {
+        remove_unshared_blobs(db, blobs, hashes).await;
+    }
+    info!("delete_task, deleted task: {:?}", req.task_id);
+    Ok(web::Json(req.req_id))
+}
+
+#[delete("/task")]
+async fn delete_task_request(
+    data: web::Data<DatabaseConnection>,
+    blobs: web::Data<BlobStore>,
+    req: web::Json<DeleteTaskRequest>,
+) -> Result<web::Json<DeleteTaskResponse>> {
+    info!("delete_task_request, req: {:?}", req);
+    delete_task(&data, &blobs, &req).await
+}
+#[delete("/tasks")]
+async fn delete_tasks_request(
+    data: web::Data<DatabaseConnection>,
+    blobs: web::Data<BlobStore>,
+    req: web::Json<DeleteTasksRequest>,
+) -> Result<web::Json<DeleteTasksResponse>> {
+    info!("delete_tasks_request, req: {:?}", req);
+    let mut res: Vec<u64> = vec![];
+    for task in req.iter() {
+        delete_task(&data, &blobs, task).await?;
+        res.push(task.req_id);
+    }
+    info!("delete_tasks_request, completed res: {:?}", res);
+    Ok(web::Json(res))
+}
+
+/// correlated `EXISTS` subquery matching tasks that have the property `field` stored in the typed
+/// property table `E` with a value satisfying `value`. Each leaf gets its own subquery so that
+/// leaves on different properties can be combined without joining (and duplicating) task rows.
+fn property_exists<E: EntityTrait>(
+    task_id: E::Column,
+    name: E::Column,
+    field: &str,
+    value: SimpleExpr,
+) -> Condition {
+    Condition::all().add(Expr::exists(
+        Query::select()
+            .expr(Expr::val(1))
+            .from(E::default())
+            .and_where(Expr::col((E::default(), task_id)).equals((task::Entity, task::Column::Id)))
+            .and_where(name.eq(field))
+            .and_where(value)
+            .to_owned(),
+    ))
+}
+
+/// correlated `EXISTS` subquery matching tasks whose properties `left` and `right` are both stored in the
+/// typed property table `E` and satisfy `comparator`.
+fn properties_compare<E: EntityTrait>(
+    task_id: E::Column,
+    name: E::Column,
+    value: E::Column,
+    left: &str,
+    comparator: &Comparator,
+    right: &str,
+) -> actix_web::Result<SimpleExpr> {
+    let (l, r) = (Alias::new("l"), Alias::new("r"));
+    Ok(Expr::exists(
+        Query::select()
+            .expr(Expr::val(1))
+            .from_as(E::default(), l.clone())
+            .join_as(
+                JoinType::InnerJoin,
+                E::default(),
+                r.clone(),
+                Expr::col((l.clone(), task_id)).equals((r.clone(), task_id)),
+            )
+            .and_where(Expr::col((l.clone(), task_id)).equals((task::Entity, task::Column::Id)))
+            .and_where(Expr::col((l.clone(), name)).eq(left))
+            .and_where(Expr::col((r.clone(), name)).eq(right))
+            .and_where(compare_ord(
+                Expr::col((l, value)),
+                comparator,
+                Expr::col((r, value)),
+            )?)
+            .to_owned(),
+    ))
+}
+
+/// correlated `EXISTS` subquery matching tasks whose property `field`, stored in the typed property table `E`
+/// as (task id, name, value), satisfies `comparator` against the global `global` stored in the table `G`
+/// as (name, value).
+fn property_global_compare<E: EntityTrait, G: EntityTrait>(
+    (task_id, name, value): (E::Column, E::Column, E::Column),
+    (global_name, global_value): (G::Column, G::Column),
+    field: &str,
+    comparator: &Comparator,
+    global: &str,
+) -> actix_web::Result<SimpleExpr> {
+    Ok(Expr::exists(
+        Query::select()
+            .expr(Expr::val(1))
+            .from(E::default())
+            .join(
+                JoinType::InnerJoin,
+                G::default(),
+                Expr::col((G::default(), global_name)).eq(global),
+            )
+            .and_where(Expr::col((E::default(), task_id)).equals((task::Entity, task::Column::Id)))
+            .and_where(Expr::col((E::default(), name)).eq(field))
+            .and_where(compare_ord(
+                Expr::col((E::default(), value)),
+                comparator,
+                Expr::col((G::default(), global_value)),
+            )?)
+            .to_owned(),
+    ))
+}
+
+/// correlated subquery counting the dependency rows whose `column` is the task,
+/// `TaskId` counts the task's dependencies and `DependsOnId` counts its dependents.
+fn dependency_count(column: dependency::Column) -> SimpleExpr {
+    SimpleExpr::SubQuery(
+        None,
+        Box::new(
+            Query::select()
+                .expr(Func::count(Expr::col(Asterisk)))
+                .from(dependency::Entity)
+                .and_where(
+                    Expr::col((dependency::Entity, column))
+                        .equals((task::Entity, task::Column::Id)),
+                )
+                .to_owned()
+                .into_sub_query_statement(),
+        ),
+    )
+}
+
+// tasks that depend on $1 directly or through other dependencies
+const DEPENDS_ON_TRANSITIVE: &str = r#""task"."id" IN (
+    WITH RECURSIVE "dependent" ("task_id") AS (
+        SELECT "task_id" FROM "dependency" WHERE "depends_on_id" = $1
+        UNION
+        SELECT "dependency"."task_id" FROM "dependency"
+        JOIN "dependent" ON "dependency"."depends_on_id" = "dependent"."task_id"
+    )
+    SELECT "task_id" FROM "dependent"
+)"#;
+
+// tasks tagged with $1 or a tag nested under it
+const HAS_TAG_TRANSITIVE: &str = r#""task"."id" IN (
+    WITH RECURSIVE "subtag" ("id") AS (
+        SELECT "id" FROM "tag" WHERE "id" = $1
+        UNION
+        SELECT "tag"."id" FROM "tag"
+        JOIN "subtag" ON "tag"."parent_id" = "subtag"."id"
+    )
+    SELECT "task_id" FROM "task_tag"
+    JOIN "subtag" ON "task_tag"."tag_id" = "subtag"."id"
+)"#;
+
+fn construct_tag_filter(tag: TagID, descendants: bool) -> Condition {
+    let condition = if descendants {
+        Expr::cust_with_values(HAS_TAG_TRANSITIVE, [tag])
+    } else {
+        Expr::exists(
+            Query::select()
+                .expr(Expr::val(1))
+                .from(task_tag::Entity)
+                .and_where(
+                    Expr::col((task_tag::Entity, task_tag::Column::TaskId))
+                        .equals((task::Entity, task::Column::Id)),
+                )
+                .and_where(task_tag::Column::TagId.eq(tag))
+                .to_owned(),
+        )
+    };
+    Condition::all().add(condition)
+}
+
+fn construct_dependency_filter(filter: &DependencyFilter) -> actix_web::Result<Condition> {
+    let condition = match filter {
+        DependencyFilter::HasIncompleteDependency => {
+            let dep = Alias::new("dep");
+            Expr::exists(
+                Query::select()
+                    .expr(Expr::val(1))
+                    .from(dependency::Entity)
+                    .join_as(
+                        JoinType::InnerJoin,
+                        task::Entity,
+                        dep.clone(),
+                        Expr::col((dep.clone(), task::Column::Id))
+                            .equals((dependency::Entity, dependency::Column::DependsOnId)),
+                    )
+                    .and_where(
+                        Expr::col((dependency::Entity, dependency::Column::TaskId))
+                            .equals((task::Entity, task::Column::Id)),
+                    )
+                    .and_where(Expr::col((dep, task::Column::Completed)).eq(false))
+                    .to_owned(),
+            )
+        }
+        DependencyFilter::DependencyCount { comparator, count } => compare_ord(
+            Expr::expr(dependency_count(dependency::Column::TaskId)),
+            comparator,
+            *count as i64,
+        )?,
+        DependencyFilter::DependentCount { comparator, count } => compare_ord(
+            Expr::expr(dependency_count(dependency::Column::DependsOnId)),
+            comparator,
+            *count as i64,
+        )?,
+        DependencyFilter::DependsOn {
+            task,
+            transitive: false,
+        } => Expr::exists(
+            Query::select()
+                .expr(Expr::val(1))
+                .from(dependency::Entity)
+                .and_where(
+                    Expr::col((dependency::Entity, dependency::Column::TaskId))
+                        .equals((task::Entity, task::Column::Id)),
+                )
+                .and_where(dependency::Column::DependsOnId.eq(*task))
+                .to_owned(),
+        ),
+        DependencyFilter::DependsOn {
+            task,
+            transitive: true,
+        } => Expr::cust_with_values(DEPENDS_ON_TRANSITIVE, [*task]),
+    };
+    Ok(Condition::all().add(condition))
+}
+
+// ordering comparison for leaves that compare two expressions instead of a column and an immediate
+fn compare_ord<T: Into<SimpleExpr>>(
+    left: Expr,
+    comparator: &Comparator,
+    right: T,
+) -> actix_web::Result<SimpleExpr> {
+    Ok(match comparator {
+        Comparator::LT => left.lt(right),
+        Comparator::LEQ => left.lte(right),
+        Comparator::GT => left.gt(right),
+        Comparator::GEQ => left.gte(right),
+        Comparator::EQ => left.eq(right),
+        Comparator::NEQ => left.ne(right),
+        _ => {
+            return Err(ErrorInternalServerError(format!(
+                "Invalid comparator {:?} for comparing properties or counts",
+                comparator
+            )))
+        }
+    })
+}
+
+// compare a string column to an immediate, every comparator except the select ones applies to strings
+fn compare_string(
+    column: Expr,
+    comparator: &Comparator,
+    imm: &str,
+) -> actix_web::Result<SimpleExpr> {
+    Ok(match comparator {
+        Comparator::LT => column.lt(imm),
+        Comparator::LEQ => column.lte(imm),
+        Comparator::GT => column.gt(imm),
+        Comparator::GEQ => column.gte(imm),
+        Comparator::EQ => column.eq(imm),
+        Comparator::NEQ => column.ne(imm),
+        Comparator::CONTAINS => column.like(format!("%{}%", imm)),
+        Comparator::NOTCONTAINS => column.not_like(format!("%{}%", imm)),
+        Comparator::LIKE => column.like(imm),
+        Comparator::IEQ => Expr::expr(Func::lower(column)).eq(Func::lower(Expr::val(imm))),
+        Comparator::ICONTAINS => column.ilike(format!("%{}%", imm)),
+        Comparator::ILIKE => column.ilike(imm),
+        Comparator::REGEX => column.binary(PgBinOper::Regex, imm),
+        Comparator::IREGEX => column.binary(PgBinOper::RegexCaseInsensitive, imm),
+        Comparator::IN | Comparator::CONTAINSANY | Comparator::CONTAINSALL => {
+            return Err(ErrorInternalServerError(format!(
+                "Invalid comparator {:?} for type string",
+                comparator
+            )))
+        }
+    })
+}
+
+// invalid filters are the client's fault, respond with the FilterError so it can tell what's wrong
+fn filter_error(err: FilterError) -> actix_web::Error {
+    let response = HttpResponse::BadRequest().json(&err);
+    InternalError::from_response(err, response).into()
+}
+
+/// build the condition of a filter. Leaves on computed properties in `schema` compare their formula instead
+/// of a stored value.
+fn construct_filter(
+    filter: &Filter,
+    schema: &[PropertyDefinition],
+) -> actix_web::Result<Condition> {
     match filter {
         Filter::Leaf {
             field,
             comparator,
             immediate,
-        } => {
-            let mut condition = Condition::all();
-            match immediate {
-                TaskPropVariant::Number(imm) => {
-                    condition =
-                        condition.add(task_num_property::Column::TaskPropertyName.eq(field));
-                    condition = match comparator {
-                        Comparator::LT => condition.add(task_num_property::Column::Value.lt(*imm)),
-                        Comparator::LEQ => {
-                            condition.add(task_num_property::Column::Value.lte(*imm))
-                        }
-                        Comparator::GT => condition.add(task_num_property::Column::Value.gt(*imm)),
-                        Comparator::GEQ => {
-                            condition.add(task_num_property::Column::Value.gte(*imm))
-                        }
-                        Comparator::EQ => condition.add(task_num_property::Column::Value.eq(*imm)),
-                        Comparator::NEQ => condition.add(task_num_property::Column::Value.ne(*imm)),
-                        _ => {
-                            return Err(actix_web::error::ErrorInternalServerError(format!(
-                                "Invalid comparator {:?} for type number",
-                                {}
-                            )))
-                        }
-                    };
-                }
-                TaskPropVariant::Date(imm) => {
-                    condition =
-                        condition.add(task_date_property::Column::TaskPropertyName.eq(field));
-                    condition = match comparator {
-                        Comparator::LT => condition.add(task_date_property::Column::Value.lt(*imm)),
-                        Comparator::LEQ => {
-                            condition.add(task_date_property::Column::Value.lte(*imm))
-                        }
-                        Comparator::GT => condition.add(task_date_property::Column::Value.gt(*imm)),
-                        Comparator::GEQ => {
-                            condition.add(task_date_property::Column::Value.gte(*imm))
-                        }
-                        Comparator::EQ => condition.add(task_date_property::Column::Value.eq(*imm)),
-                        Comparator::NEQ => {
-                            condition.add(task_date_property::Column::Value.ne(*imm))
-                        }
-                        _ => {
-                            return Err(actix_web::error::ErrorInternalServerError(format!(
-                                "Invalid comparator {:?} for type date",
-                                {}
-                            )))
-                        }
-                    };
-                }
-                TaskPropVariant::Boolean(imm) => {
-                    condition =
-                        condition.add(task_bool_property::Column::TaskPropertyName.eq(field));
-                    condition = match comparator {
-                        Comparator::EQ => condition.add(task_bool_property::Column::Value.eq(*imm)),
-                        Comparator::NEQ => {
-                            condition.add(task_bool_property::Column::Value.ne(*imm))
-                        }
-                        _ => {
-                            return Err(actix_web::error::ErrorInternalServerError(format!(
-                                "Invalid comparator {:?} for type boolean",
-                                {}
-                            )))
-                        }
+        } if formula_of(schema, field).is_some() => {
+            construct_computed_filter(schema, field, comparator, immediate)
+        }
+        Filter::Leaf {
+            field,
+            comparator,
+            immediate,
+        } => match immediate {
+            TaskPropVariant::Number(imm) => {
+                let column = task_num_property::Column::Value;
+                let value = match comparator {
+                    Comparator::LT => column.lt(*imm),
+                    Comparator::LEQ => column.lte(*imm),
+                    Comparator::GT => column.gt(*imm),
+                    Comparator::GEQ => column.gte(*imm),
+                    Comparator::EQ => column.eq(*imm),
+                    Comparator::NEQ => column.ne(*imm),
+                    _ => {
+                        return Err(actix_web::error::ErrorInternalServerError(format!(
+                            "Invalid comparator {:?} for type number",
+                            comparator
+                        )))
                     }
-                }
-                TaskPropVariant::String(imm) => {
-                    condition =
-                        condition.add(task_string_property::Column::TaskPropertyName.eq(field));
-                    condition = match comparator {
-                        Comparator::LT => {
-                            condition.add(task_string_property::Column::Value.lt(imm.clone()))
-                        }
-                        Comparator::LEQ => {
-                            condition.add(task_string_property::Column::Value.lte(imm.clone()))
-                        }
-                        Comparator::GT => {
-                            condition.add(task_string_property::Column::Value.gt(imm.clone()))
-                        }
-                        Comparator::GEQ => {
-                            condition.add(task_string_property::Column::Value.gte(imm.clone()))
-                        }
-                        Comparator::EQ => {
-                            condition.add(task_string_property::Column::Value.eq(imm.clone()))
-                        }
-                        Comparator::NEQ => {
-                            condition.add(task_string_property::Column::Value.ne(imm.clone()))
-                        }
-                        Comparator::CONTAINS => condition
-                            .add(task_string_property::Column::Value.like(format!("%{}%", imm))),
-                        Comparator::NOTCONTAINS => {
-                            condition.add(Condition::not(Condition::all().add(
-                                task_string_property::Column::Value.like(format!("%{}%", imm)),
-                            )))
-                        }
-                        Comparator::LIKE => {
-                            condition.add(task_string_property::Column::Value.like(imm.clone()))
-                        }
+                };
+                Ok(property_exists::<task_num_property::Entity>(
+                    task_num_property::Column::TaskId,
+                    task_num_property::Column::TaskPropertyName,
+                    field,
+                    value,
+                ))
+            }
+            TaskPropVariant::Integer(imm) => Ok(property_exists::<task_int_property::Entity>(
+                task_int_property::Column::TaskId,
+                task_int_property::Column::TaskPropertyName,
+                field,
+                compare_ord(
+                    Expr::col(task_int_property::Column::Value),
+                    comparator,
+                    *imm,
+                )?,
+            )),
+            TaskPropVariant::Duration(imm) => {
+                Ok(property_exists::<task_duration_property::Entity>(
+                    task_duration_property::Column::TaskId,
+                    task_duration_property::Column::TaskPropertyName,
+                    field,
+                    compare_ord(
+                        Expr::col(task_duration_property::Column::Value),
+                        comparator,
+                        imm.0,
+                    )?,
+                ))
+            }
+            TaskPropVariant::Date(imm) => {
+                let column = task_date_property::Column::Value;
+                let value = match comparator {
+                    Comparator::LT => column.lt(*imm),
+                    Comparator::LEQ => column.lte(*imm),
+                    Comparator::GT => column.gt(*imm),
+                    Comparator::GEQ => column.gte(*imm),
+                    Comparator::EQ => column.eq(*imm),
+                    Comparator::NEQ => column.ne(*imm),
+                    _ => {
+                        return Err(actix_web::error::ErrorInternalServerError(format!(
+                            "Invalid comparator {:?} for type date",
+                            comparator
+                        )))
                     }
-                }
-            };
-            Ok(condition)
-        }
+                };
+                Ok(property_exists::<task_date_property::Entity>(
+                    task_date_property::Column::TaskId,
+                    task_date_property::Column::TaskPropertyName,
+                    field,
+                    value,
+                ))
+            }
+            TaskPropVariant::Boolean(imm) => {
+                let column = task_bool_property::Column::Value;
+                let value = match comparator {
+                    Comparator::EQ => column.eq(*imm),
+                    Comparator::NEQ => column.ne(*imm),
+                    _ => {
+                        return Err(actix_web::error::ErrorInternalServerError(format!(
+                            "Invalid comparator {:?} for type boolean",
+                            comparator
+                        )))
+                    }
+                };
+                Ok(property_exists::<task_bool_property::Entity>(
+                    task_bool_property::Column::TaskId,
+                    task_bool_property::Column::TaskPropertyName,
+                    field,
+                    value,
+                ))
+            }
+            // resolved each time the filter is run, so saved views stay current
+            TaskPropVariant::RelativeDate(date) => construct_filter(
+                &Filter::Leaf {
+                    field: field.clone(),
+                    comparator: comparator.clone(),
+                    immediate: TaskPropVariant::Date(date.resolve(chrono::Utc::now())),
+                },
+                schema,
+            ),
+            TaskPropVariant::String(imm) => Ok(property_exists::<task_string_property::Entity>(
+                task_string_property::Column::TaskId,
+                task_string_property::Column::TaskPropertyName,
+                field,
+                compare_string(
+                    Expr::col(task_string_property::Column::Value),
+                    comparator,
+                    imm,
+                )?,
+            )),
+            TaskPropVariant::Select(imm) => {
+                let column = task_select_property::Column::Value;
+                let value = match comparator {
+                    Comparator::EQ => column.eq(imm),
+                    Comparator::NEQ => column.ne(imm),
+                    _ => {
+                        return Err(ErrorInternalServerError(format!(
+                            "Invalid comparator {:?} for type select",
+                            comparator
+                        )))
+                    }
+                };
+                Ok(property_exists::<task_select_property::Entity>(
+                    task_select_property::Column::TaskId,
+                    task_select_property::Column::TaskPropertyName,
+                    field,
+                    value,
+                ))
+            }
+            // IN matches select properties, the other comparators match multi-select properties
+            TaskPropVariant::MultiSelect(imm) if *comparator == Comparator::IN => {
+                Ok(property_exists::<task_select_property::Entity>(
+                    task_select_property::Column::TaskId,
+                    task_select_property::Column::TaskPropertyName,
+                    field,
+                    task_select_property::Column::Value.is_in(imm.iter().cloned()),
+                ))
+            }
+            TaskPropVariant::MultiSelect(imm) => {
+                let column = || Expr::col(task_multiselect_property::Column::Value);
+                let same = || {
+                    column()
+                        .binary(PgBinOper::Contains, imm.clone())
+                        .and(column().binary(PgBinOper::Contained, imm.clone()))
+                };
+                let value = match comparator {
+                    Comparator::CONTAINSANY => column().binary(PgBinOper::Overlap, imm.clone()),
+                    Comparator::CONTAINSALL => column().binary(PgBinOper::Contains, imm.clone()),
+                    Comparator::EQ => same(),
+                    Comparator::NEQ => same().not(),
+                    _ => {
+                        return Err(ErrorInternalServerError(format!(
+                            "Invalid comparator {:?} for type multiselect",
+                            comparator
+                        )))
+                    }
+                };
+                Ok(property_exists::<task_multiselect_property::Entity>(
+                    task_multiselect_property::Column::TaskId,
+                    task_multiselect_property::Column::TaskPropertyName,
+                    field,
+                    value,
+                ))
+            }
+            TaskPropVariant::TaskRef(imm) => {
+                let column = task_ref_property::Column::Value;
+                let value = match comparator {
+                    Comparator::EQ => column.eq(*imm),
+                    Comparator::NEQ => column.ne(*imm),
+                    _ => {
+                        return Err(ErrorInternalServerError(format!(
+                            "Invalid comparator {:?} for type taskref",
+                            comparator
+                        )))
+                    }
+                };
+                Ok(property_exists::<task_ref_property::Entity>(
+                    task_ref_property::Column::TaskId,
+                    task_ref_property::Column::TaskPropertyName,
+                    field,
+                    value,
+                ))
+            }
+            // IN matches single references, the other comparators match lists of references
+            TaskPropVariant::TaskRefs(imm) if *comparator == Comparator::IN => {
+                Ok(property_exists::<task_ref_property::Entity>(
+                    task_ref_property::Column::TaskId,
+                    task_ref_property::Column::TaskPropertyName,
+                    field,
+                    task_ref_property::Column::Value.is_in(imm.iter().cloned()),
+                ))
+            }
+            TaskPropVariant::TaskRefs(imm) => construct_task_refs_filter(field, comparator, imm),
+            TaskPropVariant::User(imm) => {
+                let column = task_user_property::Column::Value;
+                let value = match comparator {
+                    Comparator::EQ => column.eq(imm),
+                    Comparator::NEQ => column.ne(imm),
+                    _ => {
+                        return Err(ErrorInternalServerError(format!(
+                            "Invalid comparator {:?} for type user",
+                            comparator
+                        )))
+                    }
+                };
+                Ok(property_exists::<task_user_property::Entity>(
+                    task_user_property::Column::TaskId,
+                    task_user_property::Column::TaskPropertyName,
+                    field,
+                    value,
+                ))
+            }
+        },
         Filter::LeafPrimitive {
             field,
             comparator,
             immediate,
         } => match field {
             PrimitiveField::TITLE => {
-                let mut condition = Condition::all();
                 let imm = match immediate {
                     TaskPropVariant::String(a) => a,
                     _ => return Err(ErrorInternalServerError("die")),
                 };
-
-                condition = match comparator {
-                    Comparator::LT => condition.add(task::Column::Title.lt(imm.clone())),
-                    Comparator::LEQ => condition.add(task::Column::Title.lte(imm.clone())),
-                    Comparator::GT => condition.add(task::Column::Title.gt(imm.clone())),
-                    Comparator::GEQ => condition.add(task::Column::Title.gte(imm.clone())),
-                    Comparator::EQ => condition.add(task::Column::Title.eq(imm.clone())),
-                    Comparator::NEQ => condition.add(task::Column::Title.ne(imm.clone())),
-                    Comparator::CONTAINS => {
-                        condition.add(task::Column::Title.like(format!("%{}%", imm)))
-                    }
-                    Comparator::NOTCONTAINS => condition.add(Condition::not(
-                        Condition::all().add(task::Column::Title.like(format!("%{}%", imm))),
-                    )),
-                    Comparator::LIKE => condition.add(task::Column::Title.like(imm.clone())),
-                };
-                Ok(condition)
+                Ok(Condition::all().add(compare_string(
+                    Expr::col((task::Entity, task::Column::Title)),
+                    comparator,
+                    imm,
+                )?))
             }
             PrimitiveField::COMPLETED => {
                 let mut condition = Condition::all();
@@ -589,25 +2368,179 @@ fn construct_filter(filter: &Filter) -> actix_web::Result<Condition> {
             PrimitiveField::LASTEDITED => {
                 let mut condition = Condition::all();
                 let imm = match immediate {
-                    TaskPropVariant::Date(a) => a,
+                    TaskPropVariant::Date(a) => *a,
+                    // last edited is recorded in UTC, unlike date properties
+                    TaskPropVariant::RelativeDate(a) => a.resolve_utc(chrono::Utc::now()),
                     _ => return Err(ErrorInternalServerError("invalid type")),
                 };
 
                 condition = match comparator {
-                    Comparator::LT => condition.add(task::Column::LastEdited.lt(*imm)),
-                    Comparator::LEQ => condition.add(task::Column::LastEdited.lte(*imm)),
-                    Comparator::GT => condition.add(task::Column::LastEdited.gt(*imm)),
-                    Comparator::GEQ => condition.add(task::Column::LastEdited.gte(*imm)),
-                    Comparator::EQ => condition.add(task::Column::LastEdited.eq(*imm)),
-                    Comparator::NEQ => condition.add(task::Column::LastEdited.ne(*imm)),
+                    Comparator::LT => condition.add(task::Column::LastEdited.lt(imm)),
+                    Comparator::LEQ => condition.add(task::Column::LastEdited.lte(imm)),
+                    Comparator::GT => condition.add(task::Column::LastEdited.gt(imm)),
+                    Comparator::GEQ => condition.add(task::Column::LastEdited.gte(imm)),
+                    Comparator::EQ => condition.add(task::Column::LastEdited.eq(imm)),
+                    Comparator::NEQ => condition.add(task::Column::LastEdited.ne(imm)),
                     _ => return Err(ErrorInternalServerError("invalid comparator")),
                 };
                 Ok(condition)
             }
         },
+        Filter::LeafProperty {
+            left,
+            comparator,
+            right,
+        } => Ok(Condition::any()
+            .add(properties_compare::<task_num_property::Entity>(
+                task_num_property::Column::TaskId,
+                task_num_property::Column::TaskPropertyName,
+                task_num_property::Column::Value,
+                left,
+                comparator,
+                right,
+            )?)
+            .add(properties_compare::<task_int_property::Entity>(
+                task_int_property::Column::TaskId,
+                task_int_property::Column::TaskPropertyName,
+                task_int_property::Column::Value,
+                left,
+                comparator,
+                right,
+            )?)
+            .add(properties_compare::<task_duration_property::Entity>(
+                task_duration_property::Column::TaskId,
+                task_duration_property::Column::TaskPropertyName,
+                task_duration_property::Column::Value,
+                left,
+                comparator,
+                right,
+            )?)
+            .add(properties_compare::<task_string_property::Entity>(
+                task_string_property::Column::TaskId,
+                task_string_property::Column::TaskPropertyName,
+                task_string_property::Column::Value,
+                left,
+                comparator,
+                right,
+            )?)
+            .add(properties_compare::<task_date_property::Entity>(
+                task_date_property::Column::TaskId,
+                task_date_property::Column::TaskPropertyName,
+                task_date_property::Column::Value,
+                left,
+                comparator,
+                right,
+            )?)
+            .add(properties_compare::<task_bool_property::Entity>(
+                task_bool_property::Column::TaskId,
+                task_bool_property::Column::TaskPropertyName,
+                task_bool_property::Column::Value,
+                left,
+                comparator,
+                right,
+            )?)
+            .add(properties_compare::<task_select_property::Entity>(
+                task_select_property::Column::TaskId,
+                task_select_property::Column::TaskPropertyName,
+                task_select_property::Column::Value,
+                left,
+                comparator,
+                right,
+            )?)
+            .add(properties_compare::<task_ref_property::Entity>(
+                task_ref_property::Column::TaskId,
+                task_ref_property::Column::TaskPropertyName,
+                task_ref_property::Column::Value,
+                left,
+                comparator,
+                right,
+            )?)
+            .add(properties_compare::<task_user_property::Entity>(
+                task_user_property::Column::TaskId,
+                task_user_property::Column::TaskPropertyName,
+                task_user_property::Column::Value,
+                left,
+                comparator,
+                right,
+            )?)),
+        Filter::LeafGlobal {
+            field,
+            comparator,
+            global,
+        } => Ok(Condition::any()
+            .add(property_global_compare::<
+                task_num_property::Entity,
+                global_num_property::Entity,
+            >(
+                (
+                    task_num_property::Column::TaskId,
+                    task_num_property::Column::TaskPropertyName,
+                    task_num_property::Column::Value,
+                ),
+                (
+                    global_num_property::Column::PropertyName,
+                    global_num_property::Column::Value,
+                ),
+                field,
+                comparator,
+                global,
+            )?)
+            .add(property_global_compare::<
+                task_string_property::Entity,
+                global_string_property::Entity,
+            >(
+                (
+                    task_string_property::Column::TaskId,
+                    task_string_property::Column::TaskPropertyName,
+                    task_string_property::Column::Value,
+                ),
+                (
+                    global_string_property::Column::PropertyName,
+                    global_string_property::Column::Value,
+                ),
+                field,
+                comparator,
+                global,
+            )?)
+            .add(property_global_compare::<
+                task_date_property::Entity,
+                global_date_property::Entity,
+            >(
+                (
+                    task_date_property::Column::TaskId,
+                    task_date_property::Column::TaskPropertyName,
+                    task_date_property::Column::Value,
+                ),
+                (
+                    global_date_property::Column::PropertyName,
+                    global_date_property::Column::Value,
+                ),
+                field,
+                comparator,
+                global,
+            )?)
+            .add(property_global_compare::<
+                task_bool_property::Entity,
+                global_bool_property::Entity,
+            >(
+                (
+                    task_bool_property::Column::TaskId,
+                    task_bool_property::Column::TaskPropertyName,
+                    task_bool_property::Column::Value,
+                ),
+                (
+                    global_bool_property::Column::PropertyName,
+                    global_bool_property::Column::Value,
+                ),
+                field,
+                comparator,
+                global,
+            )?)),
+        Filter::LeafDependency(filter) => construct_dependency_filter(filter),
+        Filter::LeafTag { tag, descendants } => Ok(construct_tag_filter(*tag, *descendants)),
         Filter::Operator { op, childs } => {
             if let Operator::NOT = op {
-                match construct_filter(&childs[0]) {
+                match construct_filter(&childs[0], schema) {
                     Ok(filter) => return Ok(Condition::not(filter)),
                     Err(err) => return Err(err),
                 }
@@ -618,78 +2551,982 @@ fn construct_filter(filter: &Filter) -> actix_web::Result<Condition> {
                 _ => unreachable!(),
             };
             for child in childs.iter() {
-                match construct_filter(child) {
+                match construct_filter(child, schema) {
                     Ok(filter) => condition = condition.add(filter),
                     Err(err) => return Err(err),
                 }
             }
-            Ok(condition)
+            Ok(condition)
+        }
+        Filter::None => Ok(Condition::any()),
+    }
+}
+
+/// compare the value of a computed property to a number
+fn construct_computed_filter(
+    schema: &[PropertyDefinition],
+    field: &str,
+    comparator: &Comparator,
+    immediate: &TaskPropVariant,
+) -> actix_web::Result<Condition> {
+    let imm = match immediate {
+        TaskPropVariant::Number(imm) => *imm,
+        _ => {
+            return Err(ErrorInternalServerError(format!(
+                "computed property {} can only be compared to numbers",
+                field
+            )))
+        }
+    };
+    let value = computed_value(
+        schema,
+        field,
+        Expr::col((task::Entity, task::Column::Id)).into(),
+        0,
+    )?
+    .ok_or_else(|| ErrorInternalServerError(format!("property {} isn't computed", field)))?;
+    Ok(Condition::all().add(compare_ord(Expr::expr(value), comparator, imm)?))
+}
+
+/// filter on a list of task references. Each reference is its own row, so the list comparisons are built
+/// from one `EXISTS` per referenced task. Empty lists have no rows, so the property's type is checked too.
+fn construct_task_refs_filter(
+    field: &str,
+    comparator: &Comparator,
+    imm: &[TaskID],
+) -> actix_web::Result<Condition> {
+    let has = |value: SimpleExpr| {
+        property_exists::<task_refs_property::Entity>(
+            task_refs_property::Column::TaskId,
+            task_refs_property::Column::TaskPropertyName,
+            field,
+            value,
+        )
+    };
+    let is_task_refs = property_exists::<task_property::Entity>(
+        task_property::Column::TaskId,
+        task_property::Column::Name,
+        field,
+        task_property::Column::Typ.eq(TaskPropType::TaskRefs.as_str()),
+    );
+    let contains_all = imm.iter().fold(is_task_refs.clone(), |condition, task| {
+        condition.add(has(task_refs_property::Column::Value.eq(*task)))
+    });
+    let same = || {
+        contains_all
+            .clone()
+            .add(has(task_refs_property::Column::Value.is_not_in(imm.iter().cloned())).not())
+    };
+    Ok(match comparator {
+        Comparator::CONTAINSANY => {
+            has(task_refs_property::Column::Value.is_in(imm.iter().cloned()))
+        }
+        Comparator::CONTAINSALL => contains_all.clone(),
+        Comparator::EQ => same(),
+        Comparator::NEQ => is_task_refs.add(same().not()),
+        _ => {
+            return Err(ErrorInternalServerError(format!(
+                "Invalid comparator {:?} for type taskrefs",
+                comparator
+            )))
+        }
+    })
+}
+
+/// correlated subquery selecting the value of the property `field` stored in the typed property table `E`
+fn property_value<E: EntityTrait>(
+    task_id: E::Column,
+    name: E::Column,
+    value: E::Column,
+    field: &str,
+) -> SimpleExpr {
+    property_value_of::<E>(
+        (task_id, name, value),
+        Expr::col((task::Entity, task::Column::Id)).into(),
+        field,
+    )
+}
+
+/// subquery selecting the value of the property `field` of the task whose id is `task`,
+/// stored in the typed property table `E` as (task id, name, value)
+fn property_value_of<E: EntityTrait>(
+    (task_id, name, value): (E::Column, E::Column, E::Column),
+    task: SimpleExpr,
+    field: &str,
+) -> SimpleExpr {
+    SimpleExpr::SubQuery(
+        None,
+        Box::new(
+            Query::select()
+                .column((E::default(), value))
+                .from(E::default())
+                .and_where(Expr::col((E::default(), task_id)).eq(task))
+                .and_where(name.eq(field))
+                .to_owned()
+                .into_sub_query_statement(),
+        ),
+    )
+}
+
+/// computed properties nested deeper than this are assumed to refer to themselves
+const MAX_FORMULA_DEPTH: usize = 8;
+
+/// formula of the property `name`, if the schema defines it as a computed property
+fn formula_of<'a>(schema: &'a [PropertyDefinition], name: &str) -> Option<&'a str> {
+    schema
+        .iter()
+        .find(|definition| definition.name == name)
+        .and_then(|definition| definition.formula.as_deref())
+}
+
+/// expression computing the computed property `field` of the task whose id is `task`, NULL where the formula
+/// is missing. `None` if `field` isn't a computed property.
+fn computed_value(
+    schema: &[PropertyDefinition],
+    field: &str,
+    task: SimpleExpr,
+    depth: usize,
+) -> actix_web::Result<Option<SimpleExpr>> {
+    let Some(formula) = formula_of(schema, field) else {
+        return Ok(None);
+    };
+    if depth > MAX_FORMULA_DEPTH {
+        return Err(ErrorInternalServerError(format!(
+            "formula of property {} refers to itself",
+            field
+        )));
+    }
+    let formula = formula.parse::<Formula>().map_err(|e| {
+        ErrorInternalServerError(format!("invalid formula of property {}: {}", field, e))
+    })?;
+    formula_expr(&formula, schema, task, depth + 1).map(Some)
+}
+
+/// whether the formula of the computed property `name` reads `target`, directly or through other computed
+/// properties of `schema`. `seen` collects the properties already followed
+fn formula_reads(
+    schema: &[PropertyDefinition],
+    name: &str,
+    target: &str,
+    seen: &mut HashSet<String>,
+) -> bool {
+    let Some(formula) =
+        formula_of(schema, name).and_then(|formula| formula.parse::<Formula>().ok())
+    else {
+        return false;
+    };
+    formula.references().into_iter().any(|reference| {
+        reference == target
+            || (seen.insert(reference.to_owned()) && formula_reads(schema, reference, target, seen))
+    })
+}
+
+/// dependency columns holding a task and the tasks related to it
+fn relation_columns(relation: Relation) -> (dependency::Column, dependency::Column) {
+    match relation {
+        Relation::Dependencies => (dependency::Column::TaskId, dependency::Column::DependsOnId),
+        Relation::Dependents => (dependency::Column::DependsOnId, dependency::Column::TaskId),
+    }
+}
+
+/// translate a formula to an expression evaluated for the task whose id is `task`.
+/// Subqueries over related tasks are aliased by `depth`, so the ones nested in them can still refer to them.
+fn formula_expr(
+    formula: &Formula,
+    schema: &[PropertyDefinition],
+    task: SimpleExpr,
+    depth: usize,
+) -> actix_web::Result<SimpleExpr> {
+    Ok(match formula {
+        Formula::Number(number) => Expr::val(*number).into(),
+        Formula::Prop(field) => match computed_value(schema, field, task.clone(), depth)? {
+            Some(value) => value,
+            None => Func::coalesce([
+                property_value_of::<task_num_property::Entity>(
+                    (
+                        task_num_property::Column::TaskId,
+                        task_num_property::Column::TaskPropertyName,
+                        task_num_property::Column::Value,
+                    ),
+                    task.clone(),
+                    field,
+                ),
+                property_value_of::<task_int_property::Entity>(
+                    (
+                        task_int_property::Column::TaskId,
+                        task_int_property::Column::TaskPropertyName,
+                        task_int_property::Column::Value,
+                    ),
+                    task.clone(),
+                    field,
+                ),
+                property_value_of::<task_duration_property::Entity>(
+                    (
+                        task_duration_property::Column::TaskId,
+                        task_duration_property::Column::TaskPropertyName,
+                        task_duration_property::Column::Value,
+                    ),
+                    task,
+                    field,
+                ),
+            ])
+            .into(),
+        },
+        // dates are stored in UTC
+        Formula::DaysUntil(field) => Expr::cust_with_expr(
+            "EXTRACT(EPOCH FROM ($1 - (NOW() AT TIME ZONE 'UTC'))) / 86400",
+            property_value_of::<task_date_property::Entity>(
+                (
+                    task_date_property::Column::TaskId,
+                    task_date_property::Column::TaskPropertyName,
+                    task_date_property::Column::Value,
+                ),
+                task,
+                field,
+            ),
+        ),
+        Formula::Count(relation) | Formula::Done(relation) => {
+            let (own, other) = relation_columns(*relation);
+            let related = Alias::new(format!("related{}", depth));
+            let mut query = Query::select();
+            query
+                .expr(Func::count(Expr::col(Asterisk)))
+                .from_as(dependency::Entity, related.clone())
+                .and_where(Expr::col((related.clone(), own)).eq(task));
+            if let Formula::Done(_) = formula {
+                let related_task = Alias::new(format!("related_task{}", depth));
+                query
+                    .join_as(
+                        JoinType::InnerJoin,
+                        task::Entity,
+                        related_task.clone(),
+                        Expr::col((related_task.clone(), task::Column::Id))
+                            .equals((related, other)),
+                    )
+                    .and_where(Expr::col((related_task, task::Column::Completed)).eq(true));
+            }
+            // counts are integers, which would make divisions of them round down
+            Func::cast_as(
+                SimpleExpr::SubQuery(None, Box::new(query.into_sub_query_statement())),
+                Alias::new("DOUBLE PRECISION"),
+            )
+            .into()
+        }
+        Formula::Rollup {
+            aggregate,
+            relation,
+            prop,
+        } => {
+            let (own, other) = relation_columns(*relation);
+            let related = Alias::new(format!("related{}", depth));
+            let value = formula_expr(
+                &Formula::Prop(prop.clone()),
+                schema,
+                Expr::col((related.clone(), other)).into(),
+                depth + 1,
+            )?;
+            let value: SimpleExpr = match aggregate {
+                Aggregate::Sum => {
+                    Func::coalesce([Func::sum(value).into(), Expr::val(0).into()]).into()
+                }
+                Aggregate::Avg => Func::avg(value).into(),
+                Aggregate::Min => Func::min(value).into(),
+                Aggregate::Max => Func::max(value).into(),
+            };
+            SimpleExpr::SubQuery(
+                None,
+                Box::new(
+                    Query::select()
+                        .expr(value)
+                        .from_as(dependency::Entity, related.clone())
+                        .and_where(Expr::col((related, own)).eq(task))
+                        .to_owned()
+                        .into_sub_query_statement(),
+                ),
+            )
+        }
+        Formula::Neg(formula) => {
+            Expr::cust_with_expr("-($1)", formula_expr(formula, schema, task, depth)?)
+        }
+        Formula::Binary { op, left, right } => {
+            let left = Expr::expr(formula_expr(left, schema, task.clone(), depth)?);
+            let right = formula_expr(right, schema, task, depth)?;
+            match op {
+                BinaryOp::Add => left.add(right),
+                BinaryOp::Sub => left.sub(right),
+                BinaryOp::Mul => left.mul(right),
+                // dividing by zero is missing rather than an error
+                BinaryOp::Div => left.div(Expr::cust_with_expr("NULLIF($1, 0)", right)),
+            }
+        }
+    })
+}
+
+/// add a sort key to the ordering of a task query.
+/// properties are ordered by each typed property table in turn, so tasks are grouped by property type.
+/// Computed properties are ordered by their formula.
+fn order_by_sort_key(
+    select: &mut Select<task::Entity>,
+    key: &SortKey,
+    schema: &[PropertyDefinition],
+) -> actix_web::Result<()> {
+    let order = if key.descending {
+        Order::Desc
+    } else {
+        Order::Asc
+    };
+    let nulls = if key.nulls_first {
+        NullOrdering::First
+    } else {
+        NullOrdering::Last
+    };
+    let exprs = match &key.field {
+        SortField::Primitive(PrimitiveField::TITLE) => vec![task::Column::Title.into_simple_expr()],
+        SortField::Primitive(PrimitiveField::COMPLETED) => {
+            vec![task::Column::Completed.into_simple_expr()]
+        }
+        SortField::Primitive(PrimitiveField::LASTEDITED) => {
+            vec![task::Column::LastEdited.into_simple_expr()]
+        }
+        SortField::Rank(view_id) => vec![SimpleExpr::SubQuery(
+            None,
+            Box::new(
+                Query::select()
+                    .column((view_rank::Entity, view_rank::Column::Rank))
+                    .from(view_rank::Entity)
+                    .and_where(
+                        Expr::col((view_rank::Entity, view_rank::Column::TaskId))
+                            .eq(Expr::col((task::Entity, task::Column::Id))),
+                    )
+                    .and_where(view_rank::Column::ViewId.eq(*view_id))
+                    .to_owned()
+                    .into_sub_query_statement(),
+            ),
+        )],
+        SortField::Property(field) => match computed_value(
+            schema,
+            field,
+            Expr::col((task::Entity, task::Column::Id)).into(),
+            0,
+        )? {
+            Some(value) => vec![value],
+            None => vec![
+                property_value::<task_num_property::Entity>(
+                    task_num_property::Column::TaskId,
+                    task_num_property::Column::TaskPropertyName,
+                    task_num_property::Column::Value,
+                    field,
+                ),
+                property_value::<task_int_property::Entity>(
+                    task_int_property::Column::TaskId,
+                    task_int_property::Column::TaskPropertyName,
+                    task_int_property::Column::Value,
+                    field,
+                ),
+                property_value::<task_duration_property::Entity>(
+                    task_duration_property::Column::TaskId,
+                    task_duration_property::Column::TaskPropertyName,
+                    task_duration_property::Column::Value,
+                    field,
+                ),
+                property_value::<task_string_property::Entity>(
+                    task_string_property::Column::TaskId,
+                    task_string_property::Column::TaskPropertyName,
+                    task_string_property::Column::Value,
+                    field,
+                ),
+                property_value::<task_date_property::Entity>(
+                    task_date_property::Column::TaskId,
+                    task_date_property::Column::TaskPropertyName,
+                    task_date_property::Column::Value,
+                    field,
+                ),
+                property_value::<task_bool_property::Entity>(
+                    task_bool_property::Column::TaskId,
+                    task_bool_property::Column::TaskPropertyName,
+                    task_bool_property::Column::Value,
+                    field,
+                ),
+                property_value::<task_select_property::Entity>(
+                    task_select_property::Column::TaskId,
+                    task_select_property::Column::TaskPropertyName,
+                    task_select_property::Column::Value,
+                    field,
+                ),
+                property_value::<task_user_property::Entity>(
+                    task_user_property::Column::TaskId,
+                    task_user_property::Column::TaskPropertyName,
+                    task_user_property::Column::Value,
+                    field,
+                ),
+            ],
+        },
+    };
+    for expr in exprs {
+        QueryTrait::query(select).order_by_expr_with_nulls(expr, order.clone(), nulls);
+    }
+    Ok(())
+}
+
+pub async fn filter<C: ConnectionTrait>(
+    db: &C,
+    req: &FilterRequest,
+) -> Result<web::Json<FilterResponse>> {
+    req.filter.validate().map_err(filter_error)?;
+    // computed properties are expanded into the query, only needed if it refers to properties
+    let schema = if req.filter == Filter::None && req.sort.is_empty() {
+        Vec::new()
+    } else {
+        get_schema(db).await?
+    };
+    let mut select = task::Entity::find();
+    if req.filter != Filter::None {
+        select = select.filter(construct_filter(&req.filter, &schema)?);
+    }
+    for key in req.sort.iter() {
+        order_by_sort_key(&mut select, key, &schema)?;
+    }
+    // order ties by id so pages are stable
+    select = select.order_by_asc(task::Column::Id);
+    if req.offset > 0 {
+        select = select.offset(req.offset);
+    }
+    if let Some(limit) = req.limit {
+        // fetch one extra task to find out if there are more after this page
+        select = select.limit(limit + 1);
+    }
+
+    let mut tasks: Vec<task::Model> = select.all(db).await.map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("couldn't filter tasks: {}", e))
+    })?;
+
+    let mut more = false;
+    if let Some(limit) = req.limit {
+        more = tasks.len() as u64 > limit;
+        tasks.truncate(limit as usize);
+    }
+
+    Ok(web::Json(FilterResponse {
+        tasks: tasks.iter().map(|a| a.id).collect::<Vec<i32>>(),
+        offset: req.offset,
+        more,
+        req_id: req.req_id,
+    }))
+}
+
+/// get /filter endpoint for retrieving some number of TaskShorts
+#[get("/filter")]
+async fn get_filter_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<FilterRequest>,
+) -> Result<impl Responder> {
+    filter(data.get_ref(), &req).await
+}
+
+// every task a root (a task nothing depends on) transitively depends on, with each of its roots.
+// tasks on a dependency cycle without a root aren't in it
+const ROOTED: &str = r#"
+WITH RECURSIVE "rooted" ("task_id", "root_id") AS (
+    SELECT "id", "id" FROM "task"
+    WHERE NOT EXISTS (SELECT 1 FROM "dependency" WHERE "dependency"."depends_on_id" = "task"."id")
+    UNION
+    SELECT "dependency"."depends_on_id", "rooted"."root_id" FROM "dependency"
+    JOIN "rooted" ON "dependency"."task_id" = "rooted"."task_id"
+)"#;
+
+/// lowest root of a task, the task itself if it has none
+const ROOT_OF_TASK: &str = r#"COALESCE(
+    (SELECT MIN("rooted"."root_id") FROM "rooted" WHERE "rooted"."task_id" = "task"."id"),
+    "task"."id"
+)"#;
+
+/// columns of the aggregate query holding the grouped property, one for each typed table
+const GROUP_COLUMNS: [&str; 10] = [
+    "num",
+    "int",
+    "duration",
+    "string",
+    "date",
+    "bool",
+    "select",
+    "multiselect",
+    "ref",
+    "user",
+];
+
+/// the value of the grouped property selected in the aggregate query, from the column of its type
+fn group_value(row: &QueryResult) -> Result<Option<TaskPropVariant>, DbErr> {
+    let value = if let Some(value) = row.try_get::<Option<Decimal>>("", "num")? {
+        TaskPropVariant::Number(value.to_f64().unwrap_or_default())
+    } else if let Some(value) = row.try_get::<Option<i64>>("", "int")? {
+        TaskPropVariant::Integer(value)
+    } else if let Some(value) = row.try_get::<Option<i64>>("", "duration")? {
+        TaskPropVariant::Duration(TaskDuration(value))
+    } else if let Some(value) = row.try_get::<Option<String>>("", "string")? {
+        TaskPropVariant::String(value)
+    } else if let Some(value) = row.try_get::<Option<chrono::NaiveDateTime>>("", "date")? {
+        TaskPropVariant::Date(value)
+    } else if let Some(value) = row.try_get::<Option<bool>>("", "bool")? {
+        TaskPropVariant::Boolean(value)
+    } else if let Some(value) = row.try_get::<Option<String>>("", "select")? {
+        TaskPropVariant::Select(value)
+    } else if let Some(value) = row.try_get::<Option<Vec<String>>>("", "multiselect")? {
+        TaskPropVariant::MultiSelect(value)
+    } else if let Some(value) = row.try_get::<Option<TaskID>>("", "ref")? {
+        TaskPropVariant::TaskRef(value)
+    } else if let Some(value) = row.try_get::<Option<String>>("", "user")? {
+        TaskPropVariant::User(value)
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(value))
+}
+
+/// type of the values a summary reads, from the schema. Sums and averages of properties without a
+/// definition read numbers
+fn summary_type(summary: &Summary, schema: &[PropertyDefinition]) -> Result<TaskPropType> {
+    let (field, allowed, default) = match summary {
+        Summary::Sum(field) | Summary::Average(field) => (
+            field,
+            &[
+                TaskPropType::Number,
+                TaskPropType::Integer,
+                TaskPropType::Duration,
+            ][..],
+            TaskPropType::Number,
+        ),
+        Summary::Earliest(field) => (field, &[TaskPropType::Date][..], TaskPropType::Date),
+    };
+    let typ = schema
+        .iter()
+        .find(|definition| definition.name == *field)
+        .map_or(default, |definition| definition.typ);
+    if !allowed.contains(&typ) {
+        return Err(ErrorBadRequest(format!(
+            "can't summarize property {} of type {}",
+            field,
+            typ.as_str()
+        )));
+    }
+    Ok(typ)
+}
+
+/// value of a summary from its column of the aggregate query. Integers are summed to integers and averaged
+/// to numbers, durations stay durations
+fn summary_value(
+    summary: &Summary,
+    typ: TaskPropType,
+    row: &QueryResult,
+    column: &str,
+) -> Result<Option<TaskPropVariant>, DbErr> {
+    if let Summary::Earliest(_) = summary {
+        return Ok(row
+            .try_get::<Option<chrono::NaiveDateTime>>("", column)?
+            .map(TaskPropVariant::Date));
+    }
+    let Some(value) = row.try_get::<Option<Decimal>>("", column)? else {
+        return Ok(None);
+    };
+    Ok(match (summary, typ) {
+        (Summary::Sum(_), TaskPropType::Integer) => value.to_i64().map(TaskPropVariant::Integer),
+        (_, TaskPropType::Duration) => value
+            .round()
+            .to_i64()
+            .map(|seconds| TaskPropVariant::Duration(TaskDuration(seconds))),
+        _ => value.to_f64().map(TaskPropVariant::Number),
+    })
+}
+
+/// group every task matching the filter and summarize each group in a single query. Groups are ordered
+/// not completed first, by value in order of the lowest task id with tasks without the property last,
+/// or by root id. Only stored properties can be grouped and summarized, sums and averages of numbers,
+/// integers and durations and the earliest of dates.
+pub async fn aggregate(
+    db: &DatabaseConnection,
+    req: &AggregateRequest,
+) -> Result<AggregateResponse> {
+    req.filter.validate().map_err(filter_error)?;
+    let schema = if req.filter == Filter::None && req.summaries.is_empty() {
+        Vec::new()
+    } else {
+        get_schema(db).await?
+    };
+    let types = req
+        .summaries
+        .iter()
+        .map(|summary| summary_type(summary, &schema))
+        .collect::<Result<Vec<_>>>()?;
+    // one row for each task, grouped by the outer query
+    let mut select = task::Entity::find()
+        .select_only()
+        .column(task::Column::Id)
+        .column(task::Column::Completed);
+    if req.filter != Filter::None {
+        select = select.filter(construct_filter(&req.filter, &schema)?);
+    }
+    if let GroupBy::Property(field) = &req.group_by {
+        select = select
+            .column_as(
+                property_value::<task_num_property::Entity>(
+                    task_num_property::Column::TaskId,
+                    task_num_property::Column::TaskPropertyName,
+                    task_num_property::Column::Value,
+                    field,
+                ),
+                "num",
+            )
+            .column_as(
+                property_value::<task_int_property::Entity>(
+                    task_int_property::Column::TaskId,
+                    task_int_property::Column::TaskPropertyName,
+                    task_int_property::Column::Value,
+                    field,
+                ),
+                "int",
+            )
+            .column_as(
+                property_value::<task_duration_property::Entity>(
+                    task_duration_property::Column::TaskId,
+                    task_duration_property::Column::TaskPropertyName,
+                    task_duration_property::Column::Value,
+                    field,
+                ),
+                "duration",
+            )
+            .column_as(
+                property_value::<task_string_property::Entity>(
+                    task_string_property::Column::TaskId,
+                    task_string_property::Column::TaskPropertyName,
+                    task_string_property::Column::Value,
+                    field,
+                ),
+                "string",
+            )
+            .column_as(
+                property_value::<task_date_property::Entity>(
+                    task_date_property::Column::TaskId,
+                    task_date_property::Column::TaskPropertyName,
+                    task_date_property::Column::Value,
+                    field,
+                ),
+                "date",
+            )
+            .column_as(
+                property_value::<task_bool_property::Entity>(
+                    task_bool_property::Column::TaskId,
+                    task_bool_property::Column::TaskPropertyName,
+                    task_bool_property::Column::Value,
+                    field,
+                ),
+                "bool",
+            )
+            .column_as(
+                property_value::<task_select_property::Entity>(
+                    task_select_property::Column::TaskId,
+                    task_select_property::Column::TaskPropertyName,
+                    task_select_property::Column::Value,
+                    field,
+                ),
+                "select",
+            )
+            .column_as(
+                property_value::<task_multiselect_property::Entity>(
+                    task_multiselect_property::Column::TaskId,
+                    task_multiselect_property::Column::TaskPropertyName,
+                    task_multiselect_property::Column::Value,
+                    field,
+                ),
+                "multiselect",
+            )
+            .column_as(
+                property_value::<task_ref_property::Entity>(
+                    task_ref_property::Column::TaskId,
+                    task_ref_property::Column::TaskPropertyName,
+                    task_ref_property::Column::Value,
+                    field,
+                ),
+                "ref",
+            )
+            .column_as(
+                property_value::<task_user_property::Entity>(
+                    task_user_property::Column::TaskId,
+                    task_user_property::Column::TaskPropertyName,
+                    task_user_property::Column::Value,
+                    field,
+                ),
+                "user",
+            );
+    }
+    if req.group_by == GroupBy::DependencyRoot {
+        select = select.column_as(Expr::cust(ROOT_OF_TASK), "root");
+    }
+    for (i, (summary, typ)) in req.summaries.iter().zip(types.iter()).enumerate() {
+        let field = match summary {
+            Summary::Sum(field) | Summary::Average(field) | Summary::Earliest(field) => field,
+        };
+        let value = match typ {
+            TaskPropType::Integer => property_value::<task_int_property::Entity>(
+                task_int_property::Column::TaskId,
+                task_int_property::Column::TaskPropertyName,
+                task_int_property::Column::Value,
+                field,
+            ),
+            TaskPropType::Duration => property_value::<task_duration_property::Entity>(
+                task_duration_property::Column::TaskId,
+                task_duration_property::Column::TaskPropertyName,
+                task_duration_property::Column::Value,
+                field,
+            ),
+            TaskPropType::Date => property_value::<task_date_property::Entity>(
+                task_date_property::Column::TaskId,
+                task_date_property::Column::TaskPropertyName,
+                task_date_property::Column::Value,
+                field,
+            ),
+            _ => property_value::<task_num_property::Entity>(
+                task_num_property::Column::TaskId,
+                task_num_property::Column::TaskPropertyName,
+                task_num_property::Column::Value,
+                field,
+            ),
+        };
+        select = select.column_as(value, format!("summary{i}"));
+    }
+    let tasks = select.build(DbBackend::Postgres);
+    let mut values = tasks.values.map(|values| values.0).unwrap_or_default();
+
+    let keys = match &req.group_by {
+        GroupBy::None => vec![],
+        GroupBy::Completed => vec!["completed"],
+        GroupBy::Property(_) => GROUP_COLUMNS.to_vec(),
+        GroupBy::DependencyRoot => vec!["root"],
+    };
+    let keys = keys
+        .into_iter()
+        .map(|key| format!(r#""{key}""#))
+        .collect::<Vec<_>>();
+    let mut columns = keys.clone();
+    columns.push(r#"COUNT(*) AS "count", MIN("id") AS "first_id""#.to_owned());
+    // requested tasks of each group
+    let requested = if req.task_ids.is_empty() {
+        "FALSE".to_owned()
+    } else {
+        let params = (0..req.task_ids.len())
+            .map(|i| format!("${}", values.len() + i + 1))
+            .collect::<Vec<_>>();
+        values.extend(req.task_ids.iter().map(|task_id| (*task_id).into()));
+        format!(r#""id" IN ({})"#, params.join(", "))
+    };
+    columns.push(format!(
+        r#"array_agg("id") FILTER (WHERE {requested}) AS "requested""#
+    ));
+    for (i, summary) in req.summaries.iter().enumerate() {
+        let aggregate = match summary {
+            Summary::Sum(_) => "SUM",
+            Summary::Average(_) => "AVG",
+            Summary::Earliest(_) => "MIN",
+        };
+        columns.push(format!(r#"{aggregate}("summary{i}") AS "summary{i}""#));
+    }
+    let mut sql = format!(
+        r#"SELECT {} FROM ({}) AS "tasks""#,
+        columns.join(", "),
+        tasks.sql
+    );
+    if !keys.is_empty() {
+        sql.push_str(&format!(" GROUP BY {}", keys.join(", ")));
+    }
+    if req.group_by == GroupBy::DependencyRoot {
+        sql = format!("{ROOTED}\n{sql}");
+    }
+
+    let internal = |e: DbErr| ErrorInternalServerError(format!("couldn't aggregate tasks: {}", e));
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            values,
+        ))
+        .await
+        .map_err(internal)?;
+    let mut groups = Vec::new();
+    for row in rows.iter() {
+        let count = row.try_get::<i64>("", "count").map_err(internal)?;
+        // without grouping the query counts the tasks even if none match
+        if count == 0 {
+            continue;
+        }
+        let key = match &req.group_by {
+            GroupBy::None => GroupKey::All,
+            GroupBy::Completed => {
+                GroupKey::Completed(row.try_get::<bool>("", "completed").map_err(internal)?)
+            }
+            GroupBy::Property(_) => GroupKey::Value(group_value(row).map_err(internal)?),
+            GroupBy::DependencyRoot => {
+                GroupKey::Root(row.try_get::<TaskID>("", "root").map_err(internal)?)
+            }
+        };
+        let first_id = row.try_get::<TaskID>("", "first_id").map_err(internal)?;
+        let requested = row
+            .try_get::<Option<Vec<TaskID>>>("", "requested")
+            .map_err(internal)?
+            .unwrap_or_default();
+        let summaries = req
+            .summaries
+            .iter()
+            .zip(types.iter())
+            .enumerate()
+            .map(|(i, (summary, typ))| summary_value(summary, *typ, row, &format!("summary{i}")))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(internal)?;
+        groups.push((
+            first_id,
+            requested,
+            TaskGroup {
+                key,
+                count: count as u64,
+                summaries,
+            },
+        ));
+    }
+    groups.sort_by_key(|(first_id, _, group)| match group.key {
+        GroupKey::Completed(completed) => (completed as i64, 0),
+        GroupKey::Value(None) => (1, 0),
+        GroupKey::Root(root_id) => (0, root_id as i64),
+        _ => (0, *first_id as i64),
+    });
+
+    let task_groups = req
+        .task_ids
+        .iter()
+        .map(|task_id| {
+            groups
+                .iter()
+                .position(|(_, requested, _)| requested.contains(task_id))
+        })
+        .collect();
+    Ok(AggregateResponse {
+        groups: groups.into_iter().map(|(_, _, group)| group).collect(),
+        task_groups,
+        req_id: req.req_id,
+    })
+}
+
+/// get /aggregate endpoint grouping the tasks matching a filter and summarizing each group
+#[get("/aggregate")]
+async fn get_aggregate_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<AggregateRequest>,
+) -> Result<web::Json<AggregateResponse>> {
+    Ok(web::Json(aggregate(&data, &req).await?))
+}
+
+/// ranks tasks by full-text matches on their title and string properties, and takes a highlighted
+/// snippet from the best matching field of each task. Uses the GIN indexes on both tables.
+const SEARCH_QUERY: &str = r#"
+WITH query AS (
+    SELECT websearch_to_tsquery('english', $1) AS q
+),
+matches AS (
+    SELECT task.id AS task_id, NULL::varchar AS field, task.title AS body,
+        ts_rank(to_tsvector('english', task.title), query.q) AS rank
+    FROM task, query
+    WHERE to_tsvector('english', task.title) @@ query.q
+    UNION ALL
+    SELECT p.task_id, p.task_property_name, p.value,
+        ts_rank(to_tsvector('english', p.value), query.q)
+    FROM task_string_property p, query
+    WHERE to_tsvector('english', p.value) @@ query.q
+),
+best AS (
+    SELECT DISTINCT ON (task_id) task_id, field, body,
+        SUM(rank) OVER (PARTITION BY task_id) AS total
+    FROM matches
+    ORDER BY task_id, rank DESC
+)
+SELECT best.task_id, best.field, ts_headline('english', best.body, query.q, $2) AS headline,
+    best.total AS rank
+FROM best, query
+ORDER BY best.total DESC, best.task_id
+LIMIT $3
+"#;
+/// markers ts_headline places around matched words, stripped out into `SearchResult::highlights`
+const HIGHLIGHT_START: char = '\u{2}';
+const HIGHLIGHT_STOP: char = '\u{3}';
+
+#[derive(Debug, FromQueryResult)]
+struct SearchRow {
+    task_id: i32,
+    field: Option<String>,
+    headline: String,
+    rank: f32,
+}
+
+/// strip highlight markers out of a headline, returning the snippet and the byte ranges that were highlighted
+fn split_headline(headline: &str) -> (String, Vec<(usize, usize)>) {
+    let mut snippet = String::with_capacity(headline.len());
+    let mut highlights = vec![];
+    let mut start = None;
+    for c in headline.chars() {
+        match c {
+            HIGHLIGHT_START => start = Some(snippet.len()),
+            HIGHLIGHT_STOP => {
+                if let Some(start) = start.take() {
+                    highlights.push((start, snippet.len()));
+                }
+            }
+            c => snippet.push(c),
         }
-        Filter::None => Ok(Condition::any()),
     }
+    (snippet, highlights)
 }
 
-pub async fn filter(
+pub async fn search(
     db: &DatabaseConnection,
-    req: &FilterRequest,
-) -> Result<web::Json<FilterResponse>> {
-    if let Filter::None = req.filter {
-        let tasks = task::Entity::find()
-            .all(db)
-            .await
-            .map_err(ErrorInternalServerError)?
-            .iter()
-            .map(|a| a.id)
-            .collect();
-        return Ok(web::Json(FilterResponse {
-            tasks,
-            req_id: req.req_id,
-        }));
-    }
-
-    let filter = construct_filter(&req.filter)?;
-
-    let tasks: Vec<task::Model> = task::Entity::find()
-        .join(
-            sea_orm::JoinType::LeftJoin,
-            task::Relation::TaskNumProperty.def(),
-        )
-        .join(
-            sea_orm::JoinType::LeftJoin,
-            task::Relation::TaskBoolProperty.def(),
-        )
-        .join(
-            sea_orm::JoinType::LeftJoin,
-            task::Relation::TaskStringProperty.def(),
-        )
-        .join(
-            sea_orm::JoinType::LeftJoin,
-            task::Relation::TaskDateProperty.def(),
-        )
-        .filter(filter)
-        .all(db)
-        .await
-        .map_err(|e| {
-            actix_web::error::ErrorInternalServerError(format!("couldn't filter tasks: {}", e))
-        })?;
+    req: &SearchRequest,
+) -> Result<web::Json<SearchResponse>> {
+    let rows = SearchRow::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        SEARCH_QUERY,
+        [
+            req.query.clone().into(),
+            format!("StartSel={HIGHLIGHT_START}, StopSel={HIGHLIGHT_STOP}").into(),
+            req.limit.map(|limit| limit as i64).into(),
+        ],
+    ))
+    .all(db)
+    .await
+    .map_err(|e| ErrorInternalServerError(format!("couldn't search tasks: {}", e)))?;
 
-    Ok(web::Json(FilterResponse {
-        tasks: tasks.iter().map(|a| a.id).collect::<Vec<i32>>(),
+    Ok(web::Json(SearchResponse {
+        results: rows
+            .into_iter()
+            .map(|row| {
+                let (snippet, highlights) = split_headline(&row.headline);
+                SearchResult {
+                    task_id: row.task_id,
+                    field: row.field,
+                    snippet,
+                    highlights,
+                    rank: row.rank,
+                }
+            })
+            .collect(),
         req_id: req.req_id,
     }))
 }
 
-/// get /filter endpoint for retrieving some number of TaskShorts
-#[get("/filter")]
-async fn get_filter_request(
+/// get /search endpoint for full-text searching task titles and string properties
+#[get("/search")]
+async fn get_search_request(
     data: web::Data<DatabaseConnection>,
-    req: web::Json<FilterRequest>,
+    req: web::Json<SearchRequest>,
 ) -> Result<impl Responder> {
-    filter(&data, &req).await
+    search(&data, &req).await
 }
 
-async fn get_property_or_err(
-    db: &DatabaseConnection,
+async fn get_property_or_err<C: ConnectionTrait>(
+    db: &C,
     prop: &String,
     task_id: i32,
 ) -> Result<Option<TaskPropVariant>, ()> {
@@ -697,7 +3534,7 @@ async fn get_property_or_err(
         "get_property_or_err, prop: {:?}, task_id: {:?}",
         prop, task_id
     );
-    let typ = task_property::Entity::find()
+    let typ = match task_property::Entity::find()
         .filter(
             Condition::all()
                 .add(task_property::Column::TaskId.eq(task_id))
@@ -706,11 +3543,14 @@ async fn get_property_or_err(
         .one(db)
         .await
         .map_err(|_| ())?
-        .ok_or(())?
-        .typ;
+    {
+        Some(model) => model.typ,
+        // computed properties aren't stored
+        None => return get_computed_property(db, prop, task_id).await,
+    };
 
-    let res = match typ.as_str() {
-        "string" => TaskPropVariant::String(
+    let res = match typ.parse::<TaskPropType>().map_err(|_| ())? {
+        TaskPropType::String => TaskPropVariant::String(
             task_string_property::Entity::find()
                 .filter(
                     Condition::all()
@@ -723,7 +3563,7 @@ async fn get_property_or_err(
                 .ok_or(())?
                 .value,
         ),
-        "number" => TaskPropVariant::Number(
+        TaskPropType::Number => TaskPropVariant::Number(
             Decimal::to_f64(
                 &task_num_property::Entity::find()
                     .filter(
@@ -739,7 +3579,33 @@ async fn get_property_or_err(
             )
             .unwrap(),
         ),
-        "date" => TaskPropVariant::Date(
+        TaskPropType::Integer => TaskPropVariant::Integer(
+            task_int_property::Entity::find()
+                .filter(
+                    Condition::all()
+                        .add(task_int_property::Column::TaskId.eq(task_id))
+                        .add(task_int_property::Column::TaskPropertyName.eq(prop)),
+                )
+                .one(db)
+                .await
+                .map_err(|_| ())?
+                .ok_or(())?
+                .value,
+        ),
+        TaskPropType::Duration => TaskPropVariant::Duration(TaskDuration(
+            task_duration_property::Entity::find()
+                .filter(
+                    Condition::all()
+                        .add(task_duration_property::Column::TaskId.eq(task_id))
+                        .add(task_duration_property::Column::TaskPropertyName.eq(prop)),
+                )
+                .one(db)
+                .await
+                .map_err(|_| ())?
+                .ok_or(())?
+                .value,
+        )),
+        TaskPropType::Date => TaskPropVariant::Date(
             task_date_property::Entity::find()
                 .filter(
                     Condition::all()
@@ -752,7 +3618,7 @@ async fn get_property_or_err(
                 .ok_or(())?
                 .value,
         ),
-        "boolean" => TaskPropVariant::Boolean(
+        TaskPropType::Boolean => TaskPropVariant::Boolean(
             task_bool_property::Entity::find()
                 .filter(
                     Condition::all()
@@ -765,13 +3631,110 @@ async fn get_property_or_err(
                 .ok_or(())?
                 .value,
         ),
-        _ => unreachable!(),
+        TaskPropType::Select => TaskPropVariant::Select(
+            task_select_property::Entity::find()
+                .filter(
+                    Condition::all()
+                        .add(task_select_property::Column::TaskId.eq(task_id))
+                        .add(task_select_property::Column::TaskPropertyName.eq(prop)),
+                )
+                .one(db)
+                .await
+                .map_err(|_| ())?
+                .ok_or(())?
+                .value,
+        ),
+        TaskPropType::MultiSelect => TaskPropVariant::MultiSelect(
+            task_multiselect_property::Entity::find()
+                .filter(
+                    Condition::all()
+                        .add(task_multiselect_property::Column::TaskId.eq(task_id))
+                        .add(task_multiselect_property::Column::TaskPropertyName.eq(prop)),
+                )
+                .one(db)
+                .await
+                .map_err(|_| ())?
+                .ok_or(())?
+                .value,
+        ),
+        // missing if the referenced task was deleted
+        TaskPropType::TaskRef => TaskPropVariant::TaskRef(
+            task_ref_property::Entity::find()
+                .filter(
+                    Condition::all()
+                        .add(task_ref_property::Column::TaskId.eq(task_id))
+                        .add(task_ref_property::Column::TaskPropertyName.eq(prop)),
+                )
+                .one(db)
+                .await
+                .map_err(|_| ())?
+                .ok_or(())?
+                .value,
+        ),
+        TaskPropType::TaskRefs => TaskPropVariant::TaskRefs(
+            task_refs_property::Entity::find()
+                .filter(
+                    Condition::all()
+                        .add(task_refs_property::Column::TaskId.eq(task_id))
+                        .add(task_refs_property::Column::TaskPropertyName.eq(prop)),
+                )
+                .order_by_asc(task_refs_property::Column::Value)
+                .all(db)
+                .await
+                .map_err(|_| ())?
+                .into_iter()
+                .map(|model| model.value)
+                .collect(),
+        ),
+        TaskPropType::User => TaskPropVariant::User(
+            task_user_property::Entity::find()
+                .filter(
+                    Condition::all()
+                        .add(task_user_property::Column::TaskId.eq(task_id))
+                        .add(task_user_property::Column::TaskPropertyName.eq(prop)),
+                )
+                .one(db)
+                .await
+                .map_err(|_| ())?
+                .ok_or(())?
+                .value,
+        ),
     };
 
     info!("get_property_or_err, res: {:?}", res);
     Ok(Some(res))
 }
 
+#[derive(Debug, FromQueryResult)]
+struct ComputedRow {
+    value: Option<f64>,
+}
+
+/// evaluate the computed property `prop` of a task, errors if it isn't a computed property
+async fn get_computed_property<C: ConnectionTrait>(
+    db: &C,
+    prop: &str,
+    task_id: TaskID,
+) -> Result<Option<TaskPropVariant>, ()> {
+    let schema = get_schema(db).await.map_err(|_| ())?;
+    let value = computed_value(&schema, prop, Expr::val(task_id).into(), 0)
+        .map_err(|_| ())?
+        .ok_or(())?;
+    let query = Query::select()
+        .expr_as(
+            Func::cast_as(value, Alias::new("DOUBLE PRECISION")),
+            Alias::new("value"),
+        )
+        .to_owned();
+    let row = ComputedRow::find_by_statement(db.get_database_backend().build(&query))
+        .one(db)
+        .await
+        .map_err(|_| ())?
+        .ok_or(())?;
+    info!("get_computed_property, res: {:?}", row.value);
+    Ok(row.value.map(TaskPropVariant::Number))
+}
+
 #[get("/prop")]
 async fn get_property_request(
     data: web::Data<DatabaseConnection>,
@@ -823,6 +3786,346 @@ async fn get_properties_request(
     Ok(web::Json(res))
 }
 
+fn definition_from_model(model: property_schema::Model) -> Result<PropertyDefinition> {
+    let invalid = |e: serde_json::Error| {
+        ErrorInternalServerError(format!(
+            "invalid definition of property {}: {}",
+            model.name, e
+        ))
+    };
+    Ok(PropertyDefinition {
+        typ: model.typ.parse().map_err(ErrorInternalServerError)?,
+        default: model
+            .default_value
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()
+            .map_err(invalid)?,
+        allowed_values: serde_json::from_str(&model.allowed_values).map_err(invalid)?,
+        options: serde_json::from_str(&model.options).map_err(invalid)?,
+        formula: model.formula.clone(),
+        description: model.description.clone(),
+        required: model.required,
+        name: model.name.clone(),
+    })
+}
+
+/// get every property definition of the workspace, ordered by name
+pub async fn get_schema<C: ConnectionTrait>(db: &C) -> Result<Vec<PropertyDefinition>> {
+    property_schema::Entity::find()
+        .order_by_asc(property_schema::Column::Name)
+        .all(db)
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't fetch schema: {}", e)))?
+        .into_iter()
+        .map(definition_from_model)
+        .collect()
+}
+
+/// get /schema endpoint returns every property definition
+#[get("/schema")]
+async fn get_schema_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<GetSchemaRequest>,
+) -> Result<web::Json<GetSchemaResponse>> {
+    Ok(web::Json(GetSchemaResponse {
+        definitions: get_schema(data.get_ref()).await?,
+        req_id: req.to_owned(),
+    }))
+}
+
+/// create or replace a property definition.
+/// Fails if tasks already have values of the property with a different type,
+/// or any values if the property is computed, or if its formula refers back to the property.
+pub async fn define_property(db: &DatabaseConnection, req: &DefinePropertyRequest) -> Result<()> {
+    let definition = &req.definition;
+    definition.validate().map_err(ErrorBadRequest)?;
+    let mut conflicting = task_property::Entity::find()
+        .filter(task_property::Column::Name.eq(definition.name.to_owned()));
+    if definition.formula.is_none() {
+        conflicting = conflicting.filter(task_property::Column::Typ.ne(definition.typ.as_str()));
+    }
+    let conflicting = conflicting
+        .count(db)
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't fetch properties: {}", e)))?;
+    if conflicting > 0 && definition.formula.is_some() {
+        return Err(ErrorBadRequest(format!(
+            "{} tasks have values of computed property {}",
+            conflicting, definition.name
+        )));
+    }
+    if conflicting > 0 {
+        return Err(ErrorBadRequest(format!(
+            "{} tasks have values of property {} that aren't of type {}",
+            conflicting,
+            definition.name,
+            definition.typ.as_str()
+        )));
+    }
+    if definition.formula.is_some() {
+        let mut schema = get_schema(db).await?;
+        schema.retain(|other| other.name != definition.name);
+        schema.push(definition.clone());
+        if formula_reads(
+            &schema,
+            &definition.name,
+            &definition.name,
+            &mut HashSet::new(),
+        ) {
+            return Err(ErrorBadRequest(format!(
+                "formula of property {} refers to itself",
+                definition.name
+            )));
+        }
+    }
+    property_schema::Entity::insert(property_schema::ActiveModel {
+        name: Set(definition.name.to_owned()),
+        typ: Set(definition.typ.as_str().to_owned()),
+        description: Set(definition.description.to_owned()),
+        default_value: Set(definition
+            .default
+            .as_ref()
+            .map(|default| serde_json::to_string(default).unwrap())),
+        required: Set(definition.required),
+        allowed_values: Set(serde_json::to_string(&definition.allowed_values).unwrap()),
+        options: Set(serde_json::to_string(&definition.options).unwrap()),
+        formula: Set(definition.formula.to_owned()),
+    })
+    .on_conflict(
+        OnConflict::column(property_schema::Column::Name)
+            .update_columns([
+                property_schema::Column::Typ,
+                property_schema::Column::Description,
+                property_schema::Column::DefaultValue,
+                property_schema::Column::Required,
+                property_schema::Column::AllowedValues,
+                property_schema::Column::Options,
+                property_schema::Column::Formula,
+            ])
+            .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await
+    .map_err(|e| ErrorInternalServerError(format!("couldn't define property: {}", e)))?;
+    Ok(())
+}
+
+#[post("/schema")]
+async fn define_property_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<DefinePropertyRequest>,
+) -> Result<web::Json<DefinePropertyResponse>> {
+    info!("define_property_request, req: {:?}", req);
+    define_property(&data, &req).await?;
+    Ok(web::Json(req.req_id))
+}
+
+/// delete /schema endpoint removes a property definition, tasks keep their values of the property
+#[delete("/schema")]
+async fn delete_property_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<DeletePropertyRequest>,
+) -> Result<web::Json<DeletePropertyResponse>> {
+    let res = property_schema::Entity::delete_by_id(req.to_owned())
+        .exec(data.as_ref())
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't delete definition: {}", e)))?;
+    if res.rows_affected == 0 {
+        return Err(ErrorNotFound(format!(
+            "no property definition named {}",
+            req
+        )));
+    }
+    Ok(web::Json(()))
+}
+
+/// get every global property, ordered by name
+pub async fn get_globals(db: &DatabaseConnection) -> Result<Vec<TaskProp>> {
+    let error = |e: DbErr| ErrorInternalServerError(format!("couldn't fetch globals: {}", e));
+    let mut globals = vec![];
+    for model in global_string_property::Entity::find()
+        .all(db)
+        .await
+        .map_err(error)?
+    {
+        globals.push(TaskProp {
+            name: model.property_name,
+            value: TaskPropVariant::String(model.value),
+        });
+    }
+    for model in global_num_property::Entity::find()
+        .all(db)
+        .await
+        .map_err(error)?
+    {
+        globals.push(TaskProp {
+            name: model.property_name,
+            value: TaskPropVariant::Number(model.value.to_f64().unwrap_or_default()),
+        });
+    }
+    for model in global_date_property::Entity::find()
+        .all(db)
+        .await
+        .map_err(error)?
+    {
+        globals.push(TaskProp {
+            name: model.property_name,
+            value: TaskPropVariant::Date(model.value),
+        });
+    }
+    for model in global_bool_property::Entity::find()
+        .all(db)
+        .await
+        .map_err(error)?
+    {
+        globals.push(TaskProp {
+            name: model.property_name,
+            value: TaskPropVariant::Boolean(model.value),
+        });
+    }
+    globals.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(globals)
+}
+
+/// get /globals endpoint returns every global property
+#[get("/globals")]
+async fn get_globals_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<GetGlobalsRequest>,
+) -> Result<web::Json<GetGlobalsResponse>> {
+    Ok(web::Json(GetGlobalsResponse {
+        globals: get_globals(&data).await?,
+        req_id: req.to_owned(),
+    }))
+}
+
+/// create or overwrite a global property.
+/// Globals are strings, numbers, dates or booleans, and keep the type they were created with.
+pub async fn set_global(db: &DatabaseConnection, global: &TaskProp) -> Result<()> {
+    let typ = global.value.prop_type();
+    if !matches!(
+        global.value,
+        TaskPropVariant::String(_)
+            | TaskPropVariant::Number(_)
+            | TaskPropVariant::Date(_)
+            | TaskPropVariant::Boolean(_)
+    ) {
+        return Err(ErrorBadRequest(format!(
+            "global properties can't be of type {}",
+            typ.as_str()
+        )));
+    }
+    let model = global_property::Entity::find_by_id(global.name.to_owned())
+        .one(db)
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't fetch global: {}", e)))?;
+    match model {
+        Some(model) if model.typ != typ.as_str() => {
+            return Err(ErrorBadRequest(format!(
+                "global {} has wrong type (expecting {})",
+                global.name, model.typ
+            )));
+        }
+        Some(_) => {}
+        None => {
+            global_property::Entity::insert(global_property::ActiveModel {
+                name: Set(global.name.to_owned()),
+                typ: Set(typ.as_str().to_owned()),
+            })
+            .exec_without_returning(db)
+            .await
+            .map_err(|e| ErrorInternalServerError(format!("couldn't create global: {}", e)))?;
+        }
+    }
+    let name = global.name.to_owned();
+    let res = match &global.value {
+        TaskPropVariant::String(val) => {
+            global_string_property::Entity::insert(global_string_property::ActiveModel {
+                property_name: Set(name),
+                value: Set(val.to_owned()),
+            })
+            .on_conflict(
+                OnConflict::column(global_string_property::Column::PropertyName)
+                    .update_column(global_string_property::Column::Value)
+                    .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await
+        }
+        TaskPropVariant::Number(val) => {
+            let value = Decimal::from_f64(*val)
+                .ok_or_else(|| ErrorBadRequest(format!("can't store number {}", val)))?;
+            global_num_property::Entity::insert(global_num_property::ActiveModel {
+                property_name: Set(name),
+                value: Set(value),
+            })
+            .on_conflict(
+                OnConflict::column(global_num_property::Column::PropertyName)
+                    .update_column(global_num_property::Column::Value)
+                    .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await
+        }
+        TaskPropVariant::Date(val) => {
+            global_date_property::Entity::insert(global_date_property::ActiveModel {
+                property_name: Set(name),
+                value: Set(*val),
+            })
+            .on_conflict(
+                OnConflict::column(global_date_property::Column::PropertyName)
+                    .update_column(global_date_property::Column::Value)
+                    .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await
+        }
+        TaskPropVariant::Boolean(val) => {
+            global_bool_property::Entity::insert(global_bool_property::ActiveModel {
+                property_name: Set(name),
+                value: Set(*val),
+            })
+            .on_conflict(
+                OnConflict::column(global_bool_property::Column::PropertyName)
+                    .update_column(global_bool_property::Column::Value)
+                    .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await
+        }
+        _ => unreachable!("other types are rejected above"),
+    };
+    res.map_err(|e| ErrorInternalServerError(format!("couldn't write global: {}", e)))?;
+    Ok(())
+}
+
+#[put("/global")]
+async fn set_global_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<SetGlobalRequest>,
+) -> Result<web::Json<SetGlobalResponse>> {
+    info!("set_global_request, req: {:?}", req);
+    set_global(&data, &req.global).await?;
+    Ok(web::Json(req.req_id))
+}
+
+/// delete /global endpoint removes a global property and its value
+#[delete("/global")]
+async fn delete_global_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<DeleteGlobalRequest>,
+) -> Result<web::Json<DeleteGlobalResponse>> {
+    let res = global_property::Entity::delete_by_id(req.to_owned())
+        .exec(data.as_ref())
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't delete global: {}", e)))?;
+    if res.rows_affected == 0 {
+        return Err(ErrorNotFound(format!("no global property named {}", req)));
+    }
+    Ok(web::Json(()))
+}
+
 #[get("/views")]
 async fn get_views_request(
     data: web::Data<DatabaseConnection>,
@@ -842,6 +4145,10 @@ async fn get_views_request(
                 view_id: view.id,
                 filter: serde_json::from_str(&view.filter).unwrap(),
                 props: view.properties.clone(),
+                sort: serde_json::from_str(&view.sort).unwrap_or_default(),
+                max_tasks: view.max_tasks.map(|max| max as u64),
+                group_by: serde_json::from_str(&view.group_by).unwrap_or_default(),
+                summaries: serde_json::from_str(&view.summaries).unwrap_or_default(),
             })
             .collect(),
     }))
@@ -856,6 +4163,10 @@ async fn create_view_request(
         name: Set(req.name.clone()),
         properties: Set(req.props.clone()),
         filter: Set(serde_json::to_string(&req.filter).unwrap()),
+        sort: Set(serde_json::to_string(&req.sort).unwrap()),
+        max_tasks: Set(req.max_tasks.map(|max| max as i64)),
+        group_by: Set(serde_json::to_string(&req.group_by).unwrap()),
+        summaries: Set(serde_json::to_string(&req.summaries).unwrap()),
     };
     let res = view::Entity::insert(view_model)
         .exec(data.as_ref())
@@ -881,6 +4192,10 @@ async fn update_view_request(
     view.name = Set(req.view.name.clone());
     view.properties = Set(req.view.props.clone());
     view.filter = Set(serde_json::to_string(&req.view.filter).unwrap());
+    view.sort = Set(serde_json::to_string(&req.view.sort).unwrap());
+    view.max_tasks = Set(req.view.max_tasks.map(|max| max as i64));
+    view.group_by = Set(serde_json::to_string(&req.view.group_by).unwrap());
+    view.summaries = Set(serde_json::to_string(&req.view.summaries).unwrap());
     view.update(data.as_ref())
         .await
         .map_err(ErrorInternalServerError)?;
@@ -906,6 +4221,189 @@ async fn delete_view_request(
     Ok(web::Json(()))
 }
 
+/// digits of ranks in ascending byte order, so ranks compare like the strings they are
+const RANK_DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// fractional index strictly between `low` and `high`, where "" is before and None is after every rank.
+/// ranks never end in the lowest digit, so there is always room before them
+fn rank_between(low: &str, high: Option<&str>) -> String {
+    let digit = |c: u8| RANK_DIGITS.iter().position(|d| *d == c).unwrap_or(0);
+    if let Some(high) = high {
+        // keep the common prefix, reading missing digits of `low` as the lowest digit
+        let padded_low = low.bytes().chain(std::iter::repeat(RANK_DIGITS[0]));
+        let common = high
+            .bytes()
+            .zip(padded_low)
+            .take_while(|(h, l)| h == l)
+            .count();
+        if common > 0 {
+            let rest = rank_between(low.get(common..).unwrap_or(""), Some(&high[common..]));
+            return format!("{}{rest}", &high[..common]);
+        }
+    }
+    let low_digit = low.bytes().next().map_or(0, digit);
+    let high_digit = high
+        .and_then(|high| high.bytes().next())
+        .map_or(RANK_DIGITS.len(), digit);
+    if high_digit - low_digit > 1 {
+        return char::from(RANK_DIGITS[(low_digit + high_digit).div_ceil(2)]).to_string();
+    }
+    match high {
+        // the first digit of `high` alone is already between them
+        Some(high) if high.len() > 1 => high[..1].to_owned(),
+        _ => {
+            let rest = rank_between(low.get(1..).unwrap_or(""), None);
+            format!("{}{rest}", char::from(RANK_DIGITS[low_digit]))
+        }
+    }
+}
+
+/// place a task between two others in the manual order of a view and put the view in manual order.
+/// Tasks shown before the moved one that were never moved are ranked too, so they keep their place.
+/// Fails with a conflict if the neighbours aren't next to each other in the view on the server.
+pub async fn move_task(db: &DatabaseConnection, req: &MoveTaskRequest) -> Result<Vec<SortKey>> {
+    // moves within a view are serialized on its row, so each one ranks against the ranks of the last
+    let txn = db
+        .begin()
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't begin transaction: {}", e)))?;
+    let view = view::Entity::find_by_id(req.view_id)
+        .lock_exclusive()
+        .one(&txn)
+        .await
+        .map_err(ErrorInternalServerError)?
+        .ok_or_else(|| ErrorNotFound(format!("no view with id {}", req.view_id)))?;
+    let manual = SortKey {
+        field: SortField::Rank(req.view_id),
+        descending: false,
+        nulls_first: false,
+    };
+    let mut sort: Vec<SortKey> = serde_json::from_str(&view.sort).unwrap_or_default();
+    let sort_changed = sort.first() != Some(&manual);
+    if sort_changed {
+        sort.retain(|key| key.field != manual.field);
+        sort.insert(0, manual);
+    }
+    let mut order = filter(
+        &txn,
+        &FilterRequest {
+            filter: serde_json::from_str(&view.filter).map_err(ErrorInternalServerError)?,
+            sort: sort.clone(),
+            limit: None,
+            offset: 0,
+            req_id: 0,
+        },
+    )
+    .await?
+    .into_inner()
+    .tasks;
+    if !order.contains(&req.task_id) {
+        return Err(ErrorNotFound(format!(
+            "task {} isn't in view {}",
+            req.task_id, req.view_id
+        )));
+    }
+    order.retain(|task_id| *task_id != req.task_id);
+    let index = match req.after {
+        Some(after) => order
+            .iter()
+            .position(|task_id| *task_id == after)
+            .map(|i| i + 1),
+        None => Some(0),
+    }
+    .filter(|i| order.get(*i).copied() == req.before)
+    .ok_or_else(|| ErrorConflict("the order of the view has changed"))?;
+
+    let mut ranks = view_rank::Entity::find()
+        .filter(view_rank::Column::ViewId.eq(req.view_id))
+        .all(&txn)
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't fetch ranks: {}", e)))?
+        .into_iter()
+        .map(|rank| (rank.task_id, rank.rank))
+        .collect::<HashMap<TaskID, String>>();
+    ranks.remove(&req.task_id);
+    // ranks of tasks the view's filter hides are taken too
+    let mut taken = ranks.values().cloned().collect::<BTreeSet<String>>();
+    let mut next_rank = |low: &str| {
+        let high = taken
+            .range::<str, _>((Bound::Excluded(low), Bound::Unbounded))
+            .next()
+            .cloned();
+        let rank = rank_between(low, high.as_deref());
+        taken.insert(rank.clone());
+        rank
+    };
+    // ranked tasks are shown first, so every task before one that was never moved is ranked
+    let mut new_ranks = vec![];
+    let mut low = String::new();
+    for task_id in &order[..index] {
+        low = match ranks.get(task_id) {
+            Some(rank) => rank.clone(),
+            None => {
+                let rank = next_rank(&low);
+                new_ranks.push((*task_id, rank.clone()));
+                rank
+            }
+        };
+    }
+    new_ranks.push((req.task_id, next_rank(&low)));
+    view_rank::Entity::insert_many(new_ranks.into_iter().map(|(task_id, rank)| {
+        view_rank::ActiveModel {
+            view_id: Set(req.view_id),
+            task_id: Set(task_id),
+            rank: Set(rank),
+        }
+    }))
+    .on_conflict(
+        OnConflict::columns([view_rank::Column::ViewId, view_rank::Column::TaskId])
+            .update_column(view_rank::Column::Rank)
+            .to_owned(),
+    )
+    .exec_without_returning(&txn)
+    .await
+    .map_err(|e| match e.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => {
+            ErrorConflict("the order of the view has changed")
+        }
+        _ => ErrorInternalServerError(format!("couldn't rank tasks: {}", e)),
+    })?;
+
+    if sort_changed {
+        let mut view: view::ActiveModel = view.into();
+        view.sort = Set(serde_json::to_string(&sort).unwrap());
+        view.update(&txn)
+            .await
+            .map_err(|e| ErrorInternalServerError(format!("couldn't update view: {}", e)))?;
+    }
+    txn.commit()
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("couldn't rank tasks: {}", e)))?;
+    Ok(sort)
+}
+
+/// put /rank endpoint moves a task within the manual order of a view
+#[put("/rank")]
+async fn move_task_request(
+    data: web::Data<DatabaseConnection>,
+    req: web::Json<MoveTaskRequest>,
+) -> Result<web::Json<MoveTaskResponse>> {
+    let sort = move_task(&data, &req).await?;
+    Ok(web::Json(MoveTaskResponse {
+        sort,
+        req_id: req.req_id,
+    }))
+}
+
+#[cfg(test)]
+#[path = "./tests/test_aggregate.rs"]
+mod test_aggregate;
+#[cfg(test)]
+#[path = "./tests/test_attachments.rs"]
+mod test_attachments;
+#[cfg(test)]
+#[path = "./tests/test_comments.rs"]
+mod test_comments;
 #[cfg(test)]
 #[path = "./tests/test_create.rs"]
 mod test_create;
@@ -916,9 +4414,30 @@ mod test_delete;
 #[path = "./tests/test_filter.rs"]
 mod test_filter;
 #[cfg(test)]
+#[path = "./tests/test_globals.rs"]
+mod test_globals;
+#[cfg(test)]
+#[path = "./tests/test_notifications.rs"]
+mod test_notifications;
+#[cfg(test)]
 #[path = "./tests/test_props.rs"]
 mod test_props;
 #[cfg(test)]
+#[path = "./tests/test_recurrence.rs"]
+mod test_recurrence;
+#[cfg(test)]
+#[path = "./tests/test_schema.rs"]
+mod test_schema;
+#[cfg(test)]
+#[path = "./tests/test_search.rs"]
+mod test_search;
+#[cfg(test)]
+#[path = "./tests/test_subtasks.rs"]
+mod test_subtasks;
+#[cfg(test)]
+#[path = "./tests/test_tags.rs"]
+mod test_tags;
+#[cfg(test)]
 #[path = "./tests/test_update.rs"]
 mod test_update;
 #[cfg(test)]
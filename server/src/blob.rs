@@ -0,0 +1,160 @@
+//! Content-addressed storage for attachment contents.
+//!
+//! Each blob is a file named by the hex SHA-256 hash of its content, under a subdirectory named by the
+//! first two digits of the hash so no directory grows too large. Identical files are stored once,
+//! however many tasks they are attached to.
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use sha2::{Digest, Sha256};
+
+/// where attachment contents live and how large they may be
+#[derive(Debug, Clone)]
+pub struct BlobStore {
+    dir: PathBuf,
+    /// largest blob accepted, in bytes
+    pub max_size: u64,
+}
+
+impl BlobStore {
+    pub fn new(dir: impl Into<PathBuf>, max_size: u64) -> Self {
+        Self {
+            dir: dir.into(),
+            max_size,
+        }
+    }
+    /// hex SHA-256 hash of some content
+    pub fn hash(content: &[u8]) -> String {
+        hex::encode(Sha256::digest(content))
+    }
+    fn path(&self, hash: &str) -> io::Result<PathBuf> {
+        // hashes come from the database, but never let one escape the directory
+        if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{hash} is not a blob hash"),
+            ));
+        }
+        Ok(self.dir.join(&hash[..2]).join(hash))
+    }
+    /// store content, returning its hash. content already stored isn't written again
+    pub async fn put(&self, content: &[u8]) -> io::Result<String> {
+        let hash = Self::hash(content);
+        if self.contains(&hash).await? {
+            return Ok(hash);
+        }
+        let path = self.path(&hash)?;
+        let parent = path.parent().unwrap_or(Path::new("."));
+        tokio::fs::create_dir_all(parent).await?;
+        // write next to the blob and rename, so a blob is never seen half written. Each write gets a
+        // name of its own, concurrent uploads of the same content would otherwise share one
+        static WRITES: AtomicU64 = AtomicU64::new(0);
+        let partial = parent.join(format!(
+            "{hash}.{}.{}.partial",
+            std::process::id(),
+            WRITES.fetch_add(1, Ordering::Relaxed)
+        ));
+        tokio::fs::write(&partial, content).await?;
+        tokio::fs::rename(&partial, &path).await?;
+        Ok(hash)
+    }
+    /// whether content is stored under a hash
+    pub async fn contains(&self, hash: &str) -> io::Result<bool> {
+        tokio::fs::try_exists(self.path(hash)?).await
+    }
+    /// content stored under a hash
+    pub async fn get(&self, hash: &str) -> io::Result<Vec<u8>> {
+        tokio::fs::read(self.path(hash)?).await
+    }
+    /// delete the content stored under a hash, if any
+    pub async fn remove(&self, hash: &str) -> io::Result<()> {
+        match tokio::fs::remove_file(self.path(hash)?).await {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            res => res,
+        }
+    }
+}
+
+/// media type of a file, from the signature at the start of its content, then its extension.
+/// Unknown binary content is "application/octet-stream"
+pub fn detect_mime(name: &str, content: &[u8]) -> &'static str {
+    const SIGNATURES: [(&[u8], &str); 9] = [
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"BM", "image/bmp"),
+        (b"\x7fELF", "application/x-executable"),
+    ];
+    if let Some((_, mime)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| content.starts_with(signature))
+    {
+        return mime;
+    }
+    if content.len() >= 12 && &content[..4] == b"RIFF" && &content[8..12] == b"WEBP" {
+        return "image/webp";
+    }
+    let extension = Path::new(name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    let text = std::str::from_utf8(content).is_ok();
+    match extension.as_deref() {
+        Some("svg") if text => "image/svg+xml",
+        Some("md" | "markdown") if text => "text/markdown",
+        Some("json") if text => "application/json",
+        Some("csv") if text => "text/csv",
+        Some("html" | "htm") if text => "text/html",
+        Some("xml") if text => "application/xml",
+        _ if text => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_mime() {
+        assert_eq!(
+            detect_mime("shot", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+            "image/png"
+        );
+        // the content wins over a misleading extension
+        assert_eq!(detect_mime("spec.txt", b"%PDF-1.7\n"), "application/pdf");
+        assert_eq!(detect_mime("notes.MD", b"# Notes"), "text/markdown");
+        assert_eq!(detect_mime("crash.log", b"panicked at"), "text/plain");
+        assert_eq!(
+            detect_mime("data.json", b"\xff\xfe\x00"),
+            "application/octet-stream"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_blob_store() {
+        let dir = std::env::temp_dir().join(format!("abn-blobs-{}", std::process::id()));
+        let store = BlobStore::new(&dir, 1024);
+        let hash = store.put(b"hello").await.unwrap();
+        assert_eq!(
+            hash,
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert!(dir.join("2c").join(&hash).is_file());
+        // storing the same content again keeps the one blob
+        assert_eq!(store.put(b"hello").await.unwrap(), hash);
+        assert_eq!(store.get(&hash).await.unwrap(), b"hello");
+        store.remove(&hash).await.unwrap();
+        store.remove(&hash).await.unwrap();
+        assert!(store.get(&hash).await.is_err());
+        assert!(store.get("../../etc/passwd").await.is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
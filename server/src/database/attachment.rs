@@ -0,0 +1,38 @@
+use sea_orm::entity::prelude::*;
+/// a file attached to a task, its content is stored in the blob directory under `hash`
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "attachment")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub task_id: i32,
+    pub name: String,
+    pub mime: String,
+    pub size: i64,
+    pub hash: String,
+    pub uploaded_at: chrono::NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, PartialEq, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+#[cfg(test)]
+mod attachment_tests {
+    use super::*;
+    #[test]
+    fn test_copy_clone_debug_derives() {
+        let original = Model {
+            id: 1,
+            task_id: 2,
+            name: "crash.log".to_owned(),
+            mime: "text/plain".to_owned(),
+            size: 512,
+            hash: "ab".repeat(32),
+            uploaded_at: chrono::NaiveDateTime::default(),
+        };
+        let clone = original.clone();
+        assert_eq!(original, clone);
+        assert!(format!("{:?}", original).contains("name: \"crash.log\""));
+    }
+}
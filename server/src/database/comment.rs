@@ -0,0 +1,36 @@
+use sea_orm::entity::prelude::*;
+/// a message in the discussion thread of a task
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "comment")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub task_id: i32,
+    pub author: String,
+    pub body: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub edited_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, PartialEq, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+#[cfg(test)]
+mod comment_tests {
+    use super::*;
+    #[test]
+    fn test_copy_clone_debug_derives() {
+        let original = Model {
+            id: 1,
+            task_id: 2,
+            author: "ana".to_owned(),
+            body: "looks good".to_owned(),
+            created_at: chrono::NaiveDateTime::default(),
+            edited_at: None,
+        };
+        let clone = original.clone();
+        assert_eq!(original, clone);
+        assert!(format!("{:?}", original).contains("author: \"ana\""));
+    }
+}
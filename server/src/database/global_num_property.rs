@@ -0,0 +1,45 @@
+use sea_orm::entity::prelude::*;
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "global_num_property")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub property_name: String,
+    pub value: Decimal,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, PartialEq, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::global_property::Entity",
+        from = "Column::PropertyName",
+        to = "super::global_property::Column::Name"
+    )]
+    GlobalProperty,
+}
+impl Related<super::global_property::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::GlobalProperty.def()
+    }
+}
+impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::Iterable;
+
+    use super::*;
+
+    #[test]
+    fn test_copy_clone_debug() {
+        let original = Relation::GlobalProperty;
+        let copy = original;
+        assert_eq!(original, copy);
+        assert!(format!("{:?}", original).contains("GlobalProperty"));
+    }
+    #[test]
+    fn test_enum_iter() {
+        let mut iter = Relation::iter();
+        assert_eq!(iter.next(), Some(Relation::GlobalProperty));
+        assert_eq!(iter.next(), None);
+    }
+}
@@ -0,0 +1,29 @@
+use sea_orm::entity::prelude::*;
+/// workspace-wide properties, the value is stored in the typed table of `typ`
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "global_property")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub name: String,
+    #[sea_orm(column_name = "type")]
+    pub typ: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, PartialEq, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+#[cfg(test)]
+mod global_property_tests {
+    use super::*;
+    #[test]
+    fn test_copy_clone_debug_derives() {
+        let original = Model {
+            name: "sprint end".to_owned(),
+            typ: "date".to_owned(),
+        };
+        let clone = original.clone();
+        assert_eq!(original, clone);
+        assert!(format!("{:?}", original).contains("sprint end"));
+    }
+}
@@ -1,8 +1,31 @@
+pub mod attachment;
+pub mod comment;
 pub mod dependency;
+pub mod global_bool_property;
+pub mod global_date_property;
+pub mod global_num_property;
+pub mod global_property;
+pub mod global_string_property;
+pub mod notification;
+pub mod property_schema;
+pub mod reminder;
+pub mod script_schedule;
+pub mod subtask;
+pub mod tag;
 pub mod task;
 pub mod task_bool_property;
 pub mod task_date_property;
+pub mod task_duration_property;
+pub mod task_int_property;
+pub mod task_multiselect_property;
 pub mod task_num_property;
 pub mod task_property;
+pub mod task_recurrence;
+pub mod task_ref_property;
+pub mod task_refs_property;
+pub mod task_select_property;
 pub mod task_string_property;
+pub mod task_tag;
+pub mod task_user_property;
 pub mod view;
+pub mod view_rank;
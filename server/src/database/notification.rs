@@ -0,0 +1,33 @@
+use sea_orm::entity::prelude::*;
+/// events fired by the scheduler
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "notification")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub task_id: Option<i32>,
+    /// `NotificationKind` as json
+    pub kind: String,
+    pub fire_at: chrono::NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, PartialEq, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+#[cfg(test)]
+mod notification_tests {
+    use super::*;
+    #[test]
+    fn test_copy_clone_debug_derives() {
+        let original = Model {
+            id: 1,
+            task_id: Some(2),
+            kind: r#""Overdue""#.to_owned(),
+            fire_at: chrono::NaiveDateTime::default(),
+        };
+        let clone = original.clone();
+        assert_eq!(original, clone);
+        assert!(format!("{:?}", original).contains("Overdue"));
+    }
+}
@@ -0,0 +1,41 @@
+use sea_orm::entity::prelude::*;
+/// workspace-level property definitions, values are json encoded `TaskPropVariant`s
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "property_schema")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub name: String,
+    #[sea_orm(column_name = "type")]
+    pub typ: String,
+    pub description: String,
+    pub default_value: Option<String>,
+    pub required: bool,
+    pub allowed_values: String,
+    pub options: String,
+    pub formula: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, PartialEq, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+#[cfg(test)]
+mod property_schema_tests {
+    use super::*;
+    #[test]
+    fn test_copy_clone_debug_derives() {
+        let original = Model {
+            name: "status".to_owned(),
+            typ: "string".to_owned(),
+            description: String::new(),
+            default_value: None,
+            required: false,
+            allowed_values: "[]".to_owned(),
+            options: "[]".to_owned(),
+            formula: None,
+        };
+        let clone = original.clone();
+        assert_eq!(original, clone);
+        assert!(format!("{:?}", original).contains("status"));
+    }
+}
@@ -0,0 +1,32 @@
+use sea_orm::entity::prelude::*;
+/// reminders on the date properties of tasks
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "reminder")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub task_id: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub property_name: String,
+    /// seconds before the date the reminder fires
+    pub before: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, PartialEq, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+#[cfg(test)]
+mod reminder_tests {
+    use super::*;
+    #[test]
+    fn test_copy_clone_debug_derives() {
+        let original = Model {
+            task_id: 1,
+            property_name: "due".to_owned(),
+            before: 3600,
+        };
+        let clone = original.clone();
+        assert_eq!(original, clone);
+        assert!(format!("{:?}", original).contains("due"));
+    }
+}
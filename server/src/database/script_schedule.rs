@@ -0,0 +1,31 @@
+use sea_orm::entity::prelude::*;
+/// next scheduled run of a script, and the rule runs repeat by
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "script_schedule")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub script_id: i32,
+    pub next_run: chrono::NaiveDateTime,
+    /// `RRULE` of the runs, None for a single run
+    pub rule: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, PartialEq, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+#[cfg(test)]
+mod script_schedule_tests {
+    use super::*;
+    #[test]
+    fn test_copy_clone_debug_derives() {
+        let original = Model {
+            script_id: 3,
+            next_run: chrono::NaiveDateTime::default(),
+            rule: Some("FREQ=WEEKLY;INTERVAL=1".to_owned()),
+        };
+        let clone = original.clone();
+        assert_eq!(original, clone);
+        assert!(format!("{:?}", original).contains("FREQ=WEEKLY"));
+    }
+}
@@ -0,0 +1,32 @@
+use sea_orm::entity::prelude::*;
+/// a label for tasks, nested under `parent_id` unless it's a top-level tag
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "tag")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub name: String,
+    pub parent_id: Option<i32>,
+    pub color: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, PartialEq, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+#[cfg(test)]
+mod tag_tests {
+    use super::*;
+    #[test]
+    fn test_copy_clone_debug_derives() {
+        let original = Model {
+            id: 2,
+            name: "frontend".to_owned(),
+            parent_id: Some(1),
+            color: "#1e90ff".to_owned(),
+        };
+        let clone = original.clone();
+        assert_eq!(original, clone);
+        assert!(format!("{:?}", original).contains("parent_id: Some(1)"));
+    }
+}
@@ -0,0 +1,31 @@
+use sea_orm::entity::prelude::*;
+/// recurrence rules of tasks, and the instance each task was spawned from
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "task_recurrence")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub task_id: i32,
+    /// `RRULE` of the task, None once it stopped recurring
+    pub rule: Option<String>,
+    pub previous_id: Option<i32>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, PartialEq, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+#[cfg(test)]
+mod task_recurrence_tests {
+    use super::*;
+    #[test]
+    fn test_copy_clone_debug_derives() {
+        let original = Model {
+            task_id: 2,
+            rule: Some("FREQ=DAILY;INTERVAL=1".to_owned()),
+            previous_id: Some(1),
+        };
+        let clone = original.clone();
+        assert_eq!(original, clone);
+        assert!(format!("{:?}", original).contains("FREQ=DAILY"));
+    }
+}
@@ -7,6 +7,10 @@ pub struct Model {
     pub name: String,
     pub properties: Vec<String>,
     pub filter: String,
+    pub sort: String,
+    pub max_tasks: Option<i64>,
+    pub group_by: String,
+    pub summaries: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, PartialEq, DeriveRelation)]
@@ -23,6 +27,10 @@ mod view_tests {
             name: "nothing".to_owned(),
             properties: vec!["name".to_string()],
             filter: "whatever".to_owned(),
+            sort: "[]".to_owned(),
+            max_tasks: None,
+            group_by: "\"None\"".to_owned(),
+            summaries: "[]".to_owned(),
         };
         let copy = original.clone();
         assert_eq!(original, copy);
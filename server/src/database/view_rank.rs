@@ -0,0 +1,31 @@
+use sea_orm::entity::prelude::*;
+/// place of a task in the manual order of a view, tasks are ordered by rank as a string
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "view_rank")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub view_id: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub task_id: i32,
+    pub rank: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, PartialEq, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+#[cfg(test)]
+mod view_rank_tests {
+    use super::*;
+    #[test]
+    fn test_copy_clone_debug_derives() {
+        let original = Model {
+            view_id: 1,
+            task_id: 2,
+            rank: "V".to_owned(),
+        };
+        let clone = original.clone();
+        assert_eq!(original, clone);
+        assert!(format!("{:?}", original).contains("rank: \"V\""));
+    }
+}
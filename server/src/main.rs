@@ -4,14 +4,21 @@
 #![warn(missing_docs)]
 #![warn(rustdoc::missing_crate_level_docs)]
 mod api;
+mod blob;
 mod database;
-use std::env;
+mod scheduler;
 use actix_settings::ApplySettings;
-use actix_web::{dev::Server, web::Data, App, HttpServer};
+use actix_web::{
+    dev::Server,
+    web::{Data, PayloadConfig},
+    App, HttpServer,
+};
 use api::*;
+use blob::BlobStore;
 use common::backend;
 use log::{info, warn};
-use sea_orm::{Database, DatabaseConnection, DbErr, RuntimeErr};
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection, DbErr, RuntimeErr};
+use std::env;
 use tokio::time::Duration;
 
 static INIT: std::sync::Once = std::sync::Once::new();
@@ -65,6 +72,20 @@ pub async fn connect_to_database_exponential_backoff(
         attempts
     ))))
 }
+/// the database schema, every statement of it can run again on a database it already set up
+const SCHEMA: &str = include_str!("../database/createTable.sql");
+
+/**
+brings the database schema up to date, creating what is missing and migrating what older versions created.
+The script runs as a single query, so postgres applies it as a whole or not at all
+# Arguments
+* `db` - the connection to the database
+# Errors
+* `DbErr` - the error that occurred while running the schema script
+*/
+pub async fn migrate_database(db: &DatabaseConnection) -> Result<(), DbErr> {
+    db.execute_unprepared(SCHEMA).await.map(|_| ())
+}
 #[allow(clippy::needless_return)]
 async fn start_server() -> Server {
     env::set_var("RUST_LOG", "info");
@@ -77,16 +98,32 @@ async fn start_server() -> Server {
     let db_connection = connect_to_database_exponential_backoff(4_u32, db_url.clone())
         .await
         .unwrap();
-    let db_data: Data<DatabaseConnection> = Data::new(db_connection);
     info!("connected to database");
+    migrate_database(&db_connection)
+        .await
+        .expect("could not migrate database");
+    info!("migrated database");
+    let db_data: Data<DatabaseConnection> = Data::new(db_connection);
+    let blobs = Data::new(BlobStore::new(
+        settings.application.blob_dir.clone(),
+        settings.application.max_attachment_size,
+    ));
+    actix_web::rt::spawn(scheduler::run(db_data.clone(), scheduler::SCHEDULER_PERIOD));
+    info!("started scheduler");
     info!("creating server");
     let server = HttpServer::new(move || {
         let db_data = db_data.clone();
+        // bodies are read whole, so cap them at the largest attachment
+        let payload_limit = usize::try_from(blobs.max_size).unwrap_or(usize::MAX);
         App::new()
             .app_data(db_data)
+            .app_data(blobs.clone())
+            .app_data(PayloadConfig::new(payload_limit))
             .service(get_task_request)
             .service(get_task_request)
             .service(get_filter_request)
+            .service(get_aggregate_request)
+            .service(get_search_request)
             .service(create_task_request)
             .service(get_tasks_request)
             .service(update_task_request)
@@ -95,6 +132,32 @@ async fn start_server() -> Server {
             .service(delete_tasks_request)
             .service(get_property_request)
             .service(get_properties_request)
+            .service(get_schema_request)
+            .service(define_property_request)
+            .service(delete_property_request)
+            .service(get_globals_request)
+            .service(set_global_request)
+            .service(delete_global_request)
+            .service(set_recurrence_request)
+            .service(get_recurrence_request)
+            .service(set_reminder_request)
+            .service(schedule_script_request)
+            .service(get_notifications_request)
+            .service(get_comments_request)
+            .service(create_comment_request)
+            .service(update_comment_request)
+            .service(delete_comment_request)
+            .service(get_attachments_request)
+            .service(upload_attachment_request)
+            .service(download_attachment_request)
+            .service(delete_attachment_request)
+            .service(get_tags_request)
+            .service(create_tag_request)
+            .service(update_tag_request)
+            .service(delete_tag_request)
+            .service(get_task_tags_request)
+            .service(set_task_tags_request)
+            .service(move_task_request)
     })
     .apply_settings(&settings)
     .system_exit();
@@ -140,6 +203,36 @@ mod server_unit_tests {
         assert!(db_connection.is_err());
         assert!(start_time.elapsed().as_secs() > 31);
     }
+    #[tokio::test]
+    async fn test_migrate_database() {
+        use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 0,
+            }])
+            .into_connection();
+        migrate_database(&db).await.unwrap();
+        let log = format!("{:?}", db.into_transaction_log());
+        assert!(log.contains("CREATE TABLE IF NOT EXISTS"));
+        // the script runs at every start, so nothing in it may fail on a database it already set up
+        let uncommented: String = SCHEMA
+            .split("/*")
+            .map(|part| part.split_once("*/").map_or(part, |(_, rest)| rest))
+            .collect();
+        let statements: Vec<&str> = uncommented.split(';').map(str::trim).collect();
+        for (i, statement) in statements.iter().enumerate() {
+            assert!(!statement.starts_with("CREATE TABLE \""), "{statement}");
+            assert!(!statement.starts_with("CREATE INDEX task"), "{statement}");
+            if let Some(trigger) = statement.strip_prefix("CREATE TRIGGER ") {
+                let name = trigger.split_whitespace().next().unwrap();
+                assert!(
+                    statements[i - 1].starts_with(&format!("DROP TRIGGER IF EXISTS {name} ")),
+                    "{name} is not dropped before it is created"
+                );
+            }
+        }
+    }
 }
 #[cfg(test)]
 mod integration_tests {
@@ -0,0 +1,239 @@
+//! Background job runner firing time-based events.
+//!
+//! Every tick records a notification for each event that came due: reminders on date properties,
+//! open tasks passing their due date and scheduled script runs. Clients poll GET /notifications for them.
+//! A notification is unique per task, kind and time, so an event fires once however many ticks see it.
+use std::time::Duration;
+
+use crate::database::*;
+use actix_web::web::Data;
+use common::{backend::NotificationKind, recurrence::Recurrence, DUE_PROPERTY};
+use log::{info, warn};
+use sea_orm::{
+    entity::prelude::*,
+    sea_query::{LockBehavior, LockType},
+    ActiveValue::NotSet,
+    DatabaseConnection, DbBackend, DbErr, QuerySelect, Set, Statement, TransactionTrait,
+};
+
+/// how often the scheduler checks for events
+pub const SCHEDULER_PERIOD: Duration = Duration::from_secs(30);
+
+// reminders of open tasks whose time came, $1 is now
+const FIRE_REMINDERS: &str = r#"
+INSERT INTO "notification" ("task_id", "kind", "fire_at")
+SELECT "due"."task_id", "due"."kind", "due"."fire_at" FROM (
+    SELECT "reminder"."task_id",
+        json_build_object('Reminder', json_build_object('property', "reminder"."property_name"))::text AS "kind",
+        "task_date_property"."value" - "reminder"."before" * INTERVAL '1 second' AS "fire_at"
+    FROM "reminder"
+    JOIN "task_date_property" ON "task_date_property"."task_id" = "reminder"."task_id"
+        AND "task_date_property"."task_property_name" = "reminder"."property_name"
+    JOIN "task" ON "task"."id" = "reminder"."task_id"
+    WHERE NOT "task"."completed"
+) AS "due"
+WHERE "due"."fire_at" <= $1 AND NOT EXISTS (
+    SELECT 1 FROM "notification" WHERE "notification"."task_id" = "due"."task_id"
+        AND "notification"."kind" = "due"."kind" AND "notification"."fire_at" = "due"."fire_at"
+)
+ON CONFLICT DO NOTHING
+"#;
+
+// open tasks whose due date ($2) passed, $1 is now
+const FIRE_OVERDUE: &str = r#"
+INSERT INTO "notification" ("task_id", "kind", "fire_at")
+SELECT "task_date_property"."task_id", '"Overdue"', "task_date_property"."value"
+FROM "task_date_property"
+JOIN "task" ON "task"."id" = "task_date_property"."task_id"
+WHERE "task_date_property"."task_property_name" = $2
+    AND "task_date_property"."value" <= $1
+    AND NOT "task"."completed"
+    AND NOT EXISTS (
+        SELECT 1 FROM "notification" WHERE "notification"."task_id" = "task_date_property"."task_id"
+            AND "notification"."kind" = '"Overdue"' AND "notification"."fire_at" = "task_date_property"."value"
+    )
+ON CONFLICT DO NOTHING
+"#;
+
+/// check for events every `period` for as long as the server runs
+pub async fn run(db: Data<DatabaseConnection>, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+    loop {
+        interval.tick().await;
+        match fire(&db, chrono::Utc::now().naive_utc()).await {
+            Ok(0) => {}
+            Ok(fired) => info!("scheduler fired {} notifications", fired),
+            Err(e) => warn!("scheduler couldn't fire events: {}", e),
+        }
+    }
+}
+
+/// record notifications for every event due at `now`, returns how many were fired
+pub async fn fire(db: &DatabaseConnection, now: chrono::NaiveDateTime) -> Result<u64, DbErr> {
+    let reminders = db
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            FIRE_REMINDERS,
+            [now.into()],
+        ))
+        .await?
+        .rows_affected();
+    let overdue = db
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            FIRE_OVERDUE,
+            [now.into(), DUE_PROPERTY.into()],
+        ))
+        .await?
+        .rows_affected();
+    Ok(reminders + overdue + fire_script_runs(db, now).await?)
+}
+
+/// fire the scheduled script runs due at `now` and move their schedules to the next run.
+/// Runs missed while the server was down fire once. Script runs have no task for the notification to be
+/// unique on, so each schedule is claimed in a transaction of its own that fires it and moves it on.
+/// Schedules another server is firing are skipped rather than waited for
+async fn fire_script_runs(
+    db: &DatabaseConnection,
+    now: chrono::NaiveDateTime,
+) -> Result<u64, DbErr> {
+    let due = script_schedule::Entity::find()
+        .filter(script_schedule::Column::NextRun.lte(now))
+        .all(db)
+        .await?;
+    let mut fired = 0;
+    for schedule in due {
+        let txn = db.begin().await?;
+        // the schedule may have been moved on since it was listed
+        let Some(schedule) = script_schedule::Entity::find_by_id(schedule.script_id)
+            .filter(script_schedule::Column::NextRun.lte(now))
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+            .one(&txn)
+            .await?
+        else {
+            continue;
+        };
+        notification::Entity::insert(notification::ActiveModel {
+            id: NotSet,
+            task_id: Set(None),
+            kind: Set(
+                serde_json::to_string(&NotificationKind::ScriptRun(schedule.script_id))
+                    .expect("notification kinds serialize"),
+            ),
+            fire_at: Set(schedule.next_run),
+        })
+        .exec_without_returning(&txn)
+        .await?;
+        let recurrence = schedule.rule.as_deref().and_then(|rule| {
+            rule.parse::<Recurrence>()
+                .map_err(|e| warn!("script {} has invalid rule: {}", schedule.script_id, e))
+                .ok()
+        });
+        match recurrence {
+            Some(recurrence) => {
+                let mut next = recurrence.next(schedule.next_run, now);
+                while next <= now {
+                    next = recurrence.next(next, now);
+                }
+                script_schedule::ActiveModel {
+                    script_id: Set(schedule.script_id),
+                    next_run: Set(next),
+                    rule: NotSet,
+                }
+                .update(&txn)
+                .await?;
+            }
+            None => {
+                script_schedule::Entity::delete_by_id(schedule.script_id)
+                    .exec(&txn)
+                    .await?;
+            }
+        }
+        txn.commit().await?;
+        fired += 1;
+    }
+    Ok(fired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{MockDatabase, MockExecResult};
+
+    fn exec(rows_affected: u64) -> MockExecResult {
+        MockExecResult {
+            last_insert_id: 0,
+            rows_affected,
+        }
+    }
+
+    fn date(day: u32, hour: u32) -> chrono::NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2024, 3, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_fire() {
+        let weekly = script_schedule::Model {
+            script_id: 1,
+            next_run: date(1, 9),
+            rule: Some("FREQ=WEEKLY".to_owned()),
+        };
+        let once = script_schedule::Model {
+            script_id: 2,
+            next_run: date(4, 9),
+            rule: None,
+        };
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_exec_results([exec(2), exec(1)])
+            .append_query_results([[weekly.clone(), once.clone()]])
+            .append_query_results([[weekly.clone()]])
+            .append_exec_results([exec(1)])
+            .append_query_results([[script_schedule::Model {
+                next_run: date(15, 9),
+                ..weekly.clone()
+            }]])
+            .append_query_results([[once]])
+            .append_exec_results([exec(1), exec(1)])
+            .into_connection();
+        assert_eq!(fire(&db, date(10, 12)).await.unwrap(), 5);
+
+        let log = format!("{:?}", db.into_transaction_log());
+        assert!(log.contains("json_build_object('Reminder'"));
+        assert!(log.contains(r#"'\"Overdue\"'"#));
+        assert!(log.contains(r#"String(Some("{\"ScriptRun\":1}"))"#));
+        // the run missed on the 8th is skipped, the next one is a week after it
+        assert!(log.contains(r#"UPDATE \"script_schedule\" SET \"next_run\""#));
+        assert!(log.contains("2024-03-15T09:00:00"));
+        assert!(log.contains(r#"DELETE FROM \"script_schedule\""#));
+        // each run is fired and moved on together, skipping schedules another server has claimed
+        assert_eq!(log.matches("FOR UPDATE SKIP LOCKED").count(), 2);
+        assert_eq!(log.matches(r#"sql: "COMMIT""#).count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fire_claimed() {
+        let weekly = script_schedule::Model {
+            script_id: 1,
+            next_run: date(1, 9),
+            rule: Some("FREQ=WEEKLY".to_owned()),
+        };
+        // another server holds the schedule, so it isn't fired here
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_exec_results([exec(0), exec(0)])
+            .append_query_results([[weekly]])
+            .append_query_results([Vec::<script_schedule::Model>::new()])
+            .into_connection();
+        assert_eq!(fire(&db, date(10, 12)).await.unwrap(), 0);
+        let log = format!("{:?}", db.into_transaction_log());
+        assert!(!log.contains("ScriptRun"));
+    }
+
+    #[tokio::test]
+    async fn test_fire_error() {
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres).into_connection();
+        assert!(fire(&db, date(1, 0)).await.is_err());
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::BTreeMap;
+
+use super::*;
+use actix_web::{http::StatusCode, test, web::Data, App};
+use common::{GroupBy, Summary};
+use sea_orm::{MockDatabase, Value};
+
+fn date(day: u32) -> chrono::NaiveDateTime {
+    chrono::NaiveDate::from_ymd_opt(2024, 3, day)
+        .unwrap()
+        .and_hms_opt(9, 0, 0)
+        .unwrap()
+}
+
+// row of a group of `count` tasks, the lowest of them `first_id`, with the requested tasks `requested`
+fn group_row(
+    count: i64,
+    first_id: TaskID,
+    requested: Option<Vec<TaskID>>,
+) -> BTreeMap<&'static str, Value> {
+    BTreeMap::from([
+        ("count", count.into()),
+        ("first_id", first_id.into()),
+        ("requested", requested.into()),
+    ])
+}
+
+// row of the tasks with a value of a select property, summarizing a number and a date property
+fn status_row(
+    status: Option<&str>,
+    count: i64,
+    first_id: TaskID,
+    requested: Vec<TaskID>,
+    sum: Option<i64>,
+    average: Option<i64>,
+    due: Option<chrono::NaiveDateTime>,
+) -> BTreeMap<&'static str, Value> {
+    let mut row = group_row(count, first_id, Some(requested));
+    row.extend([
+        ("num", Option::<Decimal>::None.into()),
+        ("int", Option::<i64>::None.into()),
+        ("duration", Option::<i64>::None.into()),
+        ("string", Option::<String>::None.into()),
+        ("date", Option::<chrono::NaiveDateTime>::None.into()),
+        ("bool", Option::<bool>::None.into()),
+        ("select", status.map(str::to_owned).into()),
+        ("multiselect", Option::<Vec<String>>::None.into()),
+        ("ref", Option::<TaskID>::None.into()),
+        ("user", Option::<String>::None.into()),
+        ("summary0", sum.map(Decimal::from).into()),
+        ("summary1", average.map(Decimal::from).into()),
+        ("summary2", due.into()),
+    ]);
+    row
+}
+
+#[actix_web::test]
+async fn test_aggregate_by_property() {
+    // groups come back in any order
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([Vec::<property_schema::Model>::new()])
+        .append_query_results([vec![
+            status_row(None, 1, 2, vec![2], Some(3), Some(3), None),
+            status_row(Some("todo"), 2, 1, vec![3], Some(6), Some(3), Some(date(2))),
+        ]])
+        .into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(get_aggregate_request),
+    )
+    .await;
+    let req = test::TestRequest::default()
+        .set_json(AggregateRequest {
+            filter: Filter::None,
+            group_by: GroupBy::Property("status".to_owned()),
+            summaries: vec![
+                Summary::Sum("estimate".to_owned()),
+                Summary::Average("estimate".to_owned()),
+                Summary::Earliest(DUE_PROPERTY.to_owned()),
+            ],
+            task_ids: vec![3, 2, 9],
+            req_id: 4,
+        })
+        .uri("/aggregate")
+        .to_request();
+    let resp: AggregateResponse = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(
+        resp,
+        AggregateResponse {
+            groups: vec![
+                TaskGroup {
+                    key: GroupKey::Value(Some(TaskPropVariant::Select("todo".to_owned()))),
+                    count: 2,
+                    summaries: vec![
+                        Some(TaskPropVariant::Number(6.0)),
+                        Some(TaskPropVariant::Number(3.0)),
+                        Some(TaskPropVariant::Date(date(2))),
+                    ],
+                },
+                TaskGroup {
+                    key: GroupKey::Value(None),
+                    count: 1,
+                    summaries: vec![
+                        Some(TaskPropVariant::Number(3.0)),
+                        Some(TaskPropVariant::Number(3.0)),
+                        None,
+                    ],
+                },
+            ],
+            // task 9 doesn't match the filter
+            task_groups: vec![Some(0), Some(1), None],
+            req_id: 4,
+        }
+    );
+}
+
+#[actix_web::test]
+async fn test_aggregate_by_completion() {
+    let completed_row = |completed: bool, first_id, requested| {
+        let mut row = group_row(1, first_id, requested);
+        row.insert("completed", completed.into());
+        row
+    };
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([vec![
+            completed_row(true, 1, Some(vec![1])),
+            completed_row(false, 2, None),
+        ]])
+        .into_connection();
+    let resp = aggregate(
+        &db,
+        &AggregateRequest {
+            filter: Filter::None,
+            group_by: GroupBy::Completed,
+            summaries: vec![],
+            task_ids: vec![1],
+            req_id: 0,
+        },
+    )
+    .await
+    .unwrap();
+    // tasks that aren't completed come first
+    assert_eq!(
+        resp.groups
+            .iter()
+            .map(|group| (group.key.clone(), group.count))
+            .collect::<Vec<_>>(),
+        vec![
+            (GroupKey::Completed(false), 1),
+            (GroupKey::Completed(true), 1)
+        ]
+    );
+    assert_eq!(resp.task_groups, vec![Some(1)]);
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(log.contains(r#"GROUP BY \"completed\""#));
+}
+
+#[actix_web::test]
+async fn test_aggregate_by_dependency_root() {
+    let root_row = |root_id: TaskID, count, requested| {
+        let mut row = group_row(count, root_id, Some(requested));
+        row.insert("root", root_id.into());
+        row
+    };
+    // task 4 is on a cycle without a root, so it's grouped under itself
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([vec![root_row(4, 1, vec![4]), root_row(1, 3, vec![2])]])
+        .into_connection();
+    let resp = aggregate(
+        &db,
+        &AggregateRequest {
+            filter: Filter::None,
+            group_by: GroupBy::DependencyRoot,
+            summaries: vec![],
+            task_ids: vec![4, 2],
+            req_id: 0,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        resp.groups
+            .iter()
+            .map(|group| (group.key.clone(), group.count))
+            .collect::<Vec<_>>(),
+        vec![(GroupKey::Root(1), 3), (GroupKey::Root(4), 1)]
+    );
+    assert_eq!(resp.task_groups, vec![Some(1), Some(0)]);
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(log.contains("WITH RECURSIVE \\\"rooted\\\""));
+    assert!(log.contains(r#"GROUP BY \"root\""#));
+}
+
+#[actix_web::test]
+async fn test_aggregate_summary_types() {
+    let definition = |name: &str, typ: &str| property_schema::Model {
+        name: name.to_owned(),
+        typ: typ.to_owned(),
+        description: String::new(),
+        default_value: None,
+        required: false,
+        allowed_values: "[]".to_owned(),
+        options: "[]".to_owned(),
+        formula: None,
+    };
+    let schema = vec![
+        definition("points", "integer"),
+        definition("estimate", "duration"),
+        definition("status", "string"),
+    ];
+    let mut row = group_row(3, 1, None);
+    row.extend([
+        ("summary0", Decimal::from(7).into()),
+        ("summary1", Decimal::new(2333, 3).into()),
+        ("summary2", Decimal::from(9000).into()),
+        ("summary3", Decimal::new(30005, 1).into()),
+    ]);
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([schema.clone()])
+        .append_query_results([vec![row]])
+        .into_connection();
+    let resp = aggregate(
+        &db,
+        &AggregateRequest {
+            filter: Filter::None,
+            group_by: GroupBy::None,
+            summaries: vec![
+                Summary::Sum("points".to_owned()),
+                Summary::Average("points".to_owned()),
+                Summary::Sum("estimate".to_owned()),
+                Summary::Average("estimate".to_owned()),
+            ],
+            task_ids: vec![],
+            req_id: 0,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        resp.groups,
+        vec![TaskGroup {
+            key: GroupKey::All,
+            count: 3,
+            summaries: vec![
+                Some(TaskPropVariant::Integer(7)),
+                Some(TaskPropVariant::Number(2.333)),
+                Some(TaskPropVariant::Duration(TaskDuration(9000))),
+                Some(TaskPropVariant::Duration(TaskDuration(3000))),
+            ],
+        }]
+    );
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(log.contains(r#"SUM(\"summary0\") AS \"summary0\", AVG(\"summary1\")"#));
+    assert!(log.contains(r#"FROM \"task_int_property\""#));
+    assert!(log.contains(r#"FROM \"task_duration_property\""#));
+
+    // strings can't be summed, nor numbers be the earliest
+    for summary in [
+        Summary::Sum("status".to_owned()),
+        Summary::Earliest("points".to_owned()),
+    ] {
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results([schema.clone()])
+            .into_connection();
+        let err = aggregate(
+            &db,
+            &AggregateRequest {
+                filter: Filter::None,
+                group_by: GroupBy::None,
+                summaries: vec![summary],
+                task_ids: vec![],
+                req_id: 0,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
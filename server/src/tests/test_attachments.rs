@@ -0,0 +1,259 @@
+use super::*;
+use actix_web::{http::StatusCode, test, web::Data, App};
+use sea_orm::{DbErr, MockDatabase, MockExecResult};
+
+fn exec(rows_affected: u64) -> MockExecResult {
+    MockExecResult {
+        last_insert_id: 0,
+        rows_affected,
+    }
+}
+
+fn task() -> task::Model {
+    task::Model {
+        id: 1,
+        title: "test".to_owned(),
+        completed: false,
+        last_edited: chrono::NaiveDateTime::default(),
+    }
+}
+
+fn attachment(id: i32, name: &str, content: &[u8]) -> attachment::Model {
+    attachment::Model {
+        id,
+        task_id: 1,
+        name: name.to_owned(),
+        mime: "text/plain".to_owned(),
+        size: content.len() as i64,
+        hash: BlobStore::hash(content),
+        uploaded_at: chrono::NaiveDateTime::default(),
+    }
+}
+
+/// a store in a directory of its own, removed by the caller
+fn blobs(test: &str) -> (BlobStore, std::path::PathBuf) {
+    let dir = std::env::temp_dir().join(format!("abn-{}-{}", test, std::process::id()));
+    (BlobStore::new(&dir, 16), dir)
+}
+
+#[actix_web::test]
+async fn test_upload_attachment() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task()]])
+        .append_exec_results([exec(1)])
+        .append_query_results([[attachment(3, "notes.txt", b"pack light")]])
+        .append_query_results([Vec::<task::Model>::new()])
+        .into_connection();
+    let (store, dir) = blobs("upload");
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(store.clone()))
+            .service(upload_attachment_request),
+    )
+    .await;
+    let post = |task_id, name: &str, content: &'static [u8]| {
+        test::TestRequest::post()
+            .uri(&format!("/attachment?task_id={}&name={}", task_id, name))
+            .set_payload(content)
+            .to_request()
+    };
+    let resp: UploadAttachmentResponse =
+        test::call_and_read_body_json(&app, post(1, "trip/notes.txt", b"pack light")).await;
+    assert_eq!(
+        resp,
+        attachment_from_model(attachment(3, "notes.txt", b"pack light"))
+    );
+    assert_eq!(store.get(&resp.hash).await.unwrap(), b"pack light");
+
+    // bad uploads are rejected before touching the database
+    let resp = test::call_service(&app, post(1, "trip/", b"pack light")).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = test::call_service(&app, post(1, "notes.txt", b"more than sixteen bytes")).await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let resp = test::call_service(&app, post(9, "notes.txt", b"pack light")).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[actix_web::test]
+async fn test_upload_attachment_detects_mime() {
+    let png = b"\x89PNG\r\n\x1a\n";
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task()]])
+        .append_exec_results([exec(1)])
+        .append_query_results([[attachment(3, "shot", png)]])
+        .into_connection();
+    let (store, dir) = blobs("mime");
+    let query = UploadAttachmentQuery {
+        task_id: 1,
+        name: "shot".to_owned(),
+    };
+    upload_attachment(&db, &store, &query, png).await.unwrap();
+    let log = format!("{:?}", db.into_transaction_log());
+    // the content is locked while it's stored and attached
+    assert!(log.contains(r#"Transaction { stmts: [Statement { sql: "BEGIN""#));
+    assert!(log.contains("pg_advisory_xact_lock"));
+    assert!(log.contains("INSERT INTO \\\"attachment\\\""));
+    assert!(log.contains("image/png"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[actix_web::test]
+async fn test_upload_attachment_failed() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task()], [task()]])
+        .append_exec_results([exec(1), exec(1)])
+        .append_query_errors([
+            DbErr::Custom("insert failed".to_owned()),
+            DbErr::Custom("insert failed".to_owned()),
+        ])
+        .into_connection();
+    let (store, dir) = blobs("failed");
+    let query = UploadAttachmentQuery {
+        task_id: 1,
+        name: "notes.txt".to_owned(),
+    };
+    // a file written for an attachment that couldn't be created is removed
+    assert!(upload_attachment(&db, &store, &query, b"new")
+        .await
+        .is_err());
+    assert!(!store.contains(&BlobStore::hash(b"new")).await.unwrap());
+    // while content other attachments already had stays
+    let hash = store.put(b"old").await.unwrap();
+    assert!(upload_attachment(&db, &store, &query, b"old")
+        .await
+        .is_err());
+    assert!(store.contains(&hash).await.unwrap());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[actix_web::test]
+async fn test_get_download_attachments() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[
+            attachment(2, "a.txt", b"first"),
+            attachment(5, "b.txt", b"second"),
+        ]])
+        .append_query_results([[attachment(5, "b.txt", b"second")]])
+        .append_query_results([Vec::<attachment::Model>::new()])
+        .into_connection();
+    let (store, dir) = blobs("download");
+    store.put(b"second").await.unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(store))
+            .service(get_attachments_request)
+            .service(download_attachment_request),
+    )
+    .await;
+    let req = test::TestRequest::default()
+        .set_json(GetAttachmentsRequest {
+            task_id: 1,
+            req_id: 3,
+        })
+        .uri("/attachments")
+        .to_request();
+    let resp: GetAttachmentsResponse = test::call_and_read_body_json(&app, req).await;
+    assert_eq!((resp.task_id, resp.req_id), (1, 3));
+    assert_eq!(
+        resp.attachments
+            .iter()
+            .map(|attachment| (attachment.id, attachment.name.as_str()))
+            .collect::<Vec<_>>(),
+        vec![(2, "a.txt"), (5, "b.txt")]
+    );
+
+    let download = |attachment_id| {
+        test::TestRequest::get()
+            .uri(&format!("/attachment?attachment_id={}", attachment_id))
+            .to_request()
+    };
+    let resp = test::call_service(&app, download(5)).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("content-type").unwrap(), "text/plain");
+    assert_eq!(
+        resp.headers().get("content-disposition").unwrap(),
+        "attachment; filename=\"b.txt\""
+    );
+    assert_eq!(test::read_body(resp).await, "second");
+    let resp = test::call_service(&app, download(9)).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[actix_web::test]
+async fn test_delete_attachment() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        // the content is shared with another attachment, so it's kept
+        .append_query_results([[attachment(2, "a.txt", b"same")]])
+        .append_exec_results([exec(1), exec(1)])
+        .append_query_results([[attachment(5, "b.txt", b"same")]])
+        // then the last attachment of the content goes, and the content with it
+        .append_query_results([[attachment(5, "b.txt", b"same")]])
+        .append_exec_results([exec(1), exec(1)])
+        .append_query_results([Vec::<attachment::Model>::new()])
+        .append_query_results([Vec::<attachment::Model>::new()])
+        .into_connection();
+    let (store, dir) = blobs("delete");
+    let hash = store.put(b"same").await.unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(store.clone()))
+            .service(delete_attachment_request),
+    )
+    .await;
+    let delete = |attachment_id| {
+        test::TestRequest::delete()
+            .set_json(DeleteAttachmentRequest {
+                attachment_id,
+                req_id: 4,
+            })
+            .uri("/attachment")
+            .to_request()
+    };
+    let resp: DeleteAttachmentResponse = test::call_and_read_body_json(&app, delete(2)).await;
+    assert_eq!(resp, 4);
+    assert!(store.get(&hash).await.is_ok());
+    let resp: DeleteAttachmentResponse = test::call_and_read_body_json(&app, delete(5)).await;
+    assert_eq!(resp, 4);
+    assert!(store.get(&hash).await.is_err());
+    let resp = test::call_service(&app, delete(5)).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[actix_web::test]
+async fn test_delete_task_removes_content() {
+    let mut elsewhere = attachment(7, "c.txt", b"shared");
+    elsewhere.task_id = 2;
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task()]])
+        .append_query_results([[
+            attachment(2, "a.txt", b"alone"),
+            attachment(3, "b.txt", b"shared"),
+        ]])
+        // the references and the task go, then both contents are locked
+        .append_exec_results([exec(0), exec(1), exec(1), exec(1)])
+        // and only the shared content is still attached somewhere
+        .append_query_results([[elsewhere]])
+        .into_connection();
+    let (store, dir) = blobs("delete-task");
+    let alone = store.put(b"alone").await.unwrap();
+    let shared = store.put(b"shared").await.unwrap();
+    let req = DeleteTaskRequest {
+        task_id: 1,
+        req_id: 0,
+    };
+    delete_task(&db, &store, &req).await.unwrap();
+    assert!(store.get(&alone).await.is_err());
+    assert!(store.get(&shared).await.is_ok());
+    // the files are only removed once the deletion is committed
+    let log = format!("{:?}", db.into_transaction_log());
+    let committed = log.find(r#"sql: "COMMIT""#).unwrap();
+    assert!(log.find("pg_advisory_xact_lock").unwrap() > committed);
+    assert_eq!(log.matches("pg_advisory_xact_lock").count(), 2);
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,177 @@
+use super::*;
+use actix_web::{http::StatusCode, test, web::Data, App};
+use sea_orm::{MockDatabase, MockExecResult};
+
+fn exec(rows_affected: u64) -> MockExecResult {
+    MockExecResult {
+        last_insert_id: 0,
+        rows_affected,
+    }
+}
+
+fn comment(id: i32, body: &str) -> comment::Model {
+    comment::Model {
+        id,
+        task_id: 1,
+        author: "ana".to_owned(),
+        body: body.to_owned(),
+        created_at: chrono::NaiveDateTime::default(),
+        edited_at: None,
+    }
+}
+
+#[actix_web::test]
+async fn test_get_comments() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[comment(2, "first"), comment(5, "second")]])
+        .into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(get_comments_request),
+    )
+    .await;
+    let req = test::TestRequest::default()
+        .set_json(GetCommentsRequest {
+            task_id: 1,
+            req_id: 3,
+        })
+        .uri("/comments")
+        .to_request();
+    let resp: GetCommentsResponse = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp.task_id, 1);
+    assert_eq!(resp.req_id, 3);
+    assert_eq!(
+        resp.comments
+            .iter()
+            .map(|comment| (comment.id, comment.body.as_str()))
+            .collect::<Vec<_>>(),
+        vec![(2, "first"), (5, "second")]
+    );
+}
+
+#[actix_web::test]
+async fn test_create_comment() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task::Model {
+            id: 1,
+            title: "test".to_owned(),
+            completed: false,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
+        .append_query_results([[comment(4, "on it")]])
+        .append_exec_results([exec(1)])
+        .append_query_results([Vec::<task::Model>::new()])
+        .into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(create_comment_request),
+    )
+    .await;
+    let post = |task_id, body: &str| {
+        test::TestRequest::post()
+            .set_json(CreateCommentRequest {
+                task_id,
+                author: "ana".to_owned(),
+                body: body.to_owned(),
+                req_id: 1,
+            })
+            .uri("/comment")
+            .to_request()
+    };
+    let resp: CreateCommentResponse = test::call_and_read_body_json(&app, post(1, "on it")).await;
+    assert_eq!(resp.comment, comment_from_model(comment(4, "on it")));
+
+    // blank comments are rejected before touching the database
+    let resp = test::call_service(&app, post(1, "  ")).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = test::call_service(&app, post(9, "on it")).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_create_comment_notifies() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task::Model {
+            id: 1,
+            title: "test".to_owned(),
+            completed: false,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
+        .append_query_results([[comment(4, "on it")]])
+        .append_exec_results([exec(1)])
+        .into_connection();
+    let req = CreateCommentRequest {
+        task_id: 1,
+        author: "ana".to_owned(),
+        body: "on it".to_owned(),
+        req_id: 1,
+    };
+    create_comment(&db, &req).await.unwrap();
+    let log = format!("{:?}", db.into_transaction_log());
+    // the comment and its notification are written together
+    assert!(log.contains(r#"Transaction { stmts: [Statement { sql: "BEGIN""#));
+    assert!(log.contains(r#"sql: "COMMIT""#));
+    assert!(log.contains("INSERT INTO \\\"notification\\\""));
+    // the notification names the comment and its author
+    assert!(log.contains("Comment") && log.contains("comment_id") && log.contains("4"));
+}
+
+#[actix_web::test]
+async fn test_update_delete_comment() {
+    let edited = comment::Model {
+        edited_at: Some(chrono::NaiveDateTime::default()),
+        ..comment(4, "done")
+    };
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[comment(4, "on it")]])
+        .append_query_results([[edited.clone()]])
+        .append_query_results([Vec::<comment::Model>::new()])
+        .append_query_results([[comment(4, "done")]])
+        .append_exec_results([exec(1)])
+        .append_query_results([Vec::<comment::Model>::new()])
+        .into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(update_comment_request)
+            .service(delete_comment_request),
+    )
+    .await;
+    let update = |comment_id| {
+        test::TestRequest::put()
+            .set_json(UpdateCommentRequest {
+                comment_id,
+                body: "done".to_owned(),
+                req_id: 2,
+            })
+            .uri("/comment")
+            .to_request()
+    };
+    let resp: UpdateCommentResponse = test::call_and_read_body_json(&app, update(4)).await;
+    assert_eq!(resp.comment, comment_from_model(edited));
+    let resp = test::call_service(&app, update(9)).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let delete = |comment_id| {
+        test::TestRequest::delete()
+            .set_json(DeleteCommentRequest {
+                comment_id,
+                req_id: 3,
+            })
+            .uri("/comment")
+            .to_request()
+    };
+    let resp: DeleteCommentResponse = test::call_and_read_body_json(&app, delete(4)).await;
+    assert_eq!(
+        resp,
+        DeleteCommentResponse {
+            task_id: 1,
+            comment_id: 4,
+            req_id: 3
+        }
+    );
+    let resp = test::call_service(&app, delete(4)).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
@@ -48,6 +48,7 @@ async fn insert_task_succeeds_with_good_request() {
             completed: false,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
+        .append_query_results([vec![] as Vec<property_schema::Model>])
         .into_connection();
     let app = test::init_service(
         actix_web::App::new()
@@ -91,12 +92,14 @@ async fn insert_tasks_works() {
             completed: false,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
+        .append_query_results([vec![] as Vec<property_schema::Model>])
         .append_query_results([vec![task::Model {
             id: 2,
             title: "test2".to_string(),
             completed: false,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
+        .append_query_results([vec![] as Vec<property_schema::Model>])
         .into_connection();
     let app = test::init_service(
         actix_web::App::new()
@@ -11,14 +11,23 @@ async fn test_delete() {
             completed: true,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
-        .append_exec_results([MockExecResult {
-            last_insert_id: 0,
-            rows_affected: 1,
-        }])
+        .append_query_results([Vec::<attachment::Model>::new()])
+        // the task has no attachments, no property references it, then the task itself
+        .append_exec_results([
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 0,
+            },
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            },
+        ])
         .into_connection();
 
     let res = delete_task(
         &db,
+        &BlobStore::new(std::env::temp_dir(), 0),
         &DeleteTaskRequest {
             task_id: 1,
             req_id: 0,
@@ -27,6 +36,12 @@ async fn test_delete() {
     .await;
 
     assert!(res.is_ok());
+    let log = format!("{:?}", db.into_transaction_log());
+    // single references to the task are deleted with their property, in the same transaction
+    assert!(log.starts_with(r#"[Transaction { stmts: [Statement { sql: "BEGIN""#));
+    assert!(log.contains(r#"DELETE FROM \"task_property\" WHERE \"task_property\".\"type\" = $1 AND (\"task_id\", \"name\") IN (SELECT \"task_id\", \"task_property_name\" FROM \"task_ref_property\" WHERE \"task_ref_property\".\"value\" = $2)"#));
+    assert!(log.contains(r#"String(Some("taskref"))"#));
+    assert!(log.contains(r#"DELETE FROM \"task\""#));
 }
 #[actix_web::test]
 async fn test_delete_bad_id() {
@@ -36,6 +51,7 @@ async fn test_delete_bad_id() {
 
     let res = delete_task(
         &db,
+        &BlobStore::new(std::env::temp_dir(), 0),
         &DeleteTaskRequest {
             task_id: 1,
             req_id: 0,
@@ -58,15 +74,23 @@ async fn test_delete_request() {
             completed: true,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
-        .append_exec_results([MockExecResult {
-            last_insert_id: 0,
-            rows_affected: 1,
-        }])
+        .append_query_results([Vec::<attachment::Model>::new()])
+        .append_exec_results([
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 0,
+            },
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            },
+        ])
         .into_connection();
 
     let app = test::init_service(
         actix_web::App::new()
             .app_data(web::Data::new(db))
+            .app_data(web::Data::new(BlobStore::new(std::env::temp_dir(), 0)))
             .service(delete_task_request),
     )
     .await;
@@ -91,25 +115,40 @@ async fn test_delete_many_request() {
             completed: true,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
+        .append_query_results([Vec::<attachment::Model>::new()])
         .append_query_results([[task::Model {
             id: 1,
             title: "title".to_string(),
             completed: true,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
-        .append_exec_results([MockExecResult {
-            last_insert_id: 1,
-            rows_affected: 1,
-        }])
-        .append_exec_results([MockExecResult {
-            last_insert_id: 0,
-            rows_affected: 1,
-        }])
+        .append_query_results([Vec::<attachment::Model>::new()])
+        .append_exec_results([
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 0,
+            },
+            MockExecResult {
+                last_insert_id: 1,
+                rows_affected: 1,
+            },
+        ])
+        .append_exec_results([
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 0,
+            },
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            },
+        ])
         .into_connection();
 
     let app = test::init_service(
         actix_web::App::new()
             .app_data(web::Data::new(db))
+            .app_data(web::Data::new(BlobStore::new(std::env::temp_dir(), 0)))
             .service(delete_tasks_request),
     )
     .await;
@@ -11,7 +11,24 @@ use testcontainer_common_utils::setup_db;
 use testcontainer_common_utils::DB;
 
 use super::*;
-use sea_orm::MockDatabase;
+use sea_orm::{MockDatabase, QueryTrait};
+
+async fn filter_tasks(db_conn: &DatabaseConnection, filter: Filter) -> Vec<TaskID> {
+    super::filter(
+        db_conn,
+        &FilterRequest {
+            filter,
+            sort: vec![],
+            limit: None,
+            offset: 0,
+            req_id: 0,
+        },
+    )
+    .await
+    .unwrap()
+    .tasks
+    .clone()
+}
 
 #[actix_web::test]
 async fn test_empty_filter() {
@@ -37,6 +54,9 @@ async fn test_empty_filter() {
     let req = test::TestRequest::default()
         .set_json(FilterRequest {
             filter: Filter::None,
+            sort: vec![],
+            limit: None,
+            offset: 0,
             req_id: 0,
         })
         .uri("/filter")
@@ -53,6 +73,9 @@ macro_rules! simple_test {
         let res = filter(
             $db_conn,
             &FilterRequest {
+                sort: vec![],
+                limit: None,
+                offset: 0,
                 req_id: 0,
                 filter: Filter::Leaf {
                     field: $name.to_string(),
@@ -74,6 +97,9 @@ macro_rules! simple_primitive_test {
         let res = filter(
             $db_conn,
             &FilterRequest {
+                sort: vec![],
+                limit: None,
+                offset: 0,
                 req_id: 0,
                 filter: Filter::LeafPrimitive {
                     field: $name,
@@ -128,6 +154,7 @@ macro_rules! simple_make {
                 deps_to_remove: vec![],
                 scripts_to_add: vec![],
                 scripts_to_remove: vec![],
+                parent: None,
                 req_id: 0,
             },
         )
@@ -148,6 +175,7 @@ macro_rules! simple_make {
                 deps_to_remove: vec![],
                 scripts_to_add: vec![],
                 scripts_to_remove: vec![],
+                parent: None,
                 req_id: 0,
             },
         )
@@ -179,6 +207,7 @@ macro_rules! super_make {
                 deps_to_remove: vec![],
                 scripts_to_add: vec![],
                 scripts_to_remove: vec![],
+                parent: None,
                 req_id: 0,
             }
         ).await.unwrap();
@@ -191,6 +220,9 @@ macro_rules! ultra_filter_test {
             filter(
                 $db,
                 &FilterRequest {
+                sort: vec![],
+                limit: None,
+                offset: 0,
                 req_id: 0,
                     filter: Filter::Leaf {
                         field: "doesn't matter".to_owned(),
@@ -210,6 +242,9 @@ macro_rules! ultra_filter_test2 {
             filter(
                 $db,
                 &FilterRequest {
+                sort: vec![],
+                limit: None,
+                offset: 0,
                 req_id: 0,
                     filter: Filter::LeafPrimitive {
                         field: $field,
@@ -228,6 +263,7 @@ macro_rules! ultra_filter_test2 {
 async fn ultra_test() {
     let mut res = MockDatabase::new(sea_orm::DatabaseBackend::Postgres);
     for _ in 0..45 {
+        res = res.append_query_results([vec![] as Vec<property_schema::Model>]);
         res = res.append_query_results([[task::Model {
             id: 1,
             title: "title".to_string(),
@@ -321,6 +357,9 @@ async fn ultra_test() {
     filter(
         &db,
         &FilterRequest {
+            sort: vec![],
+            limit: None,
+            offset: 0,
             req_id: 0,
             filter: Filter::Operator {
                 op: common::Operator::AND,
@@ -344,6 +383,9 @@ async fn ultra_test() {
     filter(
         &db,
         &FilterRequest {
+            sort: vec![],
+            limit: None,
+            offset: 0,
             req_id: 0,
             filter: Filter::Operator {
                 op: common::Operator::OR,
@@ -367,6 +409,9 @@ async fn ultra_test() {
     filter(
         &db,
         &FilterRequest {
+            sort: vec![],
+            limit: None,
+            offset: 0,
             req_id: 0,
             filter: Filter::Operator {
                 op: common::Operator::NOT,
@@ -385,6 +430,9 @@ async fn ultra_test() {
     assert!(filter(
         &db,
         &FilterRequest {
+            sort: vec![],
+            limit: None,
+            offset: 0,
             req_id: 0,
             filter: Filter::Leaf {
                 field: "doesn't matter".to_owned(),
@@ -398,6 +446,9 @@ async fn ultra_test() {
     assert!(filter(
         &db,
         &FilterRequest {
+            sort: vec![],
+            limit: None,
+            offset: 0,
             req_id: 0,
             filter: Filter::Leaf {
                 field: "doesn't matter".to_owned(),
@@ -411,6 +462,9 @@ async fn ultra_test() {
     assert!(filter(
         &db,
         &FilterRequest {
+            sort: vec![],
+            limit: None,
+            offset: 0,
             req_id: 0,
             filter: Filter::Leaf {
                 field: "doesn't matter".to_owned(),
@@ -429,6 +483,9 @@ async fn ultra_test() {
     assert!(filter(
         &db,
         &FilterRequest {
+            sort: vec![],
+            limit: None,
+            offset: 0,
             req_id: 0,
             filter: Filter::LeafPrimitive {
                 field: PrimitiveField::COMPLETED,
@@ -442,6 +499,9 @@ async fn ultra_test() {
     assert!(filter(
         &db,
         &FilterRequest {
+            sort: vec![],
+            limit: None,
+            offset: 0,
             req_id: 0,
             filter: Filter::LeafPrimitive {
                 field: PrimitiveField::LASTEDITED,
@@ -459,6 +519,377 @@ async fn ultra_test() {
     .is_err());
 }
 
+#[actix_web::test]
+async fn test_construct_filter_correlated() {
+    let query = task::Entity::find()
+        .filter(
+            construct_filter(
+                &Filter::Operator {
+                    op: common::Operator::AND,
+                    childs: vec![
+                        Filter::Leaf {
+                            field: "dog2s".to_string(),
+                            comparator: Comparator::GT,
+                            immediate: TaskPropVariant::Number(1.0),
+                        },
+                        Filter::Leaf {
+                            field: "dog3s".to_string(),
+                            comparator: Comparator::EQ,
+                            immediate: TaskPropVariant::Boolean(true),
+                        },
+                    ],
+                },
+                &[],
+            )
+            .unwrap(),
+        )
+        .build(sea_orm::DatabaseBackend::Postgres)
+        .to_string();
+    // every leaf is its own subquery on the task, no joined rows to duplicate tasks
+    assert!(!query.contains("JOIN"));
+    assert_eq!(query.matches("EXISTS").count(), 2);
+    assert!(query.contains(r#""task_num_property"."task_id" = "task"."id""#));
+    assert!(query.contains(r#""task_bool_property"."task_id" = "task"."id""#));
+}
+
+#[actix_web::test]
+async fn test_construct_filter_string_comparators() {
+    let sql = |comparator: Comparator| {
+        task::Entity::find()
+            .filter(
+                construct_filter(
+                    &Filter::Operator {
+                        op: common::Operator::AND,
+                        childs: vec![
+                            Filter::LeafPrimitive {
+                                field: PrimitiveField::TITLE,
+                                comparator: comparator.clone(),
+                                immediate: TaskPropVariant::String("^a".to_owned()),
+                            },
+                            Filter::Leaf {
+                                field: "owner".to_owned(),
+                                comparator,
+                                immediate: TaskPropVariant::String("^a".to_owned()),
+                            },
+                        ],
+                    },
+                    &[],
+                )
+                .unwrap(),
+            )
+            .build(sea_orm::DatabaseBackend::Postgres)
+            .to_string()
+    };
+    let query = sql(Comparator::REGEX);
+    assert!(query.contains(r#""task"."title" ~ '^a'"#));
+    assert!(query.contains(r#""value" ~ '^a'"#));
+    let query = sql(Comparator::IREGEX);
+    assert!(query.contains(r#""task"."title" ~* '^a'"#));
+    assert!(query.contains(r#""value" ~* '^a'"#));
+    assert!(sql(Comparator::ICONTAINS).contains(r#""task"."title" ILIKE '%^a%'"#));
+    assert!(sql(Comparator::ILIKE).contains(r#""value" ILIKE '^a'"#));
+    assert!(sql(Comparator::IEQ).contains(r#"LOWER("task"."title") = LOWER('^a')"#));
+}
+
+#[actix_web::test]
+async fn test_construct_filter_select() {
+    let sql = |comparator: Comparator, immediate| {
+        task::Entity::find()
+            .filter(
+                construct_filter(
+                    &Filter::Leaf {
+                        field: "labels".to_owned(),
+                        comparator,
+                        immediate,
+                    },
+                    &[],
+                )
+                .unwrap(),
+            )
+            .build(sea_orm::DatabaseBackend::Postgres)
+            .to_string()
+    };
+    let options = TaskPropVariant::MultiSelect(vec!["bug".to_owned(), "ui".to_owned()]);
+    let query = sql(Comparator::EQ, TaskPropVariant::Select("bug".to_owned()));
+    assert!(query.contains(r#"FROM "task_select_property""#));
+    assert!(query.contains(r#""value" = 'bug'"#));
+    let query = sql(Comparator::IN, options.clone());
+    assert!(query.contains(r#"FROM "task_select_property""#));
+    assert!(query.contains(r#""value" IN ('bug', 'ui')"#));
+    let query = sql(Comparator::CONTAINSANY, options.clone());
+    assert!(query.contains(r#"FROM "task_multiselect_property""#));
+    assert!(query.contains(r#""value" && ARRAY ['bug','ui']"#));
+    assert!(sql(Comparator::CONTAINSALL, options.clone()).contains(r#""value" @> ARRAY"#));
+    let query = sql(Comparator::NEQ, options.clone());
+    assert!(
+        query.contains(r#"NOT ("value" @> ARRAY ['bug','ui'] AND "value" <@ ARRAY ['bug','ui'])"#)
+    );
+    assert!(construct_filter(
+        &Filter::Leaf {
+            field: "labels".to_owned(),
+            comparator: Comparator::LT,
+            immediate: options,
+        },
+        &[]
+    )
+    .is_err());
+}
+
+#[actix_web::test]
+async fn test_construct_filter_integer_duration() {
+    let sql = |field: &str, comparator: Comparator, immediate| {
+        task::Entity::find()
+            .filter(
+                construct_filter(
+                    &Filter::Leaf {
+                        field: field.to_owned(),
+                        comparator,
+                        immediate,
+                    },
+                    &[],
+                )
+                .unwrap(),
+            )
+            .build(sea_orm::DatabaseBackend::Postgres)
+            .to_string()
+    };
+    let query = sql(
+        "points",
+        Comparator::GEQ,
+        TaskPropVariant::Integer(i64::MAX),
+    );
+    assert!(query.contains(r#"FROM "task_int_property""#));
+    assert!(query.contains(r#""value" >= 9223372036854775807"#));
+    let query = sql(
+        "estimate",
+        Comparator::LT,
+        TaskPropVariant::Duration(TaskDuration(9000)),
+    );
+    assert!(query.contains(r#"FROM "task_duration_property""#));
+    assert!(query.contains(r#""value" < 9000"#));
+    assert!(construct_filter(
+        &Filter::Leaf {
+            field: "points".to_owned(),
+            comparator: Comparator::LIKE,
+            immediate: TaskPropVariant::Integer(1),
+        },
+        &[]
+    )
+    .is_err());
+}
+
+#[actix_web::test]
+async fn test_construct_filter_global() {
+    let filter = |comparator| Filter::LeafGlobal {
+        field: "due".to_owned(),
+        comparator,
+        global: "sprint end".to_owned(),
+    };
+    let query = task::Entity::find()
+        .filter(construct_filter(&filter(Comparator::LT), &[]).unwrap())
+        .build(sea_orm::DatabaseBackend::Postgres)
+        .to_string();
+    // one subquery per type globals can have
+    assert_eq!(query.matches("EXISTS").count(), 4);
+    assert!(query.contains(
+        r#"INNER JOIN "global_date_property" ON "global_date_property"."property_name" = 'sprint end'"#
+    ));
+    assert!(query.contains(r#""task_date_property"."task_property_name" = 'due'"#));
+    assert!(query.contains(r#""task_date_property"."value" < "global_date_property"."value""#));
+    assert!(construct_filter(&filter(Comparator::LIKE), &[]).is_err());
+}
+
+#[actix_web::test]
+async fn test_construct_filter_references() {
+    let sql = |field: &str, comparator: Comparator, immediate| {
+        task::Entity::find()
+            .filter(
+                construct_filter(
+                    &Filter::Leaf {
+                        field: field.to_owned(),
+                        comparator,
+                        immediate,
+                    },
+                    &[],
+                )
+                .unwrap(),
+            )
+            .build(sea_orm::DatabaseBackend::Postgres)
+            .to_string()
+    };
+    let refs = TaskPropVariant::TaskRefs(vec![1, 4]);
+    let query = sql("duplicate of", Comparator::EQ, TaskPropVariant::TaskRef(4));
+    assert!(query.contains(r#"FROM "task_ref_property""#));
+    assert!(query.contains(r#""task_ref_property"."value" = 4"#));
+    let query = sql("duplicate of", Comparator::IN, refs.clone());
+    assert!(query.contains(r#""task_ref_property"."value" IN (1, 4)"#));
+    let query = sql("blocked by", Comparator::CONTAINSANY, refs.clone());
+    assert!(query.contains(r#""task_refs_property"."value" IN (1, 4)"#));
+    // one subquery for the type, one per referenced task and one for references outside the list
+    let query = sql("blocked by", Comparator::EQ, refs.clone());
+    assert_eq!(query.matches("EXISTS").count(), 4);
+    assert!(query.contains(r#""task_property"."type" = 'taskrefs'"#));
+    assert!(query.contains(r#"NOT EXISTS"#));
+    assert!(query.contains(r#""task_refs_property"."value" NOT IN (1, 4)"#));
+    let query = sql(
+        "assignee",
+        Comparator::NEQ,
+        TaskPropVariant::User("alex".to_owned()),
+    );
+    assert!(query.contains(r#""task_user_property"."value" <> 'alex'"#));
+    assert!(construct_filter(
+        &Filter::Leaf {
+            field: "blocked by".to_owned(),
+            comparator: Comparator::LT,
+            immediate: refs,
+        },
+        &[]
+    )
+    .is_err());
+}
+
+#[actix_web::test]
+async fn test_construct_filter_property_and_dependency() {
+    let sql = |filter| {
+        task::Entity::find()
+            .filter(construct_filter(&filter, &[]).unwrap())
+            .build(sea_orm::DatabaseBackend::Postgres)
+            .to_string()
+    };
+    // a subquery per property table, each correlated to the task
+    let query = sql(Filter::LeafProperty {
+        left: "spent".to_owned(),
+        comparator: Comparator::GT,
+        right: "estimate".to_owned(),
+    });
+    assert_eq!(query.matches("EXISTS").count(), 9);
+    assert!(query.contains(r#""l"."value" > "r"."value""#));
+    assert!(query.contains(r#""l"."task_id" = "task"."id""#));
+
+    let query = sql(Filter::LeafDependency(DependencyFilter::DependentCount {
+        comparator: Comparator::GEQ,
+        count: 3,
+    }));
+    assert!(query.contains(r#"WHERE "dependency"."depends_on_id" = "task"."id") >= 3"#));
+    let query = sql(Filter::LeafDependency(DependencyFilter::DependsOn {
+        task: 7,
+        transitive: true,
+    }));
+    assert!(query.contains("WITH RECURSIVE"));
+    assert!(query.contains(r#""depends_on_id" = 7"#));
+
+    // only ordering comparators make sense without an immediate
+    assert!(construct_filter(
+        &Filter::LeafProperty {
+            left: "spent".to_owned(),
+            comparator: Comparator::LIKE,
+            right: "estimate".to_owned(),
+        },
+        &[]
+    )
+    .is_err());
+}
+
+#[actix_web::test]
+async fn test_filter_invalid_regex() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres).into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(get_filter_request),
+    )
+    .await;
+    let req = test::TestRequest::default()
+        .set_json(FilterRequest {
+            filter: Filter::LeafPrimitive {
+                field: PrimitiveField::TITLE,
+                comparator: Comparator::REGEX,
+                immediate: TaskPropVariant::String("(".to_owned()),
+            },
+            sort: vec![],
+            limit: None,
+            offset: 0,
+            req_id: 0,
+        })
+        .uri("/filter")
+        .to_request();
+    // rejected before querying the database, with the reason in the body
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    let err: common::filter::FilterError = test::read_body_json(resp).await;
+    assert!(matches!(
+        err,
+        common::filter::FilterError::InvalidRegex { pattern, .. } if pattern == "("
+    ));
+}
+
+#[actix_web::test]
+async fn test_filter_sort_and_page() {
+    let task = |id| task::Model {
+        id,
+        title: "title".to_string(),
+        completed: true,
+        last_edited: chrono::NaiveDateTime::default(),
+    };
+    let no_schema = || vec![] as Vec<property_schema::Model>;
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([no_schema()])
+        .append_query_results([[task(3), task(4), task(5)]])
+        .append_query_results([no_schema()])
+        .append_query_results([[task(5)]])
+        .into_connection();
+    let sort = vec![
+        SortKey {
+            field: SortField::Property("due".to_string()),
+            descending: false,
+            nulls_first: false,
+        },
+        SortKey {
+            field: SortField::Primitive(PrimitiveField::TITLE),
+            descending: true,
+            nulls_first: true,
+        },
+    ];
+
+    // one more row than the limit means there's another page
+    let res = filter(
+        &db,
+        &FilterRequest {
+            filter: Filter::None,
+            sort: sort.clone(),
+            limit: Some(2),
+            offset: 2,
+            req_id: 0,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(res.tasks, vec![3, 4]);
+    assert_eq!(res.offset, 2);
+    assert!(res.more);
+
+    let res = filter(
+        &db,
+        &FilterRequest {
+            filter: Filter::None,
+            sort,
+            limit: Some(2),
+            offset: 4,
+            req_id: 0,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(res.tasks, vec![5]);
+    assert!(!res.more);
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(log.contains(r#"ORDER BY (SELECT \"task_num_property\".\"value\""#));
+    assert!(log.contains(r#"\"task\".\"title\" DESC NULLS FIRST, \"task\".\"id\" ASC"#));
+    assert!(log.contains("LIMIT $"));
+    assert!(log.contains("OFFSET $"));
+}
+
 #[actix_web::test]
 async fn db_test() {
     env::set_var("RUST_LOG", "info");
@@ -599,6 +1030,41 @@ async fn db_test() {
         id0,
         &db_conn
     );
+    simple_primitive_test!(
+        PrimitiveField::TITLE,
+        Comparator::REGEX,
+        TaskPropVariant::String("^a d.de$".to_string()),
+        id0,
+        &db_conn
+    );
+    simple_primitive_test!(
+        PrimitiveField::TITLE,
+        Comparator::IREGEX,
+        TaskPropVariant::String("^A DUDE$".to_string()),
+        id0,
+        &db_conn
+    );
+    simple_primitive_test!(
+        PrimitiveField::TITLE,
+        Comparator::IEQ,
+        TaskPropVariant::String("A DUDE".to_string()),
+        id0,
+        &db_conn
+    );
+    simple_primitive_test!(
+        PrimitiveField::TITLE,
+        Comparator::ICONTAINS,
+        TaskPropVariant::String("DUDE".to_string()),
+        id0,
+        &db_conn
+    );
+    simple_primitive_test!(
+        PrimitiveField::TITLE,
+        Comparator::ILIKE,
+        TaskPropVariant::String("%DUDE%".to_string()),
+        id0,
+        &db_conn
+    );
 
     simple_primitive_test!(
         PrimitiveField::COMPLETED,
@@ -716,6 +1182,41 @@ async fn db_test() {
         id4,
         &db_conn
     );
+    simple_test!(
+        "dogs",
+        Comparator::REGEX,
+        TaskPropVariant::String("^a d.de$".to_string()),
+        id4,
+        &db_conn
+    );
+    simple_test!(
+        "dogs",
+        Comparator::IREGEX,
+        TaskPropVariant::String("^A DUDE$".to_string()),
+        id4,
+        &db_conn
+    );
+    simple_test!(
+        "dogs",
+        Comparator::IEQ,
+        TaskPropVariant::String("A DUDE".to_string()),
+        id4,
+        &db_conn
+    );
+    simple_test!(
+        "dogs",
+        Comparator::ICONTAINS,
+        TaskPropVariant::String("DUDE".to_string()),
+        id4,
+        &db_conn
+    );
+    simple_test!(
+        "dogs",
+        Comparator::ILIKE,
+        TaskPropVariant::String("%DUDE%".to_string()),
+        id4,
+        &db_conn
+    );
     info!("making dates");
     simple_make!(
         TaskPropVariant::Date(
@@ -829,6 +1330,9 @@ async fn db_test() {
     let mut res = filter(
         &db_conn,
         &FilterRequest {
+            sort: vec![],
+            limit: None,
+            offset: 0,
             req_id: 0,
             filter: Filter::Operator {
                 op: common::Operator::AND,
@@ -857,6 +1361,9 @@ async fn db_test() {
     res = filter(
         &db_conn,
         &FilterRequest {
+            sort: vec![],
+            limit: None,
+            offset: 0,
             req_id: 0,
             filter: Filter::Operator {
                 op: common::Operator::OR,
@@ -886,6 +1393,9 @@ async fn db_test() {
     res = filter(
         &db_conn,
         &FilterRequest {
+            sort: vec![],
+            limit: None,
+            offset: 0,
             req_id: 0,
             filter: Filter::Operator {
                 op: common::Operator::NOT,
@@ -901,7 +1411,870 @@ async fn db_test() {
     .unwrap();
     assert!(!res.tasks.contains(&id9));
 
+    info!("making multi-property tests");
+    let due = |month| {
+        TaskPropVariant::Date(
+            NaiveDate::from_ymd_opt(2024, month, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+        )
+    };
+    super_make!(
+        id10,
+        &db_conn,
+        "multi 1",
+        false,
+        ("size", TaskPropVariant::Number(5.0)),
+        ("urgent", TaskPropVariant::Boolean(true)),
+        ("owner", TaskPropVariant::String("sam".to_string())),
+        ("due", due(3))
+    );
+    super_make!(
+        id11,
+        &db_conn,
+        "multi 2",
+        false,
+        ("size", TaskPropVariant::Number(1.0)),
+        ("urgent", TaskPropVariant::Boolean(true)),
+        ("owner", TaskPropVariant::String("alex".to_string())),
+        ("due", due(9))
+    );
+    super_make!(
+        id12,
+        &db_conn,
+        "multi 3",
+        false,
+        ("owner", TaskPropVariant::String("sam".to_string()))
+    );
+    let size = |comparator, n| Filter::Leaf {
+        field: "size".to_string(),
+        comparator,
+        immediate: TaskPropVariant::Number(n),
+    };
+    let owner = |comparator, s: &str| Filter::Leaf {
+        field: "owner".to_string(),
+        comparator,
+        immediate: TaskPropVariant::String(s.to_string()),
+    };
+    let urgent = Filter::Leaf {
+        field: "urgent".to_string(),
+        comparator: Comparator::EQ,
+        immediate: TaskPropVariant::Boolean(true),
+    };
+    let multi_filter = |filter| {
+        let db_conn = &db_conn;
+        async move {
+            let mut tasks = filter_tasks(db_conn, filter).await;
+            tasks.retain(|id| [id10, id11, id12].contains(id));
+            tasks
+        }
+    };
+
+    info!("multi filter AND across types");
+    let tasks = multi_filter(Filter::Operator {
+        op: common::Operator::AND,
+        childs: vec![size(Comparator::GT, 2.0), owner(Comparator::EQ, "sam")],
+    })
+    .await;
+    assert_eq!(tasks, vec![id10]);
+
+    let tasks = multi_filter(Filter::Operator {
+        op: common::Operator::AND,
+        childs: vec![
+            Filter::Leaf {
+                field: "due".to_string(),
+                comparator: Comparator::LT,
+                immediate: due(6),
+            },
+            urgent.clone(),
+            owner(Comparator::CONTAINS, "a"),
+        ],
+    })
+    .await;
+    assert_eq!(tasks, vec![id10]);
+
+    info!("multi filter results aren't duplicated per property row");
+    let mut tasks = multi_filter(Filter::Operator {
+        op: common::Operator::AND,
+        childs: vec![urgent.clone(), owner(Comparator::CONTAINS, "a")],
+    })
+    .await;
+    tasks.sort();
+    assert_eq!(tasks, vec![id10, id11]);
+
+    info!("multi filter OR across types");
+    let mut tasks = multi_filter(Filter::Operator {
+        op: common::Operator::OR,
+        childs: vec![size(Comparator::LT, 2.0), owner(Comparator::EQ, "sam")],
+    })
+    .await;
+    tasks.sort();
+    assert_eq!(tasks, vec![id10, id11, id12]);
+
+    info!("multi filter NOT");
+    let mut tasks = multi_filter(Filter::Operator {
+        op: common::Operator::NOT,
+        childs: vec![urgent.clone()],
+    })
+    .await;
+    tasks.sort();
+    assert_eq!(tasks, vec![id12]);
+
+    let tasks = multi_filter(Filter::Operator {
+        op: common::Operator::AND,
+        childs: vec![
+            owner(Comparator::EQ, "sam"),
+            Filter::Operator {
+                op: common::Operator::NOT,
+                childs: vec![size(Comparator::LT, 2.0)],
+            },
+            Filter::Operator {
+                op: common::Operator::OR,
+                childs: vec![urgent, size(Comparator::GEQ, 5.0)],
+            },
+        ],
+    })
+    .await;
+    assert_eq!(tasks, vec![id10]);
+
+    info!("relative dates");
+    let relative = |anchor, days: i64| {
+        TaskPropVariant::RelativeDate(RelativeDate {
+            anchor,
+            offset_secs: days * 86400,
+            timezone: None,
+        })
+    };
+    let mut tasks = multi_filter(Filter::Leaf {
+        field: "due".to_string(),
+        comparator: Comparator::LT,
+        immediate: relative(DateAnchor::StartOfMonth, 0),
+    })
+    .await;
+    tasks.sort();
+    assert_eq!(tasks, vec![id10, id11]);
+    let tasks = multi_filter(Filter::Leaf {
+        field: "due".to_string(),
+        comparator: Comparator::GEQ,
+        immediate: relative(DateAnchor::Now, 0),
+    })
+    .await;
+    assert!(tasks.is_empty());
+    // edited in the last hour, both sides are in UTC
+    let mut tasks = multi_filter(Filter::LeafPrimitive {
+        field: PrimitiveField::LASTEDITED,
+        comparator: Comparator::GEQ,
+        immediate: TaskPropVariant::RelativeDate(RelativeDate {
+            anchor: DateAnchor::Now,
+            offset_secs: -3600,
+            timezone: None,
+        }),
+    })
+    .await;
+    tasks.sort();
+    assert_eq!(tasks, vec![id10, id11, id12]);
+
+    info!("sorting and paging");
+    let sorted = |sort, limit, offset| {
+        let db_conn = &db_conn;
+        async move {
+            filter(
+                db_conn,
+                &FilterRequest {
+                    filter: owner(Comparator::CONTAINS, ""),
+                    sort,
+                    limit,
+                    offset,
+                    req_id: 0,
+                },
+            )
+            .await
+            .unwrap()
+        }
+    };
+    let by_size = |descending, nulls_first| {
+        vec![SortKey {
+            field: SortField::Property("size".to_string()),
+            descending,
+            nulls_first,
+        }]
+    };
+    let res = sorted(by_size(true, false), None, 0).await;
+    assert_eq!(res.tasks, vec![id10, id11, id12]);
+    let res = sorted(by_size(false, true), None, 0).await;
+    assert_eq!(res.tasks, vec![id12, id11, id10]);
+    let res = sorted(
+        vec![SortKey {
+            field: SortField::Primitive(PrimitiveField::TITLE),
+            descending: true,
+            nulls_first: false,
+        }],
+        None,
+        0,
+    )
+    .await;
+    assert_eq!(res.tasks, vec![id12, id11, id10]);
+
+    let res = sorted(by_size(true, false), Some(2), 0).await;
+    assert_eq!(res.tasks, vec![id10, id11]);
+    assert!(res.more);
+    let res = sorted(by_size(true, false), Some(2), 2).await;
+    assert_eq!(res.tasks, vec![id12]);
+    assert!(!res.more);
+
+    info!("full-text search");
+    let search_for = |query: &str, limit| {
+        let db_conn = &db_conn;
+        let req = SearchRequest {
+            query: query.to_string(),
+            limit,
+            req_id: 0,
+        };
+        async move { search(db_conn, &req).await.unwrap().results.clone() }
+    };
+    // string property matches, stemmed
+    let results = search_for("sams", None).await;
+    let mut ids = results.iter().map(|r| r.task_id).collect::<Vec<TaskID>>();
+    ids.sort();
+    assert_eq!(ids, vec![id10, id12]);
+    assert!(results.iter().all(|r| r.field.as_deref() == Some("owner")));
+    assert!(results
+        .iter()
+        .all(|r| r.snippet == "sam" && r.highlights == vec![(0, 3)]));
+
+    // title and property matches rank the task higher
+    let results = search_for("multi or alex", None).await;
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].task_id, id11);
+    assert!(results[0].rank > results[1].rank);
+    assert_eq!(search_for("multi", Some(2)).await.len(), 2);
+    assert!(search_for("zebra", None).await.is_empty());
+
+    info!("making dependency graph");
+    super_make!(
+        id13,
+        &db_conn,
+        "graph 1",
+        true,
+        ("spent", TaskPropVariant::Number(5.0)),
+        ("estimate", TaskPropVariant::Number(3.0))
+    );
+    super_make!(
+        id14,
+        &db_conn,
+        "graph 2",
+        false,
+        ("spent", TaskPropVariant::Number(2.0)),
+        ("estimate", TaskPropVariant::Number(3.0))
+    );
+    super_make!(
+        id15,
+        &db_conn,
+        "graph 3",
+        false,
+        ("spent", TaskPropVariant::String("a lot".to_string())),
+        ("estimate", TaskPropVariant::Number(1.0))
+    );
+    super_make!(id16, &db_conn, "graph 4", false,);
+    // 14 -> 13, 15 -> 14, 16 -> 13
+    for (task_id, dep) in [(id14, id13), (id15, id14), (id16, id13)] {
+        update_task(
+            &db_conn,
+            &UpdateTaskRequest {
+                task_id,
+                name: None,
+                checked: None,
+                props_to_add: vec![],
+                props_to_remove: vec![],
+                deps_to_add: vec![dep],
+                deps_to_remove: vec![],
+                scripts_to_add: vec![],
+                scripts_to_remove: vec![],
+                parent: None,
+                req_id: 0,
+            },
+        )
+        .await
+        .unwrap();
+    }
+    let graph_filter = |filter| {
+        let db_conn = &db_conn;
+        async move {
+            let mut tasks = filter_tasks(db_conn, filter).await;
+            tasks.retain(|id| [id13, id14, id15, id16].contains(id));
+            tasks.sort();
+            tasks
+        }
+    };
+
+    info!("property to property filters");
+    let spent = |comparator| Filter::LeafProperty {
+        left: "spent".to_string(),
+        comparator,
+        right: "estimate".to_string(),
+    };
+    assert_eq!(graph_filter(spent(Comparator::GT)).await, vec![id13]);
+    assert_eq!(graph_filter(spent(Comparator::LEQ)).await, vec![id14]);
+    // tasks missing a property or with mismatched types don't match
+    assert_eq!(
+        graph_filter(Filter::Operator {
+            op: common::Operator::NOT,
+            childs: vec![spent(Comparator::GT)],
+        })
+        .await,
+        vec![id14, id15, id16]
+    );
+
+    info!("dependency filters");
+    let dependency = |filter| graph_filter(Filter::LeafDependency(filter));
+    assert_eq!(
+        dependency(DependencyFilter::HasIncompleteDependency).await,
+        vec![id15]
+    );
+    assert_eq!(
+        dependency(DependencyFilter::DependentCount {
+            comparator: Comparator::GEQ,
+            count: 2,
+        })
+        .await,
+        vec![id13]
+    );
+    assert_eq!(
+        dependency(DependencyFilter::DependencyCount {
+            comparator: Comparator::EQ,
+            count: 0,
+        })
+        .await,
+        vec![id13]
+    );
+    assert_eq!(
+        dependency(DependencyFilter::DependsOn {
+            task: id13,
+            transitive: false,
+        })
+        .await,
+        vec![id14, id16]
+    );
+    assert_eq!(
+        dependency(DependencyFilter::DependsOn {
+            task: id13,
+            transitive: true,
+        })
+        .await,
+        vec![id14, id15, id16]
+    );
+
+    info!("property writes round trip");
+    let write = |name: &str, value| {
+        let db_conn = &db_conn;
+        let req = UpdateTaskRequest {
+            task_id: id16,
+            name: None,
+            checked: None,
+            props_to_add: vec![TaskProp {
+                name: name.to_string(),
+                value,
+            }],
+            props_to_remove: vec![],
+            deps_to_add: vec![],
+            deps_to_remove: vec![],
+            scripts_to_add: vec![],
+            scripts_to_remove: vec![],
+            parent: None,
+            req_id: 0,
+        };
+        async move { update_task(db_conn, &req).await }
+    };
+    let values = [
+        TaskPropVariant::String("first".to_string()),
+        TaskPropVariant::Number(1.5),
+        due(1),
+        TaskPropVariant::Boolean(false),
+    ];
+    let updated = [
+        TaskPropVariant::String("second".to_string()),
+        TaskPropVariant::Number(-2.25),
+        due(2),
+        TaskPropVariant::Boolean(true),
+    ];
+    for (value, new_value) in values.into_iter().zip(updated) {
+        let name = value.type_string();
+        write(name, value.clone()).await.unwrap();
+        let read = get_property_or_err(&db_conn, &name.to_string(), id16).await;
+        assert_eq!(read, Ok(Some(value)));
+        // overwriting keeps a single value
+        write(name, new_value.clone()).await.unwrap();
+        let read = get_property_or_err(&db_conn, &name.to_string(), id16).await;
+        assert_eq!(read, Ok(Some(new_value)));
+    }
+    assert!(write("number", TaskPropVariant::Boolean(true))
+        .await
+        .is_err());
+
+    info!("property schema");
+    let stage = PropertyDefinition {
+        name: "stage".to_string(),
+        typ: TaskPropType::String,
+        description: String::new(),
+        default: Some(TaskPropVariant::String("a".to_string())),
+        required: true,
+        allowed_values: vec![
+            TaskPropVariant::String("a".to_string()),
+            TaskPropVariant::String("b".to_string()),
+        ],
+        options: vec![],
+        formula: None,
+    };
+    let define = |definition: PropertyDefinition| {
+        let db_conn = &db_conn;
+        async move {
+            define_property(
+                db_conn,
+                &DefinePropertyRequest {
+                    definition,
+                    req_id: 0,
+                },
+            )
+            .await
+        }
+    };
+    // id16 already has a string named "string"
+    assert!(define(PropertyDefinition {
+        name: "string".to_string(),
+        typ: TaskPropType::Number,
+        ..stage.clone()
+    })
+    .await
+    .is_err());
+    define(stage.clone()).await.unwrap();
+    assert_eq!(get_schema(&db_conn).await.unwrap(), vec![stage.clone()]);
+    super_make!(id17, &db_conn, "staged", false,);
+    assert_eq!(
+        get_property_or_err(&db_conn, &"stage".to_string(), id17).await,
+        Ok(Some(TaskPropVariant::String("a".to_string())))
+    );
+    assert!(write("stage", TaskPropVariant::String("c".to_string()))
+        .await
+        .is_err());
+    write("stage", TaskPropVariant::String("b".to_string()))
+        .await
+        .unwrap();
+    let remove_stage = UpdateTaskRequest {
+        task_id: id17,
+        name: None,
+        checked: None,
+        props_to_add: vec![],
+        props_to_remove: vec!["stage".to_string()],
+        deps_to_add: vec![],
+        deps_to_remove: vec![],
+        scripts_to_add: vec![],
+        scripts_to_remove: vec![],
+        parent: None,
+        req_id: 0,
+    };
+    assert!(update_task(&db_conn, &remove_stage).await.is_err());
+    // redefining replaces the definition
+    define(PropertyDefinition {
+        required: false,
+        ..stage.clone()
+    })
+    .await
+    .unwrap();
+    update_task(&db_conn, &remove_stage).await.unwrap();
+    assert!(get_property_or_err(&db_conn, &"stage".to_string(), id17)
+        .await
+        .is_err());
+    property_schema::Entity::delete_by_id("stage")
+        .exec(&db_conn)
+        .await
+        .unwrap();
+    assert_eq!(get_schema(&db_conn).await.unwrap(), vec![]);
+
+    info!("select properties");
+    let select_definition = |name: &str, typ, options: &[&str]| PropertyDefinition {
+        name: name.to_string(),
+        typ,
+        description: String::new(),
+        default: None,
+        required: false,
+        allowed_values: vec![],
+        options: options.iter().map(|o| o.to_string()).collect(),
+        formula: None,
+    };
+    define(select_definition(
+        "status",
+        TaskPropType::Select,
+        &["todo", "doing", "done"],
+    ))
+    .await
+    .unwrap();
+    define(select_definition(
+        "labels",
+        TaskPropType::MultiSelect,
+        &["bug", "ui", "docs"],
+    ))
+    .await
+    .unwrap();
+    let options = |options: &[&str]| {
+        TaskPropVariant::MultiSelect(options.iter().map(|o| o.to_string()).collect())
+    };
+    let select = |s: &str| TaskPropVariant::Select(s.to_string());
+    for (task_id, status, labels) in [
+        (id13, select("doing"), options(&["bug", "ui"])),
+        (id14, select("todo"), options(&["docs"])),
+    ] {
+        update_task(
+            &db_conn,
+            &UpdateTaskRequest {
+                task_id,
+                name: None,
+                checked: None,
+                props_to_add: vec![
+                    TaskProp {
+                        name: "status".to_string(),
+                        value: status,
+                    },
+                    TaskProp {
+                        name: "labels".to_string(),
+                        value: labels,
+                    },
+                ],
+                props_to_remove: vec![],
+                deps_to_add: vec![],
+                deps_to_remove: vec![],
+                scripts_to_add: vec![],
+                scripts_to_remove: vec![],
+                parent: None,
+                req_id: 0,
+            },
+        )
+        .await
+        .unwrap();
+    }
+    assert_eq!(
+        get_property_or_err(&db_conn, &"labels".to_string(), id13).await,
+        Ok(Some(options(&["bug", "ui"])))
+    );
+    assert!(write("status", select("blocked")).await.is_err());
+    let leaf = |field: &str, comparator, immediate| Filter::Leaf {
+        field: field.to_string(),
+        comparator,
+        immediate,
+    };
+    assert_eq!(
+        graph_filter(leaf("status", Comparator::EQ, select("doing"))).await,
+        vec![id13]
+    );
+    assert_eq!(
+        graph_filter(leaf("status", Comparator::IN, options(&["todo", "doing"]))).await,
+        vec![id13, id14]
+    );
+    assert_eq!(
+        graph_filter(leaf(
+            "labels",
+            Comparator::CONTAINSANY,
+            options(&["ui", "docs"])
+        ))
+        .await,
+        vec![id13, id14]
+    );
+    assert_eq!(
+        graph_filter(leaf(
+            "labels",
+            Comparator::CONTAINSALL,
+            options(&["ui", "bug"])
+        ))
+        .await,
+        vec![id13]
+    );
+    assert_eq!(
+        graph_filter(leaf("labels", Comparator::EQ, options(&["ui", "bug"]))).await,
+        vec![id13]
+    );
+    assert_eq!(
+        graph_filter(leaf("labels", Comparator::NEQ, options(&["ui", "bug"]))).await,
+        vec![id14]
+    );
+
+    info!("integer and duration properties");
+    let big = i64::MAX - 1;
+    write("points", TaskPropVariant::Integer(big))
+        .await
+        .unwrap();
+    write("estimate", TaskPropVariant::Duration(TaskDuration(9000)))
+        .await
+        .unwrap();
+    // stored exactly
+    assert_eq!(
+        get_property_or_err(&db_conn, &"points".to_string(), id16).await,
+        Ok(Some(TaskPropVariant::Integer(big)))
+    );
+    assert_eq!(
+        get_property_or_err(&db_conn, &"estimate".to_string(), id16).await,
+        Ok(Some(TaskPropVariant::Duration(TaskDuration(9000))))
+    );
+    assert_eq!(
+        graph_filter(leaf(
+            "points",
+            Comparator::GT,
+            TaskPropVariant::Integer(big - 1)
+        ))
+        .await,
+        vec![id16]
+    );
+    assert_eq!(
+        graph_filter(leaf(
+            "estimate",
+            Comparator::GEQ,
+            TaskPropVariant::Duration("2h30m".parse().unwrap())
+        ))
+        .await,
+        vec![id16]
+    );
+    assert!(graph_filter(leaf(
+        "estimate",
+        Comparator::GT,
+        TaskPropVariant::Duration("2h30m".parse().unwrap())
+    ))
+    .await
+    .is_empty());
+
+    info!("task and user references");
+    let add_prop = |task_id, name: &str, value| {
+        let db_conn = &db_conn;
+        let req = UpdateTaskRequest {
+            task_id,
+            name: None,
+            checked: None,
+            props_to_add: vec![TaskProp {
+                name: name.to_string(),
+                value,
+            }],
+            props_to_remove: vec![],
+            deps_to_add: vec![],
+            deps_to_remove: vec![],
+            scripts_to_add: vec![],
+            scripts_to_remove: vec![],
+            parent: None,
+            req_id: 0,
+        };
+        async move { update_task(db_conn, &req).await }
+    };
+    add_prop(id13, "duplicate of", TaskPropVariant::TaskRef(id14))
+        .await
+        .unwrap();
+    add_prop(
+        id14,
+        "blocked by",
+        TaskPropVariant::TaskRefs(vec![id15, id13, id15]),
+    )
+    .await
+    .unwrap();
+    write("reviewer", TaskPropVariant::User("alex".to_string()))
+        .await
+        .unwrap();
+    // references have to point at existing tasks
+    assert!(write("duplicate of", TaskPropVariant::TaskRef(-1))
+        .await
+        .is_err());
+    assert_eq!(
+        get_property_or_err(&db_conn, &"blocked by".to_string(), id14).await,
+        Ok(Some(TaskPropVariant::TaskRefs(vec![id13, id15])))
+    );
+    assert_eq!(
+        graph_filter(leaf(
+            "duplicate of",
+            Comparator::EQ,
+            TaskPropVariant::TaskRef(id14)
+        ))
+        .await,
+        vec![id13]
+    );
+    let refs = |ids: &[TaskID]| TaskPropVariant::TaskRefs(ids.to_vec());
+    assert_eq!(
+        graph_filter(leaf("blocked by", Comparator::CONTAINSALL, refs(&[id13]))).await,
+        vec![id14]
+    );
+    assert_eq!(
+        graph_filter(leaf("blocked by", Comparator::EQ, refs(&[id15, id13]))).await,
+        vec![id14]
+    );
+    assert_eq!(
+        graph_filter(leaf("blocked by", Comparator::NEQ, refs(&[id13]))).await,
+        vec![id14]
+    );
+    assert!(
+        graph_filter(leaf("blocked by", Comparator::EQ, refs(&[id13])))
+            .await
+            .is_empty()
+    );
+    assert_eq!(
+        graph_filter(leaf(
+            "reviewer",
+            Comparator::EQ,
+            TaskPropVariant::User("alex".to_string())
+        ))
+        .await,
+        vec![id16]
+    );
+    // deleting a task deletes the references to it
+    delete_task(
+        &db_conn,
+        &BlobStore::new(std::env::temp_dir().join("abn-db-test"), 0),
+        &DeleteTaskRequest {
+            task_id: id15,
+            req_id: 0,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        get_property_or_err(&db_conn, &"blocked by".to_string(), id14).await,
+        Ok(Some(TaskPropVariant::TaskRefs(vec![id13])))
+    );
+    delete_task(
+        &db_conn,
+        &BlobStore::new(std::env::temp_dir().join("abn-db-test"), 0),
+        &DeleteTaskRequest {
+            task_id: id14,
+            req_id: 0,
+        },
+    )
+    .await
+    .unwrap();
+    // a single reference goes with its property, rather than leaving it without a value
+    assert_eq!(
+        get_property_or_err(&db_conn, &"duplicate of".to_string(), id13).await,
+        Ok(None)
+    );
+
+    info!("global properties");
+    let global = |name: &str, value| TaskProp {
+        name: name.to_string(),
+        value,
+    };
+    set_global(&db_conn, &global("wip limit", TaskPropVariant::Number(2.0)))
+        .await
+        .unwrap();
+    set_global(
+        &db_conn,
+        &global("team", TaskPropVariant::String("core".to_string())),
+    )
+    .await
+    .unwrap();
+    // overwriting keeps the type
+    set_global(&db_conn, &global("wip limit", TaskPropVariant::Number(3.0)))
+        .await
+        .unwrap();
+    assert!(set_global(
+        &db_conn,
+        &global("wip limit", TaskPropVariant::Boolean(true))
+    )
+    .await
+    .is_err());
+    assert_eq!(
+        get_globals(&db_conn).await.unwrap(),
+        vec![
+            global("team", TaskPropVariant::String("core".to_string())),
+            global("wip limit", TaskPropVariant::Number(3.0)),
+        ]
+    );
+    add_prop(id13, "size", TaskPropVariant::Number(5.0))
+        .await
+        .unwrap();
+    write("size", TaskPropVariant::Number(3.0)).await.unwrap();
+    let global_leaf = |field: &str, comparator, global: &str| Filter::LeafGlobal {
+        field: field.to_string(),
+        comparator,
+        global: global.to_string(),
+    };
+    assert_eq!(
+        graph_filter(global_leaf("size", Comparator::GT, "wip limit")).await,
+        vec![id13]
+    );
+    assert_eq!(
+        graph_filter(global_leaf("size", Comparator::EQ, "wip limit")).await,
+        vec![id16]
+    );
+    // values of another type than the global don't match
+    assert!(graph_filter(global_leaf("size", Comparator::EQ, "team"))
+        .await
+        .is_empty());
+    // deleting the global deletes its value
+    global_property::Entity::delete_by_id("wip limit")
+        .exec(&db_conn)
+        .await
+        .unwrap();
+    assert_eq!(
+        get_globals(&db_conn).await.unwrap(),
+        vec![global("team", TaskPropVariant::String("core".to_string()))]
+    );
+    assert!(
+        graph_filter(global_leaf("size", Comparator::EQ, "wip limit"))
+            .await
+            .is_empty()
+    );
+
     info!("shutting down db");
     // if tests are async you must await all of them before running below this will shut down the docker container
     db.stop();
 }
+
+#[actix_web::test]
+async fn test_construct_filter_computed() {
+    let computed = |name: &str, formula: &str| PropertyDefinition {
+        name: name.to_owned(),
+        typ: TaskPropType::Number,
+        description: String::new(),
+        default: None,
+        required: false,
+        allowed_values: vec![],
+        options: vec![],
+        formula: Some(formula.to_owned()),
+    };
+    let schema = [
+        computed(
+            "progress",
+            "done(dependencies) / count(dependencies) * 100 - -prop(\"bonus\")",
+        ),
+        computed("late", "-days_until(\"due\")"),
+        computed(
+            "total",
+            "sum(dependents, \"progress\") + avg(dependencies, \"estimate\")",
+        ),
+        computed("loop", "prop(\"loop\") + 1"),
+    ];
+    let filter = |field: &str, immediate| Filter::Leaf {
+        field: field.to_owned(),
+        comparator: Comparator::GT,
+        immediate,
+    };
+    let sql = |field: &str| {
+        task::Entity::find()
+            .filter(
+                construct_filter(&filter(field, TaskPropVariant::Number(1.0)), &schema).unwrap(),
+            )
+            .build(sea_orm::DatabaseBackend::Postgres)
+            .to_string()
+    };
+    // division by zero is NULL instead of an error, counts aren't divided as integers
+    let progress = sql("progress");
+    assert!(progress.contains(r#"NULLIF(CAST((SELECT COUNT(*) FROM "dependency" AS "related1""#));
+    assert!(progress.contains(r#""related_task1"."completed" = TRUE"#));
+    assert!(progress.contains(r#""task_int_property"."task_property_name" = 'bonus'"#));
+    assert!(sql("late").contains(r#"(NOW() AT TIME ZONE 'UTC'))) / 86400)) > 1"#));
+    // rollups of computed properties correlate with the related task
+    let total = sql("total");
+    assert!(total.contains(r#"SELECT COALESCE(SUM("#));
+    assert!(total.contains(r#"WHERE "related3"."task_id" = "related1"."task_id""#));
+    assert!(total.contains(r#"WHERE "related1"."depends_on_id" = "task"."id""#));
+    assert!(total.contains(r#"SELECT AVG(COALESCE("#));
+
+    assert!(construct_filter(&filter("loop", TaskPropVariant::Number(1.0)), &schema).is_err());
+    assert!(construct_filter(
+        &filter("progress", TaskPropVariant::String("1".to_owned())),
+        &schema
+    )
+    .is_err());
+}
@@ -0,0 +1,181 @@
+use super::*;
+use actix_web::{http::StatusCode, test, web::Data, App};
+use sea_orm::{MockDatabase, MockExecResult};
+
+fn exec(rows_affected: u64) -> MockExecResult {
+    MockExecResult {
+        last_insert_id: 0,
+        rows_affected,
+    }
+}
+
+#[actix_web::test]
+async fn test_get_globals() {
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 3, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[global_string_property::Model {
+            property_name: "team".to_owned(),
+            value: "core".to_owned(),
+        }]])
+        .append_query_results([[global_num_property::Model {
+            property_name: "wip limit".to_owned(),
+            value: Decimal::new(3, 0),
+        }]])
+        .append_query_results([[global_date_property::Model {
+            property_name: "sprint end".to_owned(),
+            value: date,
+        }]])
+        .append_query_results([Vec::<global_bool_property::Model>::new()])
+        .into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(get_globals_request),
+    )
+    .await;
+    let req = test::TestRequest::default()
+        .set_json(2)
+        .uri("/globals")
+        .to_request();
+    let resp: GetGlobalsResponse = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp.req_id, 2);
+    // sorted by name
+    assert_eq!(
+        resp.globals,
+        vec![
+            TaskProp {
+                name: "sprint end".to_owned(),
+                value: TaskPropVariant::Date(date),
+            },
+            TaskProp {
+                name: "team".to_owned(),
+                value: TaskPropVariant::String("core".to_owned()),
+            },
+            TaskProp {
+                name: "wip limit".to_owned(),
+                value: TaskPropVariant::Number(3.0),
+            },
+        ]
+    );
+}
+
+#[actix_web::test]
+async fn test_set_global() {
+    // new global
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([Vec::<global_property::Model>::new()])
+        .append_exec_results([exec(1), exec(1)])
+        .into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(set_global_request),
+    )
+    .await;
+    let req = test::TestRequest::default()
+        .method(actix_web::http::Method::PUT)
+        .set_json(SetGlobalRequest {
+            global: TaskProp {
+                name: "wip limit".to_owned(),
+                value: TaskPropVariant::Number(3.0),
+            },
+            req_id: 5,
+        })
+        .uri("/global")
+        .to_request();
+    let resp: SetGlobalResponse = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp, 5);
+
+    // existing global is overwritten in place
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[global_property::Model {
+            name: "team".to_owned(),
+            typ: "string".to_owned(),
+        }]])
+        .append_exec_results([exec(1)])
+        .into_connection();
+    set_global(
+        &db,
+        &TaskProp {
+            name: "team".to_owned(),
+            value: TaskPropVariant::String("core".to_owned()),
+        },
+    )
+    .await
+    .unwrap();
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(!log.contains(r#"INSERT INTO \"global_property\""#));
+    assert!(log.contains(r#"INSERT INTO \"global_string_property\""#));
+    assert!(log.contains(r#"ON CONFLICT (\"property_name\") DO UPDATE"#));
+}
+
+#[actix_web::test]
+async fn test_set_global_errors() {
+    // only strings, numbers, dates and booleans can be globals
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres).into_connection();
+    let err = set_global(
+        &db,
+        &TaskProp {
+            name: "labels".to_owned(),
+            value: TaskPropVariant::MultiSelect(vec!["home".to_owned()]),
+        },
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        err.as_response_error().status_code(),
+        StatusCode::BAD_REQUEST
+    );
+
+    // globals keep their type
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[global_property::Model {
+            name: "team".to_owned(),
+            typ: "string".to_owned(),
+        }]])
+        .into_connection();
+    let err = set_global(
+        &db,
+        &TaskProp {
+            name: "team".to_owned(),
+            value: TaskPropVariant::Boolean(true),
+        },
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        err.as_response_error().status_code(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[actix_web::test]
+async fn test_delete_global() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_exec_results([exec(1), exec(0)])
+        .into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(delete_global_request),
+    )
+    .await;
+    let req = || {
+        test::TestRequest::default()
+            .method(actix_web::http::Method::DELETE)
+            .set_json("team")
+            .uri("/global")
+            .to_request()
+    };
+    assert_eq!(
+        test::call_service(&app, req()).await.status(),
+        StatusCode::OK
+    );
+    assert_eq!(
+        test::call_service(&app, req()).await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
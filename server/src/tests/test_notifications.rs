@@ -0,0 +1,170 @@
+use super::*;
+use actix_web::{http::StatusCode, test, web::Data, App};
+use sea_orm::{MockDatabase, MockExecResult};
+
+fn exec(rows_affected: u64) -> MockExecResult {
+    MockExecResult {
+        last_insert_id: 0,
+        rows_affected,
+    }
+}
+
+fn date(day: u32) -> chrono::NaiveDateTime {
+    chrono::NaiveDate::from_ymd_opt(2024, 3, day)
+        .unwrap()
+        .and_hms_opt(9, 0, 0)
+        .unwrap()
+}
+
+fn due_property(typ: &str) -> task_property::Model {
+    task_property::Model {
+        task_id: 1,
+        name: DUE_PROPERTY.to_owned(),
+        typ: typ.to_owned(),
+    }
+}
+
+#[actix_web::test]
+async fn test_set_reminder() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[due_property("date")]])
+        .append_query_results([[due_property("date")]])
+        .append_query_results([[due_property("string")]])
+        .append_query_results([Vec::<task_property::Model>::new()])
+        .append_exec_results([exec(1), exec(1)])
+        .into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(set_reminder_request),
+    )
+    .await;
+    let reminder = |before, req_id| {
+        test::TestRequest::put()
+            .set_json(SetReminderRequest {
+                task_id: 1,
+                property: DUE_PROPERTY.to_owned(),
+                before,
+                req_id,
+            })
+            .uri("/reminder")
+            .to_request()
+    };
+    let resp: SetReminderResponse =
+        test::call_and_read_body_json(&app, reminder(Some(TaskDuration(3600)), 1)).await;
+    assert_eq!(resp, 1);
+    let resp: SetReminderResponse = test::call_and_read_body_json(&app, reminder(None, 2)).await;
+    assert_eq!(resp, 2);
+
+    // reminders can only be set on date properties the task has
+    let resp = test::call_service(&app, reminder(Some(TaskDuration(0)), 3)).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = test::call_service(&app, reminder(Some(TaskDuration(0)), 4)).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_schedule_script() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_exec_results([exec(1), exec(1)])
+        .into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(schedule_script_request),
+    )
+    .await;
+    let req = test::TestRequest::put()
+        .set_json(ScheduleScriptRequest {
+            script_id: 2,
+            at: Some(date(1)),
+            recurrence: Some(Recurrence::Daily { interval: 1 }),
+            req_id: 5,
+        })
+        .uri("/schedule")
+        .to_request();
+    let resp: ScheduleScriptResponse = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp, 5);
+    let req = test::TestRequest::put()
+        .set_json(ScheduleScriptRequest {
+            script_id: 2,
+            at: None,
+            recurrence: None,
+            req_id: 6,
+        })
+        .uri("/schedule")
+        .to_request();
+    let resp: ScheduleScriptResponse = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp, 6);
+}
+
+#[actix_web::test]
+async fn test_get_notifications() {
+    let reminder = notification::Model {
+        id: 7,
+        task_id: Some(1),
+        kind: r#"{"Reminder" : {"property" : "due"}}"#.to_owned(),
+        fire_at: date(1),
+    };
+    let overdue = notification::Model {
+        id: 8,
+        task_id: Some(1),
+        kind: r#""Overdue""#.to_owned(),
+        fire_at: date(2),
+    };
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        // latest first when there's no cursor
+        .append_query_results([[overdue.clone(), reminder.clone()]])
+        .append_query_results([[overdue.clone()]])
+        .append_query_results([[notification::Model {
+            kind: "not json".to_owned(),
+            ..overdue
+        }]])
+        .into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(get_notifications_request),
+    )
+    .await;
+    let notifications = |after, req_id| {
+        test::TestRequest::default()
+            .set_json(GetNotificationsRequest {
+                after,
+                limit: Some(20),
+                req_id,
+            })
+            .uri("/notifications")
+            .to_request()
+    };
+    let resp: GetNotificationsResponse =
+        test::call_and_read_body_json(&app, notifications(None, 1)).await;
+    assert_eq!(
+        resp,
+        GetNotificationsResponse {
+            notifications: vec![
+                Notification {
+                    id: 7,
+                    task_id: Some(1),
+                    kind: NotificationKind::Reminder {
+                        property: "due".to_owned()
+                    },
+                    fire_at: date(1),
+                },
+                Notification {
+                    id: 8,
+                    task_id: Some(1),
+                    kind: NotificationKind::Overdue,
+                    fire_at: date(2),
+                },
+            ],
+            req_id: 1,
+        }
+    );
+    let resp: GetNotificationsResponse =
+        test::call_and_read_body_json(&app, notifications(Some(7), 2)).await;
+    assert_eq!(resp.notifications.len(), 1);
+
+    let resp = test::call_service(&app, notifications(Some(7), 3)).await;
+    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
@@ -1,6 +1,8 @@
 use super::*;
 use actix_web::test;
-use sea_orm::MockDatabase;
+use common::TaskProp;
+use sea_orm::{MockDatabase, MockExecResult, Value};
+use std::collections::BTreeMap;
 
 #[actix_web::test]
 async fn get_bad_id() {
@@ -178,3 +180,235 @@ async fn test_properties_request() {
         vec![Some(TaskPropVariant::String("value".to_string()))]
     )
 }
+
+// write a property to a task that doesn't have it yet, returning the statements that were run
+async fn write_new_property(value: TaskPropVariant) -> String {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([vec![] as Vec<task_property::Model>])
+        .append_exec_results([
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            },
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            },
+        ])
+        .into_connection();
+    write_property(
+        &db,
+        1,
+        &TaskProp {
+            name: "dog".to_string(),
+            value,
+        },
+        None,
+    )
+    .await
+    .unwrap();
+    format!("{:?}", db.into_transaction_log())
+}
+
+#[actix_web::test]
+async fn write_each_type() {
+    let cases = [
+        (
+            TaskPropVariant::String("value".to_string()),
+            "task_string_property",
+        ),
+        (TaskPropVariant::Number(1.5), "task_num_property"),
+        (
+            TaskPropVariant::Date(chrono::NaiveDateTime::default()),
+            "task_date_property",
+        ),
+        (TaskPropVariant::Boolean(true), "task_bool_property"),
+    ];
+    for (value, table) in cases {
+        let typ = value.type_string();
+        let log = write_new_property(value).await;
+        // the type is recorded with the same name reads expect
+        assert!(log.contains(r#"INSERT INTO \"task_property\""#));
+        assert!(log.contains(&format!("String(Some({typ:?}))")));
+        // and the value is upserted into its typed table
+        assert!(log.contains(&format!(r#"INSERT INTO \"{table}\""#)));
+        assert!(log.contains(r#"ON CONFLICT (\"task_id\", \"task_property_name\") DO UPDATE SET \"value\" = \"excluded\".\"value\""#));
+        // both in one transaction, so a rejected value doesn't leave the type behind
+        assert!(log.starts_with(r#"[Transaction { stmts: [Statement { sql: "BEGIN""#));
+        assert!(log.contains(r#"Statement { sql: "COMMIT""#));
+    }
+}
+
+#[actix_web::test]
+async fn write_existing_property() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task_property::Model {
+            task_id: 1,
+            name: "dog".to_string(),
+            typ: "number".to_string(),
+        }]])
+        .append_exec_results([MockExecResult {
+            last_insert_id: 0,
+            rows_affected: 1,
+        }])
+        .into_connection();
+    let prop = TaskProp {
+        name: "dog".to_string(),
+        value: TaskPropVariant::Number(2.0),
+    };
+    write_property(&db, 1, &prop, None).await.unwrap();
+    let log = format!("{:?}", db.into_transaction_log());
+    // looked up by the property's name and only the value is written
+    assert!(log.contains(r#"String(Some("dog"))"#));
+    assert!(!log.contains(r#"INSERT INTO \"task_property\""#));
+    assert!(log.contains(r#"INSERT INTO \"task_num_property\""#));
+}
+
+#[actix_web::test]
+async fn write_bad_number() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task_property::Model {
+            task_id: 1,
+            name: "dog".to_string(),
+            typ: "number".to_string(),
+        }]])
+        .into_connection();
+    let prop = TaskProp {
+        name: "dog".to_string(),
+        value: TaskPropVariant::Number(f64::NAN),
+    };
+    assert!(write_property(&db, 1, &prop, None).await.is_err());
+}
+
+#[actix_web::test]
+async fn get_unknown_type() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task_property::Model {
+            task_id: 1,
+            name: "name".to_string(),
+            typ: "real".to_string(),
+        }]])
+        .into_connection();
+
+    let res = get_property_or_err(&db, &"name".to_string(), 1).await;
+    assert!(res.is_err());
+}
+
+#[actix_web::test]
+async fn write_select() {
+    let prop = TaskProp {
+        name: "status".to_string(),
+        value: TaskPropVariant::Select("doing".to_string()),
+    };
+    // options come from the schema
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres).into_connection();
+    assert!(write_property(&db, 1, &prop, None).await.is_err());
+
+    let definition = PropertyDefinition {
+        name: "status".to_string(),
+        typ: TaskPropType::Select,
+        description: String::new(),
+        default: None,
+        required: false,
+        allowed_values: vec![],
+        options: vec!["todo".to_string(), "doing".to_string()],
+        formula: None,
+    };
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([vec![] as Vec<task_property::Model>])
+        .append_exec_results([
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            },
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            },
+        ])
+        .into_connection();
+    write_property(&db, 1, &prop, Some(&definition))
+        .await
+        .unwrap();
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(log.contains(r#"INSERT INTO \"task_select_property\""#));
+    assert!(log.contains(r#"String(Some("select"))"#));
+}
+
+#[actix_web::test]
+async fn write_task_refs() {
+    let prop = TaskProp {
+        name: "blocked by".to_string(),
+        value: TaskPropVariant::TaskRefs(vec![3, 2, 3]),
+    };
+    let exec = MockExecResult {
+        last_insert_id: 0,
+        rows_affected: 1,
+    };
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([vec![task_property::Model {
+            task_id: 1,
+            name: "blocked by".to_string(),
+            typ: "taskrefs".to_string(),
+        }]])
+        .append_exec_results([exec.clone(), exec])
+        .into_connection();
+    write_property(&db, 1, &prop, None).await.unwrap();
+    let log = format!("{:?}", db.into_transaction_log());
+    // old references are replaced, without duplicates
+    assert!(log.contains(r#"DELETE FROM \"task_refs_property\""#));
+    assert!(log.contains(r#"INSERT INTO \"task_refs_property\""#));
+    assert_eq!(log.matches("Int(Some(3))").count(), 1);
+}
+
+#[actix_web::test]
+async fn get_computed() {
+    let model = property_schema::Model {
+        name: "progress".to_string(),
+        typ: "number".to_string(),
+        description: String::new(),
+        default_value: None,
+        required: false,
+        allowed_values: "[]".to_string(),
+        options: "[]".to_string(),
+        formula: Some("done(dependencies) / count(dependencies) * 100".to_string()),
+    };
+    // computed properties aren't stored, the formula is evaluated instead
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([vec![] as Vec<task_property::Model>])
+        .append_query_results([[model.clone()]])
+        .append_query_results([[BTreeMap::from([("value", Value::from(Some(50.0)))])]])
+        .append_query_results([vec![] as Vec<task_property::Model>])
+        .append_query_results([[model]])
+        .append_query_results([[BTreeMap::from([("value", Value::Double(None))])]])
+        .into_connection();
+    let res = get_property_or_err(&db, &"progress".to_string(), 1).await;
+    assert_eq!(res, Ok(Some(TaskPropVariant::Number(50.0))));
+    // no dependencies to divide by
+    let res = get_property_or_err(&db, &"progress".to_string(), 2).await;
+    assert_eq!(res, Ok(None));
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(log.contains(r#"SELECT CAST(((CAST((SELECT COUNT(*) FROM \"dependency\""#));
+    assert!(log.contains(r#"AS DOUBLE PRECISION) AS \"value\""#));
+
+    // computed properties can't be written
+    let definition = definition_from_model(property_schema::Model {
+        name: "progress".to_string(),
+        typ: "number".to_string(),
+        description: String::new(),
+        default_value: None,
+        required: false,
+        allowed_values: "[]".to_string(),
+        options: "[]".to_string(),
+        formula: Some("1".to_string()),
+    })
+    .unwrap();
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres).into_connection();
+    let prop = TaskProp {
+        name: "progress".to_string(),
+        value: TaskPropVariant::Number(1.0),
+    };
+    assert!(write_property(&db, 1, &prop, Some(&definition))
+        .await
+        .is_err());
+}
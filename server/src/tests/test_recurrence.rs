@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+
+use super::*;
+use actix_web::{http::StatusCode, test, web::Data, App};
+use sea_orm::{MockDatabase, MockExecResult, Value};
+
+fn exec(rows_affected: u64) -> MockExecResult {
+    MockExecResult {
+        last_insert_id: 0,
+        rows_affected,
+    }
+}
+
+fn count(num_items: i64) -> BTreeMap<&'static str, Value> {
+    BTreeMap::from([("num_items", num_items.into())])
+}
+
+fn date(day: u32) -> chrono::NaiveDateTime {
+    chrono::NaiveDate::from_ymd_opt(2024, 3, day)
+        .unwrap()
+        .and_hms_opt(9, 0, 0)
+        .unwrap()
+}
+
+fn task_model(id: TaskID, completed: bool) -> task::Model {
+    task::Model {
+        id,
+        title: "water plants".to_owned(),
+        completed,
+        last_edited: chrono::NaiveDateTime::default(),
+    }
+}
+
+fn recurrence_model(task_id: TaskID, previous_id: Option<TaskID>) -> task_recurrence::Model {
+    task_recurrence::Model {
+        task_id,
+        rule: Some("FREQ=DAILY;INTERVAL=2".to_owned()),
+        previous_id,
+    }
+}
+
+fn check(task_id: TaskID) -> UpdateTaskRequest {
+    UpdateTaskRequest {
+        task_id,
+        name: None,
+        checked: Some(true),
+        props_to_add: vec![],
+        props_to_remove: vec![],
+        deps_to_add: vec![],
+        deps_to_remove: vec![],
+        scripts_to_add: vec![],
+        scripts_to_remove: vec![],
+        parent: None,
+        req_id: 0,
+    }
+}
+
+// database completing task 1, due on `due` and recurring by `rule`, expecting it to spawn task 2
+fn spawning_db(rule: &str, due: u32) -> DatabaseConnection {
+    let due_property = task_property::Model {
+        task_id: 1,
+        name: DUE_PROPERTY.to_owned(),
+        typ: "date".to_owned(),
+    };
+    MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task_model(1, false)]])
+        .append_query_results([[task_model(1, true)]])
+        .append_query_results([[task_recurrence::Model {
+            rule: Some(rule.to_owned()),
+            ..recurrence_model(1, None)
+        }]])
+        .append_query_results([[count(0)]])
+        .append_query_results([[task_model(1, true)]])
+        .append_query_results([[due_property.clone()]])
+        .append_query_results([[task_date_property::Model {
+            task_id: 1,
+            task_property_name: DUE_PROPERTY.to_owned(),
+            value: date(due),
+        }]])
+        .append_query_results([Vec::<property_schema::Model>::new()])
+        .append_query_results([[task_model(2, false)]])
+        .append_query_results([[task_property::Model {
+            task_id: 2,
+            ..due_property
+        }]])
+        .append_query_results([Vec::<subtask::Model>::new()])
+        .append_exec_results((0..15).map(|_| exec(1)))
+        .into_connection()
+}
+
+#[actix_web::test]
+async fn test_complete_spawns_next_instance() {
+    let db = spawning_db("FREQ=DAILY;INTERVAL=2", 1);
+    assert_eq!(update_task(&db, &check(1)).await.unwrap(), 1);
+
+    let log = format!("{:?}", db.into_transaction_log());
+    // the instance is spawned in one transaction
+    assert!(log.contains(r#"Transaction { stmts: [Statement { sql: "BEGIN""#));
+    assert!(log.contains(r#"INSERT INTO \"task\" (\"title\", \"completed\", \"last_edited\")"#));
+    assert!(log.contains(
+        r#"INSERT INTO \"task_refs_property\" (\"task_id\", \"task_property_name\", \"value\")"#
+    ));
+    assert!(log.contains(r#"INSERT INTO \"dependency\" (\"task_id\", \"depends_on_id\")"#));
+    // due two days after the completed instance was due
+    assert!(log.contains("2024-03-03T09:00:00"));
+    assert!(
+        log.contains(r#"INSERT INTO \"task_recurrence\" (\"task_id\", \"rule\", \"previous_id\")"#)
+    );
+    assert!(log.contains(r#"sql: "COMMIT""#));
+}
+
+#[actix_web::test]
+async fn test_monthly_keeps_its_day() {
+    // the next instance after the end of march is due at the end of april, and keeps the 31st for may
+    let db = spawning_db("FREQ=MONTHLY", 31);
+    assert_eq!(update_task(&db, &check(1)).await.unwrap(), 1);
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(log.contains("2024-04-30T09:00:00"));
+    assert!(log.contains("FREQ=MONTHLY;INTERVAL=1;BYMONTHDAY=31"));
+}
+
+#[actix_web::test]
+async fn test_complete_without_recurrence() {
+    // tasks without a rule, and ones that already spawned an instance, don't spawn another
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task_model(1, false)]])
+        .append_query_results([[task_model(1, true)]])
+        .append_query_results([Vec::<task_recurrence::Model>::new()])
+        .append_query_results([Vec::<subtask::Model>::new()])
+        .append_query_results([[task_model(1, false)]])
+        .append_query_results([[task_model(1, true)]])
+        .append_query_results([[recurrence_model(1, None)]])
+        .append_query_results([[count(1)]])
+        .append_query_results([Vec::<subtask::Model>::new()])
+        .into_connection();
+    assert_eq!(update_task(&db, &check(1)).await.unwrap(), 1);
+    assert_eq!(update_task(&db, &check(1)).await.unwrap(), 1);
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(!log.contains("INSERT"));
+}
+
+#[actix_web::test]
+async fn test_set_recurrence() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task_model(1, false)]])
+        .append_query_results([Vec::<task::Model>::new()])
+        .append_exec_results([exec(1)])
+        .into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(set_recurrence_request),
+    )
+    .await;
+    let req = test::TestRequest::put()
+        .set_json(SetRecurrenceRequest {
+            task_id: 1,
+            recurrence: Some(Recurrence::Monthly {
+                interval: 1,
+                day: None,
+            }),
+            req_id: 3,
+        })
+        .uri("/recurrence")
+        .to_request();
+    let resp: SetRecurrenceResponse = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp, 3);
+
+    let req = test::TestRequest::put()
+        .set_json(SetRecurrenceRequest {
+            task_id: 2,
+            recurrence: None,
+            req_id: 4,
+        })
+        .uri("/recurrence")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    // invalid rules are rejected when the request is parsed
+    let req = test::TestRequest::put()
+        .set_payload(r#"{"task_id":1,"recurrence":"FREQ=HOURLY","req_id":5}"#)
+        .insert_header(("content-type", "application/json"))
+        .uri("/recurrence")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_get_recurrence() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[recurrence_model(3, Some(2))]])
+        .append_query_results([[recurrence_model(4, Some(3))]])
+        .append_query_results([[
+            BTreeMap::from([("task_id", Value::from(2))]),
+            BTreeMap::from([("task_id", Value::from(1))]),
+        ]])
+        .into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(get_recurrence_request),
+    )
+    .await;
+    let req = test::TestRequest::default()
+        .set_json(GetRecurrenceRequest {
+            task_id: 3,
+            req_id: 6,
+        })
+        .uri("/recurrence")
+        .to_request();
+    let resp: GetRecurrenceResponse = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(
+        resp,
+        GetRecurrenceResponse {
+            recurrence: Some(Recurrence::Daily { interval: 2 }),
+            next_instance: Some(4),
+            history: vec![2, 1],
+            req_id: 6,
+        }
+    );
+}
@@ -0,0 +1,407 @@
+use std::collections::BTreeMap;
+
+use super::*;
+use actix_web::{http::StatusCode, test, web::Data, App};
+use sea_orm::{MockDatabase, MockExecResult, Value};
+
+fn status_model() -> property_schema::Model {
+    property_schema::Model {
+        name: "status".to_owned(),
+        typ: "string".to_owned(),
+        description: "where the task is at".to_owned(),
+        default_value: Some(r#"{"String":"todo"}"#.to_owned()),
+        required: true,
+        allowed_values: r#"[{"String":"todo"},{"String":"done"}]"#.to_owned(),
+        options: "[]".to_owned(),
+        formula: None,
+    }
+}
+
+fn status_definition() -> PropertyDefinition {
+    PropertyDefinition {
+        name: "status".to_owned(),
+        typ: TaskPropType::String,
+        description: "where the task is at".to_owned(),
+        default: Some(TaskPropVariant::String("todo".to_owned())),
+        required: true,
+        allowed_values: vec![
+            TaskPropVariant::String("todo".to_owned()),
+            TaskPropVariant::String("done".to_owned()),
+        ],
+        options: vec![],
+        formula: None,
+    }
+}
+
+fn count(num_items: i64) -> BTreeMap<&'static str, Value> {
+    BTreeMap::from([("num_items", num_items.into())])
+}
+
+fn task_model() -> task::Model {
+    task::Model {
+        id: 1,
+        title: "test".to_owned(),
+        completed: false,
+        last_edited: chrono::NaiveDateTime::default(),
+    }
+}
+
+#[actix_web::test]
+async fn test_get_schema() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[status_model()]])
+        .into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(get_schema_request),
+    )
+    .await;
+    let req = test::TestRequest::default()
+        .set_json(4)
+        .uri("/schema")
+        .to_request();
+    let resp: GetSchemaResponse = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(
+        resp,
+        GetSchemaResponse {
+            definitions: vec![status_definition()],
+            req_id: 4,
+        }
+    );
+}
+
+#[actix_web::test]
+async fn test_get_schema_invalid_row() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[property_schema::Model {
+            allowed_values: "not json".to_owned(),
+            ..status_model()
+        }]])
+        .into_connection();
+    assert!(get_schema(&db).await.is_err());
+}
+
+#[actix_web::test]
+async fn test_define_property() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[count(0)]])
+        .append_exec_results([MockExecResult {
+            last_insert_id: 0,
+            rows_affected: 1,
+        }])
+        .into_connection();
+    define_property(
+        &db,
+        &DefinePropertyRequest {
+            definition: status_definition(),
+            req_id: 0,
+        },
+    )
+    .await
+    .unwrap();
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(log.contains(r#"INSERT INTO \"property_schema\""#));
+    assert!(log.contains(r#"ON CONFLICT (\"name\") DO UPDATE"#));
+    assert!(log.contains(r#"String(Some("{\"String\":\"todo\"}"))"#));
+}
+
+#[actix_web::test]
+async fn test_define_property_errors() {
+    // required without a default is rejected before touching the database
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres).into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(define_property_request),
+    )
+    .await;
+    let req = test::TestRequest::default()
+        .method(actix_web::http::Method::POST)
+        .set_json(DefinePropertyRequest {
+            definition: PropertyDefinition {
+                default: None,
+                ..status_definition()
+            },
+            req_id: 0,
+        })
+        .uri("/schema")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    // existing values of another type
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[count(2)]])
+        .into_connection();
+    let err = define_property(
+        &db,
+        &DefinePropertyRequest {
+            definition: status_definition(),
+            req_id: 0,
+        },
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        err.as_response_error().status_code(),
+        StatusCode::BAD_REQUEST
+    );
+
+    // computed properties can't hide stored values of any type
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[count(1)]])
+        .into_connection();
+    let err = define_property(
+        &db,
+        &DefinePropertyRequest {
+            definition: PropertyDefinition {
+                typ: TaskPropType::Number,
+                default: None,
+                required: false,
+                allowed_values: vec![],
+                formula: Some("count(dependents)".to_owned()),
+                ..status_definition()
+            },
+            req_id: 0,
+        },
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        err.as_response_error().status_code(),
+        StatusCode::BAD_REQUEST
+    );
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(!log.contains(r#"\"type\" <> "#));
+}
+
+#[actix_web::test]
+async fn test_define_property_cycle() {
+    let computed = |formula: &str| PropertyDefinition {
+        typ: TaskPropType::Number,
+        default: None,
+        required: false,
+        allowed_values: vec![],
+        formula: Some(formula.to_owned()),
+        ..status_definition()
+    };
+    let define = |db, formula| async move {
+        define_property(
+            &db,
+            &DefinePropertyRequest {
+                definition: computed(formula),
+                req_id: 0,
+            },
+        )
+        .await
+    };
+    let model = |name: &str, formula: &str| property_schema::Model {
+        name: name.to_owned(),
+        typ: "number".to_owned(),
+        default_value: None,
+        required: false,
+        allowed_values: "[]".to_owned(),
+        formula: Some(formula.to_owned()),
+        ..status_model()
+    };
+    // directly, and through the dependencies of the task
+    for formula in ["prop(\"status\") + 1", "max(dependencies, \"status\")"] {
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results([[count(0)]])
+            .append_query_results([Vec::<property_schema::Model>::new()])
+            .into_connection();
+        let err = define(db, formula).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+    // through another computed property, replacing the definition of status that didn't refer to it
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[count(0)]])
+        .append_query_results([[
+            model("double", "prop(\"half\") * 2"),
+            model("half", "prop(\"status\") / 2"),
+            model("status", "1"),
+        ]])
+        .into_connection();
+    let err = define(db, "prop(\"double\") + 1").await.unwrap_err();
+    assert_eq!(
+        err.as_response_error().status_code(),
+        StatusCode::BAD_REQUEST
+    );
+    // referring to other computed properties is fine
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[count(0)]])
+        .append_query_results([[model("double", "prop(\"half\") * 2")]])
+        .append_exec_results([MockExecResult {
+            last_insert_id: 0,
+            rows_affected: 1,
+        }])
+        .into_connection();
+    define(db, "prop(\"double\") + 1").await.unwrap();
+}
+
+#[actix_web::test]
+async fn test_delete_property() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_exec_results([
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            },
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 0,
+            },
+        ])
+        .into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(delete_property_request),
+    )
+    .await;
+    let req = || {
+        test::TestRequest::default()
+            .method(actix_web::http::Method::DELETE)
+            .set_json("status")
+            .uri("/schema")
+            .to_request()
+    };
+    assert_eq!(
+        test::call_service(&app, req()).await.status(),
+        StatusCode::OK
+    );
+    assert_eq!(
+        test::call_service(&app, req()).await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[actix_web::test]
+async fn test_update_checks_schema() {
+    // value isn't one of the allowed values
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task_model()]])
+        .append_query_results([[task_model()]])
+        .append_query_results([[status_model()]])
+        .into_connection();
+    let res = update_task(
+        &db,
+        &UpdateTaskRequest {
+            task_id: 1,
+            name: None,
+            checked: None,
+            props_to_add: vec![TaskProp {
+                name: "status".to_owned(),
+                value: TaskPropVariant::String("doing".to_owned()),
+            }],
+            props_to_remove: vec![],
+            deps_to_add: vec![],
+            deps_to_remove: vec![],
+            scripts_to_add: vec![],
+            scripts_to_remove: vec![],
+            parent: None,
+            req_id: 0,
+        },
+    )
+    .await;
+    assert_eq!(
+        res.unwrap_err().as_response_error().status_code(),
+        StatusCode::BAD_REQUEST
+    );
+
+    // required properties can't be removed
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task_model()]])
+        .append_query_results([[task_model()]])
+        .append_query_results([[status_model()]])
+        .into_connection();
+    let res = update_task(
+        &db,
+        &UpdateTaskRequest {
+            task_id: 1,
+            name: None,
+            checked: None,
+            props_to_add: vec![],
+            props_to_remove: vec!["status".to_owned()],
+            deps_to_add: vec![],
+            deps_to_remove: vec![],
+            scripts_to_add: vec![],
+            scripts_to_remove: vec![],
+            parent: None,
+            req_id: 0,
+        },
+    )
+    .await;
+    assert_eq!(
+        res.unwrap_err().as_response_error().status_code(),
+        StatusCode::BAD_REQUEST
+    );
+
+    // select values need the options of a definition
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task_model()]])
+        .append_query_results([[task_model()]])
+        .append_query_results([Vec::<property_schema::Model>::new()])
+        .into_connection();
+    let res = update_task(
+        &db,
+        &UpdateTaskRequest {
+            task_id: 1,
+            name: None,
+            checked: None,
+            props_to_add: vec![TaskProp {
+                name: "priority".to_owned(),
+                value: TaskPropVariant::Select("high".to_owned()),
+            }],
+            props_to_remove: vec![],
+            deps_to_add: vec![],
+            deps_to_remove: vec![],
+            scripts_to_add: vec![],
+            scripts_to_remove: vec![],
+            parent: None,
+            req_id: 0,
+        },
+    )
+    .await;
+    assert_eq!(
+        res.unwrap_err().as_response_error().status_code(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[actix_web::test]
+async fn test_create_writes_defaults() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task_model()]])
+        .append_query_results([[status_model()]])
+        .append_query_results([vec![] as Vec<task_property::Model>])
+        .append_exec_results([
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            },
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            },
+        ])
+        .into_connection();
+    let id = create_task(
+        &db,
+        &CreateTaskRequest {
+            name: "test".to_owned(),
+            completed: false,
+            req_id: 0,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(id, 1);
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(log.contains(r#"INSERT INTO \"task_string_property\""#));
+    assert!(log.contains(r#"String(Some("todo"))"#));
+}
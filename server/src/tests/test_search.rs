@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+
+use super::*;
+use actix_web::{test, web::Data, App};
+use sea_orm::{MockDatabase, Value};
+
+fn search_row(
+    task_id: i32,
+    field: Option<&str>,
+    headline: &str,
+    rank: f32,
+) -> BTreeMap<&'static str, Value> {
+    BTreeMap::from([
+        ("task_id", task_id.into()),
+        ("field", field.map(|f| f.to_owned()).into()),
+        ("headline", headline.into()),
+        ("rank", rank.into()),
+    ])
+}
+
+#[actix_web::test]
+async fn test_split_headline() {
+    assert_eq!(
+        split_headline("no matches"),
+        ("no matches".to_owned(), vec![])
+    );
+    assert_eq!(
+        split_headline("walk the \u{2}dog\u{3} and \u{2}dogs\u{3}"),
+        ("walk the dog and dogs".to_owned(), vec![(9, 12), (17, 21)])
+    );
+    // ranges are byte offsets
+    assert_eq!(
+        split_headline("café \u{2}crème\u{3}"),
+        ("café crème".to_owned(), vec![(6, 12)])
+    );
+    // unmatched stop marker is dropped
+    assert_eq!(split_headline("a\u{3}b"), ("ab".to_owned(), vec![]));
+}
+
+#[actix_web::test]
+async fn test_search() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([vec![
+            search_row(2, None, "walk the \u{2}dog\u{3}", 0.5),
+            search_row(1, Some("notes"), "\u{2}dog\u{3} food", 0.25),
+        ]])
+        .into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(get_search_request),
+    )
+    .await;
+    let req = test::TestRequest::default()
+        .set_json(SearchRequest {
+            query: "dog".to_owned(),
+            limit: Some(10),
+            req_id: 3,
+        })
+        .uri("/search")
+        .to_request();
+    let resp: SearchResponse = test::call_and_read_body_json(&app, req).await;
+
+    assert_eq!(resp.req_id, 3);
+    assert_eq!(
+        resp.results,
+        vec![
+            SearchResult {
+                task_id: 2,
+                field: None,
+                snippet: "walk the dog".to_owned(),
+                highlights: vec![(9, 12)],
+                rank: 0.5,
+            },
+            SearchResult {
+                task_id: 1,
+                field: Some("notes".to_owned()),
+                snippet: "dog food".to_owned(),
+                highlights: vec![(0, 3)],
+                rank: 0.25,
+            },
+        ]
+    );
+}
+
+#[actix_web::test]
+async fn test_search_error() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_errors([DbErr::Custom("search failed".to_owned())])
+        .into_connection();
+    assert!(search(
+        &db,
+        &SearchRequest {
+            query: "dog".to_owned(),
+            limit: None,
+            req_id: 0,
+        },
+    )
+    .await
+    .is_err());
+}
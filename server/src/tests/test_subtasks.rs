@@ -0,0 +1,215 @@
+use std::collections::BTreeMap;
+
+use super::*;
+use actix_web::http::StatusCode;
+use sea_orm::{MockDatabase, MockExecResult, RuntimeErr, Value};
+
+fn exec(rows_affected: u64) -> MockExecResult {
+    MockExecResult {
+        last_insert_id: 0,
+        rows_affected,
+    }
+}
+
+fn count(num_items: i64) -> BTreeMap<&'static str, Value> {
+    BTreeMap::from([("num_items", num_items.into())])
+}
+
+fn task_model(id: TaskID, completed: bool) -> task::Model {
+    task::Model {
+        id,
+        title: "plan trip".to_owned(),
+        completed,
+        last_edited: chrono::NaiveDateTime::default(),
+    }
+}
+
+fn subtask_model(task_id: TaskID, parent_id: TaskID, position: i32) -> subtask::Model {
+    subtask::Model {
+        task_id,
+        parent_id,
+        position,
+    }
+}
+
+fn move_to(
+    task_id: TaskID,
+    parent: Option<ParentChange>,
+    checked: Option<bool>,
+) -> UpdateTaskRequest {
+    UpdateTaskRequest {
+        task_id,
+        name: None,
+        checked,
+        props_to_add: vec![],
+        props_to_remove: vec![],
+        deps_to_add: vec![],
+        deps_to_remove: vec![],
+        scripts_to_add: vec![],
+        scripts_to_remove: vec![],
+        parent,
+        req_id: 0,
+    }
+}
+
+#[actix_web::test]
+async fn test_set_parent() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task_model(3, false)]])
+        .append_query_results([[task_model(3, false)]])
+        .append_query_results([[subtask_model(3, 7, 2)]])
+        .append_query_results([[task_model(1, true)]])
+        .append_query_results([[count(1)]])
+        // an open subtask reopens its completed parent
+        .append_query_results([[subtask_model(3, 1, 1)]])
+        .append_query_results([[task_model(1, true)]])
+        .append_query_results([[count(1)]])
+        .append_query_results([[task_model(1, false)]])
+        .append_query_results([Vec::<subtask::Model>::new()])
+        // and the old parent, left with completed subtasks, is completed
+        .append_query_results([[task_model(7, false)]])
+        .append_query_results([[count(0)]])
+        .append_query_results([[count(2)]])
+        .append_query_results([[task_model(7, true)]])
+        .append_query_results([Vec::<task_recurrence::Model>::new()])
+        .append_query_results([Vec::<subtask::Model>::new()])
+        .append_exec_results((0..4).map(|_| exec(1)))
+        .into_connection();
+    let change = ParentChange::Set {
+        parent: 1,
+        position: 5,
+    };
+    assert_eq!(
+        update_task(&db, &move_to(3, Some(change), None))
+            .await
+            .unwrap(),
+        3
+    );
+
+    let log = format!("{:?}", db.into_transaction_log());
+    // the task is moved in one transaction
+    assert!(log.contains(r#"Transaction { stmts: [Statement { sql: "BEGIN""#));
+    assert!(log.contains(r#"sql: "COMMIT""#));
+    // the subtasks after it in its old parent move up
+    assert!(log.contains(r#"DELETE FROM \"subtask\" WHERE \"subtask\".\"task_id\" = $1"#));
+    assert!(log.contains(r#"SET \"position\" = \"position\" - $1"#));
+    // and the ones at or after its position in the new parent move down
+    assert!(log.contains(r#"SET \"position\" = \"position\" + $1"#));
+    assert!(log.contains(r#"INSERT INTO \"subtask\" (\"task_id\", \"parent_id\", \"position\")"#));
+    assert_eq!(
+        log.matches(r#"UPDATE \"task\" SET \"completed\" = $1"#)
+            .count(),
+        2
+    );
+}
+
+#[actix_web::test]
+async fn test_set_parent_rejected() {
+    let change = |parent| {
+        Some(ParentChange::Set {
+            parent,
+            position: 0,
+        })
+    };
+    // a parent that doesn't exist
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task_model(3, false)]])
+        .append_query_results([[task_model(3, false)]])
+        .append_query_results([Vec::<subtask::Model>::new()])
+        .append_query_results([Vec::<task::Model>::new()])
+        .into_connection();
+    let err = update_task(&db, &move_to(3, change(9), None))
+        .await
+        .unwrap_err();
+    assert_eq!(err.as_response_error().status_code(), StatusCode::NOT_FOUND);
+
+    // a parent that is a subtask of the task, rolling back its removal from its old parent
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task_model(3, false)]])
+        .append_query_results([[task_model(3, false)]])
+        .append_query_results([[subtask_model(3, 7, 0)]])
+        .append_query_results([[task_model(4, false)]])
+        .append_query_results([[count(0)]])
+        .append_exec_results((0..3).map(|_| exec(1)))
+        .append_exec_errors([DbErr::Exec(RuntimeErr::Internal(
+            "error returned from database: Subtask cycle detected".to_owned(),
+        ))])
+        .into_connection();
+    let err = update_task(&db, &move_to(3, change(4), None))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.as_response_error().status_code(),
+        StatusCode::BAD_REQUEST
+    );
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(log.contains(r#"sql: "ROLLBACK""#));
+    assert!(!log.contains("COMMIT"));
+}
+
+#[actix_web::test]
+async fn test_remove_parent() {
+    // the parent left without subtasks isn't completed
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task_model(3, false)]])
+        .append_query_results([[task_model(3, false)]])
+        .append_query_results([[subtask_model(3, 1, 0)]])
+        .append_query_results([[task_model(1, false)]])
+        .append_query_results([[count(0)]])
+        .append_query_results([[count(0)]])
+        .append_exec_results((0..2).map(|_| exec(1)))
+        .into_connection();
+    update_task(&db, &move_to(3, Some(ParentChange::Remove), None))
+        .await
+        .unwrap();
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(log.contains(r#"DELETE FROM \"subtask\""#));
+    assert!(!log.contains("INSERT"));
+    assert!(!log.contains(r#"SET \"completed\""#));
+}
+
+#[actix_web::test]
+async fn test_complete_cascades() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task_model(2, false)]])
+        .append_query_results([[task_model(2, true)]])
+        .append_query_results([Vec::<task_recurrence::Model>::new()])
+        // completing the last open subtask completes the parent, which isn't a subtask itself
+        .append_query_results([[subtask_model(2, 1, 0)]])
+        .append_query_results([[task_model(1, false)]])
+        .append_query_results([[count(0)]])
+        .append_query_results([[count(1)]])
+        .append_query_results([[task_model(1, true)]])
+        .append_query_results([Vec::<task_recurrence::Model>::new()])
+        .append_query_results([Vec::<subtask::Model>::new()])
+        .into_connection();
+    update_task(&db, &move_to(2, None, Some(true)))
+        .await
+        .unwrap();
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert_eq!(log.matches(r#"UPDATE \"task\""#).count(), 2);
+    // the task and the cascade are committed together, with the parent locked before its subtasks are counted
+    assert!(log.starts_with(r#"[Transaction { stmts: [Statement { sql: "BEGIN""#));
+    assert_eq!(log.matches(r#"sql: "COMMIT""#).count(), 1);
+    assert!(log.contains("FOR UPDATE"));
+}
+
+#[actix_web::test]
+async fn test_complete_with_open_siblings() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task_model(2, false)]])
+        .append_query_results([[task_model(2, true)]])
+        .append_query_results([Vec::<task_recurrence::Model>::new()])
+        .append_query_results([[subtask_model(2, 1, 0)]])
+        .append_query_results([[task_model(1, false)]])
+        .append_query_results([[count(1)]])
+        .into_connection();
+    update_task(&db, &move_to(2, None, Some(true)))
+        .await
+        .unwrap();
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert_eq!(log.matches(r#"UPDATE \"task\""#).count(), 1);
+}
@@ -0,0 +1,278 @@
+use super::*;
+use actix_web::{http::StatusCode, test, web::Data, App};
+use sea_orm::{DbErr, MockDatabase, MockExecResult, RuntimeErr};
+
+fn exec(rows_affected: u64) -> MockExecResult {
+    MockExecResult {
+        last_insert_id: 0,
+        rows_affected,
+    }
+}
+
+fn tag(id: i32, name: &str, parent_id: Option<i32>) -> tag::Model {
+    tag::Model {
+        id,
+        name: name.to_owned(),
+        parent_id,
+        color: "#1e90ff".to_owned(),
+    }
+}
+
+fn task() -> task::Model {
+    task::Model {
+        id: 3,
+        title: "test".to_owned(),
+        completed: false,
+        last_edited: chrono::NaiveDateTime::default(),
+    }
+}
+
+#[actix_web::test]
+async fn test_get_tags() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[tag(1, "work", None), tag(2, "frontend", Some(1))]])
+        .into_connection();
+    let app =
+        test::init_service(App::new().app_data(Data::new(db)).service(get_tags_request)).await;
+    let req = test::TestRequest::default()
+        .set_json(4)
+        .uri("/tags")
+        .to_request();
+    let resp: GetTagsResponse = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp.req_id, 4);
+    assert_eq!(
+        resp.tags,
+        vec![
+            tag_from_model(tag(1, "work", None)),
+            tag_from_model(tag(2, "frontend", Some(1)))
+        ]
+    );
+}
+
+#[actix_web::test]
+async fn test_create_tag() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        // the parent exists and has no child of the same name
+        .append_query_results([[tag(1, "work", None)]])
+        .append_query_results([Vec::<tag::Model>::new()])
+        .append_query_results([[tag(2, "frontend", Some(1))]])
+        // the parent doesn't exist
+        .append_query_results([Vec::<tag::Model>::new()])
+        // a top-level tag has the same name
+        .append_query_results([[tag(1, "work", None)]])
+        .into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(create_tag_request),
+    )
+    .await;
+    let post = |name: &str, parent, color: &str| {
+        test::TestRequest::post()
+            .set_json(CreateTagRequest {
+                name: name.to_owned(),
+                parent,
+                color: color.to_owned(),
+                req_id: 1,
+            })
+            .uri("/tag")
+            .to_request()
+    };
+    let resp: CreateTagResponse =
+        test::call_and_read_body_json(&app, post(" frontend ", Some(1), "#1E90FF")).await;
+    assert_eq!(resp.tag, tag_from_model(tag(2, "frontend", Some(1))));
+
+    // malformed tags are rejected before touching the database
+    let resp = test::call_service(&app, post(" ", None, "#1e90ff")).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = test::call_service(&app, post("work", None, "blue")).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = test::call_service(&app, post("frontend", Some(9), "#1e90ff")).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let resp = test::call_service(&app, post("work", None, "#1e90ff")).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+}
+
+#[actix_web::test]
+async fn test_update_delete_tag() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[tag(2, "frontend", Some(1))]])
+        .append_query_results([Vec::<tag::Model>::new()])
+        .append_query_results([[tag(2, "web", None)]])
+        .append_query_results([[tag(2, "web", None)]])
+        .append_query_results([Vec::<tag::Model>::new()])
+        .append_query_results([[tag(2, "web", None)]])
+        .append_exec_results([exec(1)])
+        .append_query_results([Vec::<tag::Model>::new()])
+        .into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(update_tag_request)
+            .service(delete_tag_request),
+    )
+    .await;
+    let update = |tag_id, parent| {
+        test::TestRequest::put()
+            .set_json(UpdateTagRequest {
+                tag_id,
+                name: "web".to_owned(),
+                parent,
+                color: "#1e90ff".to_owned(),
+                req_id: 2,
+            })
+            .uri("/tag")
+            .to_request()
+    };
+    let resp: UpdateTagResponse = test::call_and_read_body_json(&app, update(2, None)).await;
+    assert_eq!(resp.tag, tag_from_model(tag(2, "web", None)));
+    let resp = test::call_service(&app, update(2, Some(2))).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = test::call_service(&app, update(9, None)).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let delete = |tag_id| {
+        test::TestRequest::delete()
+            .set_json(DeleteTagRequest { tag_id, req_id: 3 })
+            .uri("/tag")
+            .to_request()
+    };
+    let resp: DeleteTagResponse = test::call_and_read_body_json(&app, delete(2)).await;
+    assert_eq!(
+        resp,
+        DeleteTagResponse {
+            tag_id: 2,
+            req_id: 3
+        }
+    );
+    let resp = test::call_service(&app, delete(2)).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_update_tag_cycle() {
+    // work is moved under frontend, which is nested under work
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[tag(1, "work", None)]])
+        .append_query_results([[tag(2, "frontend", Some(1))]])
+        .append_query_results([Vec::<tag::Model>::new()])
+        .append_query_errors([DbErr::Query(RuntimeErr::Internal(
+            "error returned from database: Tag cycle detected".to_owned(),
+        ))])
+        .into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(update_tag_request),
+    )
+    .await;
+    let req = test::TestRequest::put()
+        .set_json(UpdateTagRequest {
+            tag_id: 1,
+            name: "work".to_owned(),
+            parent: Some(2),
+            color: "#1e90ff".to_owned(),
+            req_id: 2,
+        })
+        .uri("/tag")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_task_tags() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[
+            task_tag::Model {
+                task_id: 3,
+                tag_id: 1,
+            },
+            task_tag::Model {
+                task_id: 3,
+                tag_id: 2,
+            },
+        ]])
+        .append_query_results([[task()]])
+        .append_query_results([[tag(1, "work", None), tag(2, "frontend", Some(1))]])
+        .append_exec_results([exec(1), exec(2)])
+        // one of the tags doesn't exist
+        .append_query_results([[task()]])
+        .append_query_results([[tag(1, "work", None)]])
+        .into_connection();
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(db))
+            .service(get_task_tags_request)
+            .service(set_task_tags_request),
+    )
+    .await;
+    let req = test::TestRequest::default()
+        .set_json(GetTaskTagsRequest {
+            task_ids: vec![4, 3, 4],
+            req_id: 5,
+        })
+        .uri("/task_tags")
+        .to_request();
+    let resp: GetTaskTagsResponse = test::call_and_read_body_json(&app, req).await;
+    // tasks without tags are answered too, so clients know they were fetched
+    assert_eq!(
+        resp.tasks,
+        vec![
+            TaskTags {
+                task_id: 3,
+                tags: vec![1, 2]
+            },
+            TaskTags {
+                task_id: 4,
+                tags: vec![]
+            },
+        ]
+    );
+
+    let put = |tags| {
+        test::TestRequest::put()
+            .set_json(SetTaskTagsRequest {
+                task_id: 3,
+                tags,
+                req_id: 6,
+            })
+            .uri("/task_tags")
+            .to_request()
+    };
+    let resp: SetTaskTagsResponse = test::call_and_read_body_json(&app, put(vec![2, 1, 2])).await;
+    assert_eq!(
+        resp.task,
+        TaskTags {
+            task_id: 3,
+            tags: vec![1, 2]
+        }
+    );
+    let resp = test::call_service(&app, put(vec![1, 9])).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_construct_filter_tag() {
+    let sql = |filter| {
+        task::Entity::find()
+            .filter(construct_filter(&filter, &[]).unwrap())
+            .build(sea_orm::DatabaseBackend::Postgres)
+            .to_string()
+    };
+    let query = sql(Filter::LeafTag {
+        tag: 7,
+        descendants: false,
+    });
+    assert!(query.contains(r#""task_tag"."task_id" = "task"."id""#));
+    assert!(query.contains(r#""tag_id" = 7"#));
+    assert!(!query.contains("WITH RECURSIVE"));
+    // tags nested under the tag are found by walking down from it
+    let query = sql(Filter::LeafTag {
+        tag: 7,
+        descendants: true,
+    });
+    assert!(query.contains("WITH RECURSIVE"));
+    assert!(query.contains(r#"WHERE "id" = 7"#));
+    assert!(query.contains(r#""tag"."parent_id" = "subtag"."id""#));
+}
@@ -45,6 +45,23 @@ async fn get_task_succeeds_with_good_request() {
             completed: false,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
+        .append_query_results([vec![database::subtask::Model {
+            task_id: 1,
+            parent_id: 5,
+            position: 0,
+        }]])
+        .append_query_results([vec![
+            database::subtask::Model {
+                task_id: 3,
+                parent_id: 1,
+                position: 0,
+            },
+            database::subtask::Model {
+                task_id: 2,
+                parent_id: 1,
+                position: 1,
+            },
+        ]])
         .into_connection();
     let db_data: Data<DatabaseConnection> = Data::new(db_conn);
     let app = test::init_service(App::new().app_data(db_data).service(get_task_request)).await;
@@ -58,6 +75,8 @@ async fn get_task_succeeds_with_good_request() {
         .to_request();
     let resp: ReadTaskShortResponse = test::call_and_read_body_json(&app, req).await;
     assert_eq!(resp.task_id, 1);
+    assert_eq!(resp.parent, Some(5));
+    assert_eq!(resp.subtasks, vec![3, 2]);
 }
 #[actix_web::test]
 async fn get_tasks_request_succeeds_with_good_request() {
@@ -66,21 +85,21 @@ async fn get_tasks_request_succeeds_with_good_request() {
 
     let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres);
     let db_conn = db
-        .append_query_results([
-            vec![database::task::Model {
-                id: 1,
-                title: "test".to_string(),
-                completed: false,
-                last_edited: chrono::NaiveDateTime::default(),
-            }],
-            vec![database::task::Model {
-                id: 2,
-                title: "test2".to_string(),
-                completed: false,
-                last_edited: chrono::NaiveDateTime::default(),
-            }],
-            vec![],
-        ])
+        .append_query_results([vec![database::task::Model {
+            id: 1,
+            title: "test".to_string(),
+            completed: false,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
+        .append_query_results([Vec::<database::subtask::Model>::new(), Vec::new()])
+        .append_query_results([vec![database::task::Model {
+            id: 2,
+            title: "test2".to_string(),
+            completed: false,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
+        .append_query_results([Vec::<database::subtask::Model>::new(), Vec::new()])
+        .append_query_results([Vec::<database::task::Model>::new()])
         .into_connection();
     let db_data: Data<DatabaseConnection> = Data::new(db_conn);
     let app = test::init_service(App::new().app_data(db_data).service(get_tasks_request)).await;
@@ -23,6 +23,7 @@ async fn task_id_fails() {
             deps_to_remove: vec![],
             scripts_to_add: vec![],
             scripts_to_remove: vec![],
+            parent: None,
             req_id: 0,
         },
     )
@@ -63,6 +64,7 @@ async fn name_changed() {
             deps_to_remove: vec![],
             scripts_to_add: vec![],
             scripts_to_remove: vec![],
+            parent: None,
             req_id: 0,
         },
     )
@@ -86,6 +88,8 @@ async fn checked_changed() {
             completed: true,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
+        // the task isn't a subtask, so completion doesn't cascade
+        .append_query_results([Vec::<subtask::Model>::new()])
         .append_exec_results([MockExecResult {
             last_insert_id: 1,
             rows_affected: 1,
@@ -104,6 +108,7 @@ async fn checked_changed() {
             deps_to_remove: vec![],
             scripts_to_add: vec![],
             scripts_to_remove: vec![],
+            parent: None,
             req_id: 0,
         },
     )
@@ -121,16 +126,19 @@ async fn update_prop_string() {
             completed: true,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
+        // the update that stamps the edit
+        .append_query_results([[task::Model {
+            id: 1,
+            title: "title".to_string(),
+            completed: true,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
+        .append_query_results([vec![] as Vec<property_schema::Model>])
         .append_query_results([[task_property::Model {
             task_id: 1,
             name: "name".to_string(),
             typ: "string".to_string(),
         }]])
-        .append_query_results([[task_string_property::Model {
-            task_id: 1,
-            task_property_name: "name".to_string(),
-            value: "value".to_string(),
-        }]])
         .append_exec_results([MockExecResult {
             last_insert_id: 1,
             rows_affected: 1,
@@ -152,12 +160,18 @@ async fn update_prop_string() {
             deps_to_remove: vec![],
             scripts_to_add: vec![],
             scripts_to_remove: vec![],
+            parent: None,
             req_id: 0,
         },
     )
     .await;
 
     assert!(res.is_ok());
+    // editing a property moves the task's last edit from its old date to now
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(log.contains(r#"UPDATE \"task\" SET \"last_edited\" = $1"#));
+    assert!(log.contains(&chrono::Utc::now().date_naive().to_string()));
+    assert!(!log.contains("1970-01-01"));
 }
 #[actix_web::test]
 async fn create_prop_string() {
@@ -168,11 +182,25 @@ async fn create_prop_string() {
             completed: true,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
+        .append_query_results([[task::Model {
+            id: 1,
+            title: "title".to_string(),
+            completed: true,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
+        .append_query_results([vec![] as Vec<property_schema::Model>])
         .append_query_results([vec![] as Vec<task_property::Model>])
-        .append_exec_results([MockExecResult {
-            last_insert_id: 1,
-            rows_affected: 1,
-        }])
+        // property type, then the value
+        .append_exec_results([
+            MockExecResult {
+                last_insert_id: 1,
+                rows_affected: 1,
+            },
+            MockExecResult {
+                last_insert_id: 1,
+                rows_affected: 1,
+            },
+        ])
         .into_connection();
 
     let res = update_task(
@@ -190,6 +218,7 @@ async fn create_prop_string() {
             deps_to_remove: vec![],
             scripts_to_add: vec![],
             scripts_to_remove: vec![],
+            parent: None,
             req_id: 0,
         },
     )
@@ -206,16 +235,18 @@ async fn update_prop_num() {
             completed: true,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
+        .append_query_results([[task::Model {
+            id: 1,
+            title: "title".to_string(),
+            completed: true,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
+        .append_query_results([vec![] as Vec<property_schema::Model>])
         .append_query_results([[task_property::Model {
             task_id: 1,
             name: "name".to_string(),
             typ: "number".to_string(),
         }]])
-        .append_query_results([[task_num_property::Model {
-            task_id: 1,
-            task_property_name: "name".to_string(),
-            value: Decimal::from_f64(1.0).unwrap(),
-        }]])
         .append_exec_results([MockExecResult {
             last_insert_id: 1,
             rows_affected: 1,
@@ -237,6 +268,7 @@ async fn update_prop_num() {
             deps_to_remove: vec![],
             scripts_to_add: vec![],
             scripts_to_remove: vec![],
+            parent: None,
             req_id: 0,
         },
     )
@@ -253,11 +285,25 @@ async fn create_prop_num() {
             completed: true,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
+        .append_query_results([[task::Model {
+            id: 1,
+            title: "title".to_string(),
+            completed: true,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
+        .append_query_results([vec![] as Vec<property_schema::Model>])
         .append_query_results([vec![] as Vec<task_property::Model>])
-        .append_exec_results([MockExecResult {
-            last_insert_id: 1,
-            rows_affected: 1,
-        }])
+        // property type, then the value
+        .append_exec_results([
+            MockExecResult {
+                last_insert_id: 1,
+                rows_affected: 1,
+            },
+            MockExecResult {
+                last_insert_id: 1,
+                rows_affected: 1,
+            },
+        ])
         .into_connection();
 
     let res = update_task(
@@ -275,6 +321,7 @@ async fn create_prop_num() {
             deps_to_remove: vec![],
             scripts_to_add: vec![],
             scripts_to_remove: vec![],
+            parent: None,
             req_id: 0,
         },
     )
@@ -291,16 +338,18 @@ async fn update_prop_date() {
             completed: true,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
+        .append_query_results([[task::Model {
+            id: 1,
+            title: "title".to_string(),
+            completed: true,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
+        .append_query_results([vec![] as Vec<property_schema::Model>])
         .append_query_results([[task_property::Model {
             task_id: 1,
             name: "name".to_string(),
             typ: "date".to_string(),
         }]])
-        .append_query_results([[task_date_property::Model {
-            task_id: 1,
-            task_property_name: "name".to_string(),
-            value: chrono::NaiveDateTime::default(),
-        }]])
         .append_exec_results([MockExecResult {
             last_insert_id: 1,
             rows_affected: 1,
@@ -322,6 +371,7 @@ async fn update_prop_date() {
             deps_to_remove: vec![],
             scripts_to_add: vec![],
             scripts_to_remove: vec![],
+            parent: None,
             req_id: 0,
         },
     )
@@ -338,11 +388,25 @@ async fn create_prop_date() {
             completed: true,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
+        .append_query_results([[task::Model {
+            id: 1,
+            title: "title".to_string(),
+            completed: true,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
+        .append_query_results([vec![] as Vec<property_schema::Model>])
         .append_query_results([vec![] as Vec<task_property::Model>])
-        .append_exec_results([MockExecResult {
-            last_insert_id: 1,
-            rows_affected: 1,
-        }])
+        // property type, then the value
+        .append_exec_results([
+            MockExecResult {
+                last_insert_id: 1,
+                rows_affected: 1,
+            },
+            MockExecResult {
+                last_insert_id: 1,
+                rows_affected: 1,
+            },
+        ])
         .into_connection();
 
     let res = update_task(
@@ -360,6 +424,7 @@ async fn create_prop_date() {
             deps_to_remove: vec![],
             scripts_to_add: vec![],
             scripts_to_remove: vec![],
+            parent: None,
             req_id: 0,
         },
     )
@@ -376,16 +441,18 @@ async fn update_prop_bool() {
             completed: true,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
+        .append_query_results([[task::Model {
+            id: 1,
+            title: "title".to_string(),
+            completed: true,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
+        .append_query_results([vec![] as Vec<property_schema::Model>])
         .append_query_results([[task_property::Model {
             task_id: 1,
             name: "name".to_string(),
             typ: "boolean".to_string(),
         }]])
-        .append_query_results([[task_bool_property::Model {
-            task_id: 1,
-            task_property_name: "name".to_string(),
-            value: true,
-        }]])
         .append_exec_results([MockExecResult {
             last_insert_id: 1,
             rows_affected: 1,
@@ -407,6 +474,7 @@ async fn update_prop_bool() {
             deps_to_remove: vec![],
             scripts_to_add: vec![],
             scripts_to_remove: vec![],
+            parent: None,
             req_id: 0,
         },
     )
@@ -423,11 +491,25 @@ async fn create_prop_bool() {
             completed: true,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
+        .append_query_results([[task::Model {
+            id: 1,
+            title: "title".to_string(),
+            completed: true,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
+        .append_query_results([vec![] as Vec<property_schema::Model>])
         .append_query_results([vec![] as Vec<task_property::Model>])
-        .append_exec_results([MockExecResult {
-            last_insert_id: 1,
-            rows_affected: 1,
-        }])
+        // property type, then the value
+        .append_exec_results([
+            MockExecResult {
+                last_insert_id: 1,
+                rows_affected: 1,
+            },
+            MockExecResult {
+                last_insert_id: 1,
+                rows_affected: 1,
+            },
+        ])
         .into_connection();
 
     let res = update_task(
@@ -445,6 +527,7 @@ async fn create_prop_bool() {
             deps_to_remove: vec![],
             scripts_to_add: vec![],
             scripts_to_remove: vec![],
+            parent: None,
             req_id: 0,
         },
     )
@@ -463,6 +546,13 @@ async fn prop_wrong_type() {
             completed: true,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
+        .append_query_results([[task::Model {
+            id: 1,
+            title: "title".to_string(),
+            completed: true,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
+        .append_query_results([vec![] as Vec<property_schema::Model>])
         .append_query_results([[task_property::Model {
             task_id: 1,
             name: "name".to_string(),
@@ -485,6 +575,7 @@ async fn prop_wrong_type() {
             deps_to_remove: vec![],
             scripts_to_add: vec![],
             scripts_to_remove: vec![],
+            parent: None,
             req_id: 0,
         },
     )
@@ -497,6 +588,55 @@ async fn prop_wrong_type() {
     )
 }
 #[actix_web::test]
+async fn create_prop_relative_date() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task::Model {
+            id: 1,
+            title: "title".to_string(),
+            completed: true,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
+        .append_query_results([[task::Model {
+            id: 1,
+            title: "title".to_string(),
+            completed: true,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
+        .append_query_results([vec![] as Vec<property_schema::Model>])
+        .append_query_results([vec![] as Vec<task_property::Model>])
+        .into_connection();
+
+    let res = update_task(
+        &db,
+        &UpdateTaskRequest {
+            task_id: 1,
+            name: None,
+            checked: None,
+            props_to_add: vec![TaskProp {
+                name: "dog".to_string(),
+                value: TaskPropVariant::RelativeDate(common::RelativeDate {
+                    anchor: common::DateAnchor::Now,
+                    offset_secs: 0,
+                    timezone: None,
+                }),
+            }],
+            props_to_remove: vec![],
+            deps_to_add: vec![],
+            deps_to_remove: vec![],
+            scripts_to_add: vec![],
+            scripts_to_remove: vec![],
+            parent: None,
+            req_id: 0,
+        },
+    )
+    .await;
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        ErrorBadRequest("relative dates can only be used in filters").to_string()
+    )
+}
+#[actix_web::test]
 async fn delete_prop() {
     let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
         .append_query_results([[task::Model {
@@ -505,6 +645,13 @@ async fn delete_prop() {
             completed: true,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
+        .append_query_results([[task::Model {
+            id: 1,
+            title: "title".to_string(),
+            completed: true,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
+        .append_query_results([vec![] as Vec<property_schema::Model>])
         .append_query_results([[task_property::Model {
             task_id: 1,
             name: "name".to_string(),
@@ -528,6 +675,7 @@ async fn delete_prop() {
             deps_to_remove: vec![],
             scripts_to_add: vec![],
             scripts_to_remove: vec![],
+            parent: None,
             req_id: 0,
         },
     )
@@ -544,6 +692,13 @@ async fn delete_prop_bad_req() {
             completed: true,
             last_edited: chrono::NaiveDateTime::default(),
         }]])
+        .append_query_results([[task::Model {
+            id: 1,
+            title: "title".to_string(),
+            completed: true,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
+        .append_query_results([vec![] as Vec<property_schema::Model>])
         .append_query_results([vec![] as Vec<task_property::Model>])
         .into_connection();
 
@@ -559,6 +714,7 @@ async fn delete_prop_bad_req() {
             deps_to_remove: vec![],
             scripts_to_add: vec![],
             scripts_to_remove: vec![],
+            parent: None,
             req_id: 0,
         },
     )
@@ -573,6 +729,12 @@ async fn delete_prop_bad_req() {
 #[actix_web::test]
 async fn add_dep() {
     let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task::Model {
+            id: 1,
+            title: "title".to_string(),
+            completed: true,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
         .append_query_results([[task::Model {
             id: 1,
             title: "title".to_string(),
@@ -603,6 +765,7 @@ async fn add_dep() {
             deps_to_remove: vec![],
             scripts_to_add: vec![],
             scripts_to_remove: vec![],
+            parent: None,
             req_id: 0,
         },
     )
@@ -613,6 +776,12 @@ async fn add_dep() {
 #[actix_web::test]
 async fn add_dep_doesnt_exist() {
     let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task::Model {
+            id: 1,
+            title: "title".to_string(),
+            completed: true,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
         .append_query_results([[task::Model {
             id: 1,
             title: "title".to_string(),
@@ -634,6 +803,7 @@ async fn add_dep_doesnt_exist() {
             deps_to_remove: vec![],
             scripts_to_add: vec![],
             scripts_to_remove: vec![],
+            parent: None,
             req_id: 0,
         },
     )
@@ -648,6 +818,12 @@ async fn add_dep_doesnt_exist() {
 #[actix_web::test]
 async fn remove_dep() {
     let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task::Model {
+            id: 1,
+            title: "title".to_string(),
+            completed: true,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
         .append_query_results([[task::Model {
             id: 1,
             title: "title".to_string(),
@@ -676,6 +852,7 @@ async fn remove_dep() {
             deps_to_remove: vec![2],
             scripts_to_add: vec![],
             scripts_to_remove: vec![],
+            parent: None,
             req_id: 0,
         },
     )
@@ -686,6 +863,12 @@ async fn remove_dep() {
 #[actix_web::test]
 async fn remove_dep_bad_req() {
     let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[task::Model {
+            id: 1,
+            title: "title".to_string(),
+            completed: true,
+            last_edited: chrono::NaiveDateTime::default(),
+        }]])
         .append_query_results([[task::Model {
             id: 1,
             title: "title".to_string(),
@@ -707,6 +890,7 @@ async fn remove_dep_bad_req() {
             deps_to_remove: vec![2],
             scripts_to_add: vec![],
             scripts_to_remove: vec![],
+            parent: None,
             req_id: 0,
         },
     )
@@ -757,6 +941,7 @@ async fn test_task_update_request() {
             deps_to_remove: vec![],
             scripts_to_add: vec![],
             scripts_to_remove: vec![],
+            parent: None,
             req_id: 0,
         })
         .uri("/task")
@@ -820,6 +1005,7 @@ async fn test_tasks_update_request() {
                 deps_to_remove: vec![],
                 scripts_to_add: vec![],
                 scripts_to_remove: vec![],
+                parent: None,
                 req_id: 0,
             },
             UpdateTaskRequest {
@@ -832,6 +1018,7 @@ async fn test_tasks_update_request() {
                 deps_to_remove: vec![],
                 scripts_to_add: vec![],
                 scripts_to_remove: vec![],
+                parent: None,
                 req_id: 1,
             },
         ])
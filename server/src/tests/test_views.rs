@@ -1,6 +1,6 @@
 use super::*;
 use actix_web::test;
-use common::Filter;
+use common::{Filter, GroupBy, Summary};
 use sea_orm::MockDatabase;
 use sea_orm::MockExecResult;
 use std::vec;
@@ -40,6 +40,10 @@ async fn test_create_view() {
             name: "".to_string(),
             props: vec![],
             filter: Filter::None,
+            sort: vec![],
+            max_tasks: None,
+            group_by: GroupBy::None,
+            summaries: vec![],
             req_id: 0,
         }
     );
@@ -55,6 +59,10 @@ async fn test_update_view() {
             name: "idk".to_string(),
             properties: vec![],
             filter: "{}".to_string(),
+            sort: "[]".to_string(),
+            max_tasks: None,
+            group_by: "\"None\"".to_string(),
+            summaries: "[]".to_string(),
         }]])
         .append_exec_results([MockExecResult {
             last_insert_id: 1,
@@ -73,7 +81,11 @@ async fn test_update_view() {
                 view_id: 0,
                 name: "heyo".to_string(),
                 filter: Filter::None,
-                props: vec![]
+                props: vec![],
+                sort: vec![],
+                max_tasks: None,
+                group_by: GroupBy::Completed,
+                summaries: vec![Summary::Earliest("due".to_owned())],
             },
             req_id: 0,
         }
@@ -90,6 +102,10 @@ async fn test_get_views() {
             name: "idk".to_string(),
             properties: vec![],
             filter: serde_json::to_string(&Filter::None).unwrap(),
+            sort: "[]".to_string(),
+            max_tasks: None,
+            group_by: r#"{"Property":"status"}"#.to_string(),
+            summaries: "[]".to_string(),
         }]])
         .into_connection();
 
@@ -103,7 +119,11 @@ async fn test_get_views() {
         0
     );
 
-    test::call_service(&app, req).await;
+    let resp: GetViewResponse = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(
+        resp.views[0].group_by,
+        GroupBy::Property("status".to_owned())
+    );
 }
 
 #[actix_web::test]
@@ -114,6 +134,10 @@ async fn test_delete_view() {
             name: "idk".to_string(),
             properties: vec![],
             filter: "{}".to_string(),
+            sort: "[]".to_string(),
+            max_tasks: None,
+            group_by: "\"None\"".to_string(),
+            summaries: "[]".to_string(),
         }]])
         .append_exec_results([MockExecResult {
             last_insert_id: 1,
@@ -133,3 +157,166 @@ async fn test_delete_view() {
 
     test::call_service(&app, req).await;
 }
+
+fn ranked_view(sort: Vec<SortKey>) -> view::Model {
+    view::Model {
+        id: 2,
+        name: "today".to_string(),
+        properties: vec![],
+        filter: serde_json::to_string(&Filter::None).unwrap(),
+        sort: serde_json::to_string(&sort).unwrap(),
+        max_tasks: None,
+        group_by: "\"None\"".to_string(),
+        summaries: "[]".to_string(),
+    }
+}
+
+fn manual_order() -> SortKey {
+    SortKey {
+        field: SortField::Rank(2),
+        descending: false,
+        nulls_first: false,
+    }
+}
+
+fn task_model(id: TaskID) -> task::Model {
+    task::Model {
+        id,
+        title: "water plants".to_owned(),
+        completed: false,
+        last_edited: chrono::NaiveDateTime::default(),
+    }
+}
+
+fn rank_model(task_id: TaskID, rank: &str) -> view_rank::Model {
+    view_rank::Model {
+        view_id: 2,
+        task_id,
+        rank: rank.to_owned(),
+    }
+}
+
+fn move_request(task_id: TaskID, after: Option<TaskID>, before: Option<TaskID>) -> MoveTaskRequest {
+    MoveTaskRequest {
+        view_id: 2,
+        task_id,
+        after,
+        before,
+        req_id: 0,
+    }
+}
+
+#[actix_web::test]
+async fn test_rank_between() {
+    assert_eq!(rank_between("", None), "V");
+    assert_eq!(rank_between("V", Some("W")), "VV");
+    assert_eq!(rank_between("", Some("1")), "0V");
+    assert_eq!(rank_between("V", Some("V1")), "V0V");
+    // placing tasks first or last over and over keeps ranks ordered
+    let (mut first, mut last) = ("V".to_owned(), "V".to_owned());
+    for _ in 0..100 {
+        let before = rank_between("", Some(&first));
+        assert!(!before.is_empty() && before < first && !before.ends_with('0'));
+        first = before;
+        let after = rank_between(&last, None);
+        assert!(after > last && !after.ends_with('0'));
+        last = after;
+    }
+}
+
+#[actix_web::test]
+async fn test_move_task() {
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[ranked_view(vec![])]])
+        .append_query_results([Vec::<property_schema::Model>::new()])
+        .append_query_results([[task_model(1), task_model(2), task_model(3)]])
+        .append_query_results([Vec::<view_rank::Model>::new()])
+        .append_exec_results([MockExecResult {
+            last_insert_id: 0,
+            rows_affected: 2,
+        }])
+        .append_query_results([[ranked_view(vec![manual_order()])]])
+        .into_connection();
+    mk_app!(
+        req,
+        app,
+        db,
+        move_task_request,
+        actix_web::http::Method::PUT,
+        "/rank",
+        move_request(3, Some(1), Some(2))
+    );
+    let resp: MoveTaskResponse = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp.sort, vec![manual_order()]);
+}
+
+#[actix_web::test]
+async fn test_move_task_ranks() {
+    // task 1 was never moved, so it is ranked before task 3 to keep its place
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[ranked_view(vec![])]])
+        .append_query_results([Vec::<property_schema::Model>::new()])
+        .append_query_results([[task_model(1), task_model(2), task_model(3)]])
+        .append_query_results([Vec::<view_rank::Model>::new()])
+        .append_exec_results([MockExecResult {
+            last_insert_id: 0,
+            rows_affected: 2,
+        }])
+        .append_query_results([[ranked_view(vec![manual_order()])]])
+        .into_connection();
+    move_task(&db, &move_request(3, Some(1), Some(2)))
+        .await
+        .unwrap();
+    let log = format!("{:?}", db.into_transaction_log());
+    // moves within the view wait for each other on its row
+    assert!(log.contains(r#"Transaction { stmts: [Statement { sql: "BEGIN""#));
+    assert!(log.contains("FOR UPDATE"));
+    assert!(log.contains(r#"sql: "COMMIT""#));
+    assert!(log.contains("ORDER BY (SELECT \\\"view_rank\\\".\\\"rank\\\""));
+    assert!(log.contains("INSERT INTO \\\"view_rank\\\""));
+    assert!(log.contains("Int(Some(1)), String(Some(\"V\"))"));
+    assert!(log.contains("Int(Some(3)), String(Some(\"l\"))"));
+    assert!(log.contains("UPDATE \\\"view\\\" SET \\\"sort\\\""));
+
+    // ranks of tasks the view hides are skipped, and a view in manual order is left alone
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[ranked_view(vec![manual_order()])]])
+        .append_query_results([Vec::<property_schema::Model>::new()])
+        .append_query_results([[task_model(1), task_model(2), task_model(3)]])
+        .append_query_results([[
+            rank_model(1, "V"),
+            rank_model(2, "k"),
+            rank_model(3, "x"),
+            rank_model(9, "c"),
+        ]])
+        .append_exec_results([MockExecResult {
+            last_insert_id: 0,
+            rows_affected: 1,
+        }])
+        .into_connection();
+    move_task(&db, &move_request(3, Some(1), Some(2)))
+        .await
+        .unwrap();
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(log.contains("String(Some(\"Z\"))"));
+    assert!(!log.contains("UPDATE \\\"view\\\""));
+}
+
+#[actix_web::test]
+async fn test_move_task_conflict() {
+    // another client moved task 2 between tasks 1 and 3
+    let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+        .append_query_results([[ranked_view(vec![manual_order()])]])
+        .append_query_results([Vec::<property_schema::Model>::new()])
+        .append_query_results([[task_model(1), task_model(2), task_model(3), task_model(4)]])
+        .into_connection();
+    let err = move_task(&db, &move_request(4, Some(1), Some(3)))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.as_response_error().status_code(),
+        actix_web::http::StatusCode::CONFLICT
+    );
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(log.contains(r#"sql: "ROLLBACK""#));
+}